@@ -0,0 +1,138 @@
+//! Per-node aggregation of the edges of a GML graph.
+//!
+//! `aggregate_gml` streams the edges into per-node accumulators instead of
+//! storing them, so that degrees and other summaries of graphs too large to
+//! hold in memory take space only for the nodes.
+
+use crate::events::gml_events;
+use crate::node_map::NodeMap;
+use crate::read_blocks;
+use asexp::atom::Atom;
+use asexp::Sexp;
+use petgraph::graph::NodeIndex;
+
+/// An edge at one of its nodes, as passed to the fold of `aggregate_gml`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Incidence {
+    /// The index of the node at the other end of the edge.
+    pub other: usize,
+    /// The weight of the edge, its numeric `weight` or 1.0.
+    pub weight: f64,
+    /// Whether the edge leaves the node.
+    pub outgoing: bool,
+}
+
+/// The per-node results of `aggregate_gml`, indexed like the nodes of the
+/// graph `parse_gml` returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAggregates<A> {
+    /// The GML ids of the nodes.
+    pub ids: Vec<u64>,
+    pub out_degree: Vec<u64>,
+    pub in_degree: Vec<u64>,
+    pub weighted_out_degree: Vec<f64>,
+    pub weighted_in_degree: Vec<f64>,
+    /// The accumulators of the fold.
+    pub values: Vec<A>,
+}
+
+fn edge_weight(weight: Option<Sexp>) -> f64 {
+    match weight {
+        Some(Sexp::Atom(Atom::UInt(u))) => u as f64,
+        Some(Sexp::Atom(Atom::SInt(i))) => i as f64,
+        Some(Sexp::Atom(Atom::Float(f))) => f,
+        _ => 1.0,
+    }
+}
+
+/// Reads the nodes and edges of a GML string like `parse_gml`, without
+/// storing the edges. For every edge, the degrees of its nodes are counted
+/// and `fold` is called with the accumulator of its source and then with
+/// that of its target, which all start as `init`.
+///
+/// The input is read twice, for the nodes and then for the edges.
+pub fn aggregate_gml<A, F>(s: &str, init: A, mut fold: F) -> Result<NodeAggregates<A>, &'static str>
+where
+    A: Clone,
+    F: FnMut(&mut A, &Incidence),
+{
+    let mut node_map = NodeMap::default();
+    let mut ids = Vec::new();
+    let mut events = gml_events(s);
+    events.enter_graph()?;
+    read_blocks(&mut events, true, false, |block| {
+        let id = block.id.ok_or("Invalid id")?;
+        if !node_map.insert(id, NodeIndex::new(ids.len())) {
+            return Err("duplicate node-id");
+        }
+        ids.push(id);
+        Ok(())
+    })?;
+    events.finish()?;
+
+    let n = ids.len();
+    let mut aggregates = NodeAggregates {
+        ids,
+        out_degree: vec![0; n],
+        in_degree: vec![0; n],
+        weighted_out_degree: vec![0.0; n],
+        weighted_in_degree: vec![0.0; n],
+        values: vec![init; n],
+    };
+    let mut events = gml_events(s);
+    events.enter_graph()?;
+    read_blocks(&mut events, false, true, |block| {
+        let source = block.source.ok_or("Invalid source id")?;
+        let target = block.target.ok_or("Invalid target id")?;
+        let (source, target) = match (node_map.get(source), node_map.get(target)) {
+            (Some(source), Some(target)) => (source.index(), target.index()),
+            _ => return Err("edge references unknown node"),
+        };
+        let weight = edge_weight(block.weight);
+        aggregates.out_degree[source] += 1;
+        aggregates.in_degree[target] += 1;
+        aggregates.weighted_out_degree[source] += weight;
+        aggregates.weighted_in_degree[target] += weight;
+        let outgoing = Incidence {
+            other: target,
+            weight,
+            outgoing: true,
+        };
+        fold(&mut aggregates.values[source], &outgoing);
+        let incoming = Incidence {
+            other: source,
+            weight,
+            outgoing: false,
+        };
+        fold(&mut aggregates.values[target], &incoming);
+        Ok(())
+    })?;
+    Ok(aggregates)
+}
+
+#[test]
+fn test_aggregate_gml() {
+    let gml = "graph [ directed 1 edge [ source 9 target 5 weight 2.5 ] node [ id 5 ] \
+               node [ id 9 ] node [ id 7 ] edge [ source 9 target 7 ] \
+               edge [ source 7 target 7 weight [ x 1 ] ] ]";
+    // the largest weight of an edge at each node
+    let max = aggregate_gml(gml, 0.0, |max: &mut f64, edge| *max = max.max(edge.weight)).unwrap();
+    assert_eq!(vec![5, 9, 7], max.ids);
+    assert_eq!(vec![0, 2, 1], max.out_degree);
+    assert_eq!(vec![1, 0, 2], max.in_degree);
+    assert_eq!(vec![0.0, 3.5, 1.0], max.weighted_out_degree);
+    assert_eq!(vec![2.5, 0.0, 2.0], max.weighted_in_degree);
+    assert_eq!(vec![2.5, 2.5, 1.0], max.values);
+
+    let neighbors = aggregate_gml(gml, Vec::new(), |others: &mut Vec<usize>, edge| {
+        others.push(edge.other)
+    })
+    .unwrap();
+    assert_eq!(vec![vec![1], vec![0, 2], vec![1, 2, 2]], neighbors.values);
+
+    let gml = "graph [ node [ id 1 ] edge [ source 1 target 2 ] ]";
+    assert_eq!(
+        Some("edge references unknown node"),
+        aggregate_gml(gml, (), |_, _| {}).err()
+    );
+}
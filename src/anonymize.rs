@@ -0,0 +1,171 @@
+//! Removing or hashing identifying attributes, to share graphs without
+//! their labels.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+
+/// What `anonymize` does with the selected attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Treatment {
+    /// Removes them.
+    Strip,
+    /// Replaces every scalar in them by a hash of it, as a string of 16 hex
+    /// digits, so that equal values stay equal.
+    Hash,
+}
+
+/// Options for `anonymize`.
+#[derive(Debug, Clone)]
+pub struct AnonymizeOptions {
+    /// The keys of the attributes to treat, dotted for nested ones, like
+    /// `graphics.label`. A key of a list selects all that is in it.
+    pub keys: Vec<String>,
+    pub treatment: Treatment,
+    /// Hashed with every value. The hash is not cryptographic; without a
+    /// secret salt, values from a small set, like names, can be found by
+    /// hashing all candidates.
+    pub salt: String,
+    /// Whether graph attributes are treated as well as those of nodes and
+    /// edges.
+    pub graph: bool,
+}
+
+impl Default for AnonymizeOptions {
+    fn default() -> AnonymizeOptions {
+        AnonymizeOptions {
+            keys: vec!["label".to_string()],
+            treatment: Treatment::Strip,
+            salt: String::new(),
+            graph: true,
+        }
+    }
+}
+
+// FNV-1a over the salt, a separator and the value with its type.
+fn hash(salt: &str, value: &GmlValue) -> String {
+    let text = match *value {
+        GmlValue::Int(i) => format!("i{}", i),
+        GmlValue::UInt(u) => format!("i{}", u),
+        GmlValue::Float(f) => format!("f{:?}", f),
+        GmlValue::Str(ref s) => format!("s{}", s),
+        GmlValue::List(_) => unreachable!(),
+    };
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in salt.as_bytes().iter().chain(&[0]).chain(text.as_bytes()) {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", h)
+}
+
+fn hash_all(value: &mut GmlValue, salt: &str) {
+    match *value {
+        GmlValue::List(ref mut list) => {
+            for inner in list.values_mut() {
+                hash_all(inner, salt);
+            }
+        }
+        ref mut scalar => *scalar = GmlValue::from(hash(salt, scalar)),
+    }
+}
+
+fn treat(attrs: &mut GmlAttrs, key: &str, options: &AnonymizeOptions) {
+    if let Some((outer, rest)) = key.split_once('.') {
+        if let Some(GmlValue::List(inner)) = attrs.get_mut(outer) {
+            treat(inner, rest, options);
+        }
+        return;
+    }
+    match options.treatment {
+        Treatment::Strip => {
+            crate::document::remove_attr(attrs, key);
+        }
+        Treatment::Hash => {
+            if let Some(value) = attrs.get_mut(key) {
+                hash_all(value, &options.salt);
+            }
+        }
+    }
+}
+
+/// Removes or hashes the attributes `options` selects from the nodes and
+/// edges of `doc`, and from the graph if `options.graph`. Ids and the
+/// structure are kept.
+pub fn anonymize(doc: &mut GmlDocument, options: &AnonymizeOptions) {
+    let nodes = doc.nodes.iter_mut().map(|node| &mut node.attrs);
+    let edges = doc.edges.iter_mut().map(|edge| &mut edge.attrs);
+    let graph = if options.graph {
+        Some(&mut doc.attrs)
+    } else {
+        None
+    };
+    for attrs in graph.into_iter().chain(nodes).chain(edges) {
+        for key in &options.keys {
+            treat(attrs, key, options);
+        }
+    }
+}
+
+#[test]
+fn test_anonymize() {
+    use crate::document::parse_gml_document;
+
+    let gml = "graph [ label \"net\" creator \"me\"
+               node [ id 1 label \"alice\" graphics [ label \"A\" x 1 ] ]
+               node [ id 2 label \"bob\" age 30 ] node [ id 3 label \"alice\" ]
+               edge [ source 1 target 2 label \"knows\" ] ]";
+    let original = parse_gml_document(gml).unwrap();
+
+    let mut doc = original.clone();
+    let options = AnonymizeOptions {
+        keys: vec!["label".to_string(), "graphics.label".to_string()],
+        ..AnonymizeOptions::default()
+    };
+    anonymize(&mut doc, &options);
+    assert_eq!(None, doc.attrs.get("label"));
+    assert!(doc.attrs.contains_key("creator"));
+    assert!(doc.nodes.iter().all(|node| node.label().is_none()));
+    let graphics = doc.nodes[0].attrs.get("graphics").unwrap();
+    assert_eq!(
+        (None, Some(1)),
+        (
+            graphics.get("label"),
+            graphics.get("x").and_then(GmlValue::get_int)
+        )
+    );
+    assert_eq!(None, doc.edges[0].label());
+    assert_eq!((1, 2), (doc.edges[0].source, doc.edges[0].target));
+
+    let mut doc = original.clone();
+    let options = AnonymizeOptions {
+        keys: vec!["label".to_string(), "age".to_string()],
+        treatment: Treatment::Hash,
+        salt: "secret".to_string(),
+        graph: false,
+    };
+    anonymize(&mut doc, &options);
+    assert_eq!(
+        Some("net"),
+        doc.attrs.get("label").and_then(GmlValue::get_str)
+    );
+    let labels: Vec<_> = doc.nodes.iter().map(|node| node.label().unwrap()).collect();
+    assert_eq!(labels[0], labels[2]);
+    assert_ne!(labels[0], labels[1]);
+    assert_eq!(16, labels[0].len());
+    assert!(doc.nodes[1]
+        .attrs
+        .get("age")
+        .and_then(GmlValue::get_str)
+        .is_some());
+    let ids: Vec<_> = doc.nodes.iter().map(|node| node.id).collect();
+    assert_eq!(vec![1, 2, 3], ids);
+
+    let mut unsalted = original;
+    anonymize(
+        &mut unsalted,
+        &AnonymizeOptions {
+            salt: String::new(),
+            ..options
+        },
+    );
+    assert_ne!(labels[0], unsalted.nodes[0].label().unwrap());
+}
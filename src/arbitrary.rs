@@ -0,0 +1,152 @@
+//! `quickcheck::Arbitrary` for `GmlValue` and `GmlDocument`, with the
+//! `quickcheck` feature.
+//!
+//! Generated documents are valid: keys are identifiers, reserved keys like
+//! `id` are not attributes, node ids are unique, edges join existing nodes
+//! and reals are finite, so that every document can be written with
+//! `to_gml` and parsed back to an equal one. Strings may hold any character,
+//! some of which the `legacy-lexer` feature cannot read.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use quickcheck::{Arbitrary, Gen};
+
+// How deep lists are nested in generated values.
+const DEPTH: usize = 2;
+
+fn below(g: &mut Gen, n: usize) -> usize {
+    usize::arbitrary(g) % n.max(1)
+}
+
+fn key(g: &mut Gen) -> String {
+    const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_";
+    const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_0123456789";
+    let mut key = String::new();
+    key.push(*g.choose(FIRST).unwrap() as char);
+    for _ in 0..below(g, 8) {
+        key.push(*g.choose(REST).unwrap() as char);
+    }
+    key
+}
+
+fn value(g: &mut Gen, depth: usize) -> GmlValue {
+    match below(g, if depth == 0 { 3 } else { 4 }) {
+        0 => GmlValue::Int(i64::arbitrary(g)),
+        1 => {
+            let f = f64::arbitrary(g);
+            GmlValue::Float(if f.is_finite() { f } else { 0.0 })
+        }
+        2 => GmlValue::from(String::arbitrary(g)),
+        _ => GmlValue::List(attrs(g, depth - 1, &[])),
+    }
+}
+
+// Attributes with keys other than `reserved`.
+fn attrs(g: &mut Gen, depth: usize, reserved: &[&str]) -> GmlAttrs {
+    let mut attrs = GmlAttrs::new();
+    for _ in 0..below(g, 4) {
+        let key = key(g);
+        if !reserved.contains(&key.as_str()) {
+            attrs.insert(key, value(g, depth));
+        }
+    }
+    attrs
+}
+
+impl Arbitrary for GmlValue {
+    fn arbitrary(g: &mut Gen) -> GmlValue {
+        value(g, DEPTH)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = GmlValue>> {
+        match *self {
+            GmlValue::Int(i) => Box::new(i.shrink().map(GmlValue::Int)),
+            GmlValue::UInt(_) => quickcheck::empty_shrinker(),
+            GmlValue::Float(f) => Box::new(f.shrink().map(GmlValue::Float)),
+            GmlValue::Str(ref s) => Box::new(s.to_string().shrink().map(GmlValue::from)),
+            GmlValue::List(ref attrs) => {
+                // the list without one of its entries
+                let attrs = attrs.clone();
+                Box::new((0..attrs.len()).map(move |i| {
+                    GmlValue::List(
+                        attrs
+                            .iter()
+                            .enumerate()
+                            .filter(|&(j, _)| j != i)
+                            .map(|(_, (k, v))| (k.clone(), v.clone()))
+                            .collect(),
+                    )
+                }))
+            }
+        }
+    }
+}
+
+impl Arbitrary for GmlDocument {
+    fn arbitrary(g: &mut Gen) -> GmlDocument {
+        let mut doc = GmlDocument {
+            directed: bool::arbitrary(g),
+            attrs: attrs(g, DEPTH, &["directed", "node", "edge"]),
+            ..GmlDocument::default()
+        };
+        let mut ids: Vec<u64> = Vec::new();
+        for _ in 0..below(g, g.size()) {
+            // ids are mostly small, with an occasional large one
+            let id = if below(g, 8) == 0 {
+                u64::arbitrary(g) >> 1
+            } else {
+                below(g, 2 * g.size()) as u64
+            };
+            if !ids.contains(&id) {
+                ids.push(id);
+                doc.nodes.push(GmlNode {
+                    id,
+                    attrs: attrs(g, DEPTH, &["id"]),
+                });
+            }
+        }
+        if !ids.is_empty() {
+            for _ in 0..below(g, 2 * g.size()) {
+                doc.edges.push(GmlEdge {
+                    source: *g.choose(&ids).unwrap(),
+                    target: *g.choose(&ids).unwrap(),
+                    attrs: attrs(g, DEPTH, &["source", "target"]),
+                });
+            }
+        }
+        doc
+    }
+
+    /// Documents with the second half of the edges removed, or the last
+    /// node with its edges.
+    fn shrink(&self) -> Box<dyn Iterator<Item = GmlDocument>> {
+        let mut smaller = Vec::new();
+        if !self.edges.is_empty() {
+            let mut doc = self.clone();
+            doc.edges.truncate(self.edges.len() / 2);
+            smaller.push(doc);
+        }
+        if let Some(last) = self.nodes.last() {
+            let mut doc = self.clone();
+            doc.nodes.pop();
+            doc.edges
+                .retain(|edge| edge.source != last.id && edge.target != last.id);
+            smaller.push(doc);
+        }
+        Box::new(smaller.into_iter())
+    }
+}
+
+#[cfg(not(feature = "legacy-lexer"))]
+#[test]
+fn test_arbitrary_round_trip() {
+    use crate::validate::validate_gml;
+    use crate::writer::to_gml;
+
+    fn round_trip(doc: GmlDocument) -> bool {
+        let gml = to_gml(&doc);
+        validate_gml(&gml).is_empty() && crate::parse_gml_document(&gml) == Ok(doc)
+    }
+    quickcheck::QuickCheck::new()
+        .tests(200)
+        .quickcheck(round_trip as fn(GmlDocument) -> bool);
+}
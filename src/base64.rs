@@ -0,0 +1,108 @@
+//! Binary attributes, like embeddings or thumbnails, as base64 strings.
+//!
+//! GML has no binary type, so bytes are written as a string in the
+//! standard base64 alphabet, with padding. `BytesAttrs::get_bytes` reads
+//! them back, with or without padding and ignoring whitespace, as left by
+//! tools which wrap long lines.
+
+use crate::document::{GmlAttrs, GmlValue};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` as base64.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes base64, with or without padding. Whitespace is skipped.
+pub fn decode(s: &str) -> Result<Vec<u8>, &'static str> {
+    let invalid = "invalid base64";
+    let s = s.trim_end_matches(|c: char| c == '=' || c.is_ascii_whitespace());
+    let mut out = Vec::with_capacity(s.len() / 4 * 3 + 2);
+    let (mut n, mut bits) = (0u32, 0);
+    for b in s.bytes().filter(|b| !b.is_ascii_whitespace()) {
+        let digit = ALPHABET.iter().position(|&a| a == b).ok_or(invalid)?;
+        n = n << 6 | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+    // a single digit left over cannot come from a whole byte
+    if bits == 6 {
+        return Err(invalid);
+    }
+    Ok(out)
+}
+
+/// Binary accessors of attributes.
+pub trait BytesAttrs {
+    /// The bytes at `key`, if it is a base64 string.
+    fn get_bytes(&self, key: &str) -> Option<Vec<u8>>;
+    /// Sets `key` to `bytes` as a base64 string.
+    fn put_bytes(&mut self, key: &str, bytes: &[u8]);
+}
+
+impl BytesAttrs for GmlAttrs {
+    fn get_bytes(&self, key: &str) -> Option<Vec<u8>> {
+        self.get(key)
+            .and_then(GmlValue::get_str)
+            .and_then(|s| decode(s).ok())
+    }
+
+    fn put_bytes(&mut self, key: &str, bytes: &[u8]) {
+        self.insert(key.to_string(), GmlValue::from(encode(bytes)));
+    }
+}
+
+#[test]
+fn test_base64() {
+    let cases = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+    for &(bytes, text) in &cases {
+        assert_eq!(text, encode(bytes.as_bytes()));
+        assert_eq!(Ok(bytes.as_bytes().to_vec()), decode(text));
+        assert_eq!(
+            Ok(bytes.as_bytes().to_vec()),
+            decode(text.trim_end_matches('='))
+        );
+    }
+    assert_eq!(Ok(b"foobar".to_vec()), decode("Zm9v\n  YmFy\n"));
+    assert_eq!(Err("invalid base64"), decode("Zm9v!"));
+    assert_eq!(Err("invalid base64"), decode("Zm9vY"));
+
+    let all: Vec<u8> = (0..=255).collect();
+    let mut attrs = GmlAttrs::new();
+    attrs.put_bytes("thumbnail", &all);
+    let doc = crate::parse_gml_document(&crate::to_gml(&crate::GmlDocument {
+        directed: false,
+        nodes: vec![crate::GmlNode { id: 1, attrs }],
+        ..crate::GmlDocument::default()
+    }))
+    .unwrap();
+    assert_eq!(Some(all), doc.nodes[0].attrs.get_bytes("thumbnail"));
+    assert_eq!(None, doc.nodes[0].attrs.get_bytes("label"));
+}
@@ -0,0 +1,364 @@
+//! Loading directories of GML files.
+//!
+//! `load_gml_dir` parses every file matching a pattern like `data/*.gml`,
+//! one after another as the iterator is advanced. With the `parallel`
+//! feature, `load_gml_dir_parallel` loads the files on several threads.
+
+use crate::{parse_gml_with_buffers, read_gml_file, GmlOptions, ParseBuffers};
+use asexp::Sexp;
+use petgraph::{Directed, Graph};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// the file names a directory pattern matches
+const GML_SUFFIXES: [&str; 3] = [".gml", ".gml.gz", ".gml.zst"];
+
+// Whether `name` matches the glob `pattern`, where `*` matches any run of
+// characters and `?` any one character.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // the position after the last `*` and the name position it is tried at
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+fn is_glob(component: &str) -> bool {
+    component.contains(['*', '?'])
+}
+
+/// The directory of `pattern` before its first wildcard, which the paths
+/// `find_gml_files` returns for it start with. It is empty if `pattern`
+/// starts with a wildcard.
+pub fn glob_base(pattern: &Path) -> &Path {
+    if pattern.is_dir() {
+        return pattern;
+    }
+    let mut base = pattern.parent().unwrap_or(Path::new(""));
+    while base.iter().any(|c| c.to_str().is_some_and(is_glob)) {
+        base = base.parent().unwrap_or(Path::new(""));
+    }
+    base
+}
+
+// Adds the files below `dir` which match `components` to `paths`. `**`
+// matches any number of directories.
+fn walk(dir: &Path, components: &[&str], paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    let (first, rest) = match components.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    if *first == "**" {
+        walk(dir, rest, paths)?;
+    }
+    let read_dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    for entry in fs::read_dir(read_dir)? {
+        let entry = entry?;
+        // names which are not UTF-8 match no pattern
+        let name = match entry.file_name().to_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let file_type = entry.file_type()?;
+        if *first == "**" {
+            if file_type.is_dir() {
+                walk(&dir.join(&name), components, paths)?;
+            }
+        } else if glob_match(first.as_bytes(), name.as_bytes()) {
+            if rest.is_empty() && file_type.is_file() {
+                paths.push(dir.join(&name));
+            } else if !rest.is_empty() && file_type.is_dir() {
+                walk(&dir.join(&name), rest, paths)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The sorted paths of the files `pattern` matches, as `load_gml_dir`
+/// loads them.
+pub fn find_gml_files<P: AsRef<Path>>(pattern: P) -> io::Result<Vec<PathBuf>> {
+    let pattern = pattern.as_ref();
+    let mut paths = Vec::new();
+    if pattern.is_dir() {
+        for entry in fs::read_dir(pattern)? {
+            let entry = entry?;
+            let matches = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| GML_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)));
+            if matches && entry.file_type()?.is_file() {
+                paths.push(entry.path());
+            }
+        }
+    } else {
+        let base = glob_base(pattern);
+        let rest = pattern.strip_prefix(base).unwrap_or(pattern);
+        let components = rest
+            .iter()
+            .map(|c| c.to_str())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file pattern"))?;
+        walk(base, &components, &mut paths)?;
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// A path and the graph loaded from it.
+pub type LoadedGraph<N, E> = (PathBuf, io::Result<Graph<N, E, Directed>>);
+
+fn load_file<NodeWeightFn, EdgeWeightFn, N, E>(
+    path: &Path,
+    options: &GmlOptions,
+    buffers: &mut ParseBuffers<E>,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> io::Result<Graph<N, E, Directed>>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let s = read_gml_file(path)?;
+    parse_gml_with_buffers(&s, options, buffers, node_weight_fn, edge_weight_fn)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Parses the GML files `pattern` matches, in the order of their paths.
+///
+/// `pattern` is either a directory, matching the `.gml`, `.gml.gz` and
+/// `.gml.zst` files in it, or a path which may contain the wildcards `*`
+/// and `?` in its components and `**` for any number of directories, like
+/// `data/**/*.gml`. Files are read with
+/// `read_gml_file` and parsed with `options` when the iterator reaches
+/// them. Invalid GML is an `io::ErrorKind::InvalidData` error of its file.
+pub fn load_gml_dir<P, NodeWeightFn, EdgeWeightFn, N, E>(
+    pattern: P,
+    options: &GmlOptions,
+    node_weight_fn: NodeWeightFn,
+    edge_weight_fn: EdgeWeightFn,
+) -> io::Result<impl Iterator<Item = LoadedGraph<N, E>>>
+where
+    P: AsRef<Path>,
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let paths = find_gml_files(pattern)?;
+    let options = options.clone();
+    let mut buffers = ParseBuffers::new();
+    Ok(paths.into_iter().map(move |path| {
+        let graph = load_file(
+            &path,
+            &options,
+            &mut buffers,
+            &node_weight_fn,
+            &edge_weight_fn,
+        );
+        (path, graph)
+    }))
+}
+
+#[cfg(feature = "parallel")]
+pub use self::parallel::{load_gml_dir_parallel, ParallelLoad};
+
+#[cfg(feature = "parallel")]
+mod parallel {
+    use super::{find_gml_files, load_file, LoadedGraph};
+    use crate::{GmlOptions, ParseBuffers};
+    use asexp::Sexp;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{sync_channel, Receiver};
+    use std::sync::Arc;
+    use std::thread;
+
+    /// The iterator of `load_gml_dir_parallel`.
+    pub struct ParallelLoad<N, E> {
+        receiver: Receiver<(usize, LoadedGraph<N, E>)>,
+        // graphs loaded ahead of the next one, by index
+        pending: BTreeMap<usize, LoadedGraph<N, E>>,
+        next: usize,
+        len: usize,
+    }
+
+    impl<N, E> Iterator for ParallelLoad<N, E> {
+        type Item = LoadedGraph<N, E>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.next == self.len {
+                return None;
+            }
+            while !self.pending.contains_key(&self.next) {
+                let (i, loaded) = self.receiver.recv().expect("loader thread panicked");
+                self.pending.insert(i, loaded);
+            }
+            let item = self.pending.remove(&self.next);
+            self.next += 1;
+            item
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let n = self.len - self.next;
+            (n, Some(n))
+        }
+    }
+
+    /// Parses the GML files `pattern` matches like `load_gml_dir`, on up
+    /// to `threads` threads, or as many as are available if `threads` is 0.
+    ///
+    /// The graphs are returned in the order of their paths. Loading stops
+    /// when the iterator is dropped.
+    pub fn load_gml_dir_parallel<P, NodeWeightFn, EdgeWeightFn, N, E>(
+        pattern: P,
+        options: &GmlOptions,
+        threads: usize,
+        node_weight_fn: NodeWeightFn,
+        edge_weight_fn: EdgeWeightFn,
+    ) -> io::Result<ParallelLoad<N, E>>
+    where
+        P: AsRef<Path>,
+        NodeWeightFn: Fn(Option<&Sexp>) -> Option<N> + Send + Sync + 'static,
+        EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E> + Send + Sync + 'static,
+        N: Send + 'static,
+        E: Send + 'static,
+    {
+        let paths = find_gml_files(pattern)?;
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+        .min(paths.len());
+        let len = paths.len();
+        // a bounded channel keeps the threads from loading far ahead of the
+        // consumer
+        let (sender, receiver) = sync_channel(threads);
+        let shared = Arc::new((paths, options.clone(), node_weight_fn, edge_weight_fn));
+        let next = Arc::new(AtomicUsize::new(0));
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let shared = Arc::clone(&shared);
+            let next = Arc::clone(&next);
+            thread::spawn(move || {
+                let (paths, options, node_weight_fn, edge_weight_fn) = &*shared;
+                let mut buffers = ParseBuffers::new();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let path = match paths.get(i) {
+                        Some(path) => path,
+                        None => break,
+                    };
+                    let graph =
+                        load_file(path, options, &mut buffers, node_weight_fn, edge_weight_fn);
+                    if sender.send((i, (path.clone(), graph))).is_err() {
+                        // the iterator was dropped
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(ParallelLoad {
+            receiver,
+            pending: BTreeMap::new(),
+            next: 0,
+            len,
+        })
+    }
+}
+
+#[test]
+fn test_load_gml_dir() {
+    assert!(glob_match(b"*.gml", b"a.gml"));
+    assert!(glob_match(b"g?-*x*.gml", b"g1-axbx.gml"));
+    assert!(!glob_match(b"*.gml", b"a.gml.gz"));
+    assert!(!glob_match(b"g?.gml", b"g.gml"));
+
+    let dir = std::env::temp_dir().join(format!("gml-batch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for i in 0..12 {
+        let gml = format!(
+            "graph [ node [ id 1 ] node [ id 2 ] edge [ source 1 target {} ] ]",
+            i % 3
+        );
+        fs::write(dir.join(format!("g{:02}.gml", i)), gml).unwrap();
+    }
+    fs::write(dir.join("notes.txt"), "").unwrap();
+
+    let nodes = |_: Option<&Sexp>| Some(());
+    let edges = |_: Option<&Sexp>| Some(());
+    let loaded: Vec<_> = load_gml_dir(&dir, &GmlOptions::default(), nodes, edges)
+        .unwrap()
+        .collect();
+    assert_eq!(12, loaded.len());
+    for (i, (path, graph)) in loaded.iter().enumerate() {
+        assert_eq!(dir.join(format!("g{:02}.gml", i)), *path);
+        // the edges to node 0 have no target
+        match graph {
+            Ok(graph) => assert_eq!(1, graph.edge_count()),
+            Err(e) => assert!(i % 3 == 0 && e.kind() == io::ErrorKind::InvalidData),
+        }
+    }
+    fs::create_dir_all(dir.join("a/b")).unwrap();
+    fs::write(dir.join("a/b/h.gml"), "").unwrap();
+    fs::write(dir.join("a/h.gml"), "").unwrap();
+    fs::write(dir.join("a/h.txt"), "").unwrap();
+    let pattern = dir.join("**/h.gml");
+    assert_eq!(dir, glob_base(&pattern));
+    assert_eq!(
+        vec![dir.join("a/b/h.gml"), dir.join("a/h.gml")],
+        find_gml_files(&pattern).unwrap()
+    );
+    let pattern = dir.join("?/*/*.gml");
+    assert_eq!(
+        vec![dir.join("a/b/h.gml")],
+        find_gml_files(&pattern).unwrap()
+    );
+    assert_eq!(Path::new("data"), glob_base(Path::new("data/x*/y/*.gml")));
+
+    let pattern = dir.join("g1?.gml");
+    assert_eq!(
+        2,
+        load_gml_dir(&pattern, &GmlOptions::default(), nodes, edges)
+            .unwrap()
+            .count()
+    );
+
+    #[cfg(feature = "parallel")]
+    {
+        let parallel: Vec<_> = load_gml_dir_parallel(&dir, &GmlOptions::default(), 4, nodes, edges)
+            .unwrap()
+            .collect();
+        assert_eq!(loaded.len(), parallel.len());
+        for ((path1, graph1), (path2, graph2)) in loaded.iter().zip(&parallel) {
+            assert_eq!(path1, path2);
+            assert_eq!(graph1.is_ok(), graph2.is_ok());
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,533 @@
+//! The `gml` command-line tool, built with the `cli` feature.
+//!
+//! ```text
+//! gml validate FILE...
+//! gml convert INPUT -o OUTPUT [--to FORMAT]
+//! gml convert PATTERN --to FORMAT --out-dir DIR [-j THREADS]
+//! gml stats FILE...
+//! gml diff OLD NEW [--json]
+//! gml grep QUERY FILE...
+//! gml canonicalize INPUT -o OUTPUT
+//! gml anonymize INPUT -o OUTPUT [--key KEY]... [--hash [--salt SALT]]
+//! gml shell FILE
+//! ```
+
+use graph_io_gml::*;
+use petgraph::Undirected;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+const USAGE: &str = "usage:
+    gml validate FILE...
+    gml convert INPUT -o OUTPUT [--to FORMAT]
+    gml convert PATTERN --to FORMAT --out-dir DIR [-j THREADS]
+    gml stats FILE...
+    gml diff OLD NEW [--json]
+    gml grep QUERY FILE...
+    gml canonicalize INPUT -o OUTPUT
+    gml anonymize INPUT -o OUTPUT [--key KEY]... [--hash [--salt SALT]]
+    gml shell FILE
+
+Input is GML, compressed with gzip or zstd if built with those features,
+or one of dot, graphml, jgf, pajek, tgf, tulip, leda and ucinet, as given
+by the file extension. The output format is given by --to or by the
+extension of OUTPUT, and is one of gml, dot, graphml, gexf, jgf,
+cytoscape, graphology, graphson, geojson, pajek, tgf, tulip, leda, mtx,
+cypher, sql, turtle and mermaid.
+
+With --out-dir, every file PATTERN matches is converted, on THREADS
+threads or as many as are available. PATTERN is a directory, for its .gml
+files, or a path with the wildcards *, ? and **, like 'data/**/*.gml'. The
+outputs are placed in DIR like the inputs below the directory before the
+first wildcard.
+
+diff compares nodes by id and edges by their ends, and exits with 1 if
+the documents differ.
+
+grep prints the nodes or edges QUERY selects, like
+    gml grep 'node.degree > 5 && node.attr(\"type\") == \"switch\"' net.gml
+Nodes have id, degree, in_degree, out_degree, label and attr(\"key\"), edges
+source, target, label and attr(\"key\"), compared with == != < <= > >= and
+combined with && || ! and parentheses.
+
+canonicalize sorts the nodes by id, the edges by their ends and the
+attributes by key, so that equal graphs give equal files.
+
+anonymize removes the attributes with the keys given, or label, from the
+graph, its nodes and edges, or with --hash replaces their values by
+hashes salted with SALT. Nested keys are dotted, like graphics.label.
+
+shell reads commands like `node 42`, `neighbors 42`, `find label=~\"foo\"`
+and `stats` about the GML file FILE; `help` lists them.";
+
+// The output formats, by name and file extensions, the first of which
+// batch conversion gives its outputs.
+const FORMATS: &[(&str, &[&str])] = &[
+    ("gml", &["gml"]),
+    ("dot", &["dot", "gv"]),
+    ("graphml", &["graphml"]),
+    ("gexf", &["gexf"]),
+    ("jgf", &["jgf", "json"]),
+    ("cytoscape", &["cyjs"]),
+    ("graphology", &["json"]),
+    ("graphson", &["json"]),
+    ("geojson", &["geojson"]),
+    ("pajek", &["net"]),
+    ("tgf", &["tgf"]),
+    ("tulip", &["tlp"]),
+    ("leda", &["gw", "lgr"]),
+    ("mtx", &["mtx"]),
+    ("cypher", &["cypher", "cql"]),
+    ("sql", &["sql"]),
+    ("turtle", &["ttl"]),
+    ("mermaid", &["mmd"]),
+];
+
+// `path` without the extension of a compressed file.
+fn uncompressed(path: &Path) -> PathBuf {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") | Some("zst") => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn extension(path: &Path) -> String {
+    uncompressed(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+// The output format named `name`, or of the extension of `path`.
+fn output_format(name: Option<&str>, path: &Path) -> Option<&'static str> {
+    let ext = extension(path);
+    FORMATS
+        .iter()
+        .find(|&&(format, exts)| match name {
+            Some(name) => format == name,
+            None => exts.contains(&ext.as_str()),
+        })
+        .map(|&(format, _)| format)
+}
+
+fn read_document(path: &Path) -> Result<GmlDocument, String> {
+    let s = read_gml_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let parsed = match extension(path).as_str() {
+        "dot" | "gv" => dot::parse_dot(&s),
+        "graphml" => graphml::parse_graphml(&s),
+        "jgf" | "json" => jgf::parse_jgf(&s),
+        "net" => pajek::parse_pajek(&s),
+        "tgf" => tgf::parse_tgf(&s),
+        "tlp" => tulip::parse_tulip(&s),
+        "gw" | "lgr" => leda::parse_leda(&s),
+        "dl" => ucinet::parse_ucinet(&s),
+        _ => parse_gml_document(&s),
+    };
+    parsed.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn write_file(doc: &GmlDocument, format: &str, path: &Path) -> Result<(), String> {
+    let write = || -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        write_document(doc, format, &mut w)?;
+        w.flush()
+    };
+    write().map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn write_document<W: Write>(doc: &GmlDocument, format: &str, w: &mut W) -> io::Result<()> {
+    match format {
+        "gml" => write_gml(doc, w),
+        "dot" => dot::write_dot(doc, w),
+        "graphml" => graphml::write_graphml(doc, w),
+        "gexf" => gexf::write_gexf(doc, &gexf::GexfOptions::default(), w),
+        "jgf" => jgf::write_jgf(doc, w),
+        "cytoscape" => cytoscape::write_cytoscape(doc, w),
+        "graphology" => graphology::write_graphology(doc, w),
+        "graphson" => graphson::write_graphson(doc, &graphson::GraphsonOptions::default(), w),
+        "geojson" => geo::write_geojson(doc, &geo::GeoOptions::default(), w),
+        "pajek" => pajek::write_pajek(doc, w),
+        "tgf" => tgf::write_tgf(doc, w),
+        "tulip" => tulip::write_tulip(doc, w),
+        "leda" => leda::write_leda(doc, w),
+        "mtx" => matrix_market::write_matrix_market(doc, Some("weight"), w),
+        "cypher" => cypher::write_cypher(doc, &cypher::CypherOptions::default(), w),
+        "sql" => sql::write_sql(doc, &sql::SqlOptions::default(), w),
+        "turtle" => turtle::write_turtle(doc, &turtle::TurtleOptions::default(), w),
+        "mermaid" => {
+            w.write_all(mermaid::to_mermaid(doc, &mermaid::MermaidOptions::default()).as_bytes())
+        }
+        _ => unreachable!("unknown format {}", format),
+    }
+}
+
+fn is_gml(path: &Path) -> bool {
+    let others = [
+        "dot", "gv", "graphml", "jgf", "json", "net", "tgf", "tlp", "gw", "lgr", "dl",
+    ];
+    !others.contains(&extension(path).as_str())
+}
+
+// Validates the GML file at `path`, printing its diagnostics.
+fn validate_file(path: &Path) -> Result<String, String> {
+    let s = read_gml_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let diagnostics = validate::validate_gml(&s);
+    for diagnostic in &diagnostics {
+        let (line, col) = diagnostic.line_col(&s);
+        eprintln!("{}:{}:{}: {}", path.display(), line, col, diagnostic);
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == validate::Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!("{}: {} errors", path.display(), errors));
+    }
+    let counts = count_gml(&s).unwrap_or_default();
+    Ok(format!("{} nodes, {} edges", counts.nodes, counts.edges))
+}
+
+fn validate(paths: &[String]) -> Result<(), String> {
+    let mut failed = 0;
+    for path in paths {
+        let path = Path::new(path);
+        let summary = if is_gml(path) {
+            validate_file(path)
+        } else {
+            read_document(path)
+                .map(|doc| format!("{} nodes, {} edges", doc.nodes.len(), doc.edges.len()))
+        };
+        match summary {
+            Ok(summary) => println!("{}: ok, {}", path.display(), summary),
+            Err(e) => {
+                eprintln!("{}", e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        Err(format!("{} of {} files are invalid", failed, paths.len()))
+    } else {
+        Ok(())
+    }
+}
+
+fn convert(args: &[String]) -> Result<(), String> {
+    let (mut input, mut output, mut format, mut out_dir, mut threads) =
+        (None, None, None, None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next(),
+            "--to" => format = args.next(),
+            "--out-dir" => out_dir = args.next(),
+            "-j" | "--jobs" => {
+                let n = args.next().and_then(|n| n.parse::<usize>().ok());
+                threads = Some(n.ok_or("-j needs a number of threads")?);
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    let format = format.map(String::as_str);
+    match (input, output, out_dir) {
+        (Some(input), Some(output), None) => {
+            let output = Path::new(output);
+            let format = output_format(format, output)
+                .ok_or_else(|| format!("{}: unknown output format", output.display()))?;
+            let doc = read_document(Path::new(input))?;
+            doc.check().map_err(|e| format!("{}: {}", input, e))?;
+            let target = match format {
+                "dot" => Some(fidelity::Target::Dot),
+                "tgf" => Some(fidelity::Target::Tgf),
+                _ => None,
+            };
+            if let Some(target) = target {
+                for loss in fidelity::fidelity_report(&doc, target).losses {
+                    eprintln!("warning: {}", loss);
+                }
+            }
+            write_file(&doc, format, output)
+        }
+        (Some(input), None, Some(out_dir)) => {
+            let format = format.ok_or("--out-dir needs --to")?;
+            let format = output_format(Some(format), Path::new(""))
+                .ok_or_else(|| format!("{}: unknown output format", format))?;
+            convert_all(
+                Path::new(input),
+                format,
+                Path::new(out_dir),
+                threads.unwrap_or(0),
+            )
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+// Converts the files `pattern` matches on `threads` threads, or as many as
+// are available if it is 0, and prints the failures.
+fn convert_all(pattern: &Path, format: &str, out_dir: &Path, threads: usize) -> Result<(), String> {
+    let paths =
+        batch::find_gml_files(pattern).map_err(|e| format!("{}: {}", pattern.display(), e))?;
+    let base = batch::glob_base(pattern);
+    let extension = FORMATS
+        .iter()
+        .find(|&&(name, _)| name == format)
+        .and_then(|&(_, exts)| exts.first())
+        .copied()
+        .unwrap_or(format);
+    let convert_one = |path: &Path| -> Result<(), String> {
+        let relative = path.strip_prefix(base).unwrap_or(path);
+        let output = out_dir
+            .join(uncompressed(relative))
+            .with_extension(extension);
+        let doc = read_document(path)?;
+        doc.check()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        if let Some(dir) = output.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        write_file(&doc, format, &output)
+    };
+
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let next = AtomicUsize::new(0);
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..threads.min(paths.len()) {
+            scope.spawn(|| {
+                while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(e) = convert_one(path) {
+                        failures.lock().unwrap().push(e);
+                    }
+                }
+            });
+        }
+    });
+
+    let mut failures = failures.into_inner().unwrap();
+    failures.sort();
+    for failure in &failures {
+        eprintln!("{}", failure);
+    }
+    println!(
+        "converted {} of {} files",
+        paths.len() - failures.len(),
+        paths.len()
+    );
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} files failed", failures.len()))
+    }
+}
+
+fn stats(paths: &[String]) -> Result<(), String> {
+    for path in paths {
+        let doc = read_document(Path::new(path))?;
+        let directed = doc.directed;
+        let graph = doc.into_graph().map_err(|e| format!("{}: {}", path, e))?;
+        let summary = if directed {
+            stats::summarize(&graph)
+        } else {
+            stats::summarize(&graph.into_edge_type::<Undirected>())
+        };
+        println!("{}:", path);
+        for line in summary.to_string().lines() {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn diff(args: &[String]) -> Result<bool, String> {
+    let (paths, flags): (Vec<_>, Vec<_>) = args.iter().partition(|arg| !arg.starts_with("--"));
+    let json = match flags.as_slice() {
+        [] => false,
+        [flag] if flag.as_str() == "--json" => true,
+        _ => return Err(USAGE.to_string()),
+    };
+    let (old, new) = match paths.as_slice() {
+        [old, new] => (
+            read_document(Path::new(old))?,
+            read_document(Path::new(new))?,
+        ),
+        _ => return Err(USAGE.to_string()),
+    };
+    let diff = diff::diff(&old, &new);
+    if json {
+        println!("{}", diff.to_json());
+    } else {
+        print!("{}", diff);
+    }
+    Ok(diff.is_empty())
+}
+
+fn grep(args: &[String]) -> Result<(), String> {
+    let (query, paths) = match args.split_first() {
+        Some((query, paths)) if !paths.is_empty() => (query, paths),
+        _ => return Err(USAGE.to_string()),
+    };
+    let query = query::Query::parse(query).map_err(|e| format!("invalid query: {}", e))?;
+    for path in paths {
+        let doc = read_document(Path::new(path))?;
+        for found in query.matches(&doc) {
+            if paths.len() > 1 {
+                print!("{}: ", path);
+            }
+            println!("{}", found);
+        }
+    }
+    Ok(())
+}
+
+fn shell(args: &[String]) -> Result<(), String> {
+    let path = match args {
+        [path] => Path::new(path),
+        _ => return Err(USAGE.to_string()),
+    };
+    let s = read_gml_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let doc =
+        lazy::parse_gml_document_lazy(&s).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let shell = shell::GmlShell::new(doc);
+    let stdin = io::stdin();
+    let mut line = String::new();
+    loop {
+        print!("gml> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        line.clear();
+        if stdin.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            println!();
+            return Ok(());
+        }
+        match line.trim() {
+            "quit" | "exit" => return Ok(()),
+            command => match shell.run(command) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => println!("{}", output),
+                Err(e) => eprintln!("{}", e),
+            },
+        }
+    }
+}
+
+fn canonicalize(args: &[String]) -> Result<(), String> {
+    let (input, output) = match args {
+        [input, flag, output] if flag == "-o" || flag == "--output" => {
+            (Path::new(input), Path::new(output))
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+    let format = output_format(None, output)
+        .ok_or_else(|| format!("{}: unknown output format", output.display()))?;
+    let mut doc = read_document(input)?;
+    canonical::canonicalize(&mut doc);
+    write_file(&doc, format, output)
+}
+
+fn anonymize(args: &[String]) -> Result<(), String> {
+    let (mut input, mut output) = (None, None);
+    let mut options = anonymize::AnonymizeOptions::default();
+    let mut keys = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next(),
+            "--key" => keys.push(args.next().ok_or("--key needs a key")?.clone()),
+            "--hash" => options.treatment = anonymize::Treatment::Hash,
+            "--salt" => options.salt = args.next().ok_or("--salt needs a salt")?.clone(),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if !keys.is_empty() {
+        options.keys = keys;
+    }
+    let (input, output) = match (input, output) {
+        (Some(input), Some(output)) => (Path::new(input), Path::new(output)),
+        _ => return Err(USAGE.to_string()),
+    };
+    let format = output_format(None, output)
+        .ok_or_else(|| format!("{}: unknown output format", output.display()))?;
+    let mut doc = read_document(input)?;
+    anonymize::anonymize(&mut doc, &options);
+    write_file(&doc, format, output)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, rest)) if !rest.is_empty() => match command.as_str() {
+            "validate" => validate(rest),
+            "convert" => convert(rest),
+            "stats" => stats(rest),
+            "grep" => grep(rest),
+            "shell" => shell(rest),
+            "canonicalize" => canonicalize(rest),
+            "anonymize" => anonymize(rest),
+            "diff" => match diff(rest) {
+                Ok(true) => Ok(()),
+                Ok(false) => process::exit(1),
+                Err(e) => Err(e),
+            },
+            _ => Err(USAGE.to_string()),
+        },
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_output_format() {
+    let format = |name, path| output_format(name, Path::new(path));
+    assert_eq!(Some("dot"), format(None, "out.gv"));
+    assert_eq!(Some("gml"), format(None, "out.GML.gz"));
+    assert_eq!(Some("jgf"), format(None, "out.json"));
+    assert_eq!(Some("graphson"), format(Some("graphson"), "out.json"));
+    assert_eq!(None, format(None, "out.txt"));
+    assert_eq!(None, format(Some("xml"), "out.graphml"));
+    assert_eq!(Path::new("a/b.gml"), uncompressed(Path::new("a/b.gml.zst")));
+    assert_eq!(Path::new("a/b.gml"), uncompressed(Path::new("a/b.gml")));
+}
+
+#[test]
+fn test_convert_all() {
+    let dir = std::env::temp_dir().join(format!("gml-convert-{}", process::id()));
+    let (input, output) = (dir.join("in"), dir.join("out"));
+    fs::create_dir_all(input.join("sub")).unwrap();
+    let gml = "graph [ node [ id 1 ] node [ id 2 ] edge [ source 1 target 2 ] ]";
+    fs::write(input.join("sub/good.gml"), gml).unwrap();
+    let dangling = "graph [ node [ id 1 ] edge [ source 1 target 2 ] ]";
+    fs::write(input.join("dangling.gml"), dangling).unwrap();
+    let duplicate = "graph [ node [ id 1 ] node [ id 1 ] ]";
+    fs::write(input.join("duplicate.gml"), duplicate).unwrap();
+
+    let result = convert_all(&input.join("**/*.gml"), "dot", &output, 2);
+    assert_eq!(Err("2 files failed".to_string()), result);
+    assert!(output.join("sub/good.dot").exists());
+    assert!(!output.join("dangling.dot").exists());
+    assert!(!output.join("duplicate.dot").exists());
+
+    let out = output.join("single.dot");
+    let args = |name: &str| {
+        let input = input.join(name).display().to_string();
+        vec![input, "-o".to_string(), out.display().to_string()]
+    };
+    assert!(convert(&args("dangling.gml")).is_err());
+    assert!(!out.exists());
+    convert(&args("sub/good.gml")).unwrap();
+    assert!(out.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
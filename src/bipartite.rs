@@ -0,0 +1,142 @@
+//! Bipartite graphs, with the part of each node given by an attribute.
+
+use crate::document::{parse_gml_document, GmlDocument, GmlValue};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Options for `bipartition`.
+#[derive(Debug, Clone)]
+pub struct BipartiteOptions {
+    /// The node attribute with the part, `type` by default.
+    pub key: String,
+}
+
+impl Default for BipartiteOptions {
+    fn default() -> BipartiteOptions {
+        BipartiteOptions {
+            key: "type".to_string(),
+        }
+    }
+}
+
+/// The two parts of a bipartite graph, as node ids in document order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bipartition {
+    /// The partition values of the two parts, in their order if both are
+    /// numbers or both strings, otherwise in the order of their first
+    /// nodes.
+    pub values: [GmlValue; 2],
+    pub first: Vec<u64>,
+    pub second: Vec<u64>,
+}
+
+fn compare(a: &GmlValue, b: &GmlValue) -> Option<Ordering> {
+    match (a.get_float(), b.get_float()) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => Some(a.get_str()?.cmp(b.get_str()?)),
+    }
+}
+
+/// Splits the nodes of `doc` by the attribute `options.key`, which must
+/// have two different values, and checks that every edge joins the two
+/// parts.
+pub fn bipartition(
+    doc: &GmlDocument,
+    options: &BipartiteOptions,
+) -> Result<Bipartition, &'static str> {
+    let mut values: Vec<&GmlValue> = Vec::with_capacity(2);
+    let mut parts = HashMap::with_capacity(doc.nodes.len());
+    for node in &doc.nodes {
+        let value = node
+            .attrs
+            .get(&options.key)
+            .filter(|v| v.get_list().is_none())
+            .ok_or("node without partition value")?;
+        let part = match values.iter().position(|&v| v == value) {
+            Some(part) => part,
+            None if values.len() < 2 => {
+                values.push(value);
+                values.len() - 1
+            }
+            None => return Err("more than two partition values"),
+        };
+        parts.insert(node.id, part);
+    }
+    if values.len() < 2 {
+        return Err("fewer than two partition values");
+    }
+    for edge in &doc.edges {
+        match (parts.get(&edge.source), parts.get(&edge.target)) {
+            (Some(a), Some(b)) if a == b => return Err("edge within a part"),
+            (Some(_), Some(_)) => {}
+            _ => return Err("edge references unknown node"),
+        }
+    }
+    let swap = compare(values[0], values[1]) == Some(Ordering::Greater);
+    let mut partition = Bipartition {
+        values: [values[0].clone(), values[1].clone()],
+        first: Vec::new(),
+        second: Vec::new(),
+    };
+    if swap {
+        partition.values.swap(0, 1);
+    }
+    for node in &doc.nodes {
+        if (parts[&node.id] == 1) != swap {
+            partition.second.push(node.id);
+        } else {
+            partition.first.push(node.id);
+        }
+    }
+    Ok(partition)
+}
+
+/// Parses a GML string into a document and its bipartition.
+pub fn parse_gml_bipartite(
+    s: &str,
+    options: &BipartiteOptions,
+) -> Result<(GmlDocument, Bipartition), &'static str> {
+    let doc = parse_gml_document(s)?;
+    let partition = bipartition(&doc, options)?;
+    Ok((doc, partition))
+}
+
+#[test]
+fn test_bipartition() {
+    let gml = "graph [ node [ id 1 type 1 ] node [ id 2 type 0 ] node [ id 3 type 1 ]
+                       node [ id 4 type 0 ] edge [ source 1 target 2 ] edge [ source 4 target 3 ] ]";
+    let options = BipartiteOptions::default();
+    let (_, partition) = parse_gml_bipartite(gml, &options).unwrap();
+    assert_eq!([GmlValue::Int(0), GmlValue::Int(1)], partition.values);
+    assert_eq!(
+        (vec![2, 4], vec![1, 3]),
+        (partition.first, partition.second)
+    );
+
+    let gml = "graph [ node [ id 1 kind \"user\" ] node [ id 2 kind \"item\" ]
+                       edge [ source 1 target 2 ] ]";
+    let options = BipartiteOptions {
+        key: "kind".to_string(),
+    };
+    let (_, partition) = parse_gml_bipartite(gml, &options).unwrap();
+    assert_eq!(GmlValue::from("item"), partition.values[0]);
+    assert_eq!((vec![2], vec![1]), (partition.first, partition.second));
+
+    let check = |gml: &str| parse_gml_bipartite(gml, &options).map(|_| ());
+    assert_eq!(
+        Err("edge within a part"),
+        check("graph [ node [ id 1 kind 0 ] node [ id 2 kind 0 ] node [ id 3 kind 1 ] edge [ source 1 target 2 ] ]")
+    );
+    assert_eq!(
+        Err("more than two partition values"),
+        check("graph [ node [ id 1 kind 0 ] node [ id 2 kind 1 ] node [ id 3 kind 2 ] ]")
+    );
+    assert_eq!(
+        Err("node without partition value"),
+        check("graph [ node [ id 1 kind 0 ] node [ id 2 ] ]")
+    );
+    assert_eq!(
+        Err("fewer than two partition values"),
+        check("graph [ node [ id 1 kind 0 ] ]")
+    );
+}
@@ -0,0 +1,139 @@
+//! Writing boolean attributes in the convention of a tool.
+//!
+//! GML has no booleans, and tools write them as the integers 0 and 1 or as
+//! strings like `"true"`. `GmlValue::get_bool` reads all of them; the
+//! functions here rewrite them as one `BoolStyle`.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use crate::writer::write_gml;
+use std::io::{self, Write};
+
+/// How booleans are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoolStyle {
+    /// `1` and `0`, as `directed` is.
+    Int,
+    /// `"true"` and `"false"`.
+    Lower,
+    /// `"True"` and `"False"`, as written by Python tools.
+    Capitalized,
+    /// `"yes"` and `"no"`.
+    YesNo,
+}
+
+impl BoolStyle {
+    /// The value of `b` in this style.
+    pub fn value(self, b: bool) -> GmlValue {
+        let word = match (self, b) {
+            (BoolStyle::Int, b) => return GmlValue::Int(b as i64),
+            (BoolStyle::Lower, true) => "true",
+            (BoolStyle::Lower, false) => "false",
+            (BoolStyle::Capitalized, true) => "True",
+            (BoolStyle::Capitalized, false) => "False",
+            (BoolStyle::YesNo, true) => "yes",
+            (BoolStyle::YesNo, false) => "no",
+        };
+        GmlValue::from(word)
+    }
+}
+
+/// Options for `restyle_bools`.
+#[derive(Debug, Clone)]
+pub struct BoolOptions {
+    /// The keys of the boolean node and edge attributes, dotted for nested
+    /// ones.
+    pub keys: Vec<String>,
+    pub style: BoolStyle,
+}
+
+impl Default for BoolOptions {
+    fn default() -> BoolOptions {
+        BoolOptions {
+            keys: Vec::new(),
+            style: BoolStyle::Int,
+        }
+    }
+}
+
+fn restyle(attrs: &mut GmlAttrs, key: &str, style: BoolStyle) {
+    if let Some((outer, rest)) = key.split_once('.') {
+        if let Some(GmlValue::List(inner)) = attrs.get_mut(outer) {
+            restyle(inner, rest, style);
+        }
+        return;
+    }
+    if let Some(value) = attrs.get_mut(key) {
+        if let Some(b) = value.get_bool() {
+            *value = style.value(b);
+        }
+    }
+}
+
+/// Rewrites the booleans at the keys of `options` in the node and edge
+/// attributes of `doc` in its style. Values which are not booleans are
+/// kept.
+pub fn restyle_bools(doc: &mut GmlDocument, options: &BoolOptions) {
+    let nodes = doc.nodes.iter_mut().map(|node| &mut node.attrs);
+    let edges = doc.edges.iter_mut().map(|edge| &mut edge.attrs);
+    for attrs in nodes.chain(edges) {
+        for key in &options.keys {
+            restyle(attrs, key, options.style);
+        }
+    }
+}
+
+/// Writes `doc` as GML with the booleans restyled by `options`.
+pub fn write_gml_bools<W: Write>(
+    doc: &GmlDocument,
+    options: &BoolOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut doc = doc.clone();
+    restyle_bools(&mut doc, options);
+    write_gml(&doc, w)
+}
+
+#[test]
+fn test_bools() {
+    let gml = "graph [ node [ id 1 visible \"True\" graphics [ hidden 0 ] ]
+                       node [ id 2 visible \"maybe\" ] edge [ source 1 target 2 visible 1 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let visible = |attrs: &GmlAttrs| attrs.get("visible").and_then(GmlValue::get_bool);
+    assert_eq!(Some(true), visible(&doc.nodes[0].attrs));
+    assert_eq!(None, visible(&doc.nodes[1].attrs));
+    assert_eq!(Some(true), visible(&doc.edges[0].attrs));
+    assert_eq!(Some(false), GmlValue::from("NO").get_bool());
+    assert_eq!(None, GmlValue::Int(2).get_bool());
+
+    let options = BoolOptions {
+        keys: vec!["visible".to_string(), "graphics.hidden".to_string()],
+        style: BoolStyle::Lower,
+    };
+    let mut buf = Vec::new();
+    write_gml_bools(&doc, &options, &mut buf).unwrap();
+    let written = crate::parse_gml_document(std::str::from_utf8(&buf).unwrap()).unwrap();
+    let node = &written.nodes[0].attrs;
+    assert_eq!(Some(&GmlValue::from("true")), node.get("visible"));
+    assert_eq!(
+        Some(&GmlValue::from("false")),
+        node["graphics"].get("hidden")
+    );
+    assert_eq!(
+        Some(&GmlValue::from("maybe")),
+        written.nodes[1].attrs.get("visible")
+    );
+    assert_eq!(
+        Some(&GmlValue::from("true")),
+        written.edges[0].attrs.get("visible")
+    );
+
+    let mut doc = written;
+    restyle_bools(
+        &mut doc,
+        &BoolOptions {
+            style: BoolStyle::Int,
+            ..options
+        },
+    );
+    assert_eq!(Some(&GmlValue::Int(1)), doc.nodes[0].attrs.get("visible"));
+}
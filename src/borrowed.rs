@@ -0,0 +1,292 @@
+//! Borrowed GML documents.
+//!
+//! `parse_gml_document_borrowed` keeps keys and strings as slices of the
+//! input where possible. Only strings that contain character entities are
+//! copied, so loading a file allocates little more than the maps
+//! themselves.
+
+use crate::document::{remove_attr, AttrMap, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::events::{document_parts, GmlScalar, ListBuilder};
+use std::borrow::Cow;
+use std::sync::Mutex;
+
+/// Attributes of a GML list, borrowed from the input.
+pub type GmlAttrsRef<'a> = AttrMap<&'a str, GmlValueRef<'a>>;
+
+/// A GML value borrowed from the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GmlValueRef<'a> {
+    Int(i64),
+    /// An integer beyond `i64::MAX`, as in `GmlValue`.
+    UInt(u64),
+    Float(f64),
+    Str(Cow<'a, str>),
+    List(GmlAttrsRef<'a>),
+}
+
+impl<'a> GmlValueRef<'a> {
+    pub fn get_int(&self) -> Option<i64> {
+        match *self {
+            GmlValueRef::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as `u64`, if it is a non-negative integer.
+    pub fn get_uint(&self) -> Option<u64> {
+        match *self {
+            GmlValueRef::Int(i) if i >= 0 => Some(i as u64),
+            GmlValueRef::UInt(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as `f64`. Integers are converted.
+    pub fn get_float(&self) -> Option<f64> {
+        match *self {
+            GmlValueRef::Int(i) => Some(i as f64),
+            GmlValueRef::UInt(u) => Some(u as f64),
+            GmlValueRef::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self) -> Option<&str> {
+        match *self {
+            GmlValueRef::Str(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn get_list(&self) -> Option<&GmlAttrsRef<'a>> {
+        match *self {
+            GmlValueRef::List(ref l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is a list.
+    pub fn get(&self, key: &str) -> Option<&GmlValueRef<'a>> {
+        self.get_list().and_then(|l| l.get(key))
+    }
+
+    /// The values of a key which may be repeated, as by `GmlValue::values`.
+    pub fn values(&self) -> Vec<&GmlValueRef<'a>> {
+        repeated(self).unwrap_or_else(|| vec![self])
+    }
+
+    pub fn into_owned(self) -> GmlValue {
+        match self {
+            GmlValueRef::Int(i) => GmlValue::Int(i),
+            GmlValueRef::UInt(u) => GmlValue::UInt(u),
+            GmlValueRef::Float(f) => GmlValue::Float(f),
+            GmlValueRef::Str(s) => GmlValue::from(s.into_owned()),
+            GmlValueRef::List(l) => GmlValue::List(attrs_into_owned(l)),
+        }
+    }
+}
+
+fn attrs_into_owned(attrs: GmlAttrsRef) -> GmlAttrs {
+    attrs
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.into_owned()))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GmlNodeRef<'a> {
+    pub id: u64,
+    /// All attributes except `id`.
+    pub attrs: GmlAttrsRef<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GmlEdgeRef<'a> {
+    pub source: u64,
+    pub target: u64,
+    /// All attributes except `source` and `target`.
+    pub attrs: GmlAttrsRef<'a>,
+}
+
+/// A `GmlDocument` whose keys and strings borrow from the input.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GmlDocumentRef<'a> {
+    pub directed: bool,
+    /// Graph-level attributes except `directed`, `node` and `edge`.
+    pub attrs: GmlAttrsRef<'a>,
+    /// Top-level attributes outside the `graph` list.
+    pub top_attrs: GmlAttrsRef<'a>,
+    pub nodes: Vec<GmlNodeRef<'a>>,
+    pub edges: Vec<GmlEdgeRef<'a>>,
+}
+
+impl<'a> GmlNodeRef<'a> {
+    pub fn label(&self) -> Option<&str> {
+        self.attrs.get("label").and_then(GmlValueRef::get_str)
+    }
+}
+
+impl<'a> GmlEdgeRef<'a> {
+    pub fn label(&self) -> Option<&str> {
+        self.attrs.get("label").and_then(GmlValueRef::get_str)
+    }
+}
+
+impl<'a> GmlDocumentRef<'a> {
+    /// Copies all borrowed keys and strings into a `GmlDocument`.
+    pub fn into_owned(self) -> GmlDocument {
+        GmlDocument {
+            directed: self.directed,
+            attrs: attrs_into_owned(self.attrs),
+            top_attrs: attrs_into_owned(self.top_attrs),
+            nodes: self
+                .nodes
+                .into_iter()
+                .map(|n| GmlNode {
+                    id: n.id,
+                    attrs: attrs_into_owned(n.attrs),
+                })
+                .collect(),
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| GmlEdge {
+                    source: e.source,
+                    target: e.target,
+                    attrs: attrs_into_owned(e.attrs),
+                })
+                .collect(),
+        }
+    }
+}
+
+// The keys `0`, `1` and so on of the values of a repeated key, as in
+// `GmlValue`. They are not in the input, so each is allocated once and kept
+// for the rest of the program.
+fn index_key(i: usize) -> &'static str {
+    static KEYS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+    let mut keys = KEYS.lock().unwrap();
+    while keys.len() <= i {
+        let key = keys.len().to_string();
+        keys.push(Box::leak(key.into_boxed_str()));
+    }
+    keys[i]
+}
+
+// The values of a repeated key, in order, if `value` is the list of them.
+fn repeated<'v, 'a>(value: &'v GmlValueRef<'a>) -> Option<Vec<&'v GmlValueRef<'a>>> {
+    let list = value.get_list().filter(|l| l.len() > 1)?;
+    let mut values = vec![None; list.len()];
+    for (key, value) in list {
+        let i: usize = key.parse().ok().filter(|&i| i < list.len())?;
+        values[i] = Some(value);
+    }
+    values.into_iter().collect()
+}
+
+struct BorrowedLists;
+
+impl<'a> ListBuilder<'a> for BorrowedLists {
+    type Value = GmlValueRef<'a>;
+    type List = GmlAttrsRef<'a>;
+
+    fn scalar(&mut self, scalar: GmlScalar<'a>) -> GmlValueRef<'a> {
+        match scalar {
+            GmlScalar::Int(i) => GmlValueRef::Int(i),
+            GmlScalar::UInt(u) => GmlValueRef::UInt(u),
+            GmlScalar::Float(f) => GmlValueRef::Float(f),
+            GmlScalar::Str(s) => GmlValueRef::Str(s),
+        }
+    }
+
+    fn list(&mut self, list: GmlAttrsRef<'a>) -> GmlValueRef<'a> {
+        GmlValueRef::List(list)
+    }
+
+    fn insert(&mut self, list: &mut GmlAttrsRef<'a>, key: &'a str, value: GmlValueRef<'a>) {
+        match list.get_mut(key) {
+            None => {
+                list.insert(key, value);
+            }
+            Some(present) => {
+                if repeated(present).is_none() {
+                    let first = std::mem::replace(present, GmlValueRef::List(GmlAttrsRef::new()));
+                    let mut values = GmlAttrsRef::new();
+                    values.insert(index_key(0), first);
+                    values.insert(index_key(1), value);
+                    *present = GmlValueRef::List(values);
+                } else if let GmlValueRef::List(ref mut values) = *present {
+                    values.insert(index_key(values.len()), value);
+                }
+            }
+        }
+    }
+
+    fn take_id(
+        &mut self,
+        list: &mut GmlAttrsRef<'a>,
+        key: &str,
+        error: &'static str,
+    ) -> Result<u64, &'static str> {
+        match remove_attr(list, key) {
+            Some(ref value) if repeated(value).is_some() => Err("duplicate key"),
+            Some(ref value) => value.get_uint().ok_or(error),
+            None => Err(error),
+        }
+    }
+}
+
+/// Parses a GML string like `parse_gml_document`, borrowing keys and strings
+/// from `s`.
+pub fn parse_gml_document_borrowed(s: &str) -> Result<GmlDocumentRef<'_>, &'static str> {
+    let parts = document_parts(s, &mut BorrowedLists)?;
+    Ok(GmlDocumentRef {
+        directed: parts.directed.unwrap_or(false),
+        attrs: parts.attrs,
+        top_attrs: parts.top_attrs,
+        nodes: parts
+            .nodes
+            .into_iter()
+            .map(|(id, attrs)| GmlNodeRef { id, attrs })
+            .collect(),
+        edges: parts
+            .edges
+            .into_iter()
+            .map(|(source, target, attrs)| GmlEdgeRef {
+                source,
+                target,
+                attrs,
+            })
+            .collect(),
+    })
+}
+
+#[test]
+fn test_parse_gml_document_borrowed() {
+    let gml = "graph [ directed 1 label \"test\" \
+               node [ id 1 label \"a\" graphics [ fill \"#FF0000\" ] ] \
+               node [ id 2 label \"b &amp; c\" ] edge [ source 1 target 2 weight 1.5 ] ]";
+    let doc = parse_gml_document_borrowed(gml).unwrap();
+    assert!(doc.directed);
+    assert_eq!(2, doc.nodes.len());
+    #[cfg(not(feature = "legacy-lexer"))]
+    assert!(matches!(
+        doc.nodes[0].attrs["label"],
+        GmlValueRef::Str(Cow::Borrowed("a"))
+    ));
+    assert!(matches!(
+        doc.nodes[1].attrs["label"],
+        GmlValueRef::Str(Cow::Owned(_))
+    ));
+    assert_eq!(Some("b & c"), doc.nodes[1].label());
+    assert_eq!(
+        Some("#FF0000"),
+        doc.nodes[0].attrs["graphics"]
+            .get("fill")
+            .and_then(GmlValueRef::get_str)
+    );
+    assert_eq!(
+        crate::parse_gml_document(gml).unwrap(),
+        doc.clone().into_owned()
+    );
+}
@@ -0,0 +1,125 @@
+//! A canonical form of GML documents, so that equal graphs are written as
+//! equal text.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use std::cmp::Ordering;
+
+fn rank(value: &GmlValue) -> u8 {
+    match *value {
+        GmlValue::Int(_) | GmlValue::UInt(_) => 0,
+        GmlValue::Float(_) => 1,
+        GmlValue::Str(_) => 2,
+        GmlValue::List(_) => 3,
+    }
+}
+
+fn cmp_value(a: &GmlValue, b: &GmlValue) -> Ordering {
+    match (a, b) {
+        (GmlValue::Int(a), GmlValue::Int(b)) => a.cmp(b),
+        (GmlValue::UInt(a), GmlValue::UInt(b)) => a.cmp(b),
+        // a `UInt` is beyond every `Int`
+        (GmlValue::Int(_), GmlValue::UInt(_)) => Ordering::Less,
+        (GmlValue::UInt(_), GmlValue::Int(_)) => Ordering::Greater,
+        (GmlValue::Float(a), GmlValue::Float(b)) => a.total_cmp(b),
+        (GmlValue::Str(a), GmlValue::Str(b)) => (**a).cmp(&**b),
+        (GmlValue::List(a), GmlValue::List(b)) => cmp_attrs(a, b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+// Compares lists with sorted keys, key by key.
+fn cmp_attrs(a: &GmlAttrs, b: &GmlAttrs) -> Ordering {
+    let mut b = b.iter();
+    for (ka, va) in a {
+        let (kb, vb) = match b.next() {
+            Some(entry) => entry,
+            None => return Ordering::Greater,
+        };
+        let order = ka.cmp(kb).then_with(|| cmp_value(va, vb));
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    if b.next().is_some() {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+// A no-op for a `BTreeMap`, which is always sorted.
+#[cfg(not(feature = "preserve_order"))]
+fn sort_keys(_: &mut GmlAttrs) {}
+
+#[cfg(feature = "preserve_order")]
+fn sort_keys(attrs: &mut GmlAttrs) {
+    attrs.sort_keys();
+    for value in attrs.values_mut() {
+        if let GmlValue::List(ref mut inner) = *value {
+            sort_keys(inner);
+        }
+    }
+}
+
+/// Brings `doc` into its canonical form: attributes are sorted by key, at
+/// every level, nodes by id and edges by source and target, and then both
+/// by their attributes. In an undirected document, every edge is turned so
+/// that its source is not greater than its target.
+///
+/// Documents with the same graph and attributes, in whatever order, have
+/// the same canonical form and thus the same `to_gml` text.
+pub fn canonicalize(doc: &mut GmlDocument) {
+    sort_keys(&mut doc.attrs);
+    for node in &mut doc.nodes {
+        sort_keys(&mut node.attrs);
+    }
+    for edge in &mut doc.edges {
+        sort_keys(&mut edge.attrs);
+        if !doc.directed && edge.source > edge.target {
+            std::mem::swap(&mut edge.source, &mut edge.target);
+        }
+    }
+    doc.nodes
+        .sort_by(|a, b| a.id.cmp(&b.id).then_with(|| cmp_attrs(&a.attrs, &b.attrs)));
+    doc.edges.sort_by(|a, b| {
+        (a.source, a.target)
+            .cmp(&(b.source, b.target))
+            .then_with(|| cmp_attrs(&a.attrs, &b.attrs))
+    });
+}
+
+#[test]
+fn test_canonicalize() {
+    use crate::document::parse_gml_document;
+    use crate::writer::to_gml;
+
+    let a = "graph [ version 1 label \"g\"
+             node [ id 2 label \"b\" graphics [ y 2 x 1 ] ] node [ id 1 ]
+             edge [ source 2 target 1 weight 2 ] edge [ source 1 target 2 weight 1 ]
+             edge [ source 1 target 1 ] ]";
+    let b = "graph [ label \"g\" version 1
+             node [ id 1 ] node [ label \"b\" id 2 graphics [ x 1 y 2 ] ]
+             edge [ source 1 target 1 ] edge [ weight 1 source 2 target 1 ]
+             edge [ target 2 source 1 weight 2 ] ]";
+    let mut a = parse_gml_document(a).unwrap();
+    let mut b = parse_gml_document(b).unwrap();
+    canonicalize(&mut a);
+    canonicalize(&mut b);
+    assert_eq!(to_gml(&a), to_gml(&b));
+    let ids: Vec<_> = a.nodes.iter().map(|node| node.id).collect();
+    assert_eq!(vec![1, 2], ids);
+    let edges: Vec<_> = a
+        .edges
+        .iter()
+        .map(|edge| (edge.source, edge.target))
+        .collect();
+    assert_eq!(vec![(1, 1), (1, 2), (1, 2)], edges);
+    let keys: Vec<_> = a.attrs.keys().collect();
+    assert_eq!(vec!["label", "version"], keys);
+
+    a.directed = true;
+    a.edges[1].source = 2;
+    a.edges[1].target = 1;
+    canonicalize(&mut a);
+    assert_eq!((2, 1), (a.edges[2].source, a.edges[2].target));
+}
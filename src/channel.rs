@@ -0,0 +1,183 @@
+//! Parsing GML on a separate thread.
+//!
+//! `spawn_gml_events` reads the events of a GML string on a new thread and
+//! sends them over a bounded channel, so that the consumer, like an index
+//! or a database writer, runs while the input is still being parsed.
+
+use crate::events::{gml_events, GmlEvent, GmlScalar};
+use std::borrow::Cow;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread::{self, JoinHandle};
+use std::vec;
+
+/// A `GmlEvent` which owns its key and value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OwnedGmlEvent {
+    /// `key [`
+    Begin(String),
+    /// `]`
+    End,
+    /// `key value`
+    Value(String, GmlScalar<'static>),
+}
+
+impl OwnedGmlEvent {
+    /// The event borrowing from this one.
+    pub fn as_event(&self) -> GmlEvent<'_> {
+        match *self {
+            OwnedGmlEvent::Begin(ref key) => GmlEvent::Begin(key),
+            OwnedGmlEvent::End => GmlEvent::End,
+            OwnedGmlEvent::Value(ref key, ref value) => GmlEvent::Value(
+                key,
+                match *value {
+                    GmlScalar::Int(i) => GmlScalar::Int(i),
+                    GmlScalar::UInt(u) => GmlScalar::UInt(u),
+                    GmlScalar::Float(f) => GmlScalar::Float(f),
+                    GmlScalar::Str(ref s) => GmlScalar::Str(Cow::Borrowed(s)),
+                },
+            ),
+        }
+    }
+}
+
+impl<'a> From<GmlEvent<'a>> for OwnedGmlEvent {
+    fn from(event: GmlEvent<'a>) -> OwnedGmlEvent {
+        match event {
+            GmlEvent::Begin(key) => OwnedGmlEvent::Begin(key.to_string()),
+            GmlEvent::End => OwnedGmlEvent::End,
+            GmlEvent::Value(key, value) => OwnedGmlEvent::Value(
+                key.to_string(),
+                match value {
+                    GmlScalar::Int(i) => GmlScalar::Int(i),
+                    GmlScalar::UInt(u) => GmlScalar::UInt(u),
+                    GmlScalar::Float(f) => GmlScalar::Float(f),
+                    GmlScalar::Str(s) => GmlScalar::Str(Cow::Owned(s.into_owned())),
+                },
+            ),
+        }
+    }
+}
+
+/// Options for `spawn_gml_events`.
+#[derive(Debug, Clone)]
+pub struct ChannelOptions {
+    /// The number of events sent together, to keep the cost of the channel
+    /// low.
+    pub batch_size: usize,
+    /// The number of batches the parser may be ahead of the consumer before
+    /// it waits.
+    pub capacity: usize,
+}
+
+impl Default for ChannelOptions {
+    fn default() -> ChannelOptions {
+        ChannelOptions {
+            batch_size: 1024,
+            capacity: 16,
+        }
+    }
+}
+
+type Batch = Vec<Result<OwnedGmlEvent, &'static str>>;
+
+/// The events of a GML string parsed on another thread.
+///
+/// The events are those of `gml_events`, with an error ending them. The
+/// parser stops when this iterator is dropped.
+pub struct EventReceiver {
+    receiver: Receiver<Batch>,
+    batch: vec::IntoIter<Result<OwnedGmlEvent, &'static str>>,
+    parser: Option<JoinHandle<()>>,
+}
+
+impl Iterator for EventReceiver {
+    type Item = Result<OwnedGmlEvent, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.batch.next() {
+                return Some(event);
+            }
+            match self.receiver.recv() {
+                Ok(batch) => self.batch = batch.into_iter(),
+                Err(_) => {
+                    // the parser is done, or it panicked
+                    if let Some(parser) = self.parser.take() {
+                        if let Err(panic) = parser.join() {
+                            std::panic::resume_unwind(panic);
+                        }
+                    }
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Parses `s` on a new thread, sending its events to the returned receiver.
+pub fn spawn_gml_events(s: String, options: &ChannelOptions) -> EventReceiver {
+    let batch_size = options.batch_size.max(1);
+    let (sender, receiver) = sync_channel(options.capacity);
+    let parser = thread::spawn(move || {
+        let mut batch = Vec::with_capacity(batch_size);
+        for event in gml_events(&s) {
+            batch.push(event.map(OwnedGmlEvent::from));
+            if batch.len() == batch_size {
+                let full = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                if sender.send(full).is_err() {
+                    // the receiver was dropped
+                    return;
+                }
+            }
+        }
+        if !batch.is_empty() {
+            let _ = sender.send(batch);
+        }
+    });
+    EventReceiver {
+        receiver,
+        batch: Vec::new().into_iter(),
+        parser: Some(parser),
+    }
+}
+
+#[test]
+fn test_spawn_gml_events() {
+    let mut gml = String::from("Creator \"me\" graph [\n");
+    for i in 0..3000 {
+        gml.push_str(&format!("node [ id {} label \"n{}\" x {}.5 ]\n", i, i, i));
+    }
+    gml.push(']');
+    let options = ChannelOptions {
+        batch_size: 100,
+        capacity: 2,
+    };
+    let received: Vec<_> = spawn_gml_events(gml.clone(), &options)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let expected: Vec<_> = gml_events(&gml).collect::<Result<_, _>>().unwrap();
+    assert_eq!(expected.len(), received.len());
+    for (event, owned) in expected.iter().zip(&received) {
+        assert_eq!(*event, owned.as_event());
+    }
+
+    let errors: Vec<_> = spawn_gml_events("graph [ id ]".to_string(), &options).collect();
+    assert_eq!(
+        vec![
+            Ok(OwnedGmlEvent::Begin("graph".to_string())),
+            Err("expected value")
+        ],
+        errors
+    );
+
+    // the parser stops when the receiver is dropped
+    let mut events = spawn_gml_events(gml, &options);
+    assert_eq!(
+        Some(Ok(OwnedGmlEvent::Value(
+            "Creator".to_string(),
+            GmlScalar::Str(Cow::Borrowed("me"))
+        ))),
+        events.next()
+    );
+    drop(events);
+}
@@ -0,0 +1,162 @@
+//! Groups of nodes, like communities, given by an attribute like
+//! `cluster`.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use std::collections::HashMap;
+
+/// The nodes with the same value of the grouping key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub value: GmlValue,
+    /// The node ids, in document order.
+    pub nodes: Vec<u64>,
+}
+
+fn group_value<'a>(node: &'a GmlNode, key: &str) -> Option<&'a GmlValue> {
+    node.attrs.get(key).filter(|v| v.get_list().is_none())
+}
+
+/// The groups of the nodes of `doc` by `key`, in the order of their first
+/// nodes. Nodes without a value of `key` are in no group.
+pub fn groups(doc: &GmlDocument, key: &str) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    for node in &doc.nodes {
+        if let Some(value) = group_value(node, key) {
+            match groups.iter_mut().find(|g| g.value == *value) {
+                Some(group) => group.nodes.push(node.id),
+                None => groups.push(Group {
+                    value: value.clone(),
+                    nodes: vec![node.id],
+                }),
+            }
+        }
+    }
+    groups
+}
+
+/// The graph of the groups of `doc` by `key`, in which the node with id
+/// `i` is the `i`th group, with its value under `key` and its number of
+/// nodes as `size`. There is an edge between two groups if there are edges
+/// between their nodes, with their number as `weight`; edges within a
+/// group are left out. Every node must be in a group.
+pub fn quotient(doc: &GmlDocument, key: &str) -> Result<GmlDocument, &'static str> {
+    let groups = groups(doc, key);
+    let mut group_of = HashMap::with_capacity(doc.nodes.len());
+    for (i, group) in groups.iter().enumerate() {
+        for &id in &group.nodes {
+            group_of.insert(id, i);
+        }
+    }
+    if group_of.len() < doc.nodes.len() {
+        return Err("node without group");
+    }
+    let nodes = groups
+        .into_iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let mut attrs = GmlAttrs::new();
+            attrs.insert(key.to_string(), group.value);
+            attrs.insert("size".to_string(), GmlValue::Int(group.nodes.len() as i64));
+            GmlNode {
+                id: i as u64,
+                attrs,
+            }
+        })
+        .collect();
+    let mut edges: Vec<(usize, usize, i64)> = Vec::new();
+    let mut index = HashMap::new();
+    for edge in &doc.edges {
+        let (a, b) = match (group_of.get(&edge.source), group_of.get(&edge.target)) {
+            (Some(&a), Some(&b)) => (a, b),
+            _ => return Err("edge references unknown node"),
+        };
+        if a == b {
+            continue;
+        }
+        let pair = if doc.directed {
+            (a, b)
+        } else {
+            (a.min(b), a.max(b))
+        };
+        let i = *index.entry(pair).or_insert_with(|| {
+            edges.push((a, b, 0));
+            edges.len() - 1
+        });
+        edges[i].2 += 1;
+    }
+    Ok(GmlDocument {
+        directed: doc.directed,
+        attrs: GmlAttrs::new(),
+        top_attrs: GmlAttrs::new(),
+        nodes,
+        edges: edges
+            .into_iter()
+            .map(|(a, b, count)| {
+                let mut attrs = GmlAttrs::new();
+                attrs.insert("weight".to_string(), GmlValue::Int(count));
+                GmlEdge {
+                    source: a as u64,
+                    target: b as u64,
+                    attrs,
+                }
+            })
+            .collect(),
+    })
+}
+
+/// Sets `key` of the nodes of `groups` to the value of their group, as
+/// after community detection, so that writing `doc` keeps the grouping.
+/// The key of the other nodes is left as it is.
+pub fn set_groups(doc: &mut GmlDocument, key: &str, groups: &[Group]) {
+    let mut value_of = HashMap::new();
+    for group in groups {
+        for &id in &group.nodes {
+            value_of.insert(id, &group.value);
+        }
+    }
+    for node in &mut doc.nodes {
+        if let Some(&value) = value_of.get(&node.id) {
+            node.attrs.insert(key.to_string(), value.clone());
+        }
+    }
+}
+
+#[test]
+fn test_groups() {
+    let gml = "graph [ node [ id 1 cluster \"a\" ] node [ id 2 cluster \"b\" ] node [ id 3 cluster \"a\" ]
+                       node [ id 4 cluster 7 ] edge [ source 1 target 2 ] edge [ source 2 target 3 ]
+                       edge [ source 1 target 3 ] edge [ source 4 target 1 ] ]";
+    let mut doc = crate::parse_gml_document(gml).unwrap();
+    let found = groups(&doc, "cluster");
+    let members: Vec<_> = found.iter().map(|g| g.nodes.clone()).collect();
+    assert_eq!(vec![vec![1, 3], vec![2], vec![4]], members);
+    assert_eq!(GmlValue::Int(7), found[2].value);
+
+    let q = quotient(&doc, "cluster").unwrap();
+    assert_eq!(3, q.nodes.len());
+    assert_eq!(Some(&GmlValue::Int(2)), q.nodes[0].attrs.get("size"));
+    assert_eq!(Some(&GmlValue::from("a")), q.nodes[0].attrs.get("cluster"));
+    let edges: Vec<_> = q
+        .edges
+        .iter()
+        .map(|e| (e.source, e.target, e.attrs["weight"].get_int().unwrap()))
+        .collect();
+    assert_eq!(vec![(0, 1, 2), (2, 0, 1)], edges);
+
+    let communities = vec![
+        Group {
+            value: GmlValue::Int(0),
+            nodes: vec![1, 2],
+        },
+        Group {
+            value: GmlValue::Int(1),
+            nodes: vec![3, 4],
+        },
+    ];
+    set_groups(&mut doc, "community", &communities);
+    let doc = crate::parse_gml_document(&crate::to_gml(&doc)).unwrap();
+    assert_eq!(communities, groups(&doc, "community"));
+
+    let doc = crate::parse_gml_document("graph [ node [ id 1 cluster 1 ] node [ id 2 ] ]").unwrap();
+    assert_eq!(Err("node without group"), quotient(&doc, "cluster"));
+}
@@ -0,0 +1,146 @@
+//! Stable codes of the problems found in GML input.
+//!
+//! Every kind of problem `validate_gml` and `lint_gml` report has a code
+//! like `GML0007`, which does not change between releases, so that
+//! pipelines can allow or reject specific problems and documentation can
+//! refer to them. `parse_error_code` gives the code of the errors the
+//! parsers return. Codes are never reused; new ones are added at the end.
+
+use std::fmt;
+
+/// A kind of problem, by its stable code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// The stable code, like `GML0007`.
+    pub code: &'static str,
+    /// The name of the kind, as in `Diagnostic::code`, like
+    /// `duplicate-node-id`.
+    pub name: &'static str,
+    /// What the problem is, like `duplicate node id`.
+    pub summary: &'static str,
+}
+
+/// `GML0007: duplicate node id`
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.summary)
+    }
+}
+
+const fn code(code: &'static str, name: &'static str, summary: &'static str) -> ErrorCode {
+    ErrorCode {
+        code,
+        name,
+        summary,
+    }
+}
+
+/// All error codes, errors from `GML0001` and warnings from `GML0101`.
+pub const ERROR_CODES: &[ErrorCode] = &[
+    code("GML0001", "invalid-token", "invalid token"),
+    code("GML0002", "expected-key", "expected key"),
+    code("GML0003", "missing-value", "missing value"),
+    code("GML0004", "expected-value", "expected value"),
+    code("GML0005", "unbalanced-bracket", "unbalanced bracket"),
+    code("GML0006", "duplicate-key", "duplicate key"),
+    code("GML0007", "duplicate-node-id", "duplicate node id"),
+    code("GML0008", "invalid-id", "invalid node or edge id"),
+    code("GML0009", "missing-id", "missing node or edge id"),
+    code("GML0010", "invalid-directed", "invalid directed value"),
+    code("GML0011", "unknown-node", "edge refers to unknown node"),
+    code("GML0012", "duplicate-graph", "more than one graph"),
+    code("GML0013", "missing-graph", "no graph"),
+    code("GML0014", "invalid-node-weight", "invalid node weight"),
+    code("GML0015", "invalid-edge-weight", "invalid edge weight"),
+    code(
+        "GML0016",
+        "undirected-graph",
+        "undirected graph where a directed one is required",
+    ),
+    code("GML0017", "invalid-gml", "invalid GML"),
+    code("GML0101", "unreferenced-node", "node without edges"),
+    code("GML0102", "duplicate-label", "duplicate node label"),
+    code(
+        "GML0103",
+        "inconsistent-type",
+        "attribute of different types",
+    ),
+    code("GML0104", "zero-weight", "edge weight of 0"),
+    code("GML0105", "missing-directed", "no directed flag"),
+];
+
+/// The error code with the code or name `s`, like `GML0007` or
+/// `duplicate-node-id`.
+pub fn error_code(s: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES.iter().find(|c| c.code == s || c.name == s)
+}
+
+fn named(name: &str) -> &'static ErrorCode {
+    error_code(name).expect("a name in ERROR_CODES")
+}
+
+/// The error code of an error returned by `parse_gml`, `parse_gml_document`
+/// and the other GML parsers, or `invalid-gml` for messages it does not
+/// know.
+pub fn parse_error_code(message: &str) -> &'static ErrorCode {
+    named(match message {
+        "unexpected character"
+        | "unterminated string"
+        | "invalid number"
+        | "integer out of range"
+        | "non-ASCII character"
+        | "line exceeds 254 characters" => "invalid-token",
+        "expected key" => "expected-key",
+        "missing value" => "missing-value",
+        "expected value" => "expected-value",
+        "unbalanced [" | "unbalanced ]" => "unbalanced-bracket",
+        "duplicate key" => "duplicate-key",
+        "duplicate node-id" => "duplicate-node-id",
+        "Invalid id" | "Invalid source id" | "Invalid target id" => "invalid-id",
+        "invalid directed value" => "invalid-directed",
+        "edge references unknown node" => "unknown-node",
+        "no graph given or invalid" => "missing-graph",
+        "invalid node weight" => "invalid-node-weight",
+        "invalid edge weight" => "invalid-edge-weight",
+        "only directed graph supported" => "undirected-graph",
+        _ => "invalid-gml",
+    })
+}
+
+#[test]
+fn test_error_codes() {
+    use crate::validate::{lint_gml, validate_gml, LintOptions};
+    use std::collections::HashSet;
+
+    // codes and names are unique, and codes keep their form
+    let codes: HashSet<_> = ERROR_CODES.iter().map(|c| c.code).collect();
+    let names: HashSet<_> = ERROR_CODES.iter().map(|c| c.name).collect();
+    assert_eq!(ERROR_CODES.len(), codes.len());
+    assert_eq!(ERROR_CODES.len(), names.len());
+    assert!(codes
+        .iter()
+        .all(|c| c.len() == 7 && c.starts_with("GML") && c[3..].parse::<u16>().is_ok()));
+
+    assert_eq!(
+        "GML0007: duplicate node id",
+        error_code("duplicate-node-id").unwrap().to_string()
+    );
+    assert_eq!(Some("unknown-node"), error_code("GML0011").map(|c| c.name));
+    assert_eq!(None, error_code("GML9999"));
+
+    let gml = "graph [ node [ id 1 label \"a\" ] node [ id 1 label \"a\" ] node [ ]
+               edge [ source 1 target 3 weight 0 ] ]";
+    let diagnostics = lint_gml(gml, &LintOptions::default());
+    for diagnostic in &diagnostics {
+        assert_eq!(diagnostic.code, diagnostic.error_code().name);
+    }
+    let gml = "graph [ directed 1 node [ id 1 ] node [ id 1 ] ]";
+    let graph: Result<petgraph::Graph<(), ()>, _> =
+        crate::parse_gml(gml, &|_| Some(()), &|_| Some(()));
+    let err = graph.unwrap_err();
+    assert_eq!("GML0007", parse_error_code(err).code);
+    assert_eq!(validate_gml(gml)[0].error_code(), parse_error_code(err));
+    let err = crate::parse_gml_document("graph [ node [ ] ]").unwrap_err();
+    assert_eq!("GML0008", parse_error_code(err).code);
+    assert_eq!("GML0017", parse_error_code("something else").code);
+}
@@ -0,0 +1,110 @@
+//! Colors of `graphics` lists, like `fill "#FF8800"`.
+
+use std::fmt;
+
+/// An RGB color with an alpha channel, opaque at 255.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+// The named colors of CSS level 1, and some others tools write.
+const NAMED: &[(&str, Color)] = &[
+    ("black", Color::rgb(0x00, 0x00, 0x00)),
+    ("silver", Color::rgb(0xC0, 0xC0, 0xC0)),
+    ("gray", Color::rgb(0x80, 0x80, 0x80)),
+    ("grey", Color::rgb(0x80, 0x80, 0x80)),
+    ("white", Color::rgb(0xFF, 0xFF, 0xFF)),
+    ("maroon", Color::rgb(0x80, 0x00, 0x00)),
+    ("red", Color::rgb(0xFF, 0x00, 0x00)),
+    ("purple", Color::rgb(0x80, 0x00, 0x80)),
+    ("fuchsia", Color::rgb(0xFF, 0x00, 0xFF)),
+    ("magenta", Color::rgb(0xFF, 0x00, 0xFF)),
+    ("green", Color::rgb(0x00, 0x80, 0x00)),
+    ("lime", Color::rgb(0x00, 0xFF, 0x00)),
+    ("olive", Color::rgb(0x80, 0x80, 0x00)),
+    ("yellow", Color::rgb(0xFF, 0xFF, 0x00)),
+    ("navy", Color::rgb(0x00, 0x00, 0x80)),
+    ("blue", Color::rgb(0x00, 0x00, 0xFF)),
+    ("teal", Color::rgb(0x00, 0x80, 0x80)),
+    ("aqua", Color::rgb(0x00, 0xFF, 0xFF)),
+    ("cyan", Color::rgb(0x00, 0xFF, 0xFF)),
+    ("orange", Color::rgb(0xFF, 0xA5, 0x00)),
+    ("pink", Color::rgb(0xFF, 0xC0, 0xCB)),
+    ("brown", Color::rgb(0xA5, 0x2A, 0x2A)),
+];
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    /// Parses `#RGB`, `#RRGGBB`, `#RRGGBBAA` or a color name like `red`,
+    /// ignoring case.
+    pub fn parse(s: &str) -> Result<Color, &'static str> {
+        let hex = match s.strip_prefix('#') {
+            Some(hex) => hex,
+            None => {
+                return NAMED
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(s))
+                    .map(|&(_, color)| color)
+                    .ok_or("unknown color name");
+            }
+        };
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err("invalid hex color");
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap();
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        match hex.len() {
+            3 => Ok(Color::rgb(digit(0) * 17, digit(1) * 17, digit(2) * 17)),
+            6 => Ok(Color::rgb(byte(0), byte(2), byte(4))),
+            8 => Ok(Color {
+                a: byte(6),
+                ..Color::rgb(byte(0), byte(2), byte(4))
+            }),
+            _ => Err("invalid hex color"),
+        }
+    }
+
+    /// The name of the color, if it has one.
+    pub fn name(&self) -> Option<&'static str> {
+        NAMED
+            .iter()
+            .find(|&&(_, color)| color == *self)
+            .map(|&(name, _)| name)
+    }
+}
+
+/// `#RRGGBB`, or `#RRGGBBAA` if not opaque, in upper case as yEd writes.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02X}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_color() {
+    let orange = Color::rgb(0xFF, 0x88, 0x00);
+    assert_eq!(Ok(orange), Color::parse("#FF8800"));
+    assert_eq!(Ok(orange), Color::parse("#ff8800"));
+    assert_eq!(Ok(orange), Color::parse("#F80"));
+    assert_eq!("#FF8800", orange.to_string());
+    let translucent = Color::parse("#FF880080").unwrap();
+    assert_eq!(0x80, translucent.a);
+    assert_eq!("#FF880080", translucent.to_string());
+    assert_eq!(Ok(Color::rgb(0, 0, 255)), Color::parse("Blue"));
+    assert_eq!(Some("red"), Color::parse("#FF0000").unwrap().name());
+    assert_eq!(None, orange.name());
+    assert_eq!(Err("unknown color name"), Color::parse("blurple"));
+    assert_eq!(Err("invalid hex color"), Color::parse("#FF88"));
+    assert_eq!(Err("invalid hex color"), Color::parse("#GG8800"));
+}
@@ -0,0 +1,165 @@
+//! Compact strings.
+//!
+//! `GmlString` is a `CompactString`. With the `compact-strings` feature, it
+//! keeps strings of up to 22 bytes, like most labels, inline instead of on
+//! the heap. Without it, all strings are on the heap. The type is the same
+//! either way.
+//!
+//! Inline strings are checked to be UTF-8 when they are read, which is
+//! cheap for at most 22 bytes and needs no `unsafe`.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+
+const INLINE: usize = 22;
+
+/// A string stored inline if it is at most 22 bytes long and the
+/// `compact-strings` feature is enabled.
+#[derive(Clone)]
+pub struct CompactString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline(u8, [u8; INLINE]),
+    Heap(Box<str>),
+}
+
+impl CompactString {
+    pub fn new(s: &str) -> CompactString {
+        if cfg!(feature = "compact-strings") && s.len() <= INLINE {
+            let mut buf = [0; INLINE];
+            buf[..s.len()].copy_from_slice(s.as_bytes());
+            CompactString(Repr::Inline(s.len() as u8, buf))
+        } else {
+            CompactString(Repr::Heap(s.into()))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self.0 {
+            Repr::Inline(len, ref buf) => {
+                std::str::from_utf8(&buf[..len as usize]).expect("copied from a str")
+            }
+            Repr::Heap(ref s) => s,
+        }
+    }
+
+    /// Whether the string is stored inline.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline(..))
+    }
+}
+
+impl Deref for CompactString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for CompactString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for CompactString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for CompactString {
+    fn from(s: &str) -> CompactString {
+        CompactString::new(s)
+    }
+}
+
+impl From<String> for CompactString {
+    fn from(s: String) -> CompactString {
+        if cfg!(feature = "compact-strings") && s.len() <= INLINE {
+            CompactString::new(&s)
+        } else {
+            CompactString(Repr::Heap(s.into_boxed_str()))
+        }
+    }
+}
+
+impl From<CompactString> for String {
+    fn from(s: CompactString) -> String {
+        match s.0 {
+            Repr::Heap(s) => s.into(),
+            Repr::Inline(..) => s.as_str().to_string(),
+        }
+    }
+}
+
+impl PartialEq for CompactString {
+    fn eq(&self, other: &CompactString) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for CompactString {}
+
+impl PartialEq<str> for CompactString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for CompactString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for CompactString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for CompactString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[test]
+fn test_compact_string() {
+    assert_eq!(
+        std::mem::size_of::<String>(),
+        std::mem::size_of::<CompactString>()
+    );
+    let inline = cfg!(feature = "compact-strings");
+    let short = CompactString::from("a label");
+    assert_eq!(inline, short.is_inline());
+    assert_eq!("a label", &short[..]);
+    let long = CompactString::from("a label longer than 22 bytes".to_string());
+    assert!(!long.is_inline());
+    assert_eq!("a label longer than 22 bytes", String::from(long.clone()));
+    assert_ne!(short, long);
+
+    // at and just past the limit, also with characters of 2 and 3 bytes
+    // that end at or cross it
+    for &(s, fits) in &[
+        ("abcdefghijklmnopqrstuv", true),
+        ("abcdefghijklmnopqrstuvw", false),
+        ("abcdefghijklmnopqrst\u{e9}", true),
+        ("abcdefghijklmnopqrstu\u{e9}", false),
+        ("abcdefghijklmnopqrs\u{20ac}", true),
+        ("abcdefghijklmnopqrst\u{20ac}", false),
+        (
+            "\u{20ac}\u{20ac}\u{20ac}\u{20ac}\u{20ac}\u{20ac}\u{20ac}\u{e9}",
+            false,
+        ),
+    ] {
+        for string in &[CompactString::new(s), CompactString::from(s.to_string())] {
+            assert_eq!(inline && fits, string.is_inline(), "{}", s);
+            assert_eq!(s, string.as_str());
+        }
+    }
+}
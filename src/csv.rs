@@ -0,0 +1,369 @@
+//! CSV node-list and edge-list import and export.
+
+use crate::document::{
+    flatten, insert_dotted, numeric_ids, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// Column of the nodes file holding the node id.
+    pub id_column: String,
+    /// Columns of the edges file holding source and target node ids.
+    pub source_column: String,
+    pub target_column: String,
+    /// Maps CSV column headers to attribute names. Columns not listed keep
+    /// their header as name. Export applies the mapping in reverse.
+    pub columns: BTreeMap<String, String>,
+    /// Whether imported documents are directed.
+    pub directed: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: ',',
+            id_column: "id".to_string(),
+            source_column: "source".to_string(),
+            target_column: "target".to_string(),
+            columns: BTreeMap::new(),
+            directed: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn delimiter(mut self, delimiter: char) -> CsvOptions {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Reads CSV column `header` as attribute `attr`.
+    pub fn column(mut self, header: &str, attr: &str) -> CsvOptions {
+        self.columns.insert(header.to_string(), attr.to_string());
+        self
+    }
+
+    fn attr_name<'a>(&'a self, header: &'a str) -> &'a str {
+        self.columns.get(header).map_or(header, |a| &a[..])
+    }
+
+    fn header_name<'a>(&'a self, attr: &'a str) -> &'a str {
+        self.columns
+            .iter()
+            .find(|&(_, a)| a == attr)
+            .map_or(attr, |(h, _)| &h[..])
+    }
+}
+
+// Parses CSV records as described in RFC 4180: fields may be quoted, quotes
+// within quoted fields are doubled.
+fn parse_records(s: &str, delimiter: char) -> Result<Vec<Vec<String>>, &'static str> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = s.chars().peekable();
+    let mut in_quotes = false;
+    let mut at_field_start = true;
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if at_field_start => {
+                in_quotes = true;
+                at_field_start = false;
+            }
+            c if c == delimiter => {
+                record.push(std::mem::take(&mut field));
+                at_field_start = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+                at_field_start = true;
+            }
+            c => {
+                field.push(c);
+                at_field_start = false;
+            }
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field");
+    }
+    if !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+// Fields without digits, like `inf` or `nan`, stay text.
+fn typed(field: &str) -> GmlValue {
+    if let Ok(i) = field.parse() {
+        return GmlValue::Int(i);
+    }
+    if field.contains(|c: char| c.is_ascii_digit()) {
+        if let Ok(f) = field.parse() {
+            return GmlValue::Float(f);
+        }
+    }
+    GmlValue::from(field)
+}
+
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn parse_table(s: &str, delimiter: char) -> Result<Table, &'static str> {
+    let mut records = parse_records(s, delimiter)?.into_iter();
+    let headers = records.next().ok_or("missing header line")?;
+    let rows: Vec<_> = records.collect();
+    if rows.iter().any(|r| r.len() != headers.len()) {
+        return Err("record length does not match header");
+    }
+    Ok(Table { headers, rows })
+}
+
+impl Table {
+    fn column(&self, name: &str) -> Result<usize, &'static str> {
+        self.headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or("missing column")
+    }
+
+    // The attributes of `row`, without the columns in `skip`. Empty fields
+    // are omitted.
+    fn attrs(&self, row: &[String], skip: &[usize], options: &CsvOptions) -> GmlAttrs {
+        let mut attrs = GmlAttrs::new();
+        for (i, field) in row.iter().enumerate() {
+            if skip.contains(&i) || field.is_empty() {
+                continue;
+            }
+            let name = options.attr_name(&self.headers[i]);
+            insert_dotted(&mut attrs, name, typed(field));
+        }
+        attrs
+    }
+}
+
+/// Builds a `GmlDocument` from an optional nodes CSV and an edges CSV.
+///
+/// Attribute values are typed: fields which parse as integers or reals
+/// become `Int` or `Float`. Dotted headers such as `graphics.x` become
+/// nested lists. Nodes only referenced from the edges file are added
+/// without attributes. Node ids which are unsigned integers are used
+/// directly; otherwise nodes are numbered from 0 and the CSV id is kept as
+/// `name`.
+pub fn parse_csv(
+    nodes: Option<&str>,
+    edges: &str,
+    options: &CsvOptions,
+) -> Result<GmlDocument, &'static str> {
+    let mut names: Vec<String> = Vec::new();
+    let mut node_attrs: BTreeMap<String, GmlAttrs> = BTreeMap::new();
+
+    if let Some(nodes) = nodes {
+        let table = parse_table(nodes, options.delimiter)?;
+        let id_col = table.column(&options.id_column)?;
+        for row in &table.rows {
+            let name = row[id_col].clone();
+            let attrs = table.attrs(row, &[id_col], options);
+            if node_attrs.insert(name.clone(), attrs).is_some() {
+                return Err("duplicate node-id");
+            }
+            names.push(name);
+        }
+    }
+
+    let table = parse_table(edges, options.delimiter)?;
+    let source_col = table.column(&options.source_column)?;
+    let target_col = table.column(&options.target_column)?;
+    let mut edge_rows = Vec::new();
+    for row in &table.rows {
+        for &col in &[source_col, target_col] {
+            if !node_attrs.contains_key(&row[col]) {
+                node_attrs.insert(row[col].clone(), GmlAttrs::new());
+                names.push(row[col].clone());
+            }
+        }
+        let attrs = table.attrs(row, &[source_col, target_col], options);
+        edge_rows.push((&row[source_col], &row[target_col], attrs));
+    }
+
+    let numeric = numeric_ids(names.iter().map(|n| &n[..]), |n| n.parse().ok());
+    let mut doc = GmlDocument {
+        directed: options.directed,
+        ..GmlDocument::default()
+    };
+    let mut id_map = BTreeMap::new();
+    for (idx, name) in names.iter().enumerate() {
+        let mut attrs = node_attrs.remove(name).unwrap_or_default();
+        let id = match numeric {
+            Some(ref ids) => ids[idx],
+            None => {
+                attrs.insert("name".to_string(), GmlValue::from(name.clone()));
+                idx as u64
+            }
+        };
+        id_map.insert(&name[..], id);
+        doc.nodes.push(GmlNode { id, attrs });
+    }
+    for (source, target, attrs) in edge_rows {
+        doc.edges.push(GmlEdge {
+            source: id_map[&source[..]],
+            target: id_map[&target[..]],
+            attrs,
+        });
+    }
+    Ok(doc)
+}
+
+fn write_field<W: Write>(w: &mut W, field: &str, delimiter: char) -> io::Result<()> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        write!(w, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        write!(w, "{}", field)
+    }
+}
+
+fn write_record<W: Write>(w: &mut W, fields: &[String], delimiter: char) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(w, "{}", delimiter)?;
+        }
+        write_field(w, field, delimiter)?;
+    }
+    writeln!(w)
+}
+
+fn scalar_field(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) => f.to_string(),
+        GmlValue::Str(ref s) => s.to_string(),
+        GmlValue::List(_) => unreachable!(),
+    }
+}
+
+fn write_table<'a, W, I>(
+    w: &mut W,
+    key_headers: Vec<String>,
+    rows: I,
+    options: &CsvOptions,
+) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = (Vec<String>, &'a GmlAttrs)> + Clone,
+{
+    let mut names = BTreeSet::new();
+    for (_, attrs) in rows.clone() {
+        names.extend(flatten(attrs).into_iter().map(|(name, _)| name));
+    }
+    let mut headers = key_headers;
+    headers.extend(names.iter().map(|n| options.header_name(n).to_string()));
+    write_record(w, &headers, options.delimiter)?;
+
+    for (mut fields, attrs) in rows {
+        let flat: BTreeMap<_, _> = flatten(attrs).into_iter().collect();
+        for name in &names {
+            fields.push(flat.get(name).map(|v| scalar_field(v)).unwrap_or_default());
+        }
+        write_record(w, &fields, options.delimiter)?;
+    }
+    Ok(())
+}
+
+/// Writes `doc` as a nodes CSV and an edges CSV, the inverse of
+/// `parse_csv`. Attributes become columns sorted by name, nested lists
+/// flattened into dotted headers.
+pub fn write_csv<N: Write, E: Write>(
+    doc: &GmlDocument,
+    options: &CsvOptions,
+    nodes: &mut N,
+    edges: &mut E,
+) -> io::Result<()> {
+    let node_rows = doc.nodes.iter().map(|n| (vec![n.id.to_string()], &n.attrs));
+    write_table(nodes, vec![options.id_column.clone()], node_rows, options)?;
+
+    let edge_rows = doc
+        .edges
+        .iter()
+        .map(|e| (vec![e.source.to_string(), e.target.to_string()], &e.attrs));
+    let headers = vec![options.source_column.clone(), options.target_column.clone()];
+    write_table(edges, headers, edge_rows, options)
+}
+
+/// Renders `doc` as `(nodes, edges)` CSV strings.
+pub fn to_csv(doc: &GmlDocument, options: &CsvOptions) -> (String, String) {
+    let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+    write_csv(doc, options, &mut nodes, &mut edges).unwrap();
+    (
+        String::from_utf8(nodes).unwrap(),
+        String::from_utf8(edges).unwrap(),
+    )
+}
+
+#[test]
+fn test_csv_round_trip() {
+    let nodes = "id;Name;graphics.x\n1;\"a; b\";1.5\n2;b;\n";
+    let edges = "source;target;w\n1;2;3\n2;3;\n";
+    let options = CsvOptions::default()
+        .delimiter(';')
+        .column("Name", "label")
+        .column("w", "weight");
+    let doc = parse_csv(Some(nodes), edges, &options).unwrap();
+    assert_eq!(3, doc.nodes.len());
+    assert_eq!(Some("a; b"), doc.nodes[0].label());
+    assert_eq!(
+        Some(1.5),
+        doc.nodes[0].attrs["graphics"]
+            .get("x")
+            .and_then(GmlValue::get_float)
+    );
+    assert!(doc.nodes[2].attrs.is_empty());
+    assert_eq!(Some(3), doc.edges[0].attrs["weight"].get_int());
+    assert!(doc.edges[1].attrs.is_empty());
+
+    let (n, e) = to_csv(&doc, &options);
+    assert_eq!("id;graphics.x;Name\n1;1.5;\"a; b\"\n2;;b\n3;;\n", n);
+    assert_eq!("source;target;w\n1;2;3\n2;3;\n", e);
+
+    let gml = crate::to_gml(&doc);
+    assert_eq!(doc, crate::parse_gml_document(&gml).unwrap());
+
+    let nodes = "id,a,b,c,d\n1,inf,NaN,infinity,1e3\n";
+    let doc = parse_csv(Some(nodes), "source,target\n", &CsvOptions::default()).unwrap();
+    let values: Vec<_> = ["a", "b", "c", "d"]
+        .iter()
+        .map(|&k| doc.nodes[0].attrs[k].clone())
+        .collect();
+    assert_eq!(
+        vec![
+            GmlValue::from("inf"),
+            GmlValue::from("NaN"),
+            GmlValue::from("infinity"),
+            GmlValue::Float(1000.0)
+        ],
+        values
+    );
+}
@@ -0,0 +1,326 @@
+//! Neo4j Cypher export.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlValue};
+use crate::json::Json;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CypherMode {
+    /// `CREATE` statements, for loading into an empty database.
+    Create,
+    /// `MERGE` on the node id, so that loading twice does not duplicate.
+    Merge,
+    /// `UNWIND` over a list of rows per batch.
+    Unwind,
+}
+
+#[derive(Debug, Clone)]
+pub struct CypherOptions {
+    pub mode: CypherMode,
+    /// The Neo4j label of every node.
+    pub node_label: String,
+    /// An attribute whose string value becomes an additional Neo4j label,
+    /// e.g. `type`.
+    pub label_key: Option<String>,
+    /// The relationship type of every edge.
+    pub relationship_type: String,
+    /// Nodes or edges per statement.
+    pub batch_size: usize,
+}
+
+impl Default for CypherOptions {
+    fn default() -> CypherOptions {
+        CypherOptions {
+            mode: CypherMode::Create,
+            node_label: "Node".to_string(),
+            label_key: None,
+            relationship_type: "EDGE".to_string(),
+            batch_size: 1000,
+        }
+    }
+}
+
+/// A parameterized statement with the JSON value of its `$rows` parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CypherBatch {
+    pub statement: String,
+    pub rows: String,
+}
+
+fn identifier(s: &str) -> String {
+    let simple = s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if simple {
+        s.to_string()
+    } else {
+        format!("`{}`", s.replace('`', "``"))
+    }
+}
+
+fn literal(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) => format!("{:?}", f),
+        GmlValue::Str(ref s) => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+        GmlValue::List(_) => unreachable!(),
+    }
+}
+
+// Properties of a node or edge. Nested lists are flattened into dotted
+// names since Neo4j properties cannot hold maps.
+fn properties(id: Option<u64>, attrs: &GmlAttrs) -> Vec<(String, &GmlValue)> {
+    let mut props = flatten(attrs);
+    if id.is_some() {
+        props.retain(|(k, _)| k != "id");
+    }
+    props
+}
+
+fn map_literal(id: Option<u64>, attrs: &GmlAttrs) -> String {
+    let mut entries = Vec::new();
+    if let Some(id) = id {
+        entries.push(format!("id: {}", id));
+    }
+    for (k, v) in properties(id, attrs) {
+        entries.push(format!("{}: {}", identifier(&k), literal(v)));
+    }
+    format!("{{{}}}", entries.join(", "))
+}
+
+fn props_json(id: Option<u64>, attrs: &GmlAttrs) -> Json {
+    Json::Object(
+        properties(id, attrs)
+            .into_iter()
+            .map(|(k, v)| (k, crate::json::from_value(v)))
+            .collect(),
+    )
+}
+
+fn node_labels(attrs: &GmlAttrs, options: &CypherOptions) -> String {
+    let mut labels = format!(":{}", identifier(&options.node_label));
+    if let Some(ref key) = options.label_key {
+        if let Some(extra) = attrs.get(key).and_then(GmlValue::get_str) {
+            labels.push(':');
+            labels.push_str(&identifier(extra));
+        }
+    }
+    labels
+}
+
+/// Renders `doc` as Cypher statements, one per line. See `write_cypher`.
+pub fn to_cypher(doc: &GmlDocument, options: &CypherOptions) -> String {
+    let mut buf = Vec::new();
+    write_cypher(doc, options, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as Cypher statements, one per line, creating the nodes
+/// first. Nodes get the GML id as `id` property and their attributes as
+/// further properties; edges are matched to their endpoints by `id`.
+pub fn write_cypher<W: Write>(
+    doc: &GmlDocument,
+    options: &CypherOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    let node_label = identifier(&options.node_label);
+    let rel = identifier(&options.relationship_type);
+    let batch_size = options.batch_size.max(1);
+
+    match options.mode {
+        CypherMode::Create => {
+            for batch in doc.nodes.chunks(batch_size) {
+                let patterns: Vec<_> = batch
+                    .iter()
+                    .map(|n| {
+                        format!(
+                            "({} {})",
+                            node_labels(&n.attrs, options),
+                            map_literal(Some(n.id), &n.attrs)
+                        )
+                    })
+                    .collect();
+                writeln!(w, "CREATE {};", patterns.join(", "))?;
+            }
+            for e in &doc.edges {
+                writeln!(
+                    w,
+                    "MATCH (a:{l} {{id: {}}}), (b:{l} {{id: {}}}) CREATE (a)-[:{} {}]->(b);",
+                    e.source,
+                    e.target,
+                    rel,
+                    map_literal(None, &e.attrs),
+                    l = node_label
+                )?;
+            }
+        }
+        CypherMode::Merge => {
+            for n in &doc.nodes {
+                writeln!(
+                    w,
+                    "MERGE (n{} {{id: {}}}) SET n += {};",
+                    node_labels(&n.attrs, options),
+                    n.id,
+                    map_literal(None, &n.attrs)
+                )?;
+            }
+            for e in &doc.edges {
+                writeln!(
+                    w,
+                    "MATCH (a:{l} {{id: {}}}), (b:{l} {{id: {}}}) MERGE (a)-[r:{}]->(b) SET r += {};",
+                    e.source,
+                    e.target,
+                    rel,
+                    map_literal(None, &e.attrs),
+                    l = node_label
+                )?;
+            }
+        }
+        CypherMode::Unwind => {
+            for (statement, rows) in unwind_batches(doc, options) {
+                let statement = statement.replacen("$rows", &cypher_value(&rows), 1);
+                writeln!(w, "{}", statement)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Renders a JSON value as a Cypher literal.
+fn cypher_value(json: &Json) -> String {
+    match *json {
+        Json::Null => "null".to_string(),
+        Json::Bool(b) => b.to_string(),
+        Json::Int(i) => i.to_string(),
+        Json::UInt(u) => u.to_string(),
+        Json::Float(f) => format!("{:?}", f),
+        Json::Str(ref s) => literal(&GmlValue::from(s.clone())),
+        Json::Array(ref items) => {
+            let items: Vec<_> = items.iter().map(cypher_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Json::Object(ref members) => {
+            let members: Vec<_> = members
+                .iter()
+                .map(|(k, v)| format!("{}: {}", identifier(k), cypher_value(v)))
+                .collect();
+            format!("{{{}}}", members.join(", "))
+        }
+    }
+}
+
+/// Splits `doc` into `UNWIND` statements over a `$rows` parameter, for use
+/// with a driver. Nodes are grouped by their labels since labels cannot be
+/// parameterized.
+pub fn cypher_batches(doc: &GmlDocument, options: &CypherOptions) -> Vec<CypherBatch> {
+    unwind_batches(doc, options)
+        .into_iter()
+        .map(|(statement, rows)| CypherBatch {
+            statement,
+            rows: rows.to_string(),
+        })
+        .collect()
+}
+
+fn unwind_batches(doc: &GmlDocument, options: &CypherOptions) -> Vec<(String, Json)> {
+    let node_label = identifier(&options.node_label);
+    let rel = identifier(&options.relationship_type);
+    let batch_size = options.batch_size.max(1);
+    let mut batches = Vec::new();
+
+    let mut by_labels: BTreeMap<String, Vec<Json>> = BTreeMap::new();
+    for n in &doc.nodes {
+        let row = Json::Object(vec![
+            ("id".to_string(), Json::from(n.id)),
+            ("props".to_string(), props_json(Some(n.id), &n.attrs)),
+        ]);
+        by_labels
+            .entry(node_labels(&n.attrs, options))
+            .or_default()
+            .push(row);
+    }
+    let create = if options.mode == CypherMode::Merge {
+        "MERGE"
+    } else {
+        "CREATE"
+    };
+    for (labels, rows) in by_labels {
+        for chunk in rows.chunks(batch_size) {
+            batches.push((
+                format!(
+                    "UNWIND $rows AS row {} (n{} {{id: row.id}}) SET n += row.props;",
+                    create, labels
+                ),
+                Json::Array(chunk.to_vec()),
+            ));
+        }
+    }
+
+    for chunk in doc.edges.chunks(batch_size) {
+        let rows = chunk
+            .iter()
+            .map(|e| {
+                Json::Object(vec![
+                    ("source".to_string(), Json::from(e.source)),
+                    ("target".to_string(), Json::from(e.target)),
+                    ("props".to_string(), props_json(None, &e.attrs)),
+                ])
+            })
+            .collect();
+        batches.push((
+            format!(
+                "UNWIND $rows AS row MATCH (a:{l} {{id: row.source}}), (b:{l} {{id: row.target}}) \
+                 {} (a)-[r:{}]->(b) SET r += row.props;",
+                create,
+                rel,
+                l = node_label
+            ),
+            Json::Array(rows),
+        ));
+    }
+    batches
+}
+
+#[test]
+fn test_to_cypher() {
+    let gml = "graph [ directed 1 node [ id 1 label \"O'Neil\" type \"router\" ] \
+               node [ id 2 graphics [ x 1.5 ] ] edge [ source 1 target 2 weight 2 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+
+    let options = CypherOptions {
+        label_key: Some("type".to_string()),
+        ..CypherOptions::default()
+    };
+    assert_eq!(
+        "CREATE (:Node:router {id: 1, label: 'O\\'Neil', type: 'router'}), \
+         (:Node {id: 2, `graphics.x`: 1.5});\n\
+         MATCH (a:Node {id: 1}), (b:Node {id: 2}) CREATE (a)-[:EDGE {weight: 2}]->(b);\n",
+        to_cypher(&doc, &options)
+    );
+
+    let options = CypherOptions {
+        mode: CypherMode::Merge,
+        ..CypherOptions::default()
+    };
+    assert!(to_cypher(&doc, &options)
+        .starts_with("MERGE (n:Node {id: 1}) SET n += {label: 'O\\'Neil', type: 'router'};\n"));
+
+    let options = CypherOptions {
+        mode: CypherMode::Unwind,
+        batch_size: 1,
+        ..CypherOptions::default()
+    };
+    let batches = cypher_batches(&doc, &options);
+    assert_eq!(3, batches.len());
+    assert_eq!(
+        "UNWIND $rows AS row CREATE (n:Node {id: row.id}) SET n += row.props;",
+        batches[0].statement
+    );
+    assert_eq!(
+        "[{\"id\":1,\"props\":{\"label\":\"O'Neil\",\"type\":\"router\"}}]",
+        batches[0].rows
+    );
+    assert!(to_cypher(&doc, &options)
+        .starts_with("UNWIND [{id: 1, props: {label: 'O\\'Neil', type: 'router'}}] AS row CREATE"));
+}
@@ -0,0 +1,99 @@
+//! Cytoscape.js JSON export.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use crate::json::{self, Json};
+use std::io::{self, Write};
+
+/// Renders `doc` as the `elements` JSON accepted by Cytoscape.js.
+///
+/// Every attribute except `graphics` goes into the element's `data`, next
+/// to `id` (and `source`/`target` for edges). The `x` and `y` of a node's
+/// `graphics` block become its `position`. Edges get the ids `e0`, `e1`, ...
+/// in document order.
+pub fn to_cytoscape(doc: &GmlDocument) -> String {
+    let nodes = doc
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut data = vec![("id".to_string(), Json::Str(node.id.to_string()))];
+            push_data(&mut data, &node.attrs);
+            let mut element = vec![("data".to_string(), Json::Object(data))];
+            let graphics = node.attrs.get("graphics");
+            let coord = |k: &str| {
+                graphics
+                    .and_then(|g| g.get(k))
+                    .and_then(GmlValue::get_float)
+            };
+            if let (Some(x), Some(y)) = (coord("x"), coord("y")) {
+                let position = vec![
+                    ("x".to_string(), Json::Float(x)),
+                    ("y".to_string(), Json::Float(y)),
+                ];
+                element.push(("position".to_string(), Json::Object(position)));
+            }
+            Json::Object(element)
+        })
+        .collect();
+
+    let edges = doc
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(i, edge)| {
+            let mut data = vec![
+                ("id".to_string(), Json::Str(format!("e{}", i))),
+                ("source".to_string(), Json::Str(edge.source.to_string())),
+                ("target".to_string(), Json::Str(edge.target.to_string())),
+            ];
+            push_data(&mut data, &edge.attrs);
+            Json::Object(vec![("data".to_string(), Json::Object(data))])
+        })
+        .collect();
+
+    let elements = vec![
+        ("nodes".to_string(), Json::Array(nodes)),
+        ("edges".to_string(), Json::Array(edges)),
+    ];
+    Json::Object(vec![("elements".to_string(), Json::Object(elements))]).to_string_pretty()
+}
+
+/// Writes `doc` as Cytoscape.js JSON. See `to_cytoscape`.
+pub fn write_cytoscape<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    w.write_all(to_cytoscape(doc).as_bytes())
+}
+
+fn push_data(data: &mut Vec<(String, Json)>, attrs: &GmlAttrs) {
+    for (k, v) in attrs {
+        if k != "graphics" && k != "id" && k != "source" && k != "target" {
+            data.push((k.clone(), json::from_value(v)));
+        }
+    }
+}
+
+#[test]
+fn test_to_cytoscape() {
+    let gml = "graph [ directed 1 node [ id 1 label \"a\" graphics [ x 10 y 20.5 ] ] \
+               node [ id 2 ] edge [ source 1 target 2 weight 1.5 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let parsed = json::parse(&to_cytoscape(&doc)).unwrap();
+    let elements = parsed.get("elements").unwrap();
+
+    let node = match elements.get("nodes") {
+        Some(Json::Array(nodes)) => &nodes[0],
+        _ => panic!("no nodes"),
+    };
+    let data = node.get("data").unwrap();
+    assert_eq!(Some("1"), data.get("id").and_then(Json::as_str));
+    assert_eq!(Some("a"), data.get("label").and_then(Json::as_str));
+    assert!(data.get("graphics").is_none());
+    let position = node.get("position").unwrap();
+    assert_eq!(Some(&Json::Float(20.5)), position.get("y"));
+
+    let edge = match elements.get("edges") {
+        Some(Json::Array(edges)) => edges[0].get("data").unwrap().clone(),
+        _ => panic!("no edges"),
+    };
+    assert_eq!(Some("e0"), edge.get("id").and_then(Json::as_str));
+    assert_eq!(Some("2"), edge.get("target").and_then(Json::as_str));
+    assert_eq!(Some(&Json::Float(1.5)), edge.get("weight"));
+}
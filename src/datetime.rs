@@ -0,0 +1,276 @@
+//! Dates and times in attributes, with the `datetime` feature.
+//!
+//! Event graphs give times as ISO 8601 strings, like
+//! `"2024-03-01T12:30:00Z"`, or as seconds since the Unix epoch.
+//! `DateTimeAttrs::get_datetime` reads both, and `put_datetime` writes a
+//! time back as an ISO 8601 string.
+
+use crate::document::{GmlAttrs, GmlValue};
+use std::fmt;
+
+/// A point in time, with the UTC offset it was given in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    /// Seconds since 1970-01-01T00:00:00Z.
+    pub timestamp: i64,
+    pub nanos: u32,
+    /// The offset from UTC in minutes, east positive.
+    pub offset: i32,
+}
+
+// Days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// The year, month and day of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// A cursor over the digits and separators of a time.
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn digits(&mut self, n: usize) -> Option<u32> {
+        let digits = self.0.get(..n)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.0 = &self.0[n..];
+        Some(digits.iter().fold(0, |v, d| v * 10 + (d - b'0') as u32))
+    }
+
+    fn eat(&mut self, any: &[u8]) -> Option<u8> {
+        let c = *self.0.first().filter(|c| any.contains(c))?;
+        self.0 = &self.0[1..];
+        Some(c)
+    }
+}
+
+impl DateTime {
+    /// The time `timestamp` seconds after the epoch, in UTC.
+    pub fn from_timestamp(timestamp: i64) -> DateTime {
+        DateTime {
+            timestamp,
+            nanos: 0,
+            offset: 0,
+        }
+    }
+
+    /// Parses an ISO 8601 date, like `2024-03-01`, which is midnight UTC,
+    /// or date and time, like `2024-03-01T12:30:00.5+01:00`. The `T` may
+    /// be a space, and a time without `Z` or an offset is taken as UTC.
+    pub fn parse(s: &str) -> Result<DateTime, &'static str> {
+        let invalid = "invalid date or time";
+        let mut c = Cursor(s.as_bytes());
+        let year = c.digits(4).ok_or(invalid)? as i64;
+        c.eat(b"-").ok_or(invalid)?;
+        let month = c
+            .digits(2)
+            .filter(|m| (1..=12).contains(m))
+            .ok_or(invalid)?;
+        c.eat(b"-").ok_or(invalid)?;
+        let day = c
+            .digits(2)
+            .filter(|&d| d >= 1 && d <= days_in_month(year, month))
+            .ok_or(invalid)?;
+        let mut seconds = days_from_civil(year, month, day) * 86_400;
+        let mut nanos = 0;
+        let mut offset = 0;
+        if c.eat(b"T ").is_some() {
+            let hour = c.digits(2).filter(|&h| h < 24).ok_or(invalid)?;
+            c.eat(b":").ok_or(invalid)?;
+            let minute = c.digits(2).filter(|&m| m < 60).ok_or(invalid)?;
+            c.eat(b":").ok_or(invalid)?;
+            let second = c.digits(2).filter(|&s| s < 60).ok_or(invalid)?;
+            seconds += (hour * 3600 + minute * 60 + second) as i64;
+            if c.eat(b".").is_some() {
+                let mut scale = 100_000_000;
+                let mut any = false;
+                while let Some(d) = c.digits(1) {
+                    nanos += d * scale;
+                    scale /= 10;
+                    any = true;
+                }
+                if !any {
+                    return Err(invalid);
+                }
+            }
+            match c.eat(b"Z+-") {
+                None | Some(b'Z') => {}
+                Some(sign) => {
+                    let hours = c.digits(2).filter(|&h| h < 24).ok_or(invalid)?;
+                    c.eat(b":").ok_or(invalid)?;
+                    let minutes = c.digits(2).filter(|&m| m < 60).ok_or(invalid)?;
+                    offset = (hours * 60 + minutes) as i32;
+                    if sign == b'-' {
+                        offset = -offset;
+                    }
+                    seconds -= offset as i64 * 60;
+                }
+            }
+        }
+        if !c.0.is_empty() {
+            return Err(invalid);
+        }
+        Ok(DateTime {
+            timestamp: seconds,
+            nanos,
+            offset,
+        })
+    }
+
+    /// Reads a time from a string as by `parse`, or from a number of
+    /// seconds since the epoch.
+    pub fn from_value(value: &GmlValue) -> Option<DateTime> {
+        match *value {
+            GmlValue::Int(i) => Some(DateTime::from_timestamp(i)),
+            GmlValue::Float(f) if f.is_finite() => {
+                let seconds = f.floor();
+                let mut time = DateTime::from_timestamp(seconds as i64);
+                time.nanos = (((f - seconds) * 1e9).round() as u32).min(999_999_999);
+                Some(time)
+            }
+            GmlValue::Str(ref s) => DateTime::parse(s).ok(),
+            _ => None,
+        }
+    }
+
+    /// The time as an ISO 8601 string, as by `Display`.
+    pub fn to_value(&self) -> GmlValue {
+        GmlValue::from(self.to_string())
+    }
+}
+
+/// Writes the time in its offset, like `2024-03-01T13:30:00.5+01:00`, or
+/// with `Z` for UTC.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let local = self.timestamp + self.offset as i64 * 60;
+        let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+        let second = local.rem_euclid(86_400);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            second / 3600,
+            second / 60 % 60,
+            second % 60
+        )?;
+        if self.nanos > 0 {
+            let fraction = format!("{:09}", self.nanos);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        match self.offset {
+            0 => write!(f, "Z"),
+            o => write!(
+                f,
+                "{}{:02}:{:02}",
+                if o < 0 { '-' } else { '+' },
+                o.abs() / 60,
+                o.abs() % 60
+            ),
+        }
+    }
+}
+
+/// Date and time accessors of attributes.
+pub trait DateTimeAttrs {
+    /// The time at `key`, as by `DateTime::from_value`.
+    fn get_datetime(&self, key: &str) -> Option<DateTime>;
+    /// Sets `key` to `time` as an ISO 8601 string.
+    fn put_datetime(&mut self, key: &str, time: DateTime);
+}
+
+impl DateTimeAttrs for GmlAttrs {
+    fn get_datetime(&self, key: &str) -> Option<DateTime> {
+        self.get(key).and_then(DateTime::from_value)
+    }
+
+    fn put_datetime(&mut self, key: &str, time: DateTime) {
+        self.insert(key.to_string(), time.to_value());
+    }
+}
+
+#[test]
+fn test_datetime() {
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 created \"2024-03-01T12:30:00Z\" ]
+                 node [ id 2 created \"2024-03-01T13:30:00.250+01:00\" ]
+                 node [ id 3 created 1709296200 ] node [ id 4 created \"2024-02-29\" ]
+                 node [ id 5 created \"2023-02-29\" ] node [ id 6 created 1.5 ] ]",
+    )
+    .unwrap();
+    let created = |i: usize| doc.nodes[i].attrs.get_datetime("created");
+    let noon = DateTime::from_timestamp(1_709_296_200);
+    assert_eq!(Some(noon), created(0));
+    let later = created(1).unwrap();
+    assert_eq!(
+        (noon.timestamp, 250_000_000, 60),
+        (later.timestamp, later.nanos, later.offset)
+    );
+    assert_eq!(Some(noon), created(2));
+    assert_eq!(Some(1_709_164_800), created(3).map(|t| t.timestamp));
+    assert_eq!(None, created(4));
+    assert_eq!(Some(500_000_000), created(5).map(|t| t.nanos));
+
+    assert_eq!("2024-03-01T12:30:00Z", noon.to_string());
+    assert_eq!("2024-03-01T13:30:00.25+01:00", later.to_string());
+    assert_eq!(Ok(later), DateTime::parse(&later.to_string()));
+    assert_eq!(
+        "1969-12-31T23:59:59Z",
+        DateTime::from_timestamp(-1).to_string()
+    );
+    assert_eq!(
+        Ok(DateTime {
+            timestamp: 0,
+            nanos: 0,
+            offset: -330
+        }),
+        DateTime::parse("1969-12-31 18:30:00-05:30")
+    );
+    for bad in &[
+        "2024-3-01",
+        "2024-13-01",
+        "2024-03-01T25:00:00",
+        "2024-03-01T12:30",
+        "x",
+    ] {
+        assert!(DateTime::parse(bad).is_err(), "{}", bad);
+    }
+
+    let mut attrs = GmlAttrs::new();
+    attrs.put_datetime("created", later);
+    assert_eq!(
+        Some("2024-03-01T13:30:00.25+01:00"),
+        attrs.get("created").and_then(GmlValue::get_str)
+    );
+    assert_eq!(Some(later), attrs.get_datetime("created"));
+}
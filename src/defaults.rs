@@ -0,0 +1,195 @@
+//! Default attributes of nodes and edges, declared once for the document,
+//! like the `<default>` of GraphML keys.
+//!
+//! The defaults are a list of the graph:
+//!
+//! ```text
+//! graph [
+//!   defaults [ node [ color "gray" graphics [ w 30 ] ] edge [ weight 1 ] ]
+//!   node [ id 1 ]
+//!   node [ id 2 color "red" ]
+//! ]
+//! ```
+//!
+//! Some tools write them as the graph lists `nodeDefaults` and
+//! `edgeDefaults`, or `node_defaults` and `edge_defaults`, which are read
+//! as well. A node or edge takes the defaults it has no value for; nested
+//! lists are completed key by key.
+
+use crate::document::{remove_attr, GmlAttrs, GmlDocument, GmlValue};
+use crate::writer::write_gml;
+use std::io::{self, Write};
+
+/// The default attributes of nodes and edges.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Defaults {
+    pub node: GmlAttrs,
+    pub edge: GmlAttrs,
+}
+
+const VARIANTS: [(&str, &str); 2] = [
+    ("nodeDefaults", "edgeDefaults"),
+    ("node_defaults", "edge_defaults"),
+];
+
+fn take_list(attrs: &mut GmlAttrs, key: &str) -> GmlAttrs {
+    match remove_attr(attrs, key) {
+        Some(GmlValue::List(list)) => list,
+        Some(other) => {
+            attrs.insert(key.to_string(), other);
+            GmlAttrs::new()
+        }
+        None => GmlAttrs::new(),
+    }
+}
+
+// Adds the entries of `defaults` which `attrs` has no value for.
+fn fill(attrs: &mut GmlAttrs, defaults: &GmlAttrs) {
+    for (key, default) in defaults {
+        match (attrs.get_mut(key), default) {
+            (None, _) => {
+                attrs.insert(key.clone(), default.clone());
+            }
+            (Some(GmlValue::List(inner)), GmlValue::List(defaults)) => fill(inner, defaults),
+            (Some(_), _) => {}
+        }
+    }
+}
+
+// Removes the entries of `attrs` equal to those of `defaults`, and nested
+// lists left empty.
+fn strip(attrs: &mut GmlAttrs, defaults: &GmlAttrs) {
+    for (key, default) in defaults {
+        let remove = match (attrs.get_mut(key), default) {
+            (Some(GmlValue::List(inner)), GmlValue::List(defaults)) => {
+                strip(inner, defaults);
+                inner.is_empty()
+            }
+            (Some(value), default) => value == default,
+            (None, _) => false,
+        };
+        if remove {
+            remove_attr(attrs, key);
+        }
+    }
+}
+
+impl Defaults {
+    /// Removes the declared defaults from the graph attributes of `doc`.
+    /// Where several forms are given, the `defaults` list wins.
+    pub fn take(doc: &mut GmlDocument) -> Defaults {
+        let mut defaults = Defaults::default();
+        for &(node, edge) in VARIANTS.iter().rev() {
+            defaults.node.extend(take_list(&mut doc.attrs, node));
+            defaults.edge.extend(take_list(&mut doc.attrs, edge));
+        }
+        let mut list = take_list(&mut doc.attrs, "defaults");
+        defaults.node.extend(take_list(&mut list, "node"));
+        defaults.edge.extend(take_list(&mut list, "edge"));
+        if !list.is_empty() {
+            doc.attrs
+                .insert("defaults".to_string(), GmlValue::List(list));
+        }
+        defaults
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.node.is_empty() && self.edge.is_empty()
+    }
+}
+
+/// Removes the declared defaults from `doc` and gives every node and edge
+/// the ones it has no value for. Returns the defaults, for `compact`.
+pub fn apply_defaults(doc: &mut GmlDocument) -> Defaults {
+    let defaults = Defaults::take(doc);
+    for node in &mut doc.nodes {
+        fill(&mut node.attrs, &defaults.node);
+    }
+    for edge in &mut doc.edges {
+        fill(&mut edge.attrs, &defaults.edge);
+    }
+    defaults
+}
+
+/// Declares `defaults` in `doc`, as a `defaults` list, and removes the
+/// values of nodes and edges equal to them. Undone by `apply_defaults`.
+pub fn compact(doc: &mut GmlDocument, defaults: &Defaults) {
+    for node in &mut doc.nodes {
+        strip(&mut node.attrs, &defaults.node);
+    }
+    for edge in &mut doc.edges {
+        strip(&mut edge.attrs, &defaults.edge);
+    }
+    if defaults.is_empty() {
+        return;
+    }
+    let mut list = GmlAttrs::new();
+    for (key, attrs) in [("node", &defaults.node), ("edge", &defaults.edge)] {
+        if !attrs.is_empty() {
+            list.insert(key.to_string(), GmlValue::List(attrs.clone()));
+        }
+    }
+    doc.attrs
+        .insert("defaults".to_string(), GmlValue::List(list));
+}
+
+/// Writes `doc` as GML in the compact form of `compact`.
+pub fn write_gml_compact<W: Write>(
+    doc: &GmlDocument,
+    defaults: &Defaults,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut doc = doc.clone();
+    compact(&mut doc, defaults);
+    write_gml(&doc, w)
+}
+
+#[test]
+fn test_defaults() {
+    let gml =
+        "graph [ defaults [ node [ color \"gray\" graphics [ w 30 h 30 ] ] edge [ weight 1 ] ]
+                       node [ id 1 ] node [ id 2 color \"red\" graphics [ w 50 ] ]
+                       edge [ source 1 target 2 ] ]";
+    let mut doc = crate::parse_gml_document(gml).unwrap();
+    let defaults = apply_defaults(&mut doc);
+    assert!(doc.attrs.is_empty());
+    let color = |i: usize, doc: &GmlDocument| doc.nodes[i].attrs.get("color").cloned();
+    assert_eq!(Some(GmlValue::from("gray")), color(0, &doc));
+    assert_eq!(Some(GmlValue::from("red")), color(1, &doc));
+    let graphics = &doc.nodes[1].attrs["graphics"];
+    assert_eq!(
+        (Some(50), Some(30)),
+        (
+            graphics.get("w").and_then(GmlValue::get_int),
+            graphics.get("h").and_then(GmlValue::get_int)
+        )
+    );
+    assert_eq!(Some(&GmlValue::Int(1)), doc.edges[0].attrs.get("weight"));
+
+    let mut buf = Vec::new();
+    write_gml_compact(&doc, &defaults, &mut buf).unwrap();
+    let mut written = crate::parse_gml_document(std::str::from_utf8(&buf).unwrap()).unwrap();
+    assert!(written.nodes[0].attrs.is_empty());
+    assert!(written.edges[0].attrs.is_empty());
+    assert_eq!(
+        1,
+        written.nodes[1].attrs["graphics"].get_list().unwrap().len()
+    );
+    assert_eq!(defaults, apply_defaults(&mut written));
+    assert_eq!(doc, written);
+
+    let mut doc = crate::parse_gml_document(
+        "graph [ nodeDefaults [ shape \"box\" ] edge_defaults [ style \"dashed\" ]
+                 node [ id 1 ] edge [ source 1 target 1 ] ]",
+    )
+    .unwrap();
+    apply_defaults(&mut doc);
+    assert_eq!(
+        Some(&GmlValue::from("box")),
+        doc.nodes[0].attrs.get("shape")
+    );
+    assert_eq!(
+        Some(&GmlValue::from("dashed")),
+        doc.edges[0].attrs.get("style")
+    );
+}
@@ -0,0 +1,232 @@
+//! Guessing the format of graph files from their first bytes.
+
+/// The format `detect_format` guesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatGuess {
+    Gml,
+    Dot,
+    GraphMl,
+    Gexf,
+    Pajek,
+    /// Lines of two or three fields, a source, a target and maybe a weight.
+    EdgeList,
+    /// Compressed with gzip, in any format.
+    Gzip,
+    /// Compressed with Zstandard, in any format.
+    Zstd,
+    Unknown,
+}
+
+// How much of the input is looked at.
+const PREFIX: usize = 4096;
+
+// Skips whitespace and comments starting with `#`, or `//` and `/* */` for
+// DOT, at the start of `s`.
+fn skip_comments(mut s: &str, dot: bool) -> &str {
+    loop {
+        s = s.trim_start();
+        if s.starts_with('#') || (dot && s.starts_with("//")) {
+            s = s.find('\n').map_or("", |end| &s[end..]);
+        } else if dot && s.starts_with("/*") {
+            s = s.find("*/").map_or("", |end| &s[end + 2..]);
+        } else {
+            return s;
+        }
+    }
+}
+
+// The word at the start of `s` and the rest.
+fn word(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    (&s[..end], &s[end..])
+}
+
+fn is_dot(s: &str) -> bool {
+    let (mut first, mut rest) = word(skip_comments(s, true));
+    if first.eq_ignore_ascii_case("strict") {
+        let next = word(skip_comments(rest, true));
+        first = next.0;
+        rest = next.1;
+    }
+    if !(first.eq_ignore_ascii_case("graph") || first.eq_ignore_ascii_case("digraph")) {
+        return false;
+    }
+    let rest = skip_comments(rest, true);
+    let rest = match rest.chars().next() {
+        Some('{') => return true,
+        Some('"') => rest[1..].find('"').map_or("", |end| &rest[end + 2..]),
+        _ => word(rest).1,
+    };
+    skip_comments(rest, true).starts_with('{')
+}
+
+// Whether `s` starts with `key value` pairs up to `graph [`.
+fn is_gml(s: &str) -> bool {
+    let mut s = skip_comments(s, false);
+    loop {
+        let (key, rest) = word(s);
+        if key.is_empty() || !key.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return false;
+        }
+        let rest = skip_comments(rest, false);
+        if key == "graph" && rest.starts_with('[') {
+            return true;
+        }
+        // the value of a top-level key other than `graph`
+        s = match rest.chars().next() {
+            Some('"') => match rest[1..].find('"') {
+                Some(end) => &rest[end + 2..],
+                None => return false,
+            },
+            Some('[') => return false,
+            Some(_) => rest
+                .find(char::is_whitespace)
+                .map_or("", |end| &rest[end..]),
+            None => return false,
+        };
+        s = skip_comments(s, false);
+    }
+}
+
+fn is_pajek(s: &str) -> bool {
+    let line = s
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('%'));
+    line.is_some_and(|line| {
+        let (keyword, _) = word(line.strip_prefix('*').unwrap_or("!"));
+        [
+            "vertices",
+            "arcs",
+            "edges",
+            "network",
+            "arcslist",
+            "edgeslist",
+        ]
+        .iter()
+        .any(|k| keyword.eq_ignore_ascii_case(k))
+    })
+}
+
+fn is_edge_list(s: &str, truncated: bool) -> bool {
+    let mut lines: Vec<_> = s.lines().collect();
+    if truncated {
+        // the last line may be cut off
+        lines.pop();
+    }
+    let rows: Vec<_> = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('%'))
+        .collect();
+    let field = |f: &str| {
+        f.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '+')
+    };
+    !rows.is_empty()
+        && rows.iter().all(|row| {
+            let fields: Vec<_> = row
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|f| !f.is_empty())
+                .collect();
+            (2..=3).contains(&fields.len()) && fields.iter().all(|&f| field(f))
+        })
+}
+
+/// Guesses the format of a file from its first bytes, at most 4 KiB of
+/// which are looked at, so `bytes` may be just the start of the file.
+/// Compressed input is recognized by its magic number only; decompress it
+/// and call again to learn the format inside.
+pub fn detect_format(bytes: &[u8]) -> FormatGuess {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return FormatGuess::Gzip;
+    }
+    if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        return FormatGuess::Zstd;
+    }
+    let truncated = bytes.len() > PREFIX;
+    let prefix = &bytes[..bytes.len().min(PREFIX)];
+    // a prefix may end within a character
+    let s = match std::str::from_utf8(prefix) {
+        Ok(s) => s,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&prefix[..e.valid_up_to()]).unwrap()
+        }
+        Err(_) => return FormatGuess::Unknown,
+    };
+    let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+
+    if s.trim_start().starts_with('<') {
+        let lower = s.to_ascii_lowercase();
+        if lower.contains("<graphml") {
+            FormatGuess::GraphMl
+        } else if lower.contains("<gexf") {
+            FormatGuess::Gexf
+        } else {
+            FormatGuess::Unknown
+        }
+    } else if is_gml(s) {
+        FormatGuess::Gml
+    } else if is_dot(s) {
+        FormatGuess::Dot
+    } else if is_pajek(s) {
+        FormatGuess::Pajek
+    } else if is_edge_list(s, truncated) {
+        FormatGuess::EdgeList
+    } else {
+        FormatGuess::Unknown
+    }
+}
+
+#[test]
+fn test_detect_format() {
+    let guess = |s: &str| detect_format(s.as_bytes());
+    assert_eq!(FormatGuess::Gml, guess("graph [ node [ id 1 ] ]"));
+    assert_eq!(
+        FormatGuess::Gml,
+        guess("# written by\nCreator \"me\" Version 2\ngraph\n[\n")
+    );
+    assert_eq!(FormatGuess::Dot, guess("digraph G { a -> b }"));
+    assert_eq!(
+        FormatGuess::Dot,
+        guess("// comment\nstrict graph \"x y\" {")
+    );
+    assert_eq!(FormatGuess::Dot, guess("graph {"));
+    assert_eq!(
+        FormatGuess::GraphMl,
+        guess("\u{feff}<?xml version=\"1.0\"?>\n<graphml xmlns=\"...\">")
+    );
+    assert_eq!(
+        FormatGuess::Gexf,
+        guess("<?xml version=\"1.0\"?><gexf version=\"1.3\">")
+    );
+    assert_eq!(FormatGuess::Unknown, guess("<html>"));
+    assert_eq!(
+        FormatGuess::Pajek,
+        guess("% exported\n*Vertices 3\n1 \"a\"\n")
+    );
+    assert_eq!(
+        FormatGuess::EdgeList,
+        guess("# src dst\n1 2\n2 3 0.5\n3,1\n")
+    );
+    assert_eq!(FormatGuess::Unknown, guess("hello world, how are you\n"));
+    assert_eq!(FormatGuess::Unknown, guess(""));
+    assert_eq!(FormatGuess::Gzip, detect_format(&[0x1f, 0x8b, 8, 0]));
+    assert_eq!(
+        FormatGuess::Zstd,
+        detect_format(&[0x28, 0xb5, 0x2f, 0xfd, 0])
+    );
+
+    let mut long = String::from("graph [\n");
+    while long.len() < 2 * PREFIX {
+        long.push_str("node [ id 1 label \"\u{e9}\" ]\n");
+    }
+    assert_eq!(FormatGuess::Gml, guess(&long));
+    let mut edges = String::new();
+    for i in 0..1000 {
+        edges.push_str(&format!("{} {}\n", i, i + 1));
+    }
+    assert_eq!(FormatGuess::EdgeList, guess(&edges));
+}
@@ -0,0 +1,533 @@
+//! Semantic differences between two GML documents.
+//!
+//! Nodes are matched by id. Edges are matched by source and target, the
+//! n-th edge between two nodes in one document with the n-th in the other;
+//! in two undirected documents, `1 -- 2` matches `2 -- 1`. Attributes are
+//! compared by dotted key, so the order of keys and elements in the text
+//! does not matter.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::json::{self, Json};
+use crate::writer::format_scalar;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A changed attribute, by dotted key. `old` is `None` for an added
+/// attribute, `new` for a removed one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttrChange {
+    pub key: String,
+    pub old: Option<GmlValue>,
+    pub new: Option<GmlValue>,
+}
+
+/// A node with the same id in both documents but different attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeChange {
+    pub id: u64,
+    pub changes: Vec<AttrChange>,
+}
+
+/// An edge in both documents with different attributes. `index` counts
+/// the edges between `source` and `target` before it, from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeChange {
+    pub source: u64,
+    pub target: u64,
+    pub index: usize,
+    pub changes: Vec<AttrChange>,
+}
+
+/// The differences `diff` returns. Added elements are in the order of the
+/// second document, removed ones in that of the first, and changed nodes
+/// by id.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GmlDiff {
+    /// The `directed` flags of both documents.
+    pub directed: (bool, bool),
+    pub graph: Vec<AttrChange>,
+    pub added_nodes: Vec<GmlNode>,
+    pub removed_nodes: Vec<GmlNode>,
+    pub changed_nodes: Vec<NodeChange>,
+    pub added_edges: Vec<GmlEdge>,
+    pub removed_edges: Vec<GmlEdge>,
+    pub changed_edges: Vec<EdgeChange>,
+}
+
+fn diff_attrs(a: &GmlAttrs, b: &GmlAttrs) -> Vec<AttrChange> {
+    let old: BTreeMap<_, _> = flatten(a).into_iter().collect();
+    let new: BTreeMap<_, _> = flatten(b).into_iter().collect();
+    let mut changes = Vec::new();
+    for (key, &value) in &old {
+        match new.get(key) {
+            Some(&other) if other == value => {}
+            other => changes.push(AttrChange {
+                key: key.clone(),
+                old: Some(value.clone()),
+                new: other.map(|&v| v.clone()),
+            }),
+        }
+    }
+    for (key, &value) in &new {
+        if !old.contains_key(key) {
+            changes.push(AttrChange {
+                key: key.clone(),
+                old: None,
+                new: Some(value.clone()),
+            });
+        }
+    }
+    changes.sort_by(|x, y| x.key.cmp(&y.key));
+    changes
+}
+
+/// Numbers the edges of `doc` by their endpoints, unordered if
+/// `undirected`, and by how many edges between them come before.
+fn edge_keys(doc: &GmlDocument, undirected: bool) -> Vec<(u64, u64, usize)> {
+    let mut seen = HashMap::new();
+    doc.edges
+        .iter()
+        .map(|edge| {
+            let (s, t) = (edge.source, edge.target);
+            let ends = if undirected && s > t { (t, s) } else { (s, t) };
+            let count = seen.entry(ends).or_insert(0);
+            *count += 1;
+            (ends.0, ends.1, *count - 1)
+        })
+        .collect()
+}
+
+/// Compares the documents `a` and `b`.
+pub fn diff(a: &GmlDocument, b: &GmlDocument) -> GmlDiff {
+    let mut result = GmlDiff {
+        directed: (a.directed, b.directed),
+        graph: diff_attrs(&a.attrs, &b.attrs),
+        ..GmlDiff::default()
+    };
+
+    let mut old_nodes = HashMap::new();
+    for node in &a.nodes {
+        old_nodes.entry(node.id).or_insert(node);
+    }
+    let mut new_nodes = HashMap::new();
+    for node in &b.nodes {
+        new_nodes.entry(node.id).or_insert(node);
+    }
+    for node in &a.nodes {
+        if !new_nodes.contains_key(&node.id) {
+            result.removed_nodes.push(node.clone());
+        }
+    }
+    for node in &b.nodes {
+        match old_nodes.get(&node.id) {
+            None => result.added_nodes.push(node.clone()),
+            Some(old) if std::ptr::eq(new_nodes[&node.id], node) => {
+                let changes = diff_attrs(&old.attrs, &node.attrs);
+                if !changes.is_empty() {
+                    result.changed_nodes.push(NodeChange {
+                        id: node.id,
+                        changes,
+                    });
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    result.changed_nodes.sort_by_key(|change| change.id);
+
+    let undirected = !a.directed && !b.directed;
+    let old_keys = edge_keys(a, undirected);
+    let new_keys = edge_keys(b, undirected);
+    let old_edges: HashMap<_, _> = old_keys.iter().zip(&a.edges).collect();
+    let new_edges: HashMap<_, _> = new_keys.iter().zip(&b.edges).collect();
+    for (key, edge) in old_keys.iter().zip(&a.edges) {
+        if !new_edges.contains_key(key) {
+            result.removed_edges.push(edge.clone());
+        }
+    }
+    for (key, edge) in new_keys.iter().zip(&b.edges) {
+        match old_edges.get(key) {
+            None => result.added_edges.push(edge.clone()),
+            Some(old) => {
+                let changes = diff_attrs(&old.attrs, &edge.attrs);
+                if !changes.is_empty() {
+                    result.changed_edges.push(EdgeChange {
+                        source: edge.source,
+                        target: edge.target,
+                        index: key.2,
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+fn show(value: &Option<GmlValue>) -> String {
+    match *value {
+        Some(GmlValue::List(_)) => "[ ]".to_string(),
+        Some(ref value) => format_scalar(value),
+        None => String::new(),
+    }
+}
+
+fn show_attrs(attrs: &GmlAttrs) -> String {
+    flatten(attrs)
+        .into_iter()
+        .map(|(key, value)| format!(" {} {}", key, format_scalar(value)))
+        .collect()
+}
+
+fn attr_changes_json(changes: &[AttrChange]) -> Json {
+    let value = |v: &Option<GmlValue>| v.as_ref().map_or(Json::Null, json::from_value);
+    Json::Array(
+        changes
+            .iter()
+            .map(|change| {
+                Json::Object(vec![
+                    ("key".to_string(), Json::Str(change.key.clone())),
+                    ("old".to_string(), value(&change.old)),
+                    ("new".to_string(), value(&change.new)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn element_json(ends: Vec<(&str, u64)>, attrs: &GmlAttrs) -> Json {
+    let mut members: Vec<_> = ends
+        .into_iter()
+        .map(|(key, id)| (key.to_string(), Json::from(id)))
+        .collect();
+    members.push((
+        "attrs".to_string(),
+        json::from_value(&GmlValue::List(attrs.clone())),
+    ));
+    Json::Object(members)
+}
+
+impl GmlDiff {
+    /// Whether the documents are equal up to order.
+    pub fn is_empty(&self) -> bool {
+        self.directed.0 == self.directed.1
+            && self.graph.is_empty()
+            && self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.changed_edges.is_empty()
+    }
+
+    fn edge_name(&self, source: u64, target: u64, index: usize) -> String {
+        let arrow = if self.directed.0 || self.directed.1 {
+            "->"
+        } else {
+            "--"
+        };
+        if index == 0 {
+            format!("edge {} {} {}", source, arrow, target)
+        } else {
+            format!("edge {} {} {} #{}", source, arrow, target, index)
+        }
+    }
+
+    /// Renders the differences as JSON, with `graph`, `nodes` and `edges`
+    /// members, and `directed` if the flags differ. Values are `null` where
+    /// an attribute is absent.
+    pub fn to_json(&self) -> String {
+        let node = |n: &GmlNode| element_json(vec![("id", n.id)], &n.attrs);
+        let edge =
+            |e: &GmlEdge| element_json(vec![("source", e.source), ("target", e.target)], &e.attrs);
+        let mut members = Vec::new();
+        if self.directed.0 != self.directed.1 {
+            let (old, new) = self.directed;
+            members.push((
+                "directed".to_string(),
+                Json::Object(vec![
+                    ("old".to_string(), Json::Bool(old)),
+                    ("new".to_string(), Json::Bool(new)),
+                ]),
+            ));
+        }
+        members.push(("graph".to_string(), attr_changes_json(&self.graph)));
+        let changed_nodes = self.changed_nodes.iter().map(|change| {
+            Json::Object(vec![
+                ("id".to_string(), Json::from(change.id)),
+                ("changes".to_string(), attr_changes_json(&change.changes)),
+            ])
+        });
+        members.push((
+            "nodes".to_string(),
+            Json::Object(vec![
+                (
+                    "added".to_string(),
+                    Json::Array(self.added_nodes.iter().map(node).collect()),
+                ),
+                (
+                    "removed".to_string(),
+                    Json::Array(self.removed_nodes.iter().map(node).collect()),
+                ),
+                ("changed".to_string(), Json::Array(changed_nodes.collect())),
+            ]),
+        ));
+        let changed_edges = self.changed_edges.iter().map(|change| {
+            Json::Object(vec![
+                ("source".to_string(), Json::from(change.source)),
+                ("target".to_string(), Json::from(change.target)),
+                ("index".to_string(), Json::Int(change.index as i64)),
+                ("changes".to_string(), attr_changes_json(&change.changes)),
+            ])
+        });
+        members.push((
+            "edges".to_string(),
+            Json::Object(vec![
+                (
+                    "added".to_string(),
+                    Json::Array(self.added_edges.iter().map(edge).collect()),
+                ),
+                (
+                    "removed".to_string(),
+                    Json::Array(self.removed_edges.iter().map(edge).collect()),
+                ),
+                ("changed".to_string(), Json::Array(changed_edges.collect())),
+            ]),
+        ));
+        Json::Object(members).to_string_pretty()
+    }
+}
+
+/// One line per difference: `+` for added, `-` for removed and `~` for
+/// changed elements, followed by their changed attributes.
+impl fmt::Display for GmlDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let changes = |f: &mut fmt::Formatter, name: &str, changes: &[AttrChange]| {
+            for change in changes {
+                let sign = match (&change.old, &change.new) {
+                    (None, _) => '+',
+                    (_, None) => '-',
+                    _ => '~',
+                };
+                write!(f, "{} {} {}:", sign, name, change.key)?;
+                match (&change.old, &change.new) {
+                    (Some(_), Some(_)) => {
+                        writeln!(f, " {} -> {}", show(&change.old), show(&change.new))?
+                    }
+                    (Some(_), None) => writeln!(f, " {}", show(&change.old))?,
+                    _ => writeln!(f, " {}", show(&change.new))?,
+                }
+            }
+            Ok(())
+        };
+        if self.directed.0 != self.directed.1 {
+            let (old, new) = self.directed;
+            writeln!(f, "~ directed: {} -> {}", old as u8, new as u8)?;
+        }
+        changes(f, "graph", &self.graph)?;
+        for node in &self.removed_nodes {
+            writeln!(f, "- node {}{}", node.id, show_attrs(&node.attrs))?;
+        }
+        for node in &self.added_nodes {
+            writeln!(f, "+ node {}{}", node.id, show_attrs(&node.attrs))?;
+        }
+        for change in &self.changed_nodes {
+            changes(f, &format!("node {}", change.id), &change.changes)?;
+        }
+        for edge in &self.removed_edges {
+            let name = self.edge_name(edge.source, edge.target, 0);
+            writeln!(f, "- {}{}", name, show_attrs(&edge.attrs))?;
+        }
+        for edge in &self.added_edges {
+            let name = self.edge_name(edge.source, edge.target, 0);
+            writeln!(f, "+ {}{}", name, show_attrs(&edge.attrs))?;
+        }
+        for change in &self.changed_edges {
+            let name = self.edge_name(change.source, change.target, change.index);
+            changes(f, &name, &change.changes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Options for `graphs_equal`.
+#[derive(Debug, Clone)]
+pub struct EqualOptions {
+    /// Whether attributes are compared, or only the nodes and edges.
+    pub attributes: bool,
+    /// Keys of attributes that are not compared, dotted for nested ones; a
+    /// key of a list also ignores what is in it.
+    pub ignore_keys: Vec<String>,
+    /// How many mismatches are reported.
+    pub max_mismatches: usize,
+}
+
+impl Default for EqualOptions {
+    fn default() -> EqualOptions {
+        EqualOptions {
+            attributes: true,
+            ignore_keys: Vec::new(),
+            max_mismatches: 10,
+        }
+    }
+}
+
+/// The differences `graphs_equal` found, at most `max_mismatches` of them,
+/// each like a line of the `GmlDiff` text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatches {
+    pub mismatches: Vec<String>,
+    /// The number of all mismatches.
+    pub count: usize,
+}
+
+impl fmt::Display for Mismatches {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} mismatches", self.count)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        if self.count > self.mismatches.len() {
+            write!(f, "\n  ...")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares `a` and `b` like `diff`, with nodes matched by id and edges by
+/// their ends, and returns the first mismatches if they differ.
+pub fn graphs_equal(
+    a: &GmlDocument,
+    b: &GmlDocument,
+    options: &EqualOptions,
+) -> Result<(), Mismatches> {
+    let mut d = diff(a, b);
+    let ignored = |change: &AttrChange| {
+        !options.attributes
+            || options.ignore_keys.iter().any(|key| {
+                change.key == *key
+                    || (change.key.starts_with(key.as_str())
+                        && change.key[key.len()..].starts_with('.'))
+            })
+    };
+    d.graph.retain(|change| !ignored(change));
+    for node in &mut d.changed_nodes {
+        node.changes.retain(|change| !ignored(change));
+    }
+    d.changed_nodes.retain(|node| !node.changes.is_empty());
+    for edge in &mut d.changed_edges {
+        edge.changes.retain(|change| !ignored(change));
+    }
+    d.changed_edges.retain(|edge| !edge.changes.is_empty());
+    if d.is_empty() {
+        return Ok(());
+    }
+    let text = d.to_string();
+    let lines: Vec<_> = text.lines().collect();
+    Err(Mismatches {
+        mismatches: lines
+            .iter()
+            .take(options.max_mismatches)
+            .map(|line| line.to_string())
+            .collect(),
+        count: lines.len(),
+    })
+}
+
+#[test]
+fn test_diff() {
+    use crate::document::parse_gml_document;
+
+    let a = parse_gml_document(
+        "graph [ label \"a\"
+           node [ id 1 label \"x\" ] node [ id 2 label \"y\" graphics [ x 1 ] ]
+           node [ id 3 ]
+           edge [ source 1 target 2 weight 1 ] edge [ source 2 target 3 ] ]",
+    )
+    .unwrap();
+    let b = parse_gml_document(
+        "graph [ label \"a\"
+           node [ id 4 ] node [ id 2 label \"z\" graphics [ x 2 ] color \"red\" ]
+           node [ id 1 label \"x\" ]
+           edge [ source 2 target 1 weight 2 ] edge [ source 1 target 4 ] ]",
+    )
+    .unwrap();
+    assert!(diff(&a, &a).is_empty());
+    let d = diff(&a, &b);
+    assert_eq!((false, false), d.directed);
+    assert!(d.graph.is_empty());
+    assert_eq!(
+        vec![4],
+        d.added_nodes.iter().map(|n| n.id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![3],
+        d.removed_nodes.iter().map(|n| n.id).collect::<Vec<_>>()
+    );
+    assert_eq!(1, d.changed_nodes.len());
+    let keys: Vec<_> = d.changed_nodes[0]
+        .changes
+        .iter()
+        .map(|c| &c.key[..])
+        .collect();
+    assert_eq!(vec!["color", "graphics.x", "label"], keys);
+    assert_eq!(None, d.changed_nodes[0].changes[0].old);
+    assert_eq!(1, d.changed_edges.len());
+    assert_eq!(
+        (2, 1),
+        (d.changed_edges[0].source, d.changed_edges[0].target)
+    );
+    assert_eq!((1, 1), (d.added_edges.len(), d.removed_edges.len()));
+
+    let text = d.to_string();
+    assert!(text.contains("- node 3\n"));
+    assert!(text.contains("+ node 2 color: \"red\"\n"));
+    assert!(text.contains("~ node 2 label: \"y\" -> \"z\"\n"));
+    assert!(text.contains("~ edge 2 -- 1 weight: 1 -> 2\n"));
+    assert!(text.contains("+ edge 1 -- 4\n"));
+
+    let parsed = json::parse(&d.to_json()).unwrap();
+    let nodes = parsed.get("nodes").unwrap();
+    assert_eq!(
+        Some(&Json::Array(vec![Json::Object(vec![
+            ("id".to_string(), Json::Int(4)),
+            ("attrs".to_string(), Json::Object(vec![])),
+        ])])),
+        nodes.get("added")
+    );
+    assert_eq!(None, parsed.get("directed"));
+
+    let options = EqualOptions::default();
+    assert_eq!(Ok(()), graphs_equal(&a, &a, &options));
+    let mismatches = graphs_equal(&a, &b, &options).unwrap_err();
+    assert_eq!(text.lines().count(), mismatches.count);
+    assert_eq!(10.min(mismatches.count), mismatches.mismatches.len());
+    let options = EqualOptions {
+        attributes: false,
+        max_mismatches: 1,
+        ..EqualOptions::default()
+    };
+    let mismatches = graphs_equal(&a, &b, &options).unwrap_err();
+    assert_eq!(
+        (4, vec!["- node 3".to_string()]),
+        (mismatches.count, mismatches.mismatches)
+    );
+    let mut c = a.clone();
+    c.nodes[1].attrs.remove("graphics");
+    c.nodes[1]
+        .attrs
+        .insert("extra".to_string(), GmlValue::Int(1));
+    let options = EqualOptions {
+        ignore_keys: vec!["graphics".to_string(), "extra".to_string()],
+        ..EqualOptions::default()
+    };
+    assert_eq!(Ok(()), graphs_equal(&a, &c, &options));
+    let options = EqualOptions {
+        ignore_keys: vec!["graph".to_string()],
+        ..EqualOptions::default()
+    };
+    assert_eq!(
+        "2 mismatches\n  + node 2 extra: 1\n  - node 2 graphics.x: 1",
+        graphs_equal(&a, &c, &options).unwrap_err().to_string()
+    );
+}
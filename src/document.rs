@@ -0,0 +1,528 @@
+use crate::events::{document_parts, OwnedLists};
+use crate::node_map::NodeMap;
+use crate::trace::{self, event, span};
+#[cfg(test)]
+use petgraph::graph::NodeIndex;
+use petgraph::{Directed, Graph};
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+/// The map type of attribute lists: a `BTreeMap` ordered by key, or, with
+/// the `preserve_order` feature, an `IndexMap` in document order with hashed
+/// lookups.
+#[cfg(not(feature = "preserve_order"))]
+pub type AttrMap<K, V> = BTreeMap<K, V>;
+#[cfg(feature = "preserve_order")]
+pub type AttrMap<K, V> = indexmap::IndexMap<K, V>;
+
+/// Attributes of a GML list (`key value` pairs).
+pub type GmlAttrs = AttrMap<String, GmlValue>;
+
+/// Removes `key` from `map`, keeping the order of the other entries.
+#[cfg(not(feature = "preserve_order"))]
+pub(crate) fn remove_attr<K, V, Q>(map: &mut AttrMap<K, V>, key: &Q) -> Option<V>
+where
+    K: std::borrow::Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    map.remove(key)
+}
+
+/// Removes `key` from `map`, keeping the order of the other entries.
+#[cfg(feature = "preserve_order")]
+pub(crate) fn remove_attr<K, V, Q>(map: &mut AttrMap<K, V>, key: &Q) -> Option<V>
+where
+    K: std::borrow::Borrow<Q> + std::hash::Hash + Eq,
+    Q: std::hash::Hash + Eq + ?Sized,
+{
+    map.shift_remove(key)
+}
+
+/// The string type of `GmlValue`. It is the same type with and without the
+/// `compact-strings` feature, which only changes how it is stored.
+pub type GmlString = crate::compact::CompactString;
+
+/// A GML value: an integer, a real, a string or a nested list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GmlValue {
+    Int(i64),
+    /// An integer beyond `i64::MAX`. Smaller non-negative integers are
+    /// always `Int`.
+    UInt(u64),
+    Float(f64),
+    Str(GmlString),
+    List(GmlAttrs),
+}
+
+impl GmlValue {
+    pub fn get_int(&self) -> Option<i64> {
+        match *self {
+            GmlValue::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as `u64`, if it is a non-negative integer.
+    pub fn get_uint(&self) -> Option<u64> {
+        match *self {
+            GmlValue::Int(i) if i >= 0 => Some(i as u64),
+            GmlValue::UInt(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as `f64`. Integers are converted.
+    pub fn get_float(&self) -> Option<f64> {
+        match *self {
+            GmlValue::Int(i) => Some(i as f64),
+            GmlValue::UInt(u) => Some(u as f64),
+            GmlValue::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self) -> Option<&str> {
+        match *self {
+            GmlValue::Str(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as `bool`. GML has no booleans; the integers 0 and
+    /// 1 and the strings `true`, `false`, `yes`, `no`, `1` and `0`, in any
+    /// case, are taken as such.
+    pub fn get_bool(&self) -> Option<bool> {
+        match *self {
+            GmlValue::Int(0) => Some(false),
+            GmlValue::Int(1) => Some(true),
+            GmlValue::Str(ref s) => match s.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Some(true),
+                "false" | "no" | "0" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn get_list(&self) -> Option<&GmlAttrs> {
+        match *self {
+            GmlValue::List(ref l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is a list.
+    pub fn get(&self, key: &str) -> Option<&GmlValue> {
+        self.get_list().and_then(|l| l.get(key))
+    }
+
+    /// The values of a key which may be repeated, like `point`: in order,
+    /// if this is the list parsing makes of the values of a repeated key,
+    /// or else this value alone.
+    pub fn values(&self) -> Vec<&GmlValue> {
+        repeated(self).unwrap_or_else(|| vec![self])
+    }
+
+    /// The value of a key with the repeated `values`, written back as one
+    /// entry for each value. Empty if there are none.
+    pub fn from_values(values: Vec<GmlValue>) -> GmlValue {
+        if values.len() == 1 {
+            return values.into_iter().next().unwrap();
+        }
+        GmlValue::List(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| (i.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+/// The values of a repeated key, in order, if `value` is the list of them.
+///
+/// GML allows a key more than once in a list, like the `point`s of a
+/// `Line`. Parsing keeps its values in a list under the keys `0`, `1` and
+/// so on, which are not GML keys, and writing repeats the key again.
+pub(crate) fn repeated(value: &GmlValue) -> Option<Vec<&GmlValue>> {
+    let list = value.get_list().filter(|l| l.len() > 1)?;
+    let mut values = vec![None; list.len()];
+    for (key, value) in list {
+        let i: usize = key.parse().ok().filter(|&i| i < list.len())?;
+        values[i] = Some(value);
+    }
+    values.into_iter().collect()
+}
+
+/// Removes the id under `key`, failing with `error` unless it is a
+/// non-negative integer, or with "duplicate key" if `key` is repeated.
+pub(crate) fn take_id(
+    attrs: &mut GmlAttrs,
+    key: &str,
+    error: &'static str,
+) -> Result<u64, &'static str> {
+    match remove_attr(attrs, key) {
+        Some(ref value) if repeated(value).is_some() => Err("duplicate key"),
+        Some(ref value) => value.get_uint().ok_or(error),
+        None => Err(error),
+    }
+}
+
+/// Inserts `value` under `key`, adding it to the values of `key` if it is
+/// present.
+pub(crate) fn insert_attr(attrs: &mut GmlAttrs, key: &str, value: GmlValue) {
+    match attrs.get_mut(key) {
+        None => {
+            attrs.insert(key.to_string(), value);
+        }
+        Some(present) => {
+            if repeated(present).is_none() {
+                let first = std::mem::replace(present, GmlValue::List(GmlAttrs::new()));
+                *present = GmlValue::from_values(vec![first, value]);
+            } else if let GmlValue::List(ref mut list) = *present {
+                list.insert(list.len().to_string(), value);
+            }
+        }
+    }
+}
+
+impl From<String> for GmlValue {
+    fn from(s: String) -> GmlValue {
+        GmlValue::Str(s.into())
+    }
+}
+
+impl<'a> From<&'a str> for GmlValue {
+    fn from(s: &'a str) -> GmlValue {
+        GmlValue::Str(s.into())
+    }
+}
+
+/// The type of a scalar attribute, as used by the typed export formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ValueType {
+    Int,
+    Long,
+    /// Integers beyond `i64::MAX`.
+    UnsignedLong,
+    Double,
+    String,
+}
+
+impl ValueType {
+    pub(crate) fn of(value: &GmlValue) -> ValueType {
+        match *value {
+            GmlValue::Int(i) if i >= i32::MIN as i64 && i <= i32::MAX as i64 => ValueType::Int,
+            GmlValue::Int(_) => ValueType::Long,
+            GmlValue::UInt(_) => ValueType::UnsignedLong,
+            GmlValue::Float(_) => ValueType::Double,
+            _ => ValueType::String,
+        }
+    }
+
+    /// The narrowest type which can represent values of both types. No
+    /// integer type holds both `UnsignedLong` and signed values, so they
+    /// unify to `Double`.
+    pub(crate) fn unify(self, other: ValueType) -> ValueType {
+        use self::ValueType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (String, _) | (_, String) => String,
+            (Double, _) | (_, Double) => Double,
+            (UnsignedLong, _) | (_, UnsignedLong) => Double,
+            _ => Long,
+        }
+    }
+}
+
+/// Flattens nested lists into dotted names, e.g. `graphics.fill`.
+pub(crate) fn flatten(attrs: &GmlAttrs) -> Vec<(String, &GmlValue)> {
+    let mut out = Vec::new();
+    for (k, v) in attrs {
+        match *v {
+            GmlValue::List(ref inner) => {
+                for (ik, iv) in flatten(inner) {
+                    out.push((format!("{}.{}", k, ik), iv));
+                }
+            }
+            _ => out.push((k.clone(), v)),
+        }
+    }
+    out
+}
+
+/// Inserts `value` under a dotted name, creating nested lists as needed.
+/// The inverse of `flatten`.
+pub(crate) fn insert_dotted(attrs: &mut GmlAttrs, name: &str, value: GmlValue) {
+    match name.find('.') {
+        Some(i) => {
+            let entry = attrs
+                .entry(name[..i].to_string())
+                .or_insert_with(|| GmlValue::List(GmlAttrs::new()));
+            if let GmlValue::List(ref mut inner) = *entry {
+                insert_dotted(inner, &name[i + 1..], value);
+            }
+        }
+        None => {
+            attrs.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// Maps the node names of a foreign format to GML ids. If `parse` turns
+/// every name into a distinct id, those ids are returned; otherwise `None`,
+/// and callers number the nodes in order instead.
+pub(crate) fn numeric_ids<'a, I, F>(names: I, parse: F) -> Option<Vec<u64>>
+where
+    I: Iterator<Item = &'a str>,
+    F: Fn(&str) -> Option<u64>,
+{
+    let ids: Vec<u64> = names.map(parse).collect::<Option<_>>()?;
+    let unique: std::collections::BTreeSet<_> = ids.iter().collect();
+    if unique.len() == ids.len() {
+        Some(ids)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GmlNode {
+    pub id: u64,
+    /// All attributes except `id`.
+    pub attrs: GmlAttrs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GmlEdge {
+    pub source: u64,
+    pub target: u64,
+    /// All attributes except `source` and `target`.
+    pub attrs: GmlAttrs,
+}
+
+/// A parsed GML graph which keeps all attributes of the graph, its nodes and
+/// edges.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GmlDocument {
+    pub directed: bool,
+    /// Graph-level attributes except `directed`, `node` and `edge`.
+    pub attrs: GmlAttrs,
+    /// Top-level attributes outside the `graph` list, e.g. `Creator` and
+    /// `Version`.
+    pub top_attrs: GmlAttrs,
+    pub nodes: Vec<GmlNode>,
+    pub edges: Vec<GmlEdge>,
+}
+
+impl GmlNode {
+    pub fn label(&self) -> Option<&str> {
+        self.attrs.get("label").and_then(GmlValue::get_str)
+    }
+}
+
+impl GmlEdge {
+    pub fn label(&self) -> Option<&str> {
+        self.attrs.get("label").and_then(GmlValue::get_str)
+    }
+}
+
+impl GmlDocument {
+    /// Checks that the document is a graph, with the errors of `into_graph`
+    /// but without building it.
+    pub fn check(&self) -> Result<(), &'static str> {
+        let mut ids = HashSet::with_capacity(self.nodes.len());
+        if !self.nodes.iter().all(|node| ids.insert(node.id)) {
+            return Err("duplicate node-id");
+        }
+        if self
+            .edges
+            .iter()
+            .any(|edge| !ids.contains(&edge.source) || !ids.contains(&edge.target))
+        {
+            return Err("edge references unknown node");
+        }
+        Ok(())
+    }
+
+    /// Converts the document into a petgraph `Graph`. Nodes are added in
+    /// document order.
+    pub fn into_graph(self) -> Result<Graph<GmlNode, GmlEdge, Directed>, &'static str> {
+        let mut node_map = NodeMap::with_capacity(self.nodes.len());
+        let mut graph = Graph::with_capacity(self.nodes.len(), self.edges.len());
+
+        for node in self.nodes {
+            let id = node.id;
+            let idx = graph.add_node(node);
+            if !node_map.insert(id, idx) {
+                return Err("duplicate node-id");
+            }
+        }
+
+        for edge in self.edges {
+            match (node_map.get(edge.source), node_map.get(edge.target)) {
+                (Some(source_idx), Some(target_idx)) => {
+                    graph.add_edge(source_idx, target_idx, edge);
+                }
+                _ => {
+                    return Err("edge references unknown node");
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+/// Parses a GML string into a `GmlDocument`.
+pub fn parse_gml_document(s: &str) -> Result<GmlDocument, &'static str> {
+    span!("gml.parse_document", bytes = s.len());
+    let parts = match document_parts(s, &mut OwnedLists) {
+        Ok(parts) => parts,
+        Err(e) => {
+            trace::failed("parsing the document", &e);
+            return Err(e);
+        }
+    };
+    event!(
+        info,
+        nodes = parts.nodes.len(),
+        edges = parts.edges.len(),
+        "parsed document"
+    );
+    Ok(GmlDocument {
+        directed: parts.directed.unwrap_or(false),
+        attrs: parts.attrs,
+        top_attrs: parts.top_attrs,
+        nodes: parts
+            .nodes
+            .into_iter()
+            .map(|(id, attrs)| GmlNode { id, attrs })
+            .collect(),
+        edges: parts
+            .edges
+            .into_iter()
+            .map(|(source, target, attrs)| GmlEdge {
+                source,
+                target,
+                attrs,
+            })
+            .collect(),
+    })
+}
+
+#[test]
+fn test_parse_gml_document() {
+    let gml = "
+    graph [
+        directed 1
+        label \"test\"
+        node [ id 1 label \"a\" graphics [ fill \"#FF0000\" ] ]
+        node [ id 2 label \"b\" ]
+        edge [ source 1 target 2 weight 1.5 ]
+    ]
+    ";
+
+    let doc = parse_gml_document(gml).unwrap();
+    assert!(doc.directed);
+    assert_eq!(Some(&GmlValue::from("test")), doc.attrs.get("label"));
+    assert_eq!(2, doc.nodes.len());
+    assert_eq!(Some("a"), doc.nodes[0].label());
+    assert_eq!(
+        Some("#FF0000"),
+        doc.nodes[0].attrs["graphics"]
+            .get("fill")
+            .and_then(GmlValue::get_str)
+    );
+    assert_eq!(
+        Some(1.5),
+        doc.edges[0]
+            .attrs
+            .get("weight")
+            .and_then(GmlValue::get_float)
+    );
+
+    let g = doc.into_graph().unwrap();
+    assert!(g.find_edge(NodeIndex::new(0), NodeIndex::new(1)).is_some());
+}
+
+#[test]
+fn test_repeated_keys() {
+    let points: String = (0..12).map(|x| format!("point [ x {} y 0 ] ", x)).collect();
+    let gml = format!(
+        "graph [ edge [ source 1 target 2 graphics [ Line [ {}] ] ] ]",
+        points
+    );
+    let doc = parse_gml_document(&gml).unwrap();
+    let line = doc.edges[0].attrs["graphics"].get("Line").unwrap();
+    let xs: Vec<_> = line
+        .get("point")
+        .unwrap()
+        .values()
+        .iter()
+        .map(|p| p.get("x").and_then(GmlValue::get_int).unwrap())
+        .collect();
+    assert_eq!((0..12).collect::<Vec<_>>(), xs);
+    assert_eq!(doc, parse_gml_document(&crate::to_gml(&doc)).unwrap());
+
+    let single = GmlValue::from_values(vec![GmlValue::Int(1)]);
+    assert_eq!(vec![&GmlValue::Int(1)], single.values());
+    let doc = parse_gml_document("graph [ node [ id 1 x 1 x 2 ] ]").unwrap();
+    let x = &doc.nodes[0].attrs["x"];
+    assert_eq!(vec![&GmlValue::Int(1), &GmlValue::Int(2)], x.values());
+    assert_eq!(doc, parse_gml_document(&crate::to_gml(&doc)).unwrap());
+    let borrowed = crate::borrowed::parse_gml_document_borrowed("graph [ node [ id 1 x 1 x 2 ] ]");
+    assert_eq!(doc, borrowed.unwrap().into_owned());
+
+    let gml = "graph [ node [ id 1 id 2 ] ]";
+    let borrowed = crate::borrowed::parse_gml_document_borrowed(gml);
+    let interned = crate::intern::parse_gml_document_interned(gml);
+    assert_eq!([Some("duplicate key"); 2], [borrowed.err(), interned.err()]);
+}
+
+#[test]
+fn test_large_ids() {
+    let gml = "graph [ node [ id 18446744073709551615 x 9223372036854775808 ] \
+               node [ id 9223372036854775807 ] \
+               edge [ source 18446744073709551615 target 9223372036854775807 ] ]";
+    let doc = parse_gml_document(gml).unwrap();
+    assert_eq!(u64::MAX, doc.nodes[0].id);
+    assert_eq!(GmlValue::UInt(i64::MAX as u64 + 1), doc.nodes[0].attrs["x"]);
+    assert_eq!(
+        (u64::MAX, i64::MAX as u64),
+        (doc.edges[0].source, doc.edges[0].target)
+    );
+    assert_eq!(doc, parse_gml_document(&crate::to_gml(&doc)).unwrap());
+    let borrowed = crate::borrowed::parse_gml_document_borrowed(gml).unwrap();
+    let interned = crate::intern::parse_gml_document_interned(gml).unwrap();
+    assert_eq!(doc, borrowed.into_owned());
+    assert_eq!(doc, interned.into_document());
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn test_preserve_order() {
+    let gml =
+        "graph [ zeta 1 alpha 2 node [ label \"a\" id 1 weight 2 color \"red\" gfx [ ypos 1 xpos 2 ] ]
+                       edge [ target 1 omega 1 source 1 beta 2 ] ]";
+    let doc = parse_gml_document(gml).unwrap();
+    let keys: Vec<_> = doc.nodes[0].attrs.keys().map(|k| &k[..]).collect();
+    assert_eq!(vec!["label", "weight", "color", "gfx"], keys);
+    let written = crate::to_gml(&doc);
+    let order: Vec<_> = [
+        "zeta", "alpha", "label", "weight", "ypos", "xpos", "omega", "beta",
+    ]
+    .iter()
+    .map(|key| written.find(key).unwrap())
+    .collect();
+    assert!(order.windows(2).all(|w| w[0] < w[1]), "{}", written);
+    assert_eq!(
+        written,
+        crate::to_gml(&parse_gml_document(&written).unwrap())
+    );
+    let borrowed = crate::borrowed::parse_gml_document_borrowed(gml).unwrap();
+    assert_eq!(written, crate::to_gml(&borrowed.into_owned()));
+    let interned = crate::intern::parse_gml_document_interned(gml).unwrap();
+    assert_eq!(written, crate::to_gml(&interned.into_document()));
+}
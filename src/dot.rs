@@ -0,0 +1,799 @@
+//! Graphviz DOT import and export.
+
+use crate::document::{numeric_ids, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::graphics::{Arrow, ArrowHead, Graphics, Shape};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+/// Renders `doc` as a Graphviz DOT graph.
+pub fn to_dot(doc: &GmlDocument) -> String {
+    let mut buf = Vec::new();
+    write_dot(doc, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as a Graphviz DOT graph.
+///
+/// `label` and `weight` are written as the DOT attributes of the same name.
+/// The `fill` and `outline` colors of a `graphics` block become `fillcolor`
+/// (with `style=filled`) and `color` on nodes, and its `type` the nearest
+/// `shape`, unless the node has a `shape` of its own. On edges, `fill` becomes
+/// `color`, and the `arrow`, `sourceArrow` and `targetArrow` of an edge
+/// become `dir`, `arrowtail` and `arrowhead`. Other scalar attributes are
+/// passed through unchanged, nested lists are dropped.
+pub fn write_dot<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    let (keyword, edge_op) = if doc.directed {
+        ("digraph", "->")
+    } else {
+        ("graph", "--")
+    };
+
+    writeln!(w, "{} {{", keyword)?;
+    let graph_attrs = scalar_attrs(&doc.attrs);
+    if !graph_attrs.is_empty() {
+        writeln!(w, "  graph [{}];", format_attrs(&graph_attrs))?;
+    }
+
+    for node in &doc.nodes {
+        let mut attrs = scalar_attrs(&node.attrs);
+        if let Some(graphics) = node.attrs.get("graphics") {
+            let shape = match graphics.get("type").and_then(GmlValue::get_str) {
+                Some(_) if node.attrs.contains_key("shape") => None,
+                Some(name) => Some(Shape::from_name(name)),
+                None => None,
+            };
+            let fill = graphics.get("fill").and_then(GmlValue::get_str);
+            let mut style = Vec::new();
+            if shape == Some(Shape::RoundRectangle) {
+                style.push("rounded");
+            }
+            if fill.is_some() {
+                style.push("filled");
+            }
+            if !style.is_empty() {
+                attrs.push(("style".to_string(), style.join(",")));
+            }
+            if let Some(fill) = fill {
+                attrs.push(("fillcolor".to_string(), fill.to_string()));
+            }
+            if let Some(outline) = graphics.get("outline").and_then(GmlValue::get_str) {
+                attrs.push(("color".to_string(), outline.to_string()));
+            }
+            if let Some(shape) = shape {
+                attrs.push(("shape".to_string(), shape.dot_name().to_string()));
+            }
+        }
+        write_stmt(w, &node.id.to_string(), &attrs)?;
+    }
+
+    for edge in &doc.edges {
+        let mut attrs = scalar_attrs(&edge.attrs);
+        if let Some(graphics) = edge.attrs.get("graphics") {
+            if let Some(fill) = graphics.get("fill").and_then(GmlValue::get_str) {
+                attrs.push(("color".to_string(), fill.to_string()));
+            }
+        }
+        if let Some(graphics) = edge.graphics() {
+            arrow_attrs(&mut attrs, &graphics, doc.directed);
+        }
+        let stmt = format!("{} {} {}", edge.source, edge_op, edge.target);
+        write_stmt(w, &stmt, &attrs)?;
+    }
+
+    writeln!(w, "}}")
+}
+
+// The DOT arrows of an edge with `graphics`, if it sets any.
+fn arrow_attrs(attrs: &mut Vec<(String, String)>, graphics: &Graphics, directed: bool) {
+    let (source, target) = (&graphics.source_arrow, &graphics.target_arrow);
+    if graphics.arrow.is_none() && source.is_none() && target.is_none() {
+        return;
+    }
+    let drawn = |head: &Option<ArrowHead>| head.as_ref().is_some_and(|h| *h != ArrowHead::None);
+    let (tail, head) = match graphics.arrow {
+        Some(Arrow::None) => (false, false),
+        Some(Arrow::First) => (true, false),
+        Some(Arrow::Last) => (false, true),
+        Some(Arrow::Both) => (true, true),
+        None => (
+            drawn(source),
+            target.as_ref().map_or(directed, |_| drawn(target)),
+        ),
+    };
+    let dir = match (tail, head) {
+        (false, false) => "none",
+        (false, true) => "forward",
+        (true, false) => "back",
+        (true, true) => "both",
+    };
+    attrs.push(("dir".to_string(), dir.to_string()));
+    if let (true, Some(target)) = (head, target) {
+        attrs.push(("arrowhead".to_string(), target.dot_name().to_string()));
+    }
+    if let (true, Some(source)) = (tail, source) {
+        attrs.push(("arrowtail".to_string(), source.dot_name().to_string()));
+    }
+}
+
+fn write_stmt<W: Write>(w: &mut W, stmt: &str, attrs: &[(String, String)]) -> io::Result<()> {
+    if attrs.is_empty() {
+        writeln!(w, "  {};", stmt)
+    } else {
+        writeln!(w, "  {} [{}];", stmt, format_attrs(attrs))
+    }
+}
+
+fn scalar_attrs(attrs: &GmlAttrs) -> Vec<(String, String)> {
+    attrs
+        .iter()
+        .filter_map(|(k, v)| {
+            let v = match *v {
+                GmlValue::Int(i) => i.to_string(),
+                GmlValue::UInt(u) => u.to_string(),
+                GmlValue::Float(f) => f.to_string(),
+                GmlValue::Str(ref s) => s.to_string(),
+                GmlValue::List(_) => return None,
+            };
+            Some((k.clone(), v))
+        })
+        .collect()
+}
+
+fn format_attrs(attrs: &[(String, String)]) -> String {
+    attrs
+        .iter()
+        .map(|(k, v)| format!("{}={}", key(k), quote(v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Keys which are not DOT identifiers, like the dotted `graphics.x`, are
+// quoted.
+fn key(k: &str) -> Cow<'_, str> {
+    let mut chars = k.chars();
+    let ident = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if ident {
+        Cow::Borrowed(k)
+    } else {
+        Cow::Owned(quote(k))
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Parses a Graphviz DOT graph into a `GmlDocument`.
+///
+/// This is the inverse of `write_dot`: `fillcolor` and `color` on nodes end
+/// up in the `graphics` block as `fill` and `outline`, `color` on edges as
+/// `fill`. Values which look like numbers become `Int` or `Float`, except
+/// for `label`.
+///
+/// If every node name is an unsigned integer, it is used as the node id.
+/// Otherwise nodes are numbered from 0 in order of appearance and the name
+/// is kept in a `name` attribute. Subgraphs are flattened, ports are kept as
+/// `tailport`/`headport` edge attributes.
+pub fn parse_dot(s: &str) -> Result<GmlDocument, &'static str> {
+    let tokens = tokenize(s)?;
+    let mut parser = DotParser {
+        tokens,
+        pos: 0,
+        nodes: Vec::new(),
+        node_map: BTreeMap::new(),
+        edges: Vec::new(),
+        strict: false,
+        directed: false,
+    };
+    let graph_attrs = parser.parse_graph()?;
+    Ok(parser.into_document(graph_attrs))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DotToken {
+    // unquoted identifier or numeral
+    Id(String),
+    // quoted or HTML string
+    QId(String),
+    OpenCurly,
+    CloseCurly,
+    OpenBracket,
+    CloseBracket,
+    Semicolon,
+    Comma,
+    Equals,
+    Colon,
+    Plus,
+    EdgeOp,
+}
+
+fn tokenize(s: &str) -> Result<Vec<DotToken>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    let mut at_line_start = true;
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\n' => {
+                at_line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' if at_line_start => {
+                // preprocessor output line
+                while let Some(&(_, c)) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            '/' => match chars.next() {
+                Some((_, '/')) => {
+                    while let Some(&(_, c)) = chars.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+                Some((_, '*')) => {
+                    let mut prev = ' ';
+                    loop {
+                        match chars.next() {
+                            Some((_, '/')) if prev == '*' => break,
+                            Some((_, c)) => prev = c,
+                            None => return Err("unterminated comment"),
+                        }
+                    }
+                }
+                _ => return Err("invalid character"),
+            },
+            '{' => tokens.push(DotToken::OpenCurly),
+            '}' => tokens.push(DotToken::CloseCurly),
+            '[' => tokens.push(DotToken::OpenBracket),
+            ']' => tokens.push(DotToken::CloseBracket),
+            ';' => tokens.push(DotToken::Semicolon),
+            ',' => tokens.push(DotToken::Comma),
+            '=' => tokens.push(DotToken::Equals),
+            ':' => tokens.push(DotToken::Colon),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, '"')) => string.push('"'),
+                            // line continuation
+                            Some((_, '\n')) => {}
+                            Some((_, c)) => {
+                                string.push('\\');
+                                string.push(c);
+                            }
+                            None => return Err("unterminated string"),
+                        },
+                        Some((_, c)) => string.push(c),
+                        None => return Err("unterminated string"),
+                    }
+                }
+                tokens.push(DotToken::QId(string));
+            }
+            '+' => tokens.push(DotToken::Plus),
+            '<' => {
+                let mut depth = 1;
+                let mut html = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '<')) => {
+                            depth += 1;
+                            html.push('<');
+                        }
+                        Some((_, '>')) => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                            html.push('>');
+                        }
+                        Some((_, c)) => html.push(c),
+                        None => return Err("unterminated HTML string"),
+                    }
+                }
+                tokens.push(DotToken::QId(html));
+            }
+            '-' if matches!(chars.peek(), Some(&(_, '>')) | Some(&(_, '-'))) => {
+                chars.next();
+                tokens.push(DotToken::EdgeOp);
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || !c.is_ascii() => {
+                let mut end = pos + c.len_utf8();
+                while let Some(&(p, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii() {
+                        end = p + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(DotToken::Id(s[pos..end].to_string()));
+            }
+            _ => return Err("invalid character"),
+        }
+        at_line_start = false;
+    }
+
+    Ok(tokens)
+}
+
+struct DotNode {
+    name: String,
+    attrs: BTreeMap<String, String>,
+}
+
+struct DotEdge {
+    source: usize,
+    target: usize,
+    attrs: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Default)]
+struct Scope {
+    node_defaults: BTreeMap<String, String>,
+    edge_defaults: BTreeMap<String, String>,
+}
+
+struct DotParser {
+    tokens: Vec<DotToken>,
+    pos: usize,
+    nodes: Vec<DotNode>,
+    node_map: BTreeMap<String, usize>,
+    edges: Vec<DotEdge>,
+    strict: bool,
+    directed: bool,
+}
+
+impl DotParser {
+    fn peek(&self) -> Option<&DotToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<DotToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &DotToken) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &DotToken, err: &'static str) -> Result<(), &'static str> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(DotToken::Id(id)) => id.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn parse_id(&mut self) -> Result<String, &'static str> {
+        match self.next() {
+            Some(DotToken::Id(id)) => Ok(id),
+            Some(DotToken::QId(mut id)) => {
+                // "a" + "b" concatenation
+                while self.eat(&DotToken::Plus) {
+                    match self.next() {
+                        Some(DotToken::QId(more)) => id.push_str(&more),
+                        _ => return Err("expected string after '+'"),
+                    }
+                }
+                Ok(id)
+            }
+            _ => Err("expected identifier"),
+        }
+    }
+
+    fn parse_graph(&mut self) -> Result<BTreeMap<String, String>, &'static str> {
+        if self.peek_keyword("strict") {
+            self.pos += 1;
+            self.strict = true;
+        }
+        if self.peek_keyword("digraph") {
+            self.directed = true;
+        } else if !self.peek_keyword("graph") {
+            return Err("expected graph or digraph");
+        }
+        self.pos += 1;
+
+        let mut graph_attrs = BTreeMap::new();
+        if self.peek() != Some(&DotToken::OpenCurly) {
+            let name = self.parse_id()?;
+            graph_attrs.insert("name".to_string(), name);
+        }
+        self.expect(&DotToken::OpenCurly, "expected '{'")?;
+        let mut scope = Scope::default();
+        self.parse_stmt_list(&mut scope, &mut graph_attrs)?;
+        if self.pos != self.tokens.len() {
+            return Err("trailing input after graph");
+        }
+        Ok(graph_attrs)
+    }
+
+    // Parses statements up to and including the closing '}'. Returns the
+    // nodes mentioned within.
+    fn parse_stmt_list(
+        &mut self,
+        scope: &mut Scope,
+        graph_attrs: &mut BTreeMap<String, String>,
+    ) -> Result<BTreeSet<usize>, &'static str> {
+        let mut members = BTreeSet::new();
+        loop {
+            match self.peek() {
+                None => return Err("expected '}'"),
+                Some(DotToken::CloseCurly) => {
+                    self.pos += 1;
+                    return Ok(members);
+                }
+                Some(DotToken::Semicolon) => {
+                    self.pos += 1;
+                }
+                _ => self.parse_stmt(scope, graph_attrs, &mut members)?,
+            }
+        }
+    }
+
+    fn parse_stmt(
+        &mut self,
+        scope: &mut Scope,
+        graph_attrs: &mut BTreeMap<String, String>,
+        members: &mut BTreeSet<usize>,
+    ) -> Result<(), &'static str> {
+        for &(keyword, kind) in &[("graph", 0), ("node", 1), ("edge", 2)] {
+            if self.peek_keyword(keyword)
+                && self.tokens.get(self.pos + 1) == Some(&DotToken::OpenBracket)
+            {
+                self.pos += 1;
+                let attrs = self.parse_attr_lists()?;
+                let target = match kind {
+                    0 => &mut *graph_attrs,
+                    1 => &mut scope.node_defaults,
+                    _ => &mut scope.edge_defaults,
+                };
+                target.extend(attrs);
+                return Ok(());
+            }
+        }
+
+        if let (Some(DotToken::Id(_)), Some(DotToken::Equals)) =
+            (self.peek(), self.tokens.get(self.pos + 1))
+        {
+            let key = self.parse_id()?;
+            self.pos += 1;
+            let value = self.parse_id()?;
+            graph_attrs.insert(key, value);
+            return Ok(());
+        }
+
+        let mut operand = self.parse_operand(scope)?;
+        let is_single_node = operand.1.is_some();
+        if self.peek() != Some(&DotToken::EdgeOp) {
+            if is_single_node {
+                let attrs = self.parse_attr_lists()?;
+                for &idx in &operand.0 {
+                    self.nodes[idx].attrs.extend(attrs.clone());
+                }
+            }
+            members.extend(operand.0);
+            return Ok(());
+        }
+
+        let mut chain = Vec::new();
+        while self.eat(&DotToken::EdgeOp) {
+            let next = self.parse_operand(scope)?;
+            chain.push((operand, next.clone()));
+            operand = next;
+        }
+        let attrs = self.parse_attr_lists()?;
+
+        for ((sources, tailport), (targets, headport)) in chain {
+            members.extend(sources.iter().cloned());
+            members.extend(targets.iter().cloned());
+            for &source in &sources {
+                for &target in &targets {
+                    let mut edge_attrs = scope.edge_defaults.clone();
+                    if let Some(Some(ref port)) = tailport {
+                        edge_attrs.insert("tailport".to_string(), port.clone());
+                    }
+                    if let Some(Some(ref port)) = headport {
+                        edge_attrs.insert("headport".to_string(), port.clone());
+                    }
+                    edge_attrs.extend(attrs.clone());
+                    self.add_edge(source, target, edge_attrs);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Parses a node id or a subgraph. The second element is `Some(port)` if
+    // the operand is a single node.
+    fn parse_operand(
+        &mut self,
+        scope: &Scope,
+    ) -> Result<(BTreeSet<usize>, Option<Option<String>>), &'static str> {
+        if self.peek_keyword("subgraph") || self.peek() == Some(&DotToken::OpenCurly) {
+            if self.peek_keyword("subgraph") {
+                self.pos += 1;
+                if self.peek() != Some(&DotToken::OpenCurly) {
+                    self.parse_id()?;
+                }
+            }
+            self.expect(&DotToken::OpenCurly, "expected '{'")?;
+            let mut inner = scope.clone();
+            let mut subgraph_attrs = BTreeMap::new();
+            let members = self.parse_stmt_list(&mut inner, &mut subgraph_attrs)?;
+            return Ok((members, None));
+        }
+
+        let name = self.parse_id()?;
+        let mut port = None;
+        if self.eat(&DotToken::Colon) {
+            let mut p = self.parse_id()?;
+            if self.eat(&DotToken::Colon) {
+                p.push(':');
+                p.push_str(&self.parse_id()?);
+            }
+            port = Some(p);
+        }
+        let idx = self.node(name, scope);
+        Ok((Some(idx).into_iter().collect(), Some(port)))
+    }
+
+    fn parse_attr_lists(&mut self) -> Result<BTreeMap<String, String>, &'static str> {
+        let mut attrs = BTreeMap::new();
+        while self.eat(&DotToken::OpenBracket) {
+            while !self.eat(&DotToken::CloseBracket) {
+                let key = self.parse_id()?;
+                self.expect(&DotToken::Equals, "expected '='")?;
+                let value = self.parse_id()?;
+                attrs.insert(key, value);
+                if !self.eat(&DotToken::Comma) {
+                    self.eat(&DotToken::Semicolon);
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn node(&mut self, name: String, scope: &Scope) -> usize {
+        if let Some(&idx) = self.node_map.get(&name) {
+            return idx;
+        }
+        let idx = self.nodes.len();
+        self.node_map.insert(name.clone(), idx);
+        self.nodes.push(DotNode {
+            name,
+            attrs: scope.node_defaults.clone(),
+        });
+        idx
+    }
+
+    fn add_edge(&mut self, source: usize, target: usize, attrs: BTreeMap<String, String>) {
+        if self.strict {
+            let directed = self.directed;
+            if let Some(edge) = self.edges.iter_mut().find(|e| {
+                (e.source == source && e.target == target)
+                    || (!directed && e.source == target && e.target == source)
+            }) {
+                edge.attrs.extend(attrs);
+                return;
+            }
+        }
+        self.edges.push(DotEdge {
+            source,
+            target,
+            attrs,
+        });
+    }
+
+    fn into_document(self, graph_attrs: BTreeMap<String, String>) -> GmlDocument {
+        let numeric_ids = numeric_ids(self.nodes.iter().map(|n| &n.name[..]), |name| {
+            name.parse().ok()
+        });
+
+        let mut doc = GmlDocument {
+            directed: self.directed,
+            attrs: graph_attrs
+                .into_iter()
+                .map(|(k, v)| {
+                    let v = dot_value(&k, v);
+                    (k, v)
+                })
+                .collect(),
+            ..GmlDocument::default()
+        };
+
+        for (idx, node) in self.nodes.into_iter().enumerate() {
+            let mut attrs = GmlAttrs::new();
+            let mut graphics = GmlAttrs::new();
+            for (k, v) in node.attrs {
+                match &k[..] {
+                    "fillcolor" => {
+                        graphics.insert("fill".to_string(), GmlValue::from(v));
+                    }
+                    "color" => {
+                        graphics.insert("outline".to_string(), GmlValue::from(v));
+                    }
+                    "style" if v == "filled" => {}
+                    _ => {
+                        let v = dot_value(&k, v);
+                        attrs.insert(k, v);
+                    }
+                }
+            }
+            if !graphics.is_empty() {
+                attrs.insert("graphics".to_string(), GmlValue::List(graphics));
+            }
+            let id = match numeric_ids {
+                Some(ref ids) => ids[idx],
+                None => {
+                    attrs.insert("name".to_string(), GmlValue::from(node.name));
+                    idx as u64
+                }
+            };
+            doc.nodes.push(GmlNode { id, attrs });
+        }
+
+        let node_id = |idx: usize| match numeric_ids {
+            Some(ref ids) => ids[idx],
+            None => idx as u64,
+        };
+
+        for edge in self.edges {
+            let mut attrs = GmlAttrs::new();
+            for (k, v) in edge.attrs {
+                if k == "color" {
+                    let mut graphics = GmlAttrs::new();
+                    graphics.insert("fill".to_string(), GmlValue::from(v));
+                    attrs.insert("graphics".to_string(), GmlValue::List(graphics));
+                } else {
+                    let v = dot_value(&k, v);
+                    attrs.insert(k, v);
+                }
+            }
+            doc.edges.push(GmlEdge {
+                source: node_id(edge.source),
+                target: node_id(edge.target),
+                attrs,
+            });
+        }
+
+        doc
+    }
+}
+
+fn dot_value(key: &str, value: String) -> GmlValue {
+    if key != "label" && key != "name" {
+        if let Ok(i) = value.parse::<i64>() {
+            return GmlValue::Int(i);
+        }
+        if value.contains(|c: char| c.is_ascii_digit()) {
+            if let Ok(f) = value.parse::<f64>() {
+                return GmlValue::Float(f);
+            }
+        }
+    }
+    GmlValue::from(value)
+}
+
+#[test]
+fn test_to_dot() {
+    let gml = "
+    graph [
+        directed 1
+        node [ id 1 label \"a\" graphics [ fill \"#FF0000\" outline \"blue\" ] ]
+        node [ id 2 label \"say &quot;hi&quot;\" graphics [ type \"roundrectangle\" ] ]
+        node [ id 3 graphics [ type \"trapezoid\" ] ]
+        edge [ source 1 target 2 weight 1.5 graphics [ fill \"#00FF00\" ] ]
+        edge [ source 2 target 1 graphics [ sourceArrow \"white_diamond\" targetArrow \"none\" ] ]
+        edge [ source 2 target 2 graphics [ arrow \"both\" targetArrow \"delta\" ] ]
+    ]
+    ";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let dot = to_dot(&doc);
+    assert_eq!(
+        "digraph {\n  \
+         1 [label=\"a\", style=\"filled\", fillcolor=\"#FF0000\", color=\"blue\"];\n  \
+         2 [label=\"say \\\"hi\\\"\", style=\"rounded\", shape=\"box\"];\n  \
+         3 [shape=\"trapezium\"];\n  \
+         1 -> 2 [weight=\"1.5\", color=\"#00FF00\"];\n  \
+         2 -> 1 [dir=\"back\", arrowtail=\"odiamond\"];\n  \
+         2 -> 2 [dir=\"both\", arrowhead=\"normal\"];\n\
+         }\n",
+        dot
+    );
+}
+
+#[test]
+fn test_parse_dot() {
+    let dot = "
+    /* routers */
+    strict digraph net {
+        rankdir=LR
+        node [shape=box]
+        a [label=\"Router \" + \"A\", fillcolor=\"#FF0000\" style=filled]
+        a -> b -> c [weight=2]
+        a -> b
+        subgraph cluster_0 { edge [color=red]; c:p1 -> { a d } }
+    }
+    ";
+    let doc = parse_dot(dot).unwrap();
+    assert!(doc.directed);
+    assert_eq!(Some("LR"), doc.attrs["rankdir"].get_str());
+    assert_eq!(4, doc.nodes.len());
+    assert_eq!(Some("Router A"), doc.nodes[0].label());
+    assert_eq!(Some("a"), doc.nodes[0].attrs["name"].get_str());
+    assert_eq!(Some("box"), doc.nodes[3].attrs["shape"].get_str());
+    assert_eq!(
+        Some("#FF0000"),
+        doc.nodes[0].attrs["graphics"]
+            .get("fill")
+            .and_then(GmlValue::get_str)
+    );
+    assert_eq!(4, doc.edges.len());
+    assert_eq!((0, 1), (doc.edges[0].source, doc.edges[0].target));
+    assert_eq!(Some(2), doc.edges[0].attrs["weight"].get_int());
+    assert_eq!((2, 3), (doc.edges[3].source, doc.edges[3].target));
+    assert_eq!(Some("p1"), doc.edges[3].attrs["tailport"].get_str());
+
+    let doc = parse_dot(&to_dot(
+        &crate::parse_gml_document(
+            "graph [ node [ id 5 label \"x\" ] node [ id 7 ] edge [ source 5 target 7 ] ]",
+        )
+        .unwrap(),
+    ))
+    .unwrap();
+    assert!(!doc.directed);
+    assert_eq!(
+        vec![5, 7],
+        doc.nodes.iter().map(|n| n.id).collect::<Vec<_>>()
+    );
+    assert_eq!(Some("x"), doc.nodes[0].label());
+
+    let mut gml = crate::parse_gml_document("graph [ node [ id 1 graphics.x 10 ] ]").unwrap();
+    gml.nodes[0]
+        .attrs
+        .insert("2nd".to_string(), GmlValue::from("b"));
+    let dot = to_dot(&gml);
+    assert!(dot.contains("\"2nd\"=\"b\""), "{}", dot);
+    assert!(dot.contains("\"graphics.x\"=\"10\""), "{}", dot);
+    let doc = parse_dot(&dot).unwrap();
+    assert_eq!(Some(10), doc.nodes[0].attrs["graphics.x"].get_int());
+    assert_eq!(Some("b"), doc.nodes[0].attrs["2nd"].get_str());
+}
@@ -0,0 +1,147 @@
+//! Dotted keys, like `graphics.x 10`, as written by exporters which flatten
+//! nested lists, and the nested lists they stand for.
+//!
+//! `expand_dotted` turns the dotted keys of a document into nested lists,
+//! so that both shapes are read the same, and `flatten_dotted` turns nested
+//! lists into dotted keys for tools which only read flat attributes.
+
+use crate::document::{parse_gml_document, remove_attr, repeated, GmlAttrs, GmlDocument, GmlValue};
+use crate::writer::write_gml;
+use std::io::{self, Write};
+
+// Inserts `value` at the dotted `key` unless a value which is not a list is
+// in the way or the key is present, and otherwise gives `value` back.
+fn insert_nested(attrs: &mut GmlAttrs, key: &str, value: GmlValue) -> Result<(), GmlValue> {
+    match key.split_once('.') {
+        Some((outer, rest)) if !outer.is_empty() => {
+            let entry = attrs
+                .entry(outer.to_string())
+                .or_insert_with(|| GmlValue::List(GmlAttrs::new()));
+            let nestable = repeated(entry).is_none();
+            match *entry {
+                GmlValue::List(ref mut inner) if nestable => insert_nested(inner, rest, value),
+                _ => Err(value),
+            }
+        }
+        _ if attrs.contains_key(key) || key.is_empty() => Err(value),
+        _ => {
+            attrs.insert(key.to_string(), value);
+            Ok(())
+        }
+    }
+}
+
+fn expand(attrs: &mut GmlAttrs) {
+    let dotted: Vec<String> = attrs.keys().filter(|k| k.contains('.')).cloned().collect();
+    for key in dotted {
+        let value = remove_attr(attrs, &key[..]).unwrap();
+        if let Err(value) = insert_nested(attrs, &key, value) {
+            attrs.insert(key, value);
+        }
+    }
+    for value in attrs.values_mut() {
+        if let GmlValue::List(ref mut inner) = *value {
+            expand(inner);
+        }
+    }
+}
+
+// Adds the entries of `attrs` to `out`, with the keys after `prefix`.
+// Values of repeated keys and empty lists are kept as they are.
+fn flatten_into(out: &mut GmlAttrs, prefix: &str, attrs: GmlAttrs) {
+    for (key, value) in attrs {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let nested = matches!(value, GmlValue::List(ref inner) if !inner.is_empty())
+            && repeated(&value).is_none();
+        match value {
+            GmlValue::List(inner) if nested => flatten_into(out, &key, inner),
+            value => {
+                out.insert(key, value);
+            }
+        }
+    }
+}
+
+fn all_attrs(doc: &mut GmlDocument) -> impl Iterator<Item = &mut GmlAttrs> {
+    let nodes = doc.nodes.iter_mut().map(|node| &mut node.attrs);
+    let edges = doc.edges.iter_mut().map(|edge| &mut edge.attrs);
+    std::iter::once(&mut doc.attrs).chain(nodes).chain(edges)
+}
+
+/// Moves the values of dotted keys into nested lists, like `graphics.x 10`
+/// to `graphics [ x 10 ]`, in the graph, nodes and edges of `doc`. A
+/// dotted key stays as it is where a value which is not a list, or one at
+/// the nested key, is in the way.
+pub fn expand_dotted(doc: &mut GmlDocument) {
+    for attrs in all_attrs(doc) {
+        expand(attrs);
+    }
+}
+
+/// Replaces nested lists by dotted keys, like `graphics [ x 10 ]` by
+/// `graphics.x 10`, in the graph, nodes and edges of `doc`. Empty lists and
+/// the lists of repeated keys, like the `point`s of a `Line`, are kept.
+pub fn flatten_dotted(doc: &mut GmlDocument) {
+    for attrs in all_attrs(doc) {
+        let mut flat = GmlAttrs::new();
+        flatten_into(&mut flat, "", std::mem::take(attrs));
+        *attrs = flat;
+    }
+}
+
+/// Parses a GML string into a document with its dotted keys expanded.
+pub fn parse_gml_expanded(s: &str) -> Result<GmlDocument, &'static str> {
+    let mut doc = parse_gml_document(s)?;
+    expand_dotted(&mut doc);
+    Ok(doc)
+}
+
+/// Writes `doc` as GML with its nested lists flattened into dotted keys.
+pub fn write_gml_flat<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    let mut doc = doc.clone();
+    flatten_dotted(&mut doc);
+    write_gml(&doc, w)
+}
+
+#[test]
+fn test_dotted() {
+    let flat = "graph [ node [ id 1 graphics.x 10 graphics.y 20 graphics.fill \"red\" label \"a\" ]
+                        node [ id 2 graphics [ x 1 ] graphics.y 2 LabelGraphics.font.size 12 ]
+                        node [ id 3 color 1 color.r 255 ] ]";
+    let doc = parse_gml_expanded(flat).unwrap();
+    let nested = crate::parse_gml_document(
+        "graph [ node [ id 1 graphics [ x 10 y 20 fill \"red\" ] label \"a\" ]
+                 node [ id 2 graphics [ x 1 y 2 ] LabelGraphics [ font [ size 12 ] ] ]
+                 node [ id 3 color 1 color.r 255 ] ]",
+    )
+    .unwrap();
+    assert_eq!(nested, doc);
+
+    let mut buf = Vec::new();
+    write_gml_flat(&doc, &mut buf).unwrap();
+    let written = crate::parse_gml_document(std::str::from_utf8(&buf).unwrap()).unwrap();
+    assert_eq!(
+        Some(&GmlValue::Int(10)),
+        written.nodes[0].attrs.get("graphics.x")
+    );
+    assert_eq!(
+        Some(&GmlValue::Int(12)),
+        written.nodes[1].attrs.get("LabelGraphics.font.size")
+    );
+    assert!(!written.nodes[0].attrs.contains_key("graphics"));
+    let mut expanded = written;
+    expand_dotted(&mut expanded);
+    assert_eq!(doc, expanded);
+
+    let points = "graph [ edge [ source 1 target 2 graphics [ Line [ point [ x 0 y 0 ] point [ x 1 y 1 ] ] ] ] ]";
+    let mut doc = crate::parse_gml_document(points).unwrap();
+    let original = doc.clone();
+    flatten_dotted(&mut doc);
+    assert_eq!(2, doc.edges[0].attrs["graphics.Line.point"].values().len());
+    expand_dotted(&mut doc);
+    assert_eq!(original, doc);
+}
@@ -0,0 +1,146 @@
+//! Categorical attributes as Rust enums, like `type "router"` as
+//! `NodeType::Router`.
+//!
+//! An enum implements `GmlEnum` by listing its variants with their names,
+//! like `("router", NodeType::Router)`, and is then read with
+//! `attrs.get_enum("type", &options)`, also from the `extra` attributes of
+//! the `typed` weights.
+
+use crate::document::{GmlAttrs, GmlValue};
+
+/// An enum with a name for each variant.
+pub trait GmlEnum: Copy + PartialEq + 'static {
+    /// The variants with their names.
+    const VARIANTS: &'static [(&'static str, Self)];
+
+    /// The variant named `name`, ignoring ASCII case unless `exact`.
+    fn from_name(name: &str, exact: bool) -> Option<Self> {
+        Self::VARIANTS
+            .iter()
+            .find(|(n, _)| {
+                if exact {
+                    *n == name
+                } else {
+                    n.eq_ignore_ascii_case(name)
+                }
+            })
+            .map(|&(_, v)| v)
+    }
+
+    /// The name of the variant.
+    fn name(&self) -> &'static str {
+        Self::VARIANTS
+            .iter()
+            .find(|(_, v)| v == self)
+            .map(|&(n, _)| n)
+            .expect("variant without a name")
+    }
+}
+
+/// What `get_enum` does with a value which names no variant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unknown<E> {
+    /// Fails with "unknown enum value".
+    Error,
+    /// Returns `None`, as for an absent key.
+    Ignore,
+    /// Returns this variant.
+    Fallback(E),
+}
+
+/// Options for `get_enum`.
+#[derive(Debug, Clone, Copy)]
+pub struct EnumOptions<E> {
+    pub unknown: Unknown<E>,
+    /// Whether names must match in case.
+    pub exact: bool,
+}
+
+impl<E> Default for EnumOptions<E> {
+    fn default() -> EnumOptions<E> {
+        EnumOptions {
+            unknown: Unknown::Error,
+            exact: false,
+        }
+    }
+}
+
+/// Enum accessors of attributes.
+pub trait EnumAttrs {
+    /// The variant named by the string at `key`, or `None` if there is no
+    /// such key. Other values, and strings which name no variant, are
+    /// handled as `options.unknown` says.
+    fn get_enum<E: GmlEnum>(
+        &self,
+        key: &str,
+        options: &EnumOptions<E>,
+    ) -> Result<Option<E>, &'static str>;
+    /// Sets `key` to the name of `value`.
+    fn put_enum<E: GmlEnum>(&mut self, key: &str, value: E);
+}
+
+impl EnumAttrs for GmlAttrs {
+    fn get_enum<E: GmlEnum>(
+        &self,
+        key: &str,
+        options: &EnumOptions<E>,
+    ) -> Result<Option<E>, &'static str> {
+        let value = match self.get(key) {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+        match value.get_str().and_then(|s| E::from_name(s, options.exact)) {
+            Some(variant) => Ok(Some(variant)),
+            None => match options.unknown {
+                Unknown::Error => Err("unknown enum value"),
+                Unknown::Ignore => Ok(None),
+                Unknown::Fallback(variant) => Ok(Some(variant)),
+            },
+        }
+    }
+
+    fn put_enum<E: GmlEnum>(&mut self, key: &str, value: E) {
+        self.insert(key.to_string(), GmlValue::from(value.name()));
+    }
+}
+
+#[test]
+fn test_enums() {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Kind {
+        Router,
+        Host,
+        Other,
+    }
+    impl GmlEnum for Kind {
+        const VARIANTS: &'static [(&'static str, Kind)] = &[
+            ("router", Kind::Router),
+            ("host", Kind::Host),
+            ("other", Kind::Other),
+        ];
+    }
+
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 type \"router\" ] node [ id 2 type \"Host\" ]
+                 node [ id 3 type \"vlan\" ] node [ id 4 type 7 ] node [ id 5 ] ]",
+    )
+    .unwrap();
+    let get = |i: usize, options: &EnumOptions<Kind>| doc.nodes[i].attrs.get_enum("type", options);
+    let mut options = EnumOptions::default();
+    assert_eq!(Ok(Some(Kind::Router)), get(0, &options));
+    assert_eq!(Ok(Some(Kind::Host)), get(1, &options));
+    assert_eq!(Err("unknown enum value"), get(2, &options));
+    assert_eq!(Err("unknown enum value"), get(3, &options));
+    assert_eq!(Ok(None), get(4, &options));
+    options.unknown = Unknown::Fallback(Kind::Other);
+    assert_eq!(Ok(Some(Kind::Other)), get(2, &options));
+    options.unknown = Unknown::Ignore;
+    assert_eq!(Ok(None), get(3, &options));
+    options.exact = true;
+    assert_eq!(Ok(None), get(1, &options));
+
+    let mut attrs = GmlAttrs::new();
+    attrs.put_enum("type", Kind::Host);
+    assert_eq!(Some("host"), attrs.get("type").and_then(GmlValue::get_str));
+    assert_eq!("router", Kind::Router.name());
+}
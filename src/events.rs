@@ -0,0 +1,459 @@
+//! Streaming GML events.
+//!
+//! `gml_events` turns GML text into a flat sequence of events, one per
+//! key-value pair and one per closing bracket, without building any tree.
+//! The graph and document builders consume these events and keep only what
+//! they need.
+
+use crate::document::{insert_attr, take_id, GmlAttrs, GmlValue};
+use crate::lexer::{GmlToken, Lexer};
+use std::borrow::Cow;
+
+/// A scalar GML value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GmlScalar<'a> {
+    Int(i64),
+    /// An integer beyond `i64::MAX`.
+    UInt(u64),
+    Float(f64),
+    Str(Cow<'a, str>),
+}
+
+impl<'a> GmlScalar<'a> {
+    pub fn into_value(self) -> GmlValue {
+        match self {
+            GmlScalar::Int(i) => GmlValue::Int(i),
+            GmlScalar::UInt(u) => GmlValue::UInt(u),
+            GmlScalar::Float(f) => GmlValue::Float(f),
+            GmlScalar::Str(s) => GmlValue::from(s.into_owned()),
+        }
+    }
+
+    /// Returns the value as `u64`, if it is a non-negative integer.
+    pub fn get_uint(&self) -> Option<u64> {
+        match *self {
+            GmlScalar::Int(i) if i >= 0 => Some(i as u64),
+            GmlScalar::UInt(u) => Some(u),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GmlEvent<'a> {
+    /// `key [`
+    Begin(&'a str),
+    /// `]`
+    End,
+    /// `key value`
+    Value(&'a str, GmlScalar<'a>),
+}
+
+enum Tokens<'a> {
+    Lexer(Lexer<'a>),
+    #[cfg(feature = "legacy-lexer")]
+    Legacy(asexp::token::Tokenizer<'a>),
+}
+
+impl<'a> Tokens<'a> {
+    fn new(s: &'a str) -> Tokens<'a> {
+        #[cfg(feature = "legacy-lexer")]
+        return Tokens::Legacy(asexp::token::Tokenizer::new(s, true));
+        #[cfg(not(feature = "legacy-lexer"))]
+        Tokens::Lexer(Lexer::new(s))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<GmlToken<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Tokens::Lexer(ref mut lexer) => lexer.next(),
+            #[cfg(feature = "legacy-lexer")]
+            Tokens::Legacy(ref mut tokenizer) => legacy_token(tokenizer),
+        }
+    }
+}
+
+/// Maps the tokens of the asexp tokenizer, as used before the GML lexer,
+/// to GML tokens. It follows asexp's rules for quoting, escapes and
+/// numbers, not GML's; only character entities are decoded as well, so
+/// that the output of `write_gml` reads back.
+#[cfg(feature = "legacy-lexer")]
+fn legacy_token<'a>(
+    tokenizer: &mut asexp::token::Tokenizer<'a>,
+) -> Option<Result<GmlToken<'a>, &'static str>> {
+    use asexp::token::Token;
+    loop {
+        let token = match tokenizer.next()? {
+            Token::Whitespace(_) | Token::Comment(_) => continue,
+            Token::OpenBracket => GmlToken::Open,
+            Token::CloseBracket => GmlToken::Close,
+            Token::Str(s) => GmlToken::Key(s),
+            Token::QStr(s) => {
+                GmlToken::Str(Cow::Owned(crate::lexer::decode_entities(&s).into_owned()))
+            }
+            Token::UInt(u) if u <= i64::MAX as u64 => GmlToken::Int(u as i64),
+            Token::UInt(u) => GmlToken::UInt(u),
+            Token::SInt(i) => GmlToken::Int(i),
+            Token::Float(f) => GmlToken::Float(f),
+            _ => return Some(Err("Invalid GML")),
+        };
+        return Some(Ok(token));
+    }
+}
+
+/// An iterator over the events of a GML string.
+pub struct GmlEvents<'a> {
+    tokens: Tokens<'a>,
+    depth: usize,
+    failed: bool,
+}
+
+/// Returns the events of the GML string `s`.
+pub fn gml_events(s: &str) -> GmlEvents<'_> {
+    GmlEvents {
+        tokens: Tokens::new(s),
+        depth: 0,
+        failed: false,
+    }
+}
+
+/// Returns the events of `s` read with the GML lexer, even with the
+/// `legacy-lexer` feature, so that their offsets are known.
+pub(crate) fn lexer_events(s: &str) -> GmlEvents<'_> {
+    GmlEvents {
+        tokens: Tokens::Lexer(Lexer::new(s)),
+        depth: 0,
+        failed: false,
+    }
+}
+
+impl<'a> GmlEvents<'a> {
+    /// The byte offset after the last event, if the events are read with
+    /// the GML lexer.
+    pub(crate) fn offset(&self) -> Option<usize> {
+        match self.tokens {
+            Tokens::Lexer(ref lexer) => Some(lexer.offset()),
+            #[cfg(feature = "legacy-lexer")]
+            Tokens::Legacy(_) => None,
+        }
+    }
+
+    /// The nesting depth of lists after the last event.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn event(&mut self) -> Option<Result<GmlEvent<'a>, &'static str>> {
+        let key = match self.tokens.next() {
+            None if self.depth > 0 => return Some(Err("unbalanced [")),
+            None => return None,
+            Some(Err(e)) => return Some(Err(e)),
+            Some(Ok(GmlToken::Key(key))) => key,
+            Some(Ok(GmlToken::Close)) if self.depth > 0 => {
+                self.depth -= 1;
+                return Some(Ok(GmlEvent::End));
+            }
+            Some(Ok(GmlToken::Close)) => return Some(Err("unbalanced ]")),
+            Some(Ok(_)) => return Some(Err("expected key")),
+        };
+        let scalar = match self.tokens.next() {
+            None => return Some(Err("missing value")),
+            Some(Err(e)) => return Some(Err(e)),
+            Some(Ok(GmlToken::Open)) => {
+                self.depth += 1;
+                return Some(Ok(GmlEvent::Begin(key)));
+            }
+            Some(Ok(GmlToken::Int(i))) => GmlScalar::Int(i),
+            Some(Ok(GmlToken::UInt(u))) => GmlScalar::UInt(u),
+            Some(Ok(GmlToken::Float(f))) => GmlScalar::Float(f),
+            Some(Ok(GmlToken::Str(s))) => GmlScalar::Str(s),
+            Some(Ok(GmlToken::Key("INF"))) => GmlScalar::Float(f64::INFINITY),
+            Some(Ok(GmlToken::Key("NAN"))) => GmlScalar::Float(f64::NAN),
+            Some(Ok(_)) => return Some(Err("expected value")),
+        };
+        Some(Ok(GmlEvent::Value(key, scalar)))
+    }
+
+    /// Reads the rest of the current list, after its `Begin`, up to and
+    /// including its `End`.
+    pub fn read_list(&mut self) -> Result<GmlAttrs, &'static str> {
+        self.read_list_with(&mut OwnedLists)
+    }
+
+    pub(crate) fn read_list_with<B: ListBuilder<'a>>(
+        &mut self,
+        builder: &mut B,
+    ) -> Result<B::List, &'static str> {
+        let mut list = B::List::default();
+        loop {
+            let (key, value) = match self.next().ok_or("unbalanced [")?? {
+                GmlEvent::End => return Ok(list),
+                GmlEvent::Value(key, scalar) => (key, builder.scalar(scalar)),
+                GmlEvent::Begin(key) => {
+                    let inner = self.read_list_with(builder)?;
+                    (key, builder.list(inner))
+                }
+            };
+            builder.insert(&mut list, key, value);
+        }
+    }
+
+    /// Advances into the top-level `graph` list, reading the top-level keys
+    /// before it into `top`.
+    pub(crate) fn enter_graph_with<B: ListBuilder<'a>>(
+        &mut self,
+        builder: &mut B,
+        top: &mut B::List,
+    ) -> Result<(), &'static str> {
+        if !self.read_top_level_with(builder, top)? {
+            return Err("no graph given or invalid");
+        }
+        Ok(())
+    }
+
+    /// Reads the top-level keys after the `graph` list into `top`.
+    pub(crate) fn finish_with<B: ListBuilder<'a>>(
+        mut self,
+        builder: &mut B,
+        top: &mut B::List,
+    ) -> Result<(), &'static str> {
+        if self.read_top_level_with(builder, top)? {
+            return Err("duplicate key");
+        }
+        Ok(())
+    }
+
+    // Reads top-level keys up to the next `graph` list, returning whether
+    // there is one.
+    fn read_top_level_with<B: ListBuilder<'a>>(
+        &mut self,
+        builder: &mut B,
+        top: &mut B::List,
+    ) -> Result<bool, &'static str> {
+        while let Some(event) = self.next() {
+            let (key, value) = match event? {
+                GmlEvent::Begin("graph") => return Ok(true),
+                GmlEvent::End => continue,
+                GmlEvent::Value(key, scalar) => (key, builder.scalar(scalar)),
+                GmlEvent::Begin(key) => {
+                    let inner = self.read_list_with(builder)?;
+                    (key, builder.list(inner))
+                }
+            };
+            builder.insert(top, key, value);
+        }
+        Ok(false)
+    }
+
+    /// Advances into the top-level `graph` list, skipping the top-level
+    /// keys before it.
+    pub(crate) fn enter_graph(&mut self) -> Result<(), &'static str> {
+        loop {
+            match self.next() {
+                None => return Err("no graph given or invalid"),
+                Some(Err(e)) => return Err(e),
+                Some(Ok(GmlEvent::Begin("graph"))) => return Ok(()),
+                Some(Ok(GmlEvent::Begin(_))) => self.skip_list()?,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+
+    /// Checks the top-level keys after the `graph` list.
+    pub(crate) fn finish(mut self) -> Result<(), &'static str> {
+        while let Some(event) = self.next() {
+            match event? {
+                GmlEvent::Begin("graph") => return Err("duplicate key"),
+                GmlEvent::Begin(_) => self.skip_list()?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips the rest of the current list, like `read_list`.
+    pub fn skip_list(&mut self) -> Result<(), &'static str> {
+        let depth = self.depth;
+        while self.depth >= depth {
+            self.next().ok_or("unbalanced [")??;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the attribute lists of a document representation from events.
+pub(crate) trait ListBuilder<'a> {
+    type Value;
+    type List: Default;
+
+    fn scalar(&mut self, scalar: GmlScalar<'a>) -> Self::Value;
+    fn list(&mut self, list: Self::List) -> Self::Value;
+    /// Inserts `value` under `key`, adding it to the values of `key` if it
+    /// is present, as `insert_attr` does.
+    fn insert(&mut self, list: &mut Self::List, key: &'a str, value: Self::Value);
+    /// Removes the id under `key`, like `document::take_id`.
+    fn take_id(
+        &mut self,
+        list: &mut Self::List,
+        key: &str,
+        error: &'static str,
+    ) -> Result<u64, &'static str>;
+}
+
+pub(crate) struct OwnedLists;
+
+impl<'a> ListBuilder<'a> for OwnedLists {
+    type Value = GmlValue;
+    type List = GmlAttrs;
+
+    fn scalar(&mut self, scalar: GmlScalar<'a>) -> GmlValue {
+        scalar.into_value()
+    }
+
+    fn list(&mut self, list: GmlAttrs) -> GmlValue {
+        GmlValue::List(list)
+    }
+
+    fn insert(&mut self, list: &mut GmlAttrs, key: &'a str, value: GmlValue) {
+        insert_attr(list, key, value)
+    }
+
+    fn take_id(
+        &mut self,
+        list: &mut GmlAttrs,
+        key: &str,
+        error: &'static str,
+    ) -> Result<u64, &'static str> {
+        take_id(list, key, error)
+    }
+}
+
+/// The graph of a document, with its nodes and edges in document order.
+pub(crate) struct DocumentParts<L> {
+    /// The last `directed` value, if any.
+    pub directed: Option<bool>,
+    pub attrs: L,
+    /// The top-level keys outside the `graph` list.
+    pub top_attrs: L,
+    pub nodes: Vec<(u64, L)>,
+    pub edges: Vec<(u64, u64, L)>,
+}
+
+/// Reads the `graph` list of `s`, one node or edge block at a time.
+pub(crate) fn document_parts<'a, B: ListBuilder<'a>>(
+    s: &'a str,
+    builder: &mut B,
+) -> Result<DocumentParts<B::List>, &'static str> {
+    let mut events = gml_events(s);
+    let mut top_attrs = B::List::default();
+    events.enter_graph_with(builder, &mut top_attrs)?;
+    let mut parts = graph_parts(&mut events, builder)?;
+    events.finish_with(builder, &mut top_attrs)?;
+    parts.top_attrs = top_attrs;
+    Ok(parts)
+}
+
+/// Reads the items of a `graph` list up to its end, or, if `events` is at
+/// the top level, up to the end of the input.
+pub(crate) fn graph_parts<'a, B: ListBuilder<'a>>(
+    events: &mut GmlEvents<'a>,
+    builder: &mut B,
+) -> Result<DocumentParts<B::List>, &'static str> {
+    let mut parts = DocumentParts {
+        directed: None,
+        attrs: B::List::default(),
+        top_attrs: B::List::default(),
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+
+    while let Some(event) = events.next() {
+        let (key, value) = match event? {
+            GmlEvent::End => break,
+            GmlEvent::Value("directed", GmlScalar::Int(0)) => {
+                parts.directed = Some(false);
+                continue;
+            }
+            GmlEvent::Value("directed", GmlScalar::Int(1)) => {
+                parts.directed = Some(true);
+                continue;
+            }
+            GmlEvent::Value("directed", _) | GmlEvent::Begin("directed") => {
+                return Err("invalid directed value");
+            }
+            GmlEvent::Begin("node") => {
+                let mut attrs = events.read_list_with(builder)?;
+                let id = builder.take_id(&mut attrs, "id", "Invalid id")?;
+                parts.nodes.push((id, attrs));
+                continue;
+            }
+            GmlEvent::Begin("edge") => {
+                let mut attrs = events.read_list_with(builder)?;
+                let source = builder.take_id(&mut attrs, "source", "Invalid source id")?;
+                let target = builder.take_id(&mut attrs, "target", "Invalid target id")?;
+                parts.edges.push((source, target, attrs));
+                continue;
+            }
+            GmlEvent::Value(key, scalar) => (key, builder.scalar(scalar)),
+            GmlEvent::Begin(key) => {
+                let list = events.read_list_with(builder)?;
+                (key, builder.list(list))
+            }
+        };
+        builder.insert(&mut parts.attrs, key, value);
+    }
+
+    Ok(parts)
+}
+
+impl<'a> Iterator for GmlEvents<'a> {
+    type Item = Result<GmlEvent<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let event = self.event();
+        if let Some(Err(_)) = event {
+            self.failed = true;
+        }
+        event
+    }
+}
+
+#[test]
+fn test_gml_events() {
+    let gml = "Creator \"me\" graph [ node [ id 1 graphics [ x 1.5 ] ] ]";
+    let events: Vec<_> = gml_events(gml).collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        vec![
+            GmlEvent::Value("Creator", GmlScalar::Str(Cow::Borrowed("me"))),
+            GmlEvent::Begin("graph"),
+            GmlEvent::Begin("node"),
+            GmlEvent::Value("id", GmlScalar::Int(1)),
+            GmlEvent::Begin("graphics"),
+            GmlEvent::Value("x", GmlScalar::Float(1.5)),
+            GmlEvent::End,
+            GmlEvent::End,
+            GmlEvent::End,
+        ],
+        events
+    );
+
+    let mut events = gml_events("node [ graphics [ x 1 ] id 2 ] edge [ ]");
+    assert_eq!(Some(Ok(GmlEvent::Begin("node"))), events.next());
+    events.skip_list().unwrap();
+    assert_eq!(Some(Ok(GmlEvent::Begin("edge"))), events.next());
+    assert_eq!(Ok(GmlAttrs::new()), events.read_list());
+    assert_eq!(None, events.next());
+
+    let errors: Vec<_> = gml_events("graph [ id ]").collect();
+    assert_eq!(
+        vec![Ok(GmlEvent::Begin("graph")), Err("expected value")],
+        errors
+    );
+    assert_eq!(Some(Err("unbalanced [")), gml_events("graph [").nth(1));
+}
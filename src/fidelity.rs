@@ -0,0 +1,236 @@
+//! Reports of what is lost in the conversion to formats that cannot
+//! represent everything GML can.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlValue};
+use crate::graphics::Arrow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The formats `fidelity_report` knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// `write_dot`, which keeps scalar attributes and, of the `graphics`
+    /// lists, the `fill` and `outline` of nodes and the `fill` of edges.
+    Dot,
+    /// `write_tgf`, which keeps only ids and string labels.
+    Tgf,
+    /// `write_csv`, which keeps all node and edge attributes.
+    Csv,
+}
+
+/// Something a conversion does not keep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Loss {
+    /// An attribute that is not written, by the kind of element it belongs
+    /// to, `graph`, `node` or `edge`, and its dotted key, with the number
+    /// of elements that have it.
+    Attribute {
+        element: &'static str,
+        key: String,
+        count: usize,
+    },
+    /// The graph is undirected, which the format does not store; it is read
+    /// back as directed.
+    Undirected,
+    /// Labels of `node` or `edge` elements with line breaks, which are
+    /// written as spaces.
+    LineBreaks { element: &'static str, count: usize },
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Loss::Attribute {
+                element: "graph",
+                ref key,
+                ..
+            } => write!(f, "graph attribute `{}` is not written", key),
+            Loss::Attribute {
+                element,
+                ref key,
+                count,
+            } => write!(
+                f,
+                "{} attribute `{}` of {} {}s is not written",
+                element, key, count, element
+            ),
+            Loss::Undirected => write!(f, "the graph is undirected, which is not stored"),
+            Loss::LineBreaks { element, count } => write!(
+                f,
+                "{} {} labels have line breaks, which are written as spaces",
+                count, element
+            ),
+        }
+    }
+}
+
+/// What `fidelity_report` found, in the order of graph, node and edge
+/// attributes by key, then the other losses.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FidelityReport {
+    pub losses: Vec<Loss>,
+}
+
+impl FidelityReport {
+    /// Whether the conversion keeps everything.
+    pub fn is_lossless(&self) -> bool {
+        self.losses.is_empty()
+    }
+}
+
+impl fmt::Display for FidelityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for loss in &self.losses {
+            writeln!(f, "{}", loss)?;
+        }
+        Ok(())
+    }
+}
+
+// Counts the attributes of `attrs` that `kept` rejects, by dotted key.
+fn count_dropped(
+    dropped: &mut BTreeMap<String, usize>,
+    attrs: &GmlAttrs,
+    kept: impl Fn(&str, &GmlValue) -> bool,
+) {
+    for (key, value) in flatten(attrs) {
+        if !kept(&key, value) {
+            *dropped.entry(key).or_insert(0) += 1;
+        }
+    }
+}
+
+/// Lists what converting `doc` to `target` loses.
+pub fn fidelity_report(doc: &GmlDocument, target: Target) -> FidelityReport {
+    let scalar = |key: &str, _: &GmlValue| !key.contains('.');
+    let string = |value: &GmlValue| matches!(*value, GmlValue::Str(_));
+    let label = |key: &str, value: &GmlValue| key == "label" && string(value);
+    let none = |_: &str, _: &GmlValue| false;
+
+    let (mut graph, mut nodes, mut edges) = (BTreeMap::new(), BTreeMap::new(), BTreeMap::new());
+    match target {
+        Target::Dot => {
+            count_dropped(&mut graph, &doc.attrs, scalar);
+            for node in &doc.nodes {
+                count_dropped(&mut nodes, &node.attrs, |key, value| {
+                    scalar(key, value)
+                        || (["graphics.fill", "graphics.outline"].contains(&key) && string(value))
+                        || (key == "graphics.type"
+                            && string(value)
+                            && !node.attrs.contains_key("shape"))
+                });
+            }
+            for edge in &doc.edges {
+                count_dropped(&mut edges, &edge.attrs, |key, value| {
+                    let arrow = value.get_str().and_then(Arrow::parse);
+                    scalar(key, value)
+                        || (key == "graphics.arrow" && arrow.is_some())
+                        || ([
+                            "graphics.fill",
+                            "graphics.sourceArrow",
+                            "graphics.targetArrow",
+                        ]
+                        .contains(&key)
+                            && string(value))
+                });
+            }
+        }
+        Target::Tgf | Target::Csv => {
+            count_dropped(&mut graph, &doc.attrs, none);
+            let kept = |key: &str, value: &GmlValue| target == Target::Csv || label(key, value);
+            for node in &doc.nodes {
+                count_dropped(&mut nodes, &node.attrs, kept);
+            }
+            for edge in &doc.edges {
+                count_dropped(&mut edges, &edge.attrs, kept);
+            }
+        }
+    }
+
+    let mut report = FidelityReport::default();
+    for (element, dropped) in [("graph", graph), ("node", nodes), ("edge", edges)] {
+        for (key, count) in dropped {
+            report.losses.push(Loss::Attribute {
+                element,
+                key,
+                count,
+            });
+        }
+    }
+    if target != Target::Dot && !doc.directed {
+        report.losses.push(Loss::Undirected);
+    }
+    if target == Target::Tgf {
+        let broken = |label: Option<&str>| label.is_some_and(|l| l.contains(['\r', '\n']));
+        let counts = [
+            (
+                "node",
+                doc.nodes.iter().filter(|n| broken(n.label())).count(),
+            ),
+            (
+                "edge",
+                doc.edges.iter().filter(|e| broken(e.label())).count(),
+            ),
+        ];
+        for (element, count) in counts {
+            if count > 0 {
+                report.losses.push(Loss::LineBreaks { element, count });
+            }
+        }
+    }
+    report
+}
+
+#[test]
+fn test_fidelity_report() {
+    use crate::document::parse_gml_document;
+
+    let doc = parse_gml_document(
+        "graph [ label \"g\" meta [ by \"me\" ]
+           node [ id 1 label \"a\nb\" graphics [ fill \"#f00\" x 1 ] ]
+           node [ id 2 label 7 graphics [ x 2 y 3 ] ]
+           edge [ source 1 target 2 weight 1 graphics [ fill \"red\" width 2 ] ] ]",
+    )
+    .unwrap();
+
+    let dot = fidelity_report(&doc, Target::Dot);
+    let attribute = |element, key: &str, count| Loss::Attribute {
+        element,
+        key: key.to_string(),
+        count,
+    };
+    assert_eq!(
+        vec![
+            attribute("graph", "meta.by", 1),
+            attribute("node", "graphics.x", 2),
+            attribute("node", "graphics.y", 1),
+            attribute("edge", "graphics.width", 1),
+        ],
+        dot.losses
+    );
+    assert_eq!(
+        "graph attribute `meta.by` is not written\nnode attribute `graphics.x` of 2 nodes is not written",
+        dot.to_string().lines().take(2).collect::<Vec<_>>().join("\n")
+    );
+
+    let tgf = fidelity_report(&doc, Target::Tgf);
+    assert!(tgf.losses.contains(&attribute("node", "label", 1)));
+    assert!(tgf.losses.contains(&attribute("edge", "weight", 1)));
+    assert!(tgf.losses.contains(&Loss::Undirected));
+    assert!(tgf.losses.contains(&Loss::LineBreaks {
+        element: "node",
+        count: 1
+    }));
+
+    let csv = fidelity_report(&doc, Target::Csv);
+    assert_eq!(
+        vec![
+            attribute("graph", "label", 1),
+            attribute("graph", "meta.by", 1),
+            Loss::Undirected
+        ],
+        csv.losses
+    );
+    let plain = parse_gml_document("graph [ directed 1 node [ id 1 label \"a\" ] ]").unwrap();
+    assert!(fidelity_report(&plain, Target::Tgf).is_lossless());
+}
@@ -0,0 +1,137 @@
+//! Network flows, with the `capacity`, `flow` and `cost` of edges.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use crate::node_map::NodeMap;
+use petgraph::{Directed, Graph};
+
+/// The flow attributes of an edge. `flow` and `cost` are 0 if not given.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FlowEdge {
+    pub capacity: f64,
+    pub flow: f64,
+    /// The cost of a unit of flow.
+    pub cost: f64,
+}
+
+impl FlowEdge {
+    /// Reads the flow attributes of an edge, which must have a non-negative
+    /// `capacity` and a `flow` between 0 and the capacity.
+    pub fn from_attrs(attrs: &GmlAttrs) -> Result<FlowEdge, &'static str> {
+        let number = |key, error| match attrs.get(key) {
+            None => Ok(None),
+            Some(value) => value.get_float().map(Some).ok_or(error),
+        };
+        let capacity = number("capacity", "invalid capacity")?
+            .filter(|&c| c >= 0.0)
+            .ok_or("invalid capacity")?;
+        let flow = number("flow", "invalid flow")?.unwrap_or(0.0);
+        if !(0.0..=capacity).contains(&flow) {
+            return Err("flow not between 0 and capacity");
+        }
+        Ok(FlowEdge {
+            capacity,
+            flow,
+            cost: number("cost", "invalid cost")?.unwrap_or(0.0),
+        })
+    }
+
+    /// Writes the flow attributes into `attrs` as reals, as after solving a
+    /// flow problem.
+    pub fn set_attrs(&self, attrs: &mut GmlAttrs) {
+        let values = [
+            ("capacity", self.capacity),
+            ("flow", self.flow),
+            ("cost", self.cost),
+        ];
+        for (key, value) in values {
+            attrs.insert(key.to_string(), GmlValue::Float(value));
+        }
+    }
+
+    /// The capacity left for more flow.
+    pub fn residual(&self) -> f64 {
+        self.capacity - self.flow
+    }
+}
+
+/// The directed flow network of `doc`, with the node ids as node weights.
+/// An algorithm which takes the capacities as edge weights, like a max-flow
+/// algorithm, gets them with
+/// `graph.map(|_, &id| id, |_, e| e.capacity)`.
+pub fn flow_graph(doc: &GmlDocument) -> Result<Graph<u64, FlowEdge, Directed>, &'static str> {
+    let mut graph = Graph::with_capacity(doc.nodes.len(), doc.edges.len());
+    let mut node_map = NodeMap::with_capacity(doc.nodes.len());
+    for node in &doc.nodes {
+        if !node_map.insert(node.id, graph.add_node(node.id)) {
+            return Err("duplicate node-id");
+        }
+    }
+    for edge in &doc.edges {
+        match (node_map.get(edge.source), node_map.get(edge.target)) {
+            (Some(source), Some(target)) => {
+                graph.add_edge(source, target, FlowEdge::from_attrs(&edge.attrs)?);
+            }
+            _ => return Err("edge references unknown node"),
+        }
+    }
+    Ok(graph)
+}
+
+#[test]
+fn test_flow_graph() {
+    use petgraph::visit::EdgeRef;
+
+    let doc = crate::parse_gml_document(
+        "graph [ directed 1 node [ id 1 ] node [ id 2 ] node [ id 3 ]
+                 edge [ source 1 target 2 capacity 10 flow 4 cost 2.5 ]
+                 edge [ source 2 target 3 capacity 3.5 ] ]",
+    )
+    .unwrap();
+    let graph = flow_graph(&doc).unwrap();
+    let edges: Vec<_> = graph
+        .edge_references()
+        .map(|e| (graph[e.source()], graph[e.target()], *e.weight()))
+        .collect();
+    assert_eq!(
+        (
+            1,
+            2,
+            FlowEdge {
+                capacity: 10.0,
+                flow: 4.0,
+                cost: 2.5
+            }
+        ),
+        edges[0]
+    );
+    assert_eq!(6.0, edges[0].2.residual());
+    assert_eq!((0.0, 0.0), (edges[1].2.flow, edges[1].2.cost));
+    let capacities = graph.map(|_, &id| id, |_, e| e.capacity);
+    assert_eq!(
+        vec![10.0, 3.5],
+        capacities
+            .raw_edges()
+            .iter()
+            .map(|e| e.weight)
+            .collect::<Vec<_>>()
+    );
+
+    let mut attrs = GmlAttrs::new();
+    edges[1].2.set_attrs(&mut attrs);
+    assert_eq!(Ok(edges[1].2), FlowEdge::from_attrs(&attrs));
+
+    let check = |edge: &str| {
+        let gml = format!(
+            "graph [ node [ id 1 ] edge [ source 1 target 1 {} ] ]",
+            edge
+        );
+        flow_graph(&crate::parse_gml_document(&gml).unwrap()).map(|_| ())
+    };
+    assert_eq!(Err("invalid capacity"), check(""));
+    assert_eq!(Err("invalid capacity"), check("capacity -1"));
+    assert_eq!(
+        Err("flow not between 0 and capacity"),
+        check("capacity 1 flow 2")
+    );
+    assert_eq!(Err("invalid cost"), check("capacity 1 cost \"high\""));
+}
@@ -0,0 +1,185 @@
+//! Synthetic graphs as GML documents, for benchmarks and test fixtures.
+//!
+//! The random generators are seeded, so that the same arguments always give
+//! the same document. Nodes have the ids `0` to `n - 1`.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::sample::Rng;
+
+fn nodes(n: usize) -> Vec<GmlNode> {
+    (0..n as u64)
+        .map(|id| GmlNode {
+            id,
+            attrs: GmlAttrs::new(),
+        })
+        .collect()
+}
+
+fn edge(source: usize, target: usize) -> GmlEdge {
+    GmlEdge {
+        source: source as u64,
+        target: target as u64,
+        attrs: GmlAttrs::new(),
+    }
+}
+
+// A number in [0, 1).
+fn unit(rng: &mut Rng) -> f64 {
+    (rng.next() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// The Erdős–Rényi graph G(n, p) on `n` nodes, with each edge between two
+/// different nodes present with probability `p`, and without self-loops.
+/// A directed graph has both edges of a pair with probability `p` each.
+/// Takes time in the number of edges, not of pairs, so large sparse graphs
+/// are quick.
+pub fn erdos_renyi(
+    n: usize,
+    p: f64,
+    directed: bool,
+    seed: u64,
+) -> Result<GmlDocument, &'static str> {
+    if !(0.0..=1.0).contains(&p) {
+        return Err("probability not in [0, 1]");
+    }
+    let mut doc = GmlDocument {
+        directed,
+        nodes: nodes(n),
+        ..GmlDocument::default()
+    };
+    if p == 0.0 || n < 2 {
+        return Ok(doc);
+    }
+    // Batagelj and Brandes: skip over the pairs without an edge, whose
+    // number before the next edge is geometrically distributed
+    let mut rng = Rng(seed);
+    let log_q = (1.0 - p).ln();
+    let mut skip = || (((1.0 - unit(&mut rng)).ln() / log_q).floor() as usize).saturating_add(1);
+    // the pairs of node `v` are with the `row` nodes `w` below it, or all
+    // others if directed
+    let row = |v: usize| if directed { n - 1 } else { v };
+    let (mut v, mut w) = (usize::from(!directed), skip() - 1);
+    loop {
+        while v < n && w >= row(v) {
+            w -= row(v);
+            v += 1;
+        }
+        if v == n {
+            break;
+        }
+        let target = if directed && w >= v { w + 1 } else { w };
+        doc.edges.push(edge(v, target));
+        w = w.saturating_add(skip());
+    }
+    Ok(doc)
+}
+
+/// The undirected Barabási–Albert graph on `n` nodes: starting with `m`
+/// nodes without edges, every further node is joined to `m` different
+/// earlier nodes chosen with probability proportional to their degree.
+pub fn barabasi_albert(n: usize, m: usize, seed: u64) -> Result<GmlDocument, &'static str> {
+    if m == 0 || m >= n {
+        return Err("m not between 1 and n - 1");
+    }
+    let mut doc = GmlDocument {
+        nodes: nodes(n),
+        ..GmlDocument::default()
+    };
+    let mut rng = Rng(seed);
+    // every node as often as its degree, so that a uniform choice from it
+    // is proportional to degree
+    let mut repeated = Vec::with_capacity(2 * m * (n - m));
+    let mut targets: Vec<usize> = (0..m).collect();
+    for source in m..n {
+        for &target in &targets {
+            doc.edges.push(edge(source, target));
+        }
+        repeated.extend_from_slice(&targets);
+        repeated.extend(std::iter::repeat_n(source, m));
+        targets.clear();
+        while targets.len() < m {
+            let target = repeated[rng.below(repeated.len())];
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    }
+    Ok(doc)
+}
+
+/// The undirected grid graph of `rows` by `columns` nodes, each joined to
+/// its right and lower neighbor. The node in row `r` and column `c` has the
+/// id `r * columns + c` and `graphics [ x c y r ]`.
+pub fn grid(rows: usize, columns: usize) -> GmlDocument {
+    let mut doc = GmlDocument {
+        nodes: nodes(rows * columns),
+        ..GmlDocument::default()
+    };
+    for (i, node) in doc.nodes.iter_mut().enumerate() {
+        let mut graphics = GmlAttrs::new();
+        graphics.insert("x".to_string(), GmlValue::Int((i % columns) as i64));
+        graphics.insert("y".to_string(), GmlValue::Int((i / columns) as i64));
+        node.attrs
+            .insert("graphics".to_string(), GmlValue::List(graphics));
+    }
+    for r in 0..rows {
+        for c in 0..columns {
+            let i = r * columns + c;
+            if c + 1 < columns {
+                doc.edges.push(edge(i, i + 1));
+            }
+            if r + 1 < rows {
+                doc.edges.push(edge(i, i + columns));
+            }
+        }
+    }
+    doc
+}
+
+#[test]
+fn test_gen() {
+    use std::collections::HashSet;
+
+    let pairs = |doc: &GmlDocument| -> HashSet<(u64, u64)> {
+        doc.edges.iter().map(|e| (e.source, e.target)).collect()
+    };
+
+    let complete = erdos_renyi(5, 1.0, false, 1).unwrap();
+    assert_eq!((5, 10), (complete.nodes.len(), complete.edges.len()));
+    assert!(complete.edges.iter().all(|e| e.source > e.target));
+    let complete = erdos_renyi(5, 1.0, true, 1).unwrap();
+    assert_eq!(20, pairs(&complete).len());
+    assert!(complete.edges.iter().all(|e| e.source != e.target));
+    assert!(erdos_renyi(5, 0.0, true, 1).unwrap().edges.is_empty());
+    assert_eq!(
+        Err("probability not in [0, 1]"),
+        erdos_renyi(5, 1.5, true, 1).map(|_| ())
+    );
+
+    let sparse = erdos_renyi(1000, 0.01, false, 7).unwrap();
+    // 4995 edges expected, with a standard deviation of about 70
+    assert!((4600..5400).contains(&sparse.edges.len()));
+    assert_eq!(sparse.edges.len(), pairs(&sparse).len());
+    assert_eq!(sparse, erdos_renyi(1000, 0.01, false, 7).unwrap());
+    assert_ne!(sparse, erdos_renyi(1000, 0.01, false, 8).unwrap());
+
+    let ba = barabasi_albert(200, 3, 5).unwrap();
+    assert!(!ba.directed);
+    assert_eq!((200, 197 * 3), (ba.nodes.len(), ba.edges.len()));
+    assert_eq!(ba.edges.len(), pairs(&ba).len());
+    assert!(ba.edges.iter().all(|e| e.source > e.target));
+    assert_eq!(
+        Err("m not between 1 and n - 1"),
+        barabasi_albert(3, 3, 5).map(|_| ())
+    );
+
+    let grid = grid(3, 4);
+    assert_eq!((12, 3 * 3 + 4 * 2), (grid.nodes.len(), grid.edges.len()));
+    assert!(pairs(&grid).contains(&(6, 10)));
+    assert_eq!(
+        Some(&GmlValue::Int(2)),
+        grid.nodes[6].attrs["graphics"].get("x")
+    );
+    let gml = crate::to_gml(&grid);
+    assert_eq!(Ok(grid), crate::parse_gml_document(&gml));
+}
@@ -0,0 +1,182 @@
+//! Geographic coordinates of nodes, and GeoJSON export.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlNode, GmlValue};
+use crate::json::{self, Json};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// The keys of the coordinates of nodes.
+#[derive(Debug, Clone)]
+pub struct GeoOptions {
+    /// The latitude, `lat` by default.
+    pub lat: String,
+    /// The longitude, `lon` by default.
+    pub lon: String,
+}
+
+impl Default for GeoOptions {
+    fn default() -> GeoOptions {
+        GeoOptions {
+            lat: "lat".to_string(),
+            lon: "lon".to_string(),
+        }
+    }
+}
+
+/// A position on the earth, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// The coordinate of `node`, if it has both keys with numbers in range.
+pub fn coordinate(node: &GmlNode, options: &GeoOptions) -> Option<Coordinate> {
+    let degrees = |key: &str| node.attrs.get(key).and_then(GmlValue::get_float);
+    let (lat, lon) = (degrees(&options.lat)?, degrees(&options.lon)?);
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+        Some(Coordinate { lat, lon })
+    } else {
+        None
+    }
+}
+
+fn position(c: Coordinate) -> Json {
+    Json::Array(vec![Json::Float(c.lon), Json::Float(c.lat)])
+}
+
+fn feature(geometry: &str, coordinates: Json, properties: Vec<(String, Json)>) -> Json {
+    let geometry = vec![
+        ("type".to_string(), Json::Str(geometry.to_string())),
+        ("coordinates".to_string(), coordinates),
+    ];
+    Json::Object(vec![
+        ("type".to_string(), Json::Str("Feature".to_string())),
+        ("geometry".to_string(), Json::Object(geometry)),
+        ("properties".to_string(), Json::Object(properties)),
+    ])
+}
+
+fn push_properties(properties: &mut Vec<(String, Json)>, attrs: &GmlAttrs, skip: &[&str]) {
+    for (k, v) in attrs {
+        if !skip.contains(&k.as_str()) {
+            properties.push((k.clone(), json::from_value(v)));
+        }
+    }
+}
+
+/// Renders `doc` as a GeoJSON `FeatureCollection`.
+///
+/// Nodes with a coordinate become `Point`s and edges between two of them
+/// `LineString`s, in document order; the others are left out. The
+/// attributes except the coordinates become the `properties`, with the
+/// `id` of nodes and the `source` and `target` of edges.
+pub fn to_geojson(doc: &GmlDocument, options: &GeoOptions) -> String {
+    let coordinates: HashMap<u64, Coordinate> = doc
+        .nodes
+        .iter()
+        .filter_map(|n| Some((n.id, coordinate(n, options)?)))
+        .collect();
+    let skip = [options.lat.as_str(), options.lon.as_str()];
+    let mut features = Vec::new();
+    for node in &doc.nodes {
+        if let Some(&c) = coordinates.get(&node.id) {
+            let mut properties = vec![("id".to_string(), Json::from(node.id))];
+            push_properties(&mut properties, &node.attrs, &skip);
+            features.push(feature("Point", position(c), properties));
+        }
+    }
+    for edge in &doc.edges {
+        if let (Some(&a), Some(&b)) = (coordinates.get(&edge.source), coordinates.get(&edge.target))
+        {
+            let mut properties = vec![
+                ("source".to_string(), Json::from(edge.source)),
+                ("target".to_string(), Json::from(edge.target)),
+            ];
+            push_properties(&mut properties, &edge.attrs, &[]);
+            let line = Json::Array(vec![position(a), position(b)]);
+            features.push(feature("LineString", line, properties));
+        }
+    }
+    Json::Object(vec![
+        (
+            "type".to_string(),
+            Json::Str("FeatureCollection".to_string()),
+        ),
+        ("features".to_string(), Json::Array(features)),
+    ])
+    .to_string_pretty()
+}
+
+/// Writes `doc` as GeoJSON. See `to_geojson`.
+pub fn write_geojson<W: Write>(
+    doc: &GmlDocument,
+    options: &GeoOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    w.write_all(to_geojson(doc, options).as_bytes())
+}
+
+#[test]
+fn test_to_geojson() {
+    let gml = "graph [ node [ id 1 label \"Berlin\" lat 52.52 lon 13.405 ]
+                       node [ id 2 label \"Paris\" lat 48.8566 lon 2.3522 ]
+                       node [ id 3 label \"nowhere\" ] node [ id 4 lat 95 lon 0 ]
+                       edge [ source 1 target 2 km 878 ] edge [ source 1 target 3 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let options = GeoOptions::default();
+    assert_eq!(
+        Some(Coordinate {
+            lat: 52.52,
+            lon: 13.405
+        }),
+        coordinate(&doc.nodes[0], &options)
+    );
+    assert_eq!(None, coordinate(&doc.nodes[2], &options));
+    assert_eq!(None, coordinate(&doc.nodes[3], &options));
+
+    let parsed = json::parse(&to_geojson(&doc, &options)).unwrap();
+    assert_eq!(
+        Some("FeatureCollection"),
+        parsed.get("type").and_then(Json::as_str)
+    );
+    let features = match parsed.get("features") {
+        Some(Json::Array(features)) => features.clone(),
+        _ => panic!("no features"),
+    };
+    assert_eq!(3, features.len());
+    let point = features[0].get("geometry").unwrap();
+    assert_eq!(Some("Point"), point.get("type").and_then(Json::as_str));
+    assert_eq!(
+        Some(&Json::Array(vec![Json::Float(13.405), Json::Float(52.52)])),
+        point.get("coordinates")
+    );
+    let properties = features[0].get("properties").unwrap();
+    assert_eq!(
+        Some("Berlin"),
+        properties.get("label").and_then(Json::as_str)
+    );
+    assert!(properties.get("lat").is_none());
+    let line = &features[2];
+    assert_eq!(
+        Some("LineString"),
+        line.get("geometry")
+            .unwrap()
+            .get("type")
+            .and_then(Json::as_str)
+    );
+    assert_eq!(
+        Some(&Json::Int(878)),
+        line.get("properties").unwrap().get("km")
+    );
+
+    let options = GeoOptions {
+        lat: "latitude".to_string(),
+        lon: "longitude".to_string(),
+    };
+    let doc = crate::parse_gml_document("graph [ node [ id 1 latitude 1 longitude 2 ] ]").unwrap();
+    assert_eq!(
+        Some(Coordinate { lat: 1.0, lon: 2.0 }),
+        coordinate(&doc.nodes[0], &options)
+    );
+}
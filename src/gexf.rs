@@ -0,0 +1,307 @@
+//! GEXF 1.3 export for Gephi.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlValue, ValueType};
+use crate::xml::escape;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Default)]
+pub struct GexfOptions {
+    /// Write the `start` and `end` attributes of nodes and edges as GEXF
+    /// lifetimes and declare the graph as dynamic.
+    pub dynamic: bool,
+}
+
+/// Renders `doc` as GEXF.
+pub fn to_gexf(doc: &GmlDocument, options: &GexfOptions) -> String {
+    let mut buf = Vec::new();
+    write_gexf(doc, options, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as GEXF 1.3.
+///
+/// `label` (and `weight` on edges) map to the GEXF attributes of the same
+/// name. The `x`, `y`, `z`, `w` and `fill` keys of `graphics` blocks become
+/// `viz:position`, `viz:size` and `viz:color`. All other attributes are
+/// declared in `<attributes>` and written as `<attvalue>`s, nested lists
+/// flattened into dotted titles.
+pub fn write_gexf<W: Write>(doc: &GmlDocument, options: &GexfOptions, w: &mut W) -> io::Result<()> {
+    let node_attrs = declare(doc.nodes.iter().map(|n| &n.attrs), &["label"], options);
+    let edge_attrs = declare(
+        doc.edges.iter().map(|e| &e.attrs),
+        &["label", "weight"],
+        options,
+    );
+
+    writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        w,
+        "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd\" \
+         version=\"1.3\">"
+    )?;
+    if let Some(label) = doc.attrs.get("label").and_then(GmlValue::get_str) {
+        writeln!(w, "  <meta>")?;
+        writeln!(w, "    <description>{}</description>", escape(label))?;
+        writeln!(w, "  </meta>")?;
+    }
+
+    let edge_type = if doc.directed {
+        "directed"
+    } else {
+        "undirected"
+    };
+    if options.dynamic {
+        writeln!(
+            w,
+            "  <graph defaultedgetype=\"{}\" mode=\"dynamic\" timeformat=\"{}\">",
+            edge_type,
+            time_format(doc)
+        )?;
+    } else {
+        writeln!(
+            w,
+            "  <graph defaultedgetype=\"{}\" mode=\"static\">",
+            edge_type
+        )?;
+    }
+    write_declarations(w, "node", &node_attrs)?;
+    write_declarations(w, "edge", &edge_attrs)?;
+
+    writeln!(w, "    <nodes>")?;
+    for node in &doc.nodes {
+        let mut tag = format!("<node id=\"{}\"", node.id);
+        if let Some(label) = node.label() {
+            tag.push_str(&format!(" label=\"{}\"", escape(label)));
+        }
+        push_lifetime(&mut tag, &node.attrs, options);
+        let viz = viz_elements(node.attrs.get("graphics"));
+        write_element(w, "node", tag, &node.attrs, &node_attrs, &viz)?;
+    }
+    writeln!(w, "    </nodes>")?;
+
+    writeln!(w, "    <edges>")?;
+    for (i, edge) in doc.edges.iter().enumerate() {
+        let mut tag = format!(
+            "<edge id=\"{}\" source=\"{}\" target=\"{}\"",
+            i, edge.source, edge.target
+        );
+        if let Some(label) = edge.label() {
+            tag.push_str(&format!(" label=\"{}\"", escape(label)));
+        }
+        if let Some(weight) = edge.attrs.get("weight").and_then(GmlValue::get_float) {
+            tag.push_str(&format!(" weight=\"{}\"", weight));
+        }
+        push_lifetime(&mut tag, &edge.attrs, options);
+        let viz = viz_elements(edge.attrs.get("graphics"));
+        write_element(w, "edge", tag, &edge.attrs, &edge_attrs, &viz)?;
+    }
+    writeln!(w, "    </edges>")?;
+
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</gexf>")
+}
+
+/// Attribute title mapped to its declared id and type.
+type Declarations = BTreeMap<String, (usize, ValueType)>;
+
+fn is_declared(name: &str, reserved: &[&str], options: &GexfOptions) -> bool {
+    !(reserved.contains(&name)
+        || name.starts_with("graphics.")
+        || (options.dynamic && (name == "start" || name == "end")))
+}
+
+fn declare<'a, I>(attrs: I, reserved: &[&str], options: &GexfOptions) -> Declarations
+where
+    I: Iterator<Item = &'a GmlAttrs>,
+{
+    let mut decls = Declarations::new();
+    for attrs in attrs {
+        for (name, value) in flatten(attrs) {
+            if !is_declared(&name, reserved, options) {
+                continue;
+            }
+            let next_id = decls.len();
+            let entry = decls
+                .entry(name)
+                .or_insert_with(|| (next_id, ValueType::of(value)));
+            entry.1 = entry.1.unify(ValueType::of(value));
+        }
+    }
+    decls
+}
+
+fn type_name(ty: ValueType) -> &'static str {
+    match ty {
+        ValueType::Int => "integer",
+        ValueType::Long | ValueType::UnsignedLong => "long",
+        ValueType::Double => "double",
+        ValueType::String => "string",
+    }
+}
+
+fn write_declarations<W: Write>(w: &mut W, class: &str, decls: &Declarations) -> io::Result<()> {
+    if decls.is_empty() {
+        return Ok(());
+    }
+    let mut sorted: Vec<_> = decls.iter().collect();
+    sorted.sort_by_key(|&(_, &(id, _))| id);
+    writeln!(w, "    <attributes class=\"{}\">", class)?;
+    for (title, &(id, ty)) in sorted {
+        writeln!(
+            w,
+            "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+            id,
+            escape(title),
+            type_name(ty)
+        )?;
+    }
+    writeln!(w, "    </attributes>")
+}
+
+fn scalar_text(value: &GmlValue) -> Option<String> {
+    match *value {
+        GmlValue::Int(i) => Some(i.to_string()),
+        GmlValue::UInt(u) => Some(u.to_string()),
+        GmlValue::Float(f) => Some(f.to_string()),
+        GmlValue::Str(ref s) => Some(escape(s)),
+        GmlValue::List(_) => None,
+    }
+}
+
+fn push_lifetime(tag: &mut String, attrs: &GmlAttrs, options: &GexfOptions) {
+    if !options.dynamic {
+        return;
+    }
+    for key in &["start", "end"] {
+        if let Some(text) = attrs.get(*key).and_then(scalar_text) {
+            tag.push_str(&format!(" {}=\"{}\"", key, text));
+        }
+    }
+}
+
+// The GEXF time format which can represent all lifetimes in `doc`.
+fn time_format(doc: &GmlDocument) -> &'static str {
+    let attrs = doc
+        .nodes
+        .iter()
+        .map(|n| &n.attrs)
+        .chain(doc.edges.iter().map(|e| &e.attrs));
+    let mut format = "integer";
+    for attrs in attrs {
+        for key in &["start", "end"] {
+            match attrs.get(*key) {
+                Some(&GmlValue::Float(_)) if format == "integer" => format = "double",
+                Some(GmlValue::Str(s)) if s.contains('T') => return "dateTime",
+                Some(&GmlValue::Str(_)) => format = "date",
+                _ => {}
+            }
+        }
+    }
+    format
+}
+
+fn viz_elements(graphics: Option<&GmlValue>) -> Vec<String> {
+    let mut viz = Vec::new();
+    let graphics = match graphics {
+        Some(g) => g,
+        None => return viz,
+    };
+    let coord = |k: &str| graphics.get(k).and_then(GmlValue::get_float);
+    if let (Some(x), Some(y)) = (coord("x"), coord("y")) {
+        viz.push(format!(
+            "<viz:position x=\"{}\" y=\"{}\" z=\"{}\"/>",
+            x,
+            y,
+            coord("z").unwrap_or(0.0)
+        ));
+    }
+    if let Some(size) = coord("w") {
+        viz.push(format!("<viz:size value=\"{}\"/>", size));
+    }
+    if let Some(fill) = graphics.get("fill").and_then(GmlValue::get_str) {
+        if let Some((r, g, b)) = parse_hex_color(fill) {
+            viz.push(format!("<viz:color r=\"{}\" g=\"{}\" b=\"{}\"/>", r, g, b));
+        }
+    }
+    viz
+}
+
+fn parse_hex_color(s: &str) -> Option<(u8, u8, u8)> {
+    let hex = s.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn write_element<W: Write>(
+    w: &mut W,
+    name: &str,
+    tag: String,
+    attrs: &GmlAttrs,
+    decls: &Declarations,
+    viz: &[String],
+) -> io::Result<()> {
+    let values: Vec<_> = flatten(attrs)
+        .into_iter()
+        .filter_map(|(title, value)| {
+            let &(id, _) = decls.get(&title)?;
+            Some((id, scalar_text(value)?))
+        })
+        .collect();
+
+    if values.is_empty() && viz.is_empty() {
+        return writeln!(w, "      {}/>", tag);
+    }
+    writeln!(w, "      {}>", tag)?;
+    if !values.is_empty() {
+        writeln!(w, "        <attvalues>")?;
+        for (id, value) in values {
+            writeln!(
+                w,
+                "          <attvalue for=\"{}\" value=\"{}\"/>",
+                id, value
+            )?;
+        }
+        writeln!(w, "        </attvalues>")?;
+    }
+    for v in viz {
+        writeln!(w, "        {}", v)?;
+    }
+    writeln!(w, "      </{}>", name)
+}
+
+#[test]
+// expects the attributes in key order
+fn test_to_gexf() {
+    let gml = "
+    graph [
+        directed 1
+        node [ id 1 label \"a\" kind \"router\" start 1 end 5
+               graphics [ x 1.5 y 2 fill \"#FF8800\" ] ]
+        node [ id 2 kind \"host\" ]
+        edge [ source 1 target 2 weight 0.5 start 2 ]
+    ]
+    ";
+    let doc = crate::parse_gml_document(gml).unwrap();
+
+    let gexf = to_gexf(&doc, &GexfOptions::default());
+    assert!(gexf.contains("mode=\"static\""));
+    // attribute ids follow the key order of the attributes
+    assert!(gexf.contains(" title=\"end\" type=\"integer\"/>"));
+    assert!(gexf.contains("<node id=\"1\" label=\"a\">"));
+    assert!(gexf.contains("<viz:position x=\"1.5\" y=\"2\" z=\"0\"/>"));
+    assert!(gexf.contains("<viz:color r=\"255\" g=\"136\" b=\"0\"/>"));
+    assert!(gexf.contains("<edge id=\"0\" source=\"1\" target=\"2\" weight=\"0.5\">"));
+
+    let gexf = to_gexf(&doc, &GexfOptions { dynamic: true });
+    assert!(gexf.contains("mode=\"dynamic\" timeformat=\"integer\""));
+    assert!(gexf.contains(" title=\"kind\" type=\"string\"/>"));
+    assert!(!gexf.contains(" title=\"end\""));
+    assert!(gexf.contains("<node id=\"1\" label=\"a\" start=\"1\" end=\"5\">"));
+    assert!(gexf.contains("<edge id=\"0\" source=\"1\" target=\"2\" weight=\"0.5\" start=\"2\"/>"));
+}
@@ -0,0 +1,666 @@
+//! The `graphics` and `LabelGraphics` lists of nodes and edges and the
+//! `edgeAnchor` lists of edges, with their common keys typed.
+
+use crate::color::Color;
+use crate::document::{GmlAttrs, GmlEdge, GmlNode, GmlValue};
+
+/// A `graphics` list. Keys it does not know, and known keys with values of
+/// another type, are kept in `other`, so that `to_attrs` gives the list
+/// back with all of its entries.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Graphics {
+    /// The position of the center.
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    /// The width.
+    pub w: Option<f64>,
+    /// The height.
+    pub h: Option<f64>,
+    /// The `type`.
+    pub shape: Option<Shape>,
+    /// The fill color, from a string like `#FF0000`.
+    pub fill: Option<Color>,
+    /// The color of the outline.
+    pub outline: Option<Color>,
+    /// The width of the outline, or of an edge line.
+    pub width: Option<f64>,
+    /// The points of the `Line` of an edge, from its source to its target
+    /// through its bends. A `Line` with other entries is kept in `other`.
+    pub line: Vec<Point>,
+    /// At which ends of an edge the `arrow` key draws arrows.
+    pub arrow: Option<Arrow>,
+    /// The yEd `sourceArrow` and `targetArrow` of an edge.
+    pub source_arrow: Option<ArrowHead>,
+    pub target_arrow: Option<ArrowHead>,
+    pub other: GmlAttrs,
+}
+
+/// The `type` of a node, as yEd names it, with the others by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    Rectangle,
+    RoundRectangle,
+    Ellipse,
+    Triangle,
+    Diamond,
+    Hexagon,
+    Octagon,
+    Parallelogram,
+    Trapezoid,
+    Other(String),
+}
+
+const SHAPES: &[(&str, Shape)] = &[
+    ("rectangle", Shape::Rectangle),
+    ("roundrectangle", Shape::RoundRectangle),
+    ("ellipse", Shape::Ellipse),
+    ("triangle", Shape::Triangle),
+    ("diamond", Shape::Diamond),
+    ("hexagon", Shape::Hexagon),
+    ("octagon", Shape::Octagon),
+    ("parallelogram", Shape::Parallelogram),
+    ("trapezoid", Shape::Trapezoid),
+];
+
+impl Shape {
+    pub fn from_name(name: &str) -> Shape {
+        SHAPES
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map_or_else(|| Shape::Other(name.to_string()), |(_, s)| s.clone())
+    }
+
+    pub fn name(&self) -> &str {
+        match *self {
+            Shape::Other(ref name) => name,
+            ref shape => SHAPES.iter().find(|(_, s)| s == shape).unwrap().0,
+        }
+    }
+
+    /// The Graphviz `shape`, with the rounded rectangle a `box`. Others are
+    /// passed by name.
+    pub fn dot_name(&self) -> &str {
+        match *self {
+            Shape::Rectangle | Shape::RoundRectangle => "box",
+            Shape::Trapezoid => "trapezium",
+            ref shape => shape.name(),
+        }
+    }
+}
+
+/// The `arrow` key of the GML specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrow {
+    None,
+    /// At the source.
+    First,
+    /// At the target.
+    Last,
+    Both,
+}
+
+impl Arrow {
+    pub fn parse(s: &str) -> Option<Arrow> {
+        match s {
+            "none" => Some(Arrow::None),
+            "first" => Some(Arrow::First),
+            "last" => Some(Arrow::Last),
+            "both" => Some(Arrow::Both),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Arrow::None => "none",
+            Arrow::First => "first",
+            Arrow::Last => "last",
+            Arrow::Both => "both",
+        }
+    }
+}
+
+/// An arrowhead of yEd, with the others by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrowHead {
+    None,
+    Standard,
+    Delta,
+    WhiteDelta,
+    Diamond,
+    WhiteDiamond,
+    Short,
+    Circle,
+    TransparentCircle,
+    TShape,
+    Other(String),
+}
+
+const ARROW_HEADS: &[(&str, ArrowHead)] = &[
+    ("none", ArrowHead::None),
+    ("standard", ArrowHead::Standard),
+    ("delta", ArrowHead::Delta),
+    ("white_delta", ArrowHead::WhiteDelta),
+    ("diamond", ArrowHead::Diamond),
+    ("white_diamond", ArrowHead::WhiteDiamond),
+    ("short", ArrowHead::Short),
+    ("circle", ArrowHead::Circle),
+    ("transparent_circle", ArrowHead::TransparentCircle),
+    ("t_shape", ArrowHead::TShape),
+];
+
+impl ArrowHead {
+    pub fn from_name(name: &str) -> ArrowHead {
+        ARROW_HEADS
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map_or_else(|| ArrowHead::Other(name.to_string()), |(_, a)| a.clone())
+    }
+
+    pub fn name(&self) -> &str {
+        match *self {
+            ArrowHead::Other(ref name) => name,
+            ref head => ARROW_HEADS.iter().find(|(_, a)| a == head).unwrap().0,
+        }
+    }
+
+    /// The nearest Graphviz `arrowhead`, `normal` for unknown ones.
+    pub fn dot_name(&self) -> &'static str {
+        match *self {
+            ArrowHead::None => "none",
+            ArrowHead::WhiteDelta => "onormal",
+            ArrowHead::Diamond => "diamond",
+            ArrowHead::WhiteDiamond => "odiamond",
+            ArrowHead::Circle => "dot",
+            ArrowHead::TransparentCircle => "odot",
+            ArrowHead::TShape => "tee",
+            _ => "normal",
+        }
+    }
+}
+
+/// A `point` of a `Line`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+// The points of a `Line` list, if it has nothing else.
+fn points(line: &GmlAttrs) -> Option<Vec<Point>> {
+    if line.len() != 1 {
+        return None;
+    }
+    let mut points = Vec::new();
+    for point in line.get("point")?.values() {
+        let point = point.get_list().filter(|p| p.len() == 2)?;
+        points.push(Point {
+            x: point.get("x")?.get_float()?,
+            y: point.get("y")?.get_float()?,
+        });
+    }
+    Some(points)
+}
+
+impl Graphics {
+    /// Reads the entries of a `graphics` list.
+    pub fn from_attrs(attrs: &GmlAttrs) -> Graphics {
+        let mut g = Graphics::default();
+        for (key, value) in attrs {
+            let number = match *value {
+                GmlValue::Int(i) => Some(i as f64),
+                GmlValue::Float(f) => Some(f),
+                _ => None,
+            };
+            let string = value.get_str().map(str::to_string);
+            let color = value.get_str().and_then(|s| Color::parse(s).ok());
+            match (key.as_str(), number, string) {
+                ("x", Some(n), _) => g.x = Some(n),
+                ("y", Some(n), _) => g.y = Some(n),
+                ("z", Some(n), _) => g.z = Some(n),
+                ("w", Some(n), _) => g.w = Some(n),
+                ("h", Some(n), _) => g.h = Some(n),
+                ("width", Some(n), _) => g.width = Some(n),
+                ("type", _, Some(s)) => g.shape = Some(Shape::from_name(&s)),
+                ("fill", _, _) if color.is_some() => g.fill = color,
+                ("outline", _, _) if color.is_some() => g.outline = color,
+                ("arrow", _, Some(s)) if Arrow::parse(&s).is_some() => {
+                    g.arrow = Arrow::parse(&s);
+                }
+                ("sourceArrow", _, Some(s)) => g.source_arrow = Some(ArrowHead::from_name(&s)),
+                ("targetArrow", _, Some(s)) => g.target_arrow = Some(ArrowHead::from_name(&s)),
+                ("Line", _, _) if value.get_list().and_then(points).is_some() => {
+                    g.line = value.get_list().and_then(points).unwrap();
+                }
+                _ => {
+                    g.other.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        g
+    }
+
+    /// The entries of a `graphics` list, the known keys first. Numbers are
+    /// written as reals.
+    pub fn to_attrs(&self) -> GmlAttrs {
+        let mut attrs = GmlAttrs::new();
+        let numbers = [
+            ("x", self.x),
+            ("y", self.y),
+            ("z", self.z),
+            ("w", self.w),
+            ("h", self.h),
+            ("width", self.width),
+        ];
+        for (key, number) in numbers {
+            if let Some(number) = number {
+                attrs.insert(key.to_string(), GmlValue::Float(number));
+            }
+        }
+        if let Some(shape) = &self.shape {
+            attrs.insert("type".to_string(), GmlValue::from(shape.name()));
+        }
+        for (key, color) in [("fill", self.fill), ("outline", self.outline)] {
+            if let Some(color) = color {
+                attrs.insert(key.to_string(), GmlValue::from(color.to_string()));
+            }
+        }
+        if !self.line.is_empty() {
+            let points = self.line.iter().map(|p| {
+                let mut point = GmlAttrs::new();
+                point.insert("x".to_string(), GmlValue::Float(p.x));
+                point.insert("y".to_string(), GmlValue::Float(p.y));
+                GmlValue::List(point)
+            });
+            let mut line = GmlAttrs::new();
+            line.insert("point".to_string(), GmlValue::from_values(points.collect()));
+            attrs.insert("Line".to_string(), GmlValue::List(line));
+        }
+        if let Some(arrow) = self.arrow {
+            attrs.insert("arrow".to_string(), GmlValue::from(arrow.name()));
+        }
+        let heads = [
+            ("sourceArrow", &self.source_arrow),
+            ("targetArrow", &self.target_arrow),
+        ];
+        for (key, head) in heads {
+            if let Some(head) = head {
+                attrs.insert(key.to_string(), GmlValue::from(head.name()));
+            }
+        }
+        for (key, value) in &self.other {
+            attrs.insert(key.clone(), value.clone());
+        }
+        attrs
+    }
+}
+
+/// A `LabelGraphics` list, as written by yEd for the styling of a label.
+/// Like `Graphics`, it keeps all other entries in `other`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LabelGraphics {
+    /// The text shown, which yEd takes over the `label` of the element.
+    pub text: Option<String>,
+    pub font_size: Option<f64>,
+    /// The `fontName`, like `Dialog`.
+    pub font_name: Option<String>,
+    /// The `fontStyle`, like `bold` or `italic`.
+    pub font_style: Option<String>,
+    /// The text color, from a string like `#000000`.
+    pub color: Option<Color>,
+    /// Where the label is placed, like `c` or `t`.
+    pub anchor: Option<String>,
+    /// The alignment of lines, like `center`.
+    pub alignment: Option<String>,
+    pub other: GmlAttrs,
+}
+
+impl LabelGraphics {
+    /// Reads the entries of a `LabelGraphics` list.
+    pub fn from_attrs(attrs: &GmlAttrs) -> LabelGraphics {
+        let mut g = LabelGraphics::default();
+        for (key, value) in attrs {
+            let string = value.get_str().map(str::to_string);
+            match (key.as_str(), value.get_float(), string) {
+                ("fontSize", Some(n), _) => g.font_size = Some(n),
+                ("text", _, Some(s)) => g.text = Some(s),
+                ("fontName", _, Some(s)) => g.font_name = Some(s),
+                ("fontStyle", _, Some(s)) => g.font_style = Some(s),
+                ("color", _, Some(s)) if Color::parse(&s).is_ok() => {
+                    g.color = Color::parse(&s).ok();
+                }
+                ("anchor", _, Some(s)) => g.anchor = Some(s),
+                ("alignment", _, Some(s)) => g.alignment = Some(s),
+                _ => {
+                    g.other.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        g
+    }
+
+    /// The entries of a `LabelGraphics` list, the known keys first. A font
+    /// size without a fraction is written as an integer, as yEd does.
+    pub fn to_attrs(&self) -> GmlAttrs {
+        let mut attrs = GmlAttrs::new();
+        if let Some(s) = &self.text {
+            attrs.insert("text".to_string(), GmlValue::from(s.clone()));
+        }
+        if let Some(size) = self.font_size {
+            let value = if size.fract() == 0.0 && size.abs() < 1e15 {
+                GmlValue::Int(size as i64)
+            } else {
+                GmlValue::Float(size)
+            };
+            attrs.insert("fontSize".to_string(), value);
+        }
+        let strings = [
+            ("fontName", &self.font_name),
+            ("fontStyle", &self.font_style),
+            ("anchor", &self.anchor),
+            ("alignment", &self.alignment),
+        ];
+        for (key, s) in strings {
+            if let Some(s) = s {
+                attrs.insert(key.to_string(), GmlValue::from(s.clone()));
+            }
+        }
+        if let Some(color) = self.color {
+            attrs.insert("color".to_string(), GmlValue::from(color.to_string()));
+        }
+        for (key, value) in &self.other {
+            attrs.insert(key.clone(), value.clone());
+        }
+        attrs
+    }
+}
+
+/// The `edgeAnchor` list of yEd, which attaches an edge to points of its
+/// nodes instead of their centers. A point is relative to the center of its
+/// node, with `-1` the left or top side and `1` the right or bottom side.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EdgeAnchor {
+    pub x_source: Option<f64>,
+    pub y_source: Option<f64>,
+    pub x_target: Option<f64>,
+    pub y_target: Option<f64>,
+    pub other: GmlAttrs,
+}
+
+const ANCHOR_KEYS: [&str; 4] = ["xSource", "ySource", "xTarget", "yTarget"];
+
+impl EdgeAnchor {
+    /// Reads the entries of an `edgeAnchor` list.
+    pub fn from_attrs(attrs: &GmlAttrs) -> EdgeAnchor {
+        let mut anchor = EdgeAnchor::default();
+        for (key, value) in attrs {
+            let field = match key.as_str() {
+                "xSource" => &mut anchor.x_source,
+                "ySource" => &mut anchor.y_source,
+                "xTarget" => &mut anchor.x_target,
+                "yTarget" => &mut anchor.y_target,
+                _ => {
+                    anchor.other.insert(key.clone(), value.clone());
+                    continue;
+                }
+            };
+            match value.get_float() {
+                Some(n) => *field = Some(n),
+                None => {
+                    anchor.other.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        anchor
+    }
+
+    /// The entries of an `edgeAnchor` list, the known keys first.
+    pub fn to_attrs(&self) -> GmlAttrs {
+        let mut attrs = GmlAttrs::new();
+        let numbers = [self.x_source, self.y_source, self.x_target, self.y_target];
+        for (key, number) in ANCHOR_KEYS.iter().zip(numbers) {
+            if let Some(number) = number {
+                attrs.insert(key.to_string(), GmlValue::Float(number));
+            }
+        }
+        for (key, value) in &self.other {
+            attrs.insert(key.clone(), value.clone());
+        }
+        attrs
+    }
+}
+
+fn list<'a>(attrs: &'a GmlAttrs, key: &str) -> Option<&'a GmlAttrs> {
+    attrs.get(key).and_then(GmlValue::get_list)
+}
+
+impl GmlNode {
+    /// The `graphics` list of the node, if it has one.
+    pub fn graphics(&self) -> Option<Graphics> {
+        list(&self.attrs, "graphics").map(Graphics::from_attrs)
+    }
+
+    /// Replaces the `graphics` list of the node.
+    pub fn set_graphics(&mut self, graphics: &Graphics) {
+        self.attrs
+            .insert("graphics".to_string(), GmlValue::List(graphics.to_attrs()));
+    }
+
+    /// The `LabelGraphics` list of the node, if it has one.
+    pub fn label_graphics(&self) -> Option<LabelGraphics> {
+        list(&self.attrs, "LabelGraphics").map(LabelGraphics::from_attrs)
+    }
+
+    /// Replaces the `LabelGraphics` list of the node.
+    pub fn set_label_graphics(&mut self, graphics: &LabelGraphics) {
+        self.attrs.insert(
+            "LabelGraphics".to_string(),
+            GmlValue::List(graphics.to_attrs()),
+        );
+    }
+}
+
+impl GmlEdge {
+    /// The `graphics` list of the edge, if it has one.
+    pub fn graphics(&self) -> Option<Graphics> {
+        list(&self.attrs, "graphics").map(Graphics::from_attrs)
+    }
+
+    /// Replaces the `graphics` list of the edge.
+    pub fn set_graphics(&mut self, graphics: &Graphics) {
+        self.attrs
+            .insert("graphics".to_string(), GmlValue::List(graphics.to_attrs()));
+    }
+
+    /// The `LabelGraphics` list of the edge, if it has one.
+    pub fn label_graphics(&self) -> Option<LabelGraphics> {
+        list(&self.attrs, "LabelGraphics").map(LabelGraphics::from_attrs)
+    }
+
+    /// Replaces the `LabelGraphics` list of the edge.
+    pub fn set_label_graphics(&mut self, graphics: &LabelGraphics) {
+        self.attrs.insert(
+            "LabelGraphics".to_string(),
+            GmlValue::List(graphics.to_attrs()),
+        );
+    }
+
+    /// The `edgeAnchor` list of the edge, if it has one.
+    pub fn edge_anchor(&self) -> Option<EdgeAnchor> {
+        list(&self.attrs, "edgeAnchor").map(EdgeAnchor::from_attrs)
+    }
+
+    /// Replaces the `edgeAnchor` list of the edge.
+    pub fn set_edge_anchor(&mut self, anchor: &EdgeAnchor) {
+        self.attrs
+            .insert("edgeAnchor".to_string(), GmlValue::List(anchor.to_attrs()));
+    }
+}
+
+#[test]
+fn test_graphics() {
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 graphics [ x 10 y 2.5 w 30.0 h 30.0 type \"ellipse\"
+                          fill \"#FF0000\" outline 7 Line [ point [ x 1 ] ] ] ]
+                 node [ id 2 ] edge [ source 1 target 2 graphics [ width 2 ] ] ]",
+    )
+    .unwrap();
+    let graphics = doc.nodes[0].graphics().unwrap();
+    assert_eq!(
+        (Some(10.0), Some(2.5), None),
+        (graphics.x, graphics.y, graphics.z)
+    );
+    assert_eq!((Some(30.0), Some(30.0)), (graphics.w, graphics.h));
+    assert_eq!(Some(Shape::Ellipse), graphics.shape);
+    assert_eq!(Some(Color::rgb(255, 0, 0)), graphics.fill);
+    // not a string, so kept as it is
+    assert_eq!(None, graphics.outline);
+    assert_eq!(Some(&GmlValue::Int(7)), graphics.other.get("outline"));
+    assert!(graphics.other.contains_key("Line"));
+    assert_eq!(None, doc.nodes[1].graphics());
+    assert_eq!(Some(2.0), doc.edges[0].graphics().unwrap().width);
+
+    let mut node = doc.nodes[0].clone();
+    let mut moved = graphics.clone();
+    moved.x = Some(-1.0);
+    node.set_graphics(&moved);
+    assert_eq!(moved, node.graphics().unwrap());
+    let attrs = node.attrs["graphics"].get_list().unwrap();
+    assert_eq!(Some(&GmlValue::Float(-1.0)), attrs.get("x"));
+    assert_eq!(Some(&GmlValue::Int(7)), attrs.get("outline"));
+    assert_eq!(8, attrs.len());
+}
+
+#[test]
+fn test_line() {
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 ] node [ id 2 ]
+                 edge [ source 1 target 2 graphics [ width 2 Line [
+                     point [ x 0 y 0 ] point [ x 10.5 y 0 ] point [ x 10.5 y 20 ] ] ] ] ]",
+    )
+    .unwrap();
+    let graphics = doc.edges[0].graphics().unwrap();
+    assert_eq!(
+        vec![(0.0, 0.0), (10.5, 0.0), (10.5, 20.0)],
+        graphics.line.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>()
+    );
+    assert!(graphics.other.is_empty());
+
+    let mut edge = doc.edges[0].clone();
+    let mut rerouted = graphics.clone();
+    rerouted.line.insert(1, Point { x: 5.0, y: -5.0 });
+    edge.set_graphics(&rerouted);
+    let doc = crate::document::GmlDocument {
+        edges: vec![edge],
+        ..doc
+    };
+    let gml = crate::to_gml(&doc);
+    assert_eq!(4, gml.matches("point [").count());
+    let parsed = crate::parse_gml_document(&gml).unwrap();
+    assert_eq!(Some(rerouted), parsed.edges[0].graphics());
+}
+
+#[test]
+fn test_shapes() {
+    assert_eq!(Shape::RoundRectangle, Shape::from_name("roundrectangle"));
+    assert_eq!(
+        ("trapezoid", "trapezium"),
+        (Shape::Trapezoid.name(), Shape::Trapezoid.dot_name())
+    );
+    let star = Shape::from_name("star5");
+    assert_eq!(Shape::Other("star5".to_string()), star);
+    assert_eq!(("star5", "star5"), (star.name(), star.dot_name()));
+    for (name, shape) in SHAPES {
+        assert_eq!(*shape, Shape::from_name(name));
+        assert_eq!(*name, shape.name());
+    }
+}
+
+#[test]
+fn test_arrows() {
+    let doc = crate::parse_gml_document(
+        "graph [ edge [ source 1 target 2 graphics [ arrow \"last\"
+                          sourceArrow \"white_diamond\" targetArrow \"crows_foot_many\" ] ]
+                 edge [ source 1 target 2 graphics [ arrow \"sideways\" ] ] ]",
+    )
+    .unwrap();
+    let graphics = doc.edges[0].graphics().unwrap();
+    assert_eq!(Some(Arrow::Last), graphics.arrow);
+    assert_eq!(Some(ArrowHead::WhiteDiamond), graphics.source_arrow);
+    let other = ArrowHead::Other("crows_foot_many".to_string());
+    assert_eq!(Some(&other), graphics.target_arrow.as_ref());
+    assert_eq!(
+        ("white_diamond", "odiamond"),
+        (
+            ArrowHead::WhiteDiamond.name(),
+            ArrowHead::WhiteDiamond.dot_name()
+        )
+    );
+    assert_eq!(
+        ("crows_foot_many", "normal"),
+        (other.name(), other.dot_name())
+    );
+    assert_eq!(graphics, Graphics::from_attrs(&graphics.to_attrs()));
+    let graphics = doc.edges[1].graphics().unwrap();
+    assert_eq!(None, graphics.arrow);
+    assert!(graphics.other.contains_key("arrow"));
+}
+
+#[test]
+fn test_label_graphics() {
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 label \"a\" LabelGraphics [ text \"a\" fontSize 12
+                          fontName \"Dialog\" fontStyle \"bold\" anchor \"c\" model \"sides\" ] ]
+                 node [ id 2 ] edge [ source 1 target 2 LabelGraphics [ fontSize 9.5 ] ] ]",
+    )
+    .unwrap();
+    let label = doc.nodes[0].label_graphics().unwrap();
+    assert_eq!(Some("a"), label.text.as_deref());
+    assert_eq!(Some(12.0), label.font_size);
+    assert_eq!(Some("Dialog"), label.font_name.as_deref());
+    assert_eq!(Some("bold"), label.font_style.as_deref());
+    assert_eq!((Some("c"), None), (label.anchor.as_deref(), label.color));
+    assert_eq!(Some(&GmlValue::from("sides")), label.other.get("model"));
+    assert_eq!(None, doc.nodes[1].label_graphics());
+    assert_eq!(Some(9.5), doc.edges[0].label_graphics().unwrap().font_size);
+
+    let mut node = doc.nodes[0].clone();
+    let mut styled = label.clone();
+    styled.text = Some("b".to_string());
+    styled.color = Color::parse("red").ok();
+    node.set_label_graphics(&styled);
+    assert_eq!(styled, node.label_graphics().unwrap());
+    let attrs = node.attrs["LabelGraphics"].get_list().unwrap();
+    assert_eq!(Some(&GmlValue::Int(12)), attrs.get("fontSize"));
+    assert_eq!(Some(&GmlValue::from("#FF0000")), attrs.get("color"));
+    let gml = crate::to_gml(&crate::document::GmlDocument {
+        nodes: vec![node.clone()],
+        ..Default::default()
+    });
+    let parsed = crate::parse_gml_document(&gml).unwrap();
+    assert_eq!(Some(styled), parsed.nodes[0].label_graphics());
+}
+
+#[test]
+fn test_edge_anchor() {
+    let doc = crate::parse_gml_document(
+        "graph [ edge [ source 1 target 2 edgeAnchor [ xSource 1 ySource 0.0
+                          yTarget -1.0 port \"a\" ] ] edge [ source 2 target 1 ] ]",
+    )
+    .unwrap();
+    let anchor = doc.edges[0].edge_anchor().unwrap();
+    assert_eq!((Some(1.0), Some(0.0)), (anchor.x_source, anchor.y_source));
+    assert_eq!((None, Some(-1.0)), (anchor.x_target, anchor.y_target));
+    assert_eq!(Some(&GmlValue::from("a")), anchor.other.get("port"));
+    assert_eq!(None, doc.edges[1].edge_anchor());
+
+    let mut edge = doc.edges[1].clone();
+    edge.set_edge_anchor(&anchor);
+    let doc = crate::document::GmlDocument {
+        edges: vec![edge],
+        ..doc
+    };
+    let parsed = crate::parse_gml_document(&crate::to_gml(&doc)).unwrap();
+    assert_eq!(Some(anchor), parsed.edges[0].edge_anchor());
+}
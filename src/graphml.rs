@@ -0,0 +1,393 @@
+//! GraphML import and export.
+
+use crate::document::{
+    flatten, insert_dotted, numeric_ids, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue,
+    ValueType,
+};
+use crate::xml::{self, escape, Element};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+fn type_name(ty: ValueType) -> &'static str {
+    match ty {
+        ValueType::Int => "int",
+        ValueType::Long | ValueType::UnsignedLong => "long",
+        ValueType::Double => "double",
+        ValueType::String => "string",
+    }
+}
+
+/// `(domain, attribute name)` mapped to the key number and type.
+type Keys = BTreeMap<(&'static str, String), (usize, ValueType)>;
+
+fn collect_keys(keys: &mut Keys, domain: &'static str, attrs: &GmlAttrs) {
+    for (name, value) in flatten(attrs) {
+        let next_id = keys.len();
+        let entry = keys
+            .entry((domain, name))
+            .or_insert_with(|| (next_id, ValueType::of(value)));
+        entry.1 = entry.1.unify(ValueType::of(value));
+    }
+}
+
+/// Renders `doc` as GraphML.
+pub fn to_graphml(doc: &GmlDocument) -> String {
+    let mut buf = Vec::new();
+    write_graphml(doc, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as GraphML.
+///
+/// A `<key>` is declared for every attribute name per domain (`graph`,
+/// `node`, `edge`), typed `int`, `long`, `double` or `string` depending on
+/// the values seen. Nested lists are flattened into dotted names such as
+/// `graphics.fill`. Node `n` gets the GraphML id `n<id>`.
+pub fn write_graphml<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    let mut keys = Keys::new();
+    collect_keys(&mut keys, "graph", &doc.attrs);
+    for node in &doc.nodes {
+        collect_keys(&mut keys, "node", &node.attrs);
+    }
+    for edge in &doc.edges {
+        collect_keys(&mut keys, "edge", &edge.attrs);
+    }
+
+    writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        w,
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns \
+         http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">"
+    )?;
+
+    let mut sorted_keys: Vec<_> = keys.iter().collect();
+    sorted_keys.sort_by_key(|&(_, &(id, _))| id);
+    for ((domain, name), (id, ty)) in sorted_keys {
+        writeln!(
+            w,
+            "  <key id=\"d{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+            id,
+            domain,
+            escape(name),
+            type_name(*ty)
+        )?;
+    }
+
+    let edgedefault = if doc.directed {
+        "directed"
+    } else {
+        "undirected"
+    };
+    writeln!(w, "  <graph id=\"G\" edgedefault=\"{}\">", edgedefault)?;
+    write_data(w, &keys, "graph", &doc.attrs, "    ")?;
+
+    for node in &doc.nodes {
+        if node.attrs.is_empty() {
+            writeln!(w, "    <node id=\"n{}\"/>", node.id)?;
+        } else {
+            writeln!(w, "    <node id=\"n{}\">", node.id)?;
+            write_data(w, &keys, "node", &node.attrs, "      ")?;
+            writeln!(w, "    </node>")?;
+        }
+    }
+
+    for edge in &doc.edges {
+        if edge.attrs.is_empty() {
+            writeln!(
+                w,
+                "    <edge source=\"n{}\" target=\"n{}\"/>",
+                edge.source, edge.target
+            )?;
+        } else {
+            writeln!(
+                w,
+                "    <edge source=\"n{}\" target=\"n{}\">",
+                edge.source, edge.target
+            )?;
+            write_data(w, &keys, "edge", &edge.attrs, "      ")?;
+            writeln!(w, "    </edge>")?;
+        }
+    }
+
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</graphml>")
+}
+
+fn write_data<W: Write>(
+    w: &mut W,
+    keys: &Keys,
+    domain: &'static str,
+    attrs: &GmlAttrs,
+    indent: &str,
+) -> io::Result<()> {
+    for (name, value) in flatten(attrs) {
+        let text = match *value {
+            GmlValue::Int(i) => i.to_string(),
+            GmlValue::UInt(u) => u.to_string(),
+            GmlValue::Float(f) => f.to_string(),
+            GmlValue::Str(ref s) => escape(s),
+            GmlValue::List(_) => unreachable!(),
+        };
+        let id = keys[&(domain, name)].0;
+        writeln!(w, "{}<data key=\"d{}\">{}</data>", indent, id, text)?;
+    }
+    Ok(())
+}
+
+struct KeyDecl {
+    domain: String,
+    name: String,
+    ty: String,
+    default: Option<String>,
+}
+
+/// Parses a GraphML document into a `GmlDocument`.
+///
+/// `<data>` values are converted according to the `attr.type` of their
+/// `<key>` (`boolean` becomes `Int` 0/1) and key `<default>`s are applied
+/// to elements without the data. Dotted attribute names are expanded into
+/// nested lists, the inverse of `write_graphml`.
+///
+/// Nodes of nested graphs are flattened into the document and get a
+/// `parent` attribute with the id of the enclosing node. Node ids of the
+/// form `n<id>` or `<id>` are used directly; otherwise nodes are numbered
+/// from 0 in document order and the GraphML id is kept as `name`.
+pub fn parse_graphml(s: &str) -> Result<GmlDocument, &'static str> {
+    let root = xml::parse(s)?;
+    if root.name != "graphml" {
+        return Err("not a GraphML document");
+    }
+
+    let mut keys = BTreeMap::new();
+    for key in root.elements_named("key") {
+        let id = key.attr("id").ok_or("key without id")?;
+        let decl = KeyDecl {
+            domain: key.attr("for").unwrap_or("all").to_string(),
+            name: key.attr("attr.name").unwrap_or(id).to_string(),
+            ty: key.attr("attr.type").unwrap_or("string").to_string(),
+            default: key.elements_named("default").next().map(Element::text),
+        };
+        keys.insert(id.to_string(), decl);
+    }
+
+    let graph = root
+        .elements_named("graph")
+        .next()
+        .ok_or("no graph given or invalid")?;
+
+    let mut doc = GmlDocument {
+        directed: graph.attr("edgedefault") != Some("undirected"),
+        attrs: data_attrs(graph, "graph", &keys)?,
+        ..GmlDocument::default()
+    };
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    collect_graph(graph, None, &keys, &mut nodes, &mut edges)?;
+
+    let numeric_ids = numeric_ids(nodes.iter().map(|n| n.0), |name| {
+        name.strip_prefix('n').unwrap_or(name).parse().ok()
+    });
+    let mut id_map = BTreeMap::new();
+    for (idx, &(name, _, _)) in nodes.iter().enumerate() {
+        let id = match numeric_ids {
+            Some(ref ids) => ids[idx],
+            None => idx as u64,
+        };
+        if id_map.insert(name, id).is_some() {
+            return Err("duplicate node-id");
+        }
+    }
+
+    for (name, mut attrs, parent) in nodes {
+        if numeric_ids.is_none() {
+            attrs.insert("name".to_string(), GmlValue::from(name));
+        }
+        if let Some(parent) = parent {
+            attrs.insert("parent".to_string(), GmlValue::Int(id_map[parent] as i64));
+        }
+        doc.nodes.push(GmlNode {
+            id: id_map[name],
+            attrs,
+        });
+    }
+
+    for (source, target, attrs) in edges {
+        let source = *id_map.get(source).ok_or("edge references unknown node")?;
+        let target = *id_map.get(target).ok_or("edge references unknown node")?;
+        doc.edges.push(GmlEdge {
+            source,
+            target,
+            attrs,
+        });
+    }
+
+    Ok(doc)
+}
+
+type Nodes<'a> = Vec<(&'a str, GmlAttrs, Option<&'a str>)>;
+type Edges<'a> = Vec<(&'a str, &'a str, GmlAttrs)>;
+
+fn collect_graph<'a>(
+    graph: &'a Element,
+    parent: Option<&'a str>,
+    keys: &BTreeMap<String, KeyDecl>,
+    nodes: &mut Nodes<'a>,
+    edges: &mut Edges<'a>,
+) -> Result<(), &'static str> {
+    for element in graph.elements() {
+        match &element.name[..] {
+            "node" => {
+                let id = element.attr("id").ok_or("node without id")?;
+                nodes.push((id, data_attrs(element, "node", keys)?, parent));
+                for subgraph in element.elements_named("graph") {
+                    collect_graph(subgraph, Some(id), keys, nodes, edges)?;
+                }
+            }
+            "edge" => {
+                let source = element.attr("source").ok_or("edge without source")?;
+                let target = element.attr("target").ok_or("edge without target")?;
+                edges.push((source, target, data_attrs(element, "edge", keys)?));
+            }
+            "hyperedge" => {
+                return Err("hyperedges are not supported");
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn data_attrs(
+    element: &Element,
+    domain: &str,
+    keys: &BTreeMap<String, KeyDecl>,
+) -> Result<GmlAttrs, &'static str> {
+    let mut flat = BTreeMap::new();
+    for key in keys.values() {
+        if key.domain == domain || key.domain == "all" {
+            if let Some(ref default) = key.default {
+                flat.insert(key.name.clone(), typed_value(&key.ty, default)?);
+            }
+        }
+    }
+    for data in element.elements_named("data") {
+        let id = data.attr("key").ok_or("data without key")?;
+        let key = keys.get(id).ok_or("data references unknown key")?;
+        if data.elements().next().is_some() {
+            // foreign XML content, e.g. yEd's graphics extensions
+            continue;
+        }
+        flat.insert(key.name.clone(), typed_value(&key.ty, &data.text())?);
+    }
+
+    let mut attrs = GmlAttrs::new();
+    for (name, value) in flat {
+        insert_dotted(&mut attrs, &name, value);
+    }
+    Ok(attrs)
+}
+
+fn typed_value(ty: &str, text: &str) -> Result<GmlValue, &'static str> {
+    let trimmed = text.trim();
+    match ty {
+        "int" | "long" => trimmed
+            .parse()
+            .map(GmlValue::Int)
+            .or_else(|_| trimmed.parse().map(GmlValue::UInt))
+            .map_err(|_| "invalid integer value"),
+        "float" | "double" => trimmed
+            .parse()
+            .map(GmlValue::Float)
+            .map_err(|_| "invalid floating point value"),
+        "boolean" => match trimmed {
+            "true" | "1" => Ok(GmlValue::Int(1)),
+            "false" | "0" => Ok(GmlValue::Int(0)),
+            _ => Err("invalid boolean value"),
+        },
+        _ => Ok(GmlValue::from(text)),
+    }
+}
+
+#[test]
+// expects the attributes in key order
+fn test_to_graphml() {
+    let gml = "
+    graph [
+        directed 1
+        node [ id 1 label \"a<b\" size 3 graphics [ fill \"#FF0000\" ] ]
+        node [ id 2 size 2.5 ]
+        edge [ source 1 target 2 weight 10000000000 ]
+    ]
+    ";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let xml = to_graphml(&doc);
+    // key ids follow the key order of the attributes
+    assert!(xml.contains("for=\"node\" attr.name=\"graphics.fill\" attr.type=\"string\"/>"));
+    assert!(xml.contains("attr.name=\"size\" attr.type=\"double\""));
+    assert!(xml.contains("attr.name=\"weight\" attr.type=\"long\""));
+    assert!(xml.contains("<graph id=\"G\" edgedefault=\"directed\">"));
+    assert!(xml.contains(">a&lt;b</data>"));
+    assert!(xml.contains("<edge source=\"n1\" target=\"n2\">"));
+    let back = parse_graphml(&xml).unwrap();
+    let node = &back.nodes[0].attrs;
+    assert_eq!(Some("a<b"), node["label"].get_str());
+    assert_eq!(
+        Some("#FF0000"),
+        node["graphics"].get("fill").and_then(GmlValue::get_str)
+    );
+    assert_eq!(Some(3.0), node["size"].get_float());
+    assert_eq!(Some(10000000000), back.edges[0].attrs["weight"].get_int());
+
+    let doc = crate::parse_gml_document("graph [ node [ id 1 n 18446744073709551615 ] ]");
+    let xml = to_graphml(&doc.unwrap());
+    assert!(xml.contains("attr.name=\"n\" attr.type=\"long\""));
+    let back = parse_graphml(&xml).unwrap();
+    assert_eq!(Some(u64::MAX), back.nodes[0].attrs["n"].get_uint());
+}
+
+#[test]
+fn test_parse_graphml() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <!-- exported -->
+    <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+      <key id="k0" for="node" attr.name="color" attr.type="string">
+        <default>yellow</default>
+      </key>
+      <key id="k1" for="edge" attr.name="weight" attr.type="double"/>
+      <key id="k2" for="node" attr.name="graphics.x" attr.type="int"/>
+      <key id="k3" for="node" attr.name="active" attr.type="boolean"/>
+      <graph id="G" edgedefault="undirected">
+        <node id="a"><data key="k0">green</data><data key="k3">true</data></node>
+        <node id="b">
+          <graph id="b:" edgedefault="undirected">
+            <node id="c"><data key="k2">10</data></node>
+          </graph>
+        </node>
+        <edge source="a" target="c"><data key="k1">1.5</data></edge>
+      </graph>
+    </graphml>"#;
+    let doc = parse_graphml(xml).unwrap();
+    assert!(!doc.directed);
+    assert_eq!(3, doc.nodes.len());
+    assert_eq!(Some("green"), doc.nodes[0].attrs["color"].get_str());
+    assert_eq!(Some(1), doc.nodes[0].attrs["active"].get_int());
+    assert_eq!(Some("yellow"), doc.nodes[1].attrs["color"].get_str());
+    assert_eq!(Some("c"), doc.nodes[2].attrs["name"].get_str());
+    assert_eq!(Some(1), doc.nodes[2].attrs["parent"].get_int());
+    assert_eq!(
+        Some(10),
+        doc.nodes[2].attrs["graphics"]
+            .get("x")
+            .and_then(GmlValue::get_int)
+    );
+    assert_eq!((0, 2), (doc.edges[0].source, doc.edges[0].target));
+    assert_eq!(Some(1.5), doc.edges[0].attrs["weight"].get_float());
+
+    let gml = "graph [ directed 1 node [ id 3 graphics [ fill \"red\" ] ] node [ id 4 ] \
+               edge [ source 3 target 4 weight 2 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    assert_eq!(doc, parse_graphml(&to_graphml(&doc)).unwrap());
+}
@@ -0,0 +1,139 @@
+//! Graphology (and Sigma.js) serialized graph export.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use crate::json::{self, Json};
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+/// Renders `doc` in graphology's serialization format, as accepted by
+/// `Graph.from()` and `graph.import()`.
+///
+/// Attributes become graphology attributes. For Sigma.js, the `x`, `y`,
+/// `w` and `fill` keys of node `graphics` blocks are also provided as `x`,
+/// `y`, `size` and `color`; the `graphics` block itself is left out.
+/// `options.multi` is set if the document has parallel edges.
+pub fn to_graphology(doc: &GmlDocument) -> String {
+    let mut seen = BTreeSet::new();
+    let multi = !doc.edges.iter().all(|e| {
+        let key = if doc.directed || e.source <= e.target {
+            (e.source, e.target)
+        } else {
+            (e.target, e.source)
+        };
+        seen.insert(key)
+    });
+    let graph_type = if doc.directed {
+        "directed"
+    } else {
+        "undirected"
+    };
+    let options = vec![
+        ("type".to_string(), Json::Str(graph_type.to_string())),
+        ("multi".to_string(), Json::Bool(multi)),
+        ("allowSelfLoops".to_string(), Json::Bool(true)),
+    ];
+
+    let nodes = doc
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut attrs = attributes(&node.attrs);
+            if let Some(graphics) = node.attrs.get("graphics") {
+                for &(gml_key, sigma_key) in &[("x", "x"), ("y", "y"), ("w", "size")] {
+                    if let Some(v) = graphics.get(gml_key).and_then(GmlValue::get_float) {
+                        attrs.push((sigma_key.to_string(), Json::Float(v)));
+                    }
+                }
+                if let Some(fill) = graphics.get("fill").and_then(GmlValue::get_str) {
+                    attrs.push(("color".to_string(), Json::Str(fill.to_string())));
+                }
+            }
+            Json::Object(vec![
+                ("key".to_string(), Json::Str(node.id.to_string())),
+                ("attributes".to_string(), Json::Object(attrs)),
+            ])
+        })
+        .collect();
+
+    let edges = doc
+        .edges
+        .iter()
+        .map(|edge| {
+            let mut members = vec![
+                ("source".to_string(), Json::Str(edge.source.to_string())),
+                ("target".to_string(), Json::Str(edge.target.to_string())),
+                (
+                    "attributes".to_string(),
+                    Json::Object(attributes(&edge.attrs)),
+                ),
+            ];
+            if !doc.directed {
+                members.push(("undirected".to_string(), Json::Bool(true)));
+            }
+            Json::Object(members)
+        })
+        .collect();
+
+    Json::Object(vec![
+        (
+            "attributes".to_string(),
+            Json::Object(attributes(&doc.attrs)),
+        ),
+        ("options".to_string(), Json::Object(options)),
+        ("nodes".to_string(), Json::Array(nodes)),
+        ("edges".to_string(), Json::Array(edges)),
+    ])
+    .to_string_pretty()
+}
+
+/// Writes `doc` in graphology's serialization format. See `to_graphology`.
+pub fn write_graphology<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    w.write_all(to_graphology(doc).as_bytes())
+}
+
+fn attributes(attrs: &GmlAttrs) -> Vec<(String, Json)> {
+    attrs
+        .iter()
+        .filter(|&(k, _)| k != "graphics")
+        .map(|(k, v)| (k.clone(), json::from_value(v)))
+        .collect()
+}
+
+#[test]
+fn test_to_graphology() {
+    let gml = "graph [ directed 0 label \"net\" \
+               node [ id 1 label \"a\" graphics [ x 1 y 2 w 5 fill \"#FF0000\" ] ] \
+               node [ id 2 ] edge [ source 1 target 2 ] edge [ source 2 target 1 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let parsed = json::parse(&to_graphology(&doc)).unwrap();
+
+    let options = parsed.get("options").unwrap();
+    assert_eq!(
+        Some("undirected"),
+        options.get("type").and_then(Json::as_str)
+    );
+    assert_eq!(Some(true), options.get("multi").and_then(Json::as_bool));
+    assert_eq!(
+        Some("net"),
+        parsed
+            .get("attributes")
+            .and_then(|a| a.get("label"))
+            .and_then(Json::as_str)
+    );
+
+    let node = match parsed.get("nodes") {
+        Some(Json::Array(nodes)) => nodes[0].clone(),
+        _ => panic!("no nodes"),
+    };
+    assert_eq!(Some("1"), node.get("key").and_then(Json::as_str));
+    let attrs = node.get("attributes").unwrap();
+    assert_eq!(Some(&Json::Float(5.0)), attrs.get("size"));
+    assert_eq!(Some("#FF0000"), attrs.get("color").and_then(Json::as_str));
+    assert!(attrs.get("graphics").is_none());
+
+    let edge = match parsed.get("edges") {
+        Some(Json::Array(edges)) => edges[0].clone(),
+        _ => panic!("no edges"),
+    };
+    assert_eq!(Some(true), edge.get("undirected").and_then(Json::as_bool));
+}
@@ -0,0 +1,178 @@
+//! GraphSON 3.0 (Apache TinkerPop) export.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlEdge, GmlValue};
+use crate::json::Json;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Default)]
+pub struct GraphsonOptions {
+    /// An attribute whose string value becomes the vertex label. Vertices
+    /// without it are labeled `vertex`.
+    pub vertex_label_key: Option<String>,
+    /// An attribute whose string value becomes the edge label. Edges without
+    /// it are labeled `edge`.
+    pub edge_label_key: Option<String>,
+}
+
+fn typed(ty: &str, value: Json) -> Json {
+    Json::Object(vec![
+        ("@type".to_string(), Json::Str(ty.to_string())),
+        ("@value".to_string(), value),
+    ])
+}
+
+fn int64(i: u64) -> Json {
+    typed("g:Int64", Json::from(i))
+}
+
+fn value(v: &GmlValue) -> Json {
+    match *v {
+        GmlValue::Int(i) => typed("g:Int64", Json::Int(i)),
+        GmlValue::UInt(u) => typed("g:Int64", Json::UInt(u)),
+        GmlValue::Float(f) => typed("g:Double", Json::Float(f)),
+        GmlValue::Str(ref s) => Json::Str(s.to_string()),
+        GmlValue::List(_) => unreachable!(),
+    }
+}
+
+fn label<'a>(attrs: &'a GmlAttrs, key: &Option<String>, default: &'a str) -> &'a str {
+    key.as_ref()
+        .and_then(|k| attrs.get(k))
+        .and_then(GmlValue::get_str)
+        .unwrap_or(default)
+}
+
+// Properties without the attribute used as label. Nested lists are
+// flattened into dotted keys.
+fn properties<'a>(attrs: &'a GmlAttrs, label_key: &Option<String>) -> Vec<(String, &'a GmlValue)> {
+    let mut props = flatten(attrs);
+    props.retain(|(k, _)| Some(k) != label_key.as_ref());
+    props
+}
+
+fn edge_value(idx: usize, e: &GmlEdge, other: (&str, u64), options: &GraphsonOptions) -> Json {
+    let props = properties(&e.attrs, &options.edge_label_key)
+        .into_iter()
+        .map(|(k, v)| {
+            let prop = Json::Object(vec![
+                ("key".to_string(), Json::Str(k.clone())),
+                ("value".to_string(), value(v)),
+            ]);
+            (k, typed("g:Property", prop))
+        })
+        .collect();
+    typed(
+        "g:Edge",
+        Json::Object(vec![
+            ("id".to_string(), int64(idx as u64)),
+            (other.0.to_string(), int64(other.1)),
+            ("properties".to_string(), Json::Object(props)),
+        ]),
+    )
+}
+
+// Groups the edges of one vertex by their label.
+fn edge_map(edges: Vec<(String, Json)>) -> Json {
+    let mut by_label: BTreeMap<String, Vec<Json>> = BTreeMap::new();
+    for (label, edge) in edges {
+        by_label.entry(label).or_default().push(edge);
+    }
+    Json::Object(
+        by_label
+            .into_iter()
+            .map(|(label, edges)| (label, Json::Array(edges)))
+            .collect(),
+    )
+}
+
+/// Renders `doc` as GraphSON 3.0. See `write_graphson`.
+pub fn to_graphson(doc: &GmlDocument, options: &GraphsonOptions) -> String {
+    let mut buf = Vec::new();
+    write_graphson(doc, options, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as typed GraphSON 3.0 in the adjacency list layout read by
+/// TinkerPop's `GraphSONReader` (and `g.io(...).read()`): one vertex per
+/// line, carrying its incident edges as `outE` and `inE`.
+///
+/// Ids are written as `g:Int64`; edges are numbered in document order.
+/// Attributes become single-valued vertex properties and edge properties.
+pub fn write_graphson<W: Write>(
+    doc: &GmlDocument,
+    options: &GraphsonOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut out_edges: BTreeMap<u64, Vec<(String, Json)>> = BTreeMap::new();
+    let mut in_edges: BTreeMap<u64, Vec<(String, Json)>> = BTreeMap::new();
+    for (idx, e) in doc.edges.iter().enumerate() {
+        let label = label(&e.attrs, &options.edge_label_key, "edge").to_string();
+        out_edges.entry(e.source).or_default().push((
+            label.clone(),
+            edge_value(idx, e, ("inV", e.target), options),
+        ));
+        in_edges
+            .entry(e.target)
+            .or_default()
+            .push((label, edge_value(idx, e, ("outV", e.source), options)));
+    }
+
+    let mut property_id = 0;
+    for n in &doc.nodes {
+        let mut members = vec![
+            ("id".to_string(), int64(n.id)),
+            (
+                "label".to_string(),
+                Json::Str(label(&n.attrs, &options.vertex_label_key, "vertex").to_string()),
+            ),
+        ];
+        if let Some(edges) = out_edges.remove(&n.id) {
+            members.push(("outE".to_string(), edge_map(edges)));
+        }
+        if let Some(edges) = in_edges.remove(&n.id) {
+            members.push(("inE".to_string(), edge_map(edges)));
+        }
+        let props = properties(&n.attrs, &options.vertex_label_key)
+            .into_iter()
+            .map(|(k, v)| {
+                let prop = Json::Object(vec![
+                    ("id".to_string(), int64(property_id)),
+                    ("value".to_string(), value(v)),
+                    ("label".to_string(), Json::Str(k.clone())),
+                ]);
+                property_id += 1;
+                (k, Json::Array(vec![typed("g:VertexProperty", prop)]))
+            })
+            .collect();
+        members.push(("properties".to_string(), Json::Object(props)));
+        writeln!(w, "{}", typed("g:Vertex", Json::Object(members)))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_to_graphson() {
+    let gml = "graph [ directed 1 node [ id 1 type \"person\" name \"marko\" ] node [ id 2 ] \
+               edge [ source 1 target 2 type \"knows\" weight 0.5 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let options = GraphsonOptions {
+        vertex_label_key: Some("type".to_string()),
+        edge_label_key: Some("type".to_string()),
+    };
+    let out = to_graphson(&doc, &options);
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(2, lines.len());
+    assert_eq!(
+        "{\"@type\":\"g:Vertex\",\"@value\":{\"id\":{\"@type\":\"g:Int64\",\"@value\":1},\
+         \"label\":\"person\",\"outE\":{\"knows\":[{\"@type\":\"g:Edge\",\"@value\":{\
+         \"id\":{\"@type\":\"g:Int64\",\"@value\":0},\"inV\":{\"@type\":\"g:Int64\",\"@value\":2},\
+         \"properties\":{\"weight\":{\"@type\":\"g:Property\",\"@value\":{\"key\":\"weight\",\
+         \"value\":{\"@type\":\"g:Double\",\"@value\":0.5}}}}}}]},\"properties\":{\"name\":[{\
+         \"@type\":\"g:VertexProperty\",\"@value\":{\"id\":{\"@type\":\"g:Int64\",\"@value\":0},\
+         \"value\":\"marko\",\"label\":\"name\"}}]}}}",
+        lines[0]
+    );
+    assert!(lines[1].contains("\"label\":\"vertex\",\"inE\":{\"knows\":[{"));
+    assert!(lines[1].contains("\"outV\":{\"@type\":\"g:Int64\",\"@value\":1}"));
+}
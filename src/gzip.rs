@@ -0,0 +1,373 @@
+//! Gzip decompression.
+//!
+//! A plain DEFLATE decoder (RFC 1951) for the members of a gzip file
+//! (RFC 1952). Huffman codes are decoded a bit at a time from the code
+//! counts, which is simple and fast enough for reading input files.
+
+// Bits are read from the least significant end of each byte.
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+    bits: u32,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8]) -> Bits<'a> {
+        Bits {
+            data,
+            pos: 0,
+            bit: 0,
+            bits: 0,
+        }
+    }
+
+    fn read(&mut self, n: u32) -> Result<u32, &'static str> {
+        while self.bits < n {
+            let byte = *self.data.get(self.pos).ok_or("truncated gzip data")?;
+            self.pos += 1;
+            self.bit |= (byte as u32) << self.bits;
+            self.bits += 8;
+        }
+        let value = self.bit & ((1u64 << n) - 1) as u32;
+        self.bit >>= n;
+        self.bits -= n;
+        Ok(value)
+    }
+
+    // Drops the bits up to the next byte boundary.
+    fn align(&mut self) {
+        self.bit = 0;
+        self.bits = 0;
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("truncated gzip data")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+}
+
+const MAX_BITS: usize = 15;
+
+// A canonical Huffman code: the number of codes of each length and the
+// symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, &'static str> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = 2 * left - count as i32;
+            if left < 0 {
+                return Err("invalid Huffman code");
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, &'static str> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= bits.read(1)? as i32;
+            let count = count as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code")
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// The order of the code length code lengths.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn fixed_codes() -> Result<(Huffman, Huffman), &'static str> {
+    let mut lengths = [0u8; 288];
+    for (symbol, len) in lengths.iter_mut().enumerate() {
+        *len = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman), &'static str> {
+    let nlen = bits.read(5)? as usize + 257;
+    let ndist = bits.read(5)? as usize + 1;
+    let ncode = bits.read(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..ncode] {
+        code_lengths[i] = bits.read(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; nlen + ndist];
+    let mut i = 0;
+    while i < lengths.len() {
+        let (len, repeat) = match code.decode(bits)? {
+            len @ 0..=15 => (len as u8, 1),
+            16 if i > 0 => (lengths[i - 1], 3 + bits.read(2)? as usize),
+            16 => return Err("invalid code lengths"),
+            17 => (0, 3 + bits.read(3)? as usize),
+            _ => (0, 11 + bits.read(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err("invalid code lengths");
+        }
+        for l in &mut lengths[i..i + repeat] {
+            *l = len;
+        }
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err("invalid code lengths");
+    }
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+fn inflate_block(
+    bits: &mut Bits,
+    lit: &Huffman,
+    dist: &Huffman,
+    out: &mut Vec<u8>,
+) -> Result<(), &'static str> {
+    loop {
+        let symbol = lit.decode(bits)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+        let i = symbol - 257;
+        if i >= LENGTH_BASE.len() {
+            return Err("invalid length code");
+        }
+        let len = LENGTH_BASE[i] as usize + bits.read(LENGTH_EXTRA[i] as u32)? as usize;
+        let d = dist.decode(bits)? as usize;
+        if d >= DIST_BASE.len() {
+            return Err("invalid distance code");
+        }
+        let distance = DIST_BASE[d] as usize + bits.read(DIST_EXTRA[d] as u32)? as usize;
+        if distance > out.len() {
+            return Err("distance too far back");
+        }
+        let start = out.len() - distance;
+        for k in 0..len {
+            let byte = out[start + k];
+            out.push(byte);
+        }
+    }
+}
+
+// Decodes a DEFLATE stream, returning the number of bytes read.
+fn inflate(data: &[u8], out: &mut Vec<u8>) -> Result<usize, &'static str> {
+    let mut bits = Bits::new(data);
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => {
+                bits.align();
+                let header = bits.bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if len != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("invalid stored block");
+                }
+                out.extend_from_slice(bits.bytes(len as usize)?);
+            }
+            1 => {
+                let (lit, dist) = fixed_codes()?;
+                inflate_block(&mut bits, &lit, &dist, out)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, &lit, &dist, out)?;
+            }
+            _ => return Err("invalid block type"),
+        }
+        if last {
+            return Ok(bits.pos);
+        }
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    let mut crc = !0u32;
+    for &b in data {
+        crc = table[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+const FHCRC: u8 = 2;
+const FEXTRA: u8 = 4;
+const FNAME: u8 = 8;
+const FCOMMENT: u8 = 16;
+
+/// Decompresses the members of a gzip file, checking their CRCs.
+pub(crate) fn decompress(mut data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 18 || data[..2] != [0x1F, 0x8B] || data[2] != 8 {
+            return Err("invalid gzip header");
+        }
+        let flags = data[3];
+        let mut pos = 10;
+        let skip_zero = |pos: usize| -> Result<usize, &'static str> {
+            let len = data
+                .get(pos..)
+                .ok_or("truncated gzip header")?
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or("truncated gzip header")?;
+            Ok(pos + len + 1)
+        };
+        if flags & FEXTRA != 0 {
+            let header = data.get(pos..pos + 2).ok_or("truncated gzip header")?;
+            let xlen = u16::from_le_bytes([header[0], header[1]]) as usize;
+            pos = pos.checked_add(2 + xlen).ok_or("truncated gzip header")?;
+        }
+        if flags & FNAME != 0 {
+            pos = skip_zero(pos)?;
+        }
+        if flags & FCOMMENT != 0 {
+            pos = skip_zero(pos)?;
+        }
+        if flags & FHCRC != 0 {
+            pos = pos.checked_add(2).ok_or("truncated gzip header")?;
+        }
+        let body = data.get(pos..).ok_or("truncated gzip header")?;
+        let start = out.len();
+        pos += inflate(body, &mut out)?;
+        let trailer = data.get(pos..pos + 8).ok_or("truncated gzip data")?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != crc32(&out[start..]) || size != (out.len() - start) as u32 {
+            return Err("gzip checksum mismatch");
+        }
+        data = &data[pos + 8..];
+    }
+    Ok(out)
+}
+
+#[test]
+fn test_decompress() {
+    // `gzip -9n` of a fixed and a dynamic block
+    let fixed = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x4b, 0x2f, 0x4a, 0x2c, 0xc8,
+        0x50, 0x88, 0x56, 0xc8, 0xcb, 0x4f, 0x49, 0x05, 0x52, 0x99, 0x29, 0x0a, 0x86, 0x0a, 0xb1,
+        0x0a, 0xb1, 0x00, 0x6d, 0x66, 0x26, 0x03, 0x17, 0x00, 0x00, 0x00,
+    ];
+    assert_eq!(
+        &b"graph [ node [ id 1 ] ]"[..],
+        &decompress(&fixed).unwrap()[..]
+    );
+    let dynamic = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x75, 0xd2, 0x3b, 0x0a, 0x03,
+        0x41, 0x0c, 0x04, 0xd1, 0xdc, 0xa7, 0x10, 0x7b, 0x82, 0x91, 0x34, 0xdf, 0xb3, 0x18, 0x07,
+        0x36, 0xbb, 0x81, 0x61, 0x19, 0xdf, 0x3f, 0x73, 0x26, 0x50, 0x50, 0x71, 0x25, 0x8f, 0xa6,
+        0xf7, 0xef, 0xbc, 0xe4, 0x29, 0xdf, 0x53, 0x8a, 0xdc, 0xef, 0xcf, 0x75, 0xcb, 0xb1, 0xcb,
+        0x21, 0xaf, 0xc7, 0x8e, 0xa0, 0x11, 0x34, 0x07, 0x8b, 0x60, 0x39, 0x78, 0x04, 0xcf, 0xa1,
+        0x46, 0xa8, 0x39, 0xb4, 0x08, 0x2d, 0x87, 0x1e, 0xa1, 0xe7, 0x30, 0x88, 0x3b, 0x89, 0xbb,
+        0x88, 0xab, 0x85, 0xbc, 0xaa, 0x04, 0x56, 0x23, 0xb1, 0x3a, 0x91, 0xb5, 0xe2, 0xc4, 0x8d,
+        0xd0, 0xda, 0x51, 0x3d, 0x50, 0x3d, 0x51, 0xbd, 0x48, 0x6d, 0x85, 0xd4, 0xa6, 0xa4, 0x36,
+        0xc3, 0x67, 0x38, 0xa9, 0xad, 0x92, 0xda, 0x1a, 0xa9, 0xad, 0xa3, 0x7a, 0xa0, 0x7a, 0xa2,
+        0x7a, 0x91, 0xda, 0x0b, 0x1e, 0x5a, 0x49, 0xed, 0x46, 0x6a, 0x77, 0x52, 0x7b, 0x25, 0xb5,
+        0x37, 0x52, 0x7b, 0x47, 0xf5, 0x40, 0xf5, 0x44, 0xf5, 0xca, 0xea, 0x3f, 0xf9, 0x8c, 0x0c,
+        0x7f, 0x06, 0x04, 0x00, 0x00,
+    ];
+    let text: String = (0..40)
+        .map(|i| format!("node [ id {} label \"n{}\" ]\n", i, i % 7))
+        .collect();
+    assert_eq!(text.as_bytes(), &decompress(&dynamic).unwrap()[..]);
+
+    let mut stored = vec![0x1f, 0x8b, 0x08, 0x00, 0, 0, 0, 0, 0x00, 0x03];
+    stored.extend_from_slice(&[0x01, 0x03, 0x00, 0xfc, 0xff]);
+    stored.extend_from_slice(b"abc");
+    stored.extend_from_slice(&crc32(b"abc").to_le_bytes());
+    stored.extend_from_slice(&3u32.to_le_bytes());
+    let mut members = stored.clone();
+    members.extend_from_slice(&fixed);
+    assert_eq!(
+        &b"abcgraph [ node [ id 1 ] ]"[..],
+        &decompress(&members).unwrap()[..]
+    );
+    stored[15] = b'x';
+    assert_eq!(Err("gzip checksum mismatch"), decompress(&stored));
+    assert_eq!(Err("truncated gzip data"), decompress(&dynamic[..100]));
+
+    // headers whose optional fields run past the end of the data
+    let mut header = vec![0x1f, 0x8b, 0x08, FEXTRA, 0, 0, 0, 0, 0x00, 0x03, 0xff, 0xff];
+    header.resize(18, 0);
+    assert_eq!(Err("truncated gzip header"), decompress(&header));
+    let mut header = vec![0x1f, 0x8b, 0x08, FNAME | FCOMMENT, 0, 0, 0, 0, 0x00, 0x03];
+    header.extend_from_slice(b"name\0comment");
+    assert_eq!(Err("truncated gzip header"), decompress(&header));
+    let mut header = vec![0x1f, 0x8b, 0x08, FNAME | FHCRC, 0, 0, 0, 0, 0x00, 0x03];
+    header.extend_from_slice(b"12345678\0");
+    assert_eq!(Err("truncated gzip header"), decompress(&header));
+    assert_eq!(Err("invalid gzip header"), decompress(&header[..17]));
+}
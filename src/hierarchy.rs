@@ -0,0 +1,221 @@
+//! Hierarchical graphs, whose nodes may hold a whole `graph` list.
+//!
+//! Some dialects, like that of yEd for groups, nest a `graph [ ... ]` inside
+//! a node for the subgraph of the node. `parse_gml_document` reads only flat
+//! graphs, keeping a nested graph as a list attribute; `parse_gml_tree` reads the nesting into a `GmlTree`, which
+//! `flatten` turns into a `GmlDocument` with a map from each nested node to
+//! the node holding it. Node ids are shared by all graphs of a tree, so
+//! edges may join nodes of different graphs.
+
+use crate::document::{insert_attr, take_id, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::events::{gml_events, GmlEvent, GmlEvents, GmlScalar};
+use crate::writer::write_attrs;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// A graph whose nodes may hold graphs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GmlTree {
+    pub directed: bool,
+    /// Graph-level attributes except `directed`, `node` and `edge`.
+    pub attrs: GmlAttrs,
+    pub nodes: Vec<GmlTreeNode>,
+    pub edges: Vec<GmlEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GmlTreeNode {
+    pub id: u64,
+    /// All attributes except `id` and `graph`.
+    pub attrs: GmlAttrs,
+    /// The nested `graph` of the node.
+    pub graph: Option<GmlTree>,
+}
+
+/// Parses a GML string whose nodes may hold nested `graph` lists.
+pub fn parse_gml_tree(s: &str) -> Result<GmlTree, &'static str> {
+    let mut events = gml_events(s);
+    events.enter_graph()?;
+    let tree = read_graph(&mut events)?;
+    events.finish()?;
+    Ok(tree)
+}
+
+// Reads the rest of a `graph` list, like `graph_parts`.
+fn read_graph(events: &mut GmlEvents) -> Result<GmlTree, &'static str> {
+    let mut tree = GmlTree::default();
+    loop {
+        let (key, value) = match events.next().ok_or("unbalanced [")?? {
+            GmlEvent::End => return Ok(tree),
+            GmlEvent::Value("directed", GmlScalar::Int(i @ 0..=1)) => {
+                tree.directed = i == 1;
+                continue;
+            }
+            GmlEvent::Value("directed", _) | GmlEvent::Begin("directed") => {
+                return Err("invalid directed value");
+            }
+            GmlEvent::Begin("node") => {
+                tree.nodes.push(read_node(events)?);
+                continue;
+            }
+            GmlEvent::Begin("edge") => {
+                let mut attrs = events.read_list()?;
+                tree.edges.push(GmlEdge {
+                    source: take_id(&mut attrs, "source", "Invalid source id")?,
+                    target: take_id(&mut attrs, "target", "Invalid target id")?,
+                    attrs,
+                });
+                continue;
+            }
+            GmlEvent::Value(key, scalar) => (key, scalar.into_value()),
+            GmlEvent::Begin(key) => (key, GmlValue::List(events.read_list()?)),
+        };
+        insert_attr(&mut tree.attrs, key, value);
+    }
+}
+
+fn read_node(events: &mut GmlEvents) -> Result<GmlTreeNode, &'static str> {
+    let mut attrs = GmlAttrs::new();
+    let mut graph = None;
+    loop {
+        let (key, value) = match events.next().ok_or("unbalanced [")?? {
+            GmlEvent::End => break,
+            GmlEvent::Begin("graph") if graph.is_none() => {
+                graph = Some(read_graph(events)?);
+                continue;
+            }
+            GmlEvent::Begin("graph") => return Err("duplicate key"),
+            GmlEvent::Value(key, scalar) => (key, scalar.into_value()),
+            GmlEvent::Begin(key) => (key, GmlValue::List(events.read_list()?)),
+        };
+        insert_attr(&mut attrs, key, value);
+    }
+    Ok(GmlTreeNode {
+        id: take_id(&mut attrs, "id", "Invalid id")?,
+        attrs,
+        graph,
+    })
+}
+
+impl GmlTree {
+    /// The nodes of all graphs of the tree, each followed by those of its
+    /// graph, and their edges, with a map from the id of every nested node
+    /// to the id of the node holding it. The attributes of a nested graph
+    /// are kept in a `graph` list of its node, if it has any.
+    pub fn flatten(self) -> (GmlDocument, BTreeMap<u64, u64>) {
+        let mut doc = GmlDocument {
+            directed: self.directed,
+            attrs: self.attrs,
+            ..GmlDocument::default()
+        };
+        let mut parents = BTreeMap::new();
+        flatten_into(&mut doc, &mut parents, self.nodes, self.edges, None);
+        (doc, parents)
+    }
+}
+
+fn flatten_into(
+    doc: &mut GmlDocument,
+    parents: &mut BTreeMap<u64, u64>,
+    nodes: Vec<GmlTreeNode>,
+    edges: Vec<GmlEdge>,
+    parent: Option<u64>,
+) {
+    for node in nodes {
+        if let Some(parent) = parent {
+            parents.insert(node.id, parent);
+        }
+        let mut attrs = node.attrs;
+        let graph = node.graph.map(|graph| {
+            if !graph.attrs.is_empty() {
+                attrs.insert("graph".to_string(), GmlValue::List(graph.attrs));
+            }
+            (graph.nodes, graph.edges)
+        });
+        doc.nodes.push(GmlNode { id: node.id, attrs });
+        if let Some((nodes, edges)) = graph {
+            flatten_into(doc, parents, nodes, edges, Some(node.id));
+        }
+    }
+    doc.edges.extend(edges);
+}
+
+/// Renders `tree` as GML.
+pub fn to_gml_tree(tree: &GmlTree) -> String {
+    let mut buf = Vec::new();
+    write_gml_tree(tree, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `tree` as GML, with the graph of a node as its last entry.
+pub fn write_gml_tree<W: Write>(tree: &GmlTree, w: &mut W) -> io::Result<()> {
+    writeln!(w, "graph [")?;
+    write_graph(w, tree, 1)?;
+    writeln!(w, "]")
+}
+
+fn write_graph<W: Write>(w: &mut W, tree: &GmlTree, level: usize) -> io::Result<()> {
+    let (outer, inner) = ("  ".repeat(level), "  ".repeat(level + 1));
+    writeln!(w, "{}directed {}", outer, tree.directed as u8)?;
+    write_attrs(w, &tree.attrs, level)?;
+    for node in &tree.nodes {
+        writeln!(w, "{}node [", outer)?;
+        writeln!(w, "{}id {}", inner, node.id)?;
+        write_attrs(w, &node.attrs, level + 1)?;
+        if let Some(ref graph) = node.graph {
+            writeln!(w, "{}graph [", inner)?;
+            write_graph(w, graph, level + 2)?;
+            writeln!(w, "{}]", inner)?;
+        }
+        writeln!(w, "{}]", outer)?;
+    }
+    for edge in &tree.edges {
+        writeln!(w, "{}edge [", outer)?;
+        writeln!(w, "{}source {}", inner, edge.source)?;
+        writeln!(w, "{}target {}", inner, edge.target)?;
+        write_attrs(w, &edge.attrs, level + 1)?;
+        writeln!(w, "{}]", outer)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_gml_tree() {
+    let gml = "graph [ directed 1 label \"net\"
+                 node [ id 1 label \"group\" graph [ label \"inner\"
+                     node [ id 2 ] node [ id 3 graph [ node [ id 4 ] ] ] edge [ source 2 target 3 ] ] ]
+                 node [ id 5 ]
+                 edge [ source 4 target 5 ] ]";
+    let tree = parse_gml_tree(gml).unwrap();
+    assert!(tree.directed);
+    assert_eq!(2, tree.nodes.len());
+    let group = tree.nodes[0].graph.as_ref().unwrap();
+    assert_eq!(Some("inner"), group.attrs["label"].get_str());
+    assert_eq!((2, 1), (group.nodes.len(), group.edges.len()));
+    assert_eq!(4, group.nodes[1].graph.as_ref().unwrap().nodes[0].id);
+    assert_eq!(None, tree.nodes[1].graph);
+    assert_eq!(Ok(tree.clone()), parse_gml_tree(&to_gml_tree(&tree)));
+    let flat = crate::parse_gml_document(gml).unwrap();
+    let nested = flat.nodes[0].attrs["graph"].get("node").unwrap();
+    assert_eq!(2, nested.values().len());
+
+    let (doc, parents) = tree.flatten();
+    let ids: Vec<_> = doc.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(vec![1, 2, 3, 4, 5], ids);
+    assert_eq!(
+        vec![(2, 1), (3, 1), (4, 3)],
+        parents.into_iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(&GmlValue::from("inner")),
+        doc.nodes[0].attrs["graph"].get("label")
+    );
+    assert!(!doc.nodes[2].attrs.contains_key("graph"));
+    assert_eq!(2, doc.edges.len());
+    assert!(doc.into_graph().is_ok());
+
+    assert_eq!(
+        Err("duplicate key"),
+        parse_gml_tree("graph [ node [ id 1 graph [ ] graph [ ] ] ]")
+    );
+}
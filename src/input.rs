@@ -0,0 +1,270 @@
+//! Reading GML input.
+//!
+//! `read_gml` and `read_gml_file` read GML text from a reader or a file.
+//! Gzip and Zstandard input, like `.gml.gz` and `.gml.zst` files, is
+//! recognized by its magic bytes and decompressed with the `gzip` and `zstd`
+//! features. `ReadOptions` sets how the input is read, which matters for
+//! network file systems and object storage gateways.
+
+use crate::trace::{event, span};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+fn invalid(error: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(feature = "gzip")]
+fn gunzip(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    crate::gzip::decompress(&data).map_err(invalid)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_: Vec<u8>) -> io::Result<Vec<u8>> {
+    Err(invalid("gzip input requires the gzip feature"))
+}
+
+#[cfg(feature = "zstd")]
+fn unzstd(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    crate::zstd::decompress(&data).map_err(invalid)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn unzstd(_: Vec<u8>) -> io::Result<Vec<u8>> {
+    Err(invalid("zstd input requires the zstd feature"))
+}
+
+/// How `read_gml_with_options` refills its buffer, which decides the reads
+/// made on the underlying reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refill {
+    /// One `read` call per refill, taking whatever it returns.
+    Once,
+    /// `read` calls until the buffer is full or the input ends, so that
+    /// short reads are coalesced into chunks of the buffer size.
+    Fill,
+}
+
+/// Options for `read_gml_with_options`.
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+    /// The most bytes asked for by one `read` call.
+    pub buffer_size: usize,
+    pub refill: Refill,
+    /// The expected size of the input, in bytes, reserved up front so the
+    /// text is not reallocated as it grows. `read_gml_file_with_options`
+    /// uses the file size if this is 0.
+    pub size_hint: usize,
+}
+
+impl Default for ReadOptions {
+    fn default() -> ReadOptions {
+        ReadOptions {
+            buffer_size: 64 * 1024,
+            refill: Refill::Once,
+            size_hint: 0,
+        }
+    }
+}
+
+// Reads into `buf` as `refill` says, returning the bytes read, which are
+// fewer than the buffer size only at the end of the input for `Fill`.
+fn refill<R: Read>(reader: &mut R, buf: &mut [u8], refill: Refill) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+        if refill == Refill::Once {
+            break;
+        }
+    }
+    Ok(n)
+}
+
+fn decode(mut data: Vec<u8>) -> io::Result<String> {
+    span!("gml.read", bytes = data.len());
+    if data.starts_with(&GZIP_MAGIC) {
+        data = gunzip(data)?;
+        event!(
+            debug,
+            compression = "gzip",
+            bytes = data.len(),
+            "decompressed input"
+        );
+    } else if data.starts_with(&ZSTD_MAGIC) {
+        data = unzstd(data)?;
+        event!(
+            debug,
+            compression = "zstd",
+            bytes = data.len(),
+            "decompressed input"
+        );
+    }
+    String::from_utf8(data).map_err(|_| invalid("stream did not contain valid UTF-8"))
+}
+
+/// Reads all of `reader` as GML text, decompressing gzip or Zstandard
+/// input. Input which is not UTF-8 is an `io::ErrorKind::InvalidData` error.
+pub fn read_gml<R: Read>(mut reader: R) -> io::Result<String> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode(data)
+}
+
+/// Reads `reader` like `read_gml`, with reads as given by `options`. The
+/// input is read directly into the returned text, without an intermediate
+/// buffer.
+pub fn read_gml_with_options<R: Read>(mut reader: R, options: &ReadOptions) -> io::Result<String> {
+    let chunk = options.buffer_size.max(1);
+    let mut data = Vec::with_capacity(options.size_hint);
+    loop {
+        let len = data.len();
+        data.resize(len + chunk, 0);
+        let n = refill(&mut reader, &mut data[len..], options.refill)?;
+        data.truncate(len + n);
+        if n == 0 {
+            break;
+        }
+    }
+    decode(data)
+}
+
+/// Reads `reader` like `read_gml`, into the caller's buffer `buf`, which
+/// sets the size of the reads. Each refill of `buf` is then copied to the
+/// returned text.
+pub fn read_gml_with_buffer<R: Read>(
+    mut reader: R,
+    refill_with: Refill,
+    buf: &mut [u8],
+) -> io::Result<String> {
+    if buf.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty buffer"));
+    }
+    let mut data = Vec::new();
+    loop {
+        let n = refill(&mut reader, buf, refill_with)?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    decode(data)
+}
+
+/// Reads the GML file at `path` like `read_gml`.
+pub fn read_gml_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    read_gml(File::open(path)?)
+}
+
+/// Reads the GML file at `path` like `read_gml_with_options`.
+pub fn read_gml_file_with_options<P: AsRef<Path>>(
+    path: P,
+    options: &ReadOptions,
+) -> io::Result<String> {
+    let file = File::open(path)?;
+    if options.size_hint != 0 {
+        return read_gml_with_options(file, options);
+    }
+    let size_hint = file.metadata().map_or(0, |m| m.len() as usize);
+    let options = ReadOptions {
+        size_hint,
+        ..options.clone()
+    };
+    read_gml_with_options(file, &options)
+}
+
+#[test]
+fn test_read_gml() {
+    let gml = "graph [ node [ id 1 ] ]";
+    assert_eq!(gml, read_gml(gml.as_bytes()).unwrap());
+    let err = read_gml(&[0x67, 0xFF][..]).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+    // `printf 'graph [ node [ id 1 ] ]' | gzip -n`
+    let gz = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x4b, 0x2f, 0x4a, 0x2c, 0xc8,
+        0x50, 0x88, 0x56, 0xc8, 0xcb, 0x4f, 0x49, 0x05, 0x52, 0x99, 0x29, 0x0a, 0x86, 0x0a, 0xb1,
+        0x0a, 0xb1, 0x00, 0x6d, 0x66, 0x26, 0x03, 0x17, 0x00, 0x00, 0x00,
+    ];
+    // `printf 'graph [ node [ id 1 ] ]' | zstd`
+    let zst = [
+        0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x58, 0xb9, 0x00, 0x00, 0x67, 0x72, 0x61, 0x70, 0x68, 0x20,
+        0x5b, 0x20, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x5b, 0x20, 0x69, 0x64, 0x20, 0x31, 0x20, 0x5d,
+        0x20, 0x5d, 0x7d, 0xcd, 0xf0, 0x74,
+    ];
+    for (data, enabled) in &[
+        (&gz[..], cfg!(feature = "gzip")),
+        (&zst[..], cfg!(feature = "zstd")),
+    ] {
+        match read_gml(*data) {
+            Ok(s) => assert_eq!(gml, s),
+            Err(e) => assert!(!enabled, "{}", e),
+        }
+    }
+
+    // returns at most 3 bytes per read
+    struct Trickle<'a>(&'a [u8]);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+    // records the size of each read asked for
+    struct Record<'a>(Trickle<'a>, Vec<usize>);
+    impl Read for Record<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.1.push(buf.len());
+            self.0.read(buf)
+        }
+    }
+    let mut buf = [0; 8];
+    assert_eq!(
+        8,
+        refill(&mut Trickle(gml.as_bytes()), &mut buf, Refill::Fill).unwrap()
+    );
+    assert_eq!(
+        3,
+        refill(&mut Trickle(gml.as_bytes()), &mut buf, Refill::Once).unwrap()
+    );
+    for &refill in &[Refill::Once, Refill::Fill] {
+        let s = read_gml_with_buffer(Trickle(gml.as_bytes()), refill, &mut buf).unwrap();
+        assert_eq!(gml, s);
+        let options = ReadOptions {
+            buffer_size: 5,
+            refill,
+            size_hint: 0,
+        };
+        let s = read_gml_with_options(Trickle(&zst), &options);
+        assert_eq!(cfg!(feature = "zstd"), s.is_ok());
+    }
+
+    // 23 bytes in reads of at most 5, of which the reader returns 3;
+    // `Fill` asks again for the rest of each chunk
+    let options = ReadOptions {
+        buffer_size: 5,
+        refill: Refill::Once,
+        size_hint: gml.len(),
+    };
+    let mut reader = Record(Trickle(gml.as_bytes()), Vec::new());
+    assert_eq!(gml, read_gml_with_options(&mut reader, &options).unwrap());
+    assert_eq!(9, reader.1.len());
+    assert!(reader.1.iter().all(|&n| n == 5));
+    let options = ReadOptions {
+        refill: Refill::Fill,
+        ..options
+    };
+    let mut reader = Record(Trickle(gml.as_bytes()), Vec::new());
+    assert_eq!(gml, read_gml_with_options(&mut reader, &options).unwrap());
+    assert_eq!(vec![5, 2, 5, 2, 5, 2, 5, 2, 5, 2, 5], reader.1);
+}
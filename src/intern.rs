@@ -0,0 +1,282 @@
+//! Interned GML documents.
+//!
+//! Large files repeat the same few keys and often the same categorical
+//! string values. `parse_gml_document_interned` stores every key and string
+//! once in an `Interner` and refers to it by `Symbol`.
+
+use crate::document::{remove_attr, AttrMap, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::events::{document_parts, GmlScalar, ListBuilder};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A handle to a string stored in an `Interner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The position of the string in the interner, starting at 0.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A set of strings, each stored once.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    map: HashMap<Arc<str>, Symbol>,
+    strings: Vec<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Returns the symbol of `s`, adding it if it is new.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&symbol) = self.map.get(s) {
+            return symbol;
+        }
+        let symbol = Symbol(self.strings.len() as u32);
+        let s: Arc<str> = Arc::from(s);
+        self.strings.push(s.clone());
+        self.map.insert(s, symbol);
+        symbol
+    }
+
+    /// Returns the symbol of `s` without adding it.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.map.get(s).copied()
+    }
+
+    /// Returns the string of `symbol`.
+    ///
+    /// Panics if `symbol` is from a different interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.index()]
+    }
+
+    // The number of values of a repeated key, as in `GmlValue`, if `value`
+    // is the list of them.
+    fn repeated_len(&self, value: &InternedValue) -> Option<usize> {
+        let list = match *value {
+            InternedValue::List(ref list) if list.len() > 1 => list,
+            _ => return None,
+        };
+        let index = |&key: &Symbol| self.resolve(key).parse::<usize>().ok();
+        if list
+            .keys()
+            .all(|key| index(key).is_some_and(|i| i < list.len()))
+        {
+            Some(list.len())
+        } else {
+            None
+        }
+    }
+
+    /// The number of distinct strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// Attributes of a GML list, keyed by interned symbols.
+pub type InternedAttrs = AttrMap<Symbol, InternedValue>;
+
+/// A GML value with interned strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternedValue {
+    Int(i64),
+    /// An integer beyond `i64::MAX`, as in `GmlValue`.
+    UInt(u64),
+    Float(f64),
+    Str(Symbol),
+    List(InternedAttrs),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternedNode {
+    pub id: u64,
+    /// All attributes except `id`.
+    pub attrs: InternedAttrs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternedEdge {
+    pub source: u64,
+    pub target: u64,
+    /// All attributes except `source` and `target`.
+    pub attrs: InternedAttrs,
+}
+
+/// A `GmlDocument` whose keys and strings are symbols of `strings`.
+#[derive(Debug, Clone, Default)]
+pub struct InternedDocument {
+    pub directed: bool,
+    /// Graph-level attributes except `directed`, `node` and `edge`.
+    pub attrs: InternedAttrs,
+    /// Top-level attributes outside the `graph` list.
+    pub top_attrs: InternedAttrs,
+    pub nodes: Vec<InternedNode>,
+    pub edges: Vec<InternedEdge>,
+    pub strings: Interner,
+}
+
+impl InternedDocument {
+    /// Looks up the string attribute `key` in `attrs`.
+    pub fn get_str(&self, attrs: &InternedAttrs, key: &str) -> Option<&str> {
+        match attrs.get(&self.strings.get(key)?) {
+            Some(&InternedValue::Str(s)) => Some(self.strings.resolve(s)),
+            _ => None,
+        }
+    }
+
+    /// Converts the document into a `GmlDocument`, copying all strings.
+    pub fn into_document(self) -> GmlDocument {
+        let strings = &self.strings;
+        GmlDocument {
+            directed: self.directed,
+            attrs: attrs_into_owned(strings, self.attrs),
+            top_attrs: attrs_into_owned(strings, self.top_attrs),
+            nodes: self
+                .nodes
+                .into_iter()
+                .map(|n| GmlNode {
+                    id: n.id,
+                    attrs: attrs_into_owned(strings, n.attrs),
+                })
+                .collect(),
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| GmlEdge {
+                    source: e.source,
+                    target: e.target,
+                    attrs: attrs_into_owned(strings, e.attrs),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn attrs_into_owned(strings: &Interner, attrs: InternedAttrs) -> GmlAttrs {
+    attrs
+        .into_iter()
+        .map(|(k, v)| {
+            let value = match v {
+                InternedValue::Int(i) => GmlValue::Int(i),
+                InternedValue::UInt(u) => GmlValue::UInt(u),
+                InternedValue::Float(f) => GmlValue::Float(f),
+                InternedValue::Str(s) => GmlValue::from(strings.resolve(s)),
+                InternedValue::List(l) => GmlValue::List(attrs_into_owned(strings, l)),
+            };
+            (strings.resolve(k).to_string(), value)
+        })
+        .collect()
+}
+
+impl<'a> ListBuilder<'a> for Interner {
+    type Value = InternedValue;
+    type List = InternedAttrs;
+
+    fn scalar(&mut self, scalar: GmlScalar<'a>) -> InternedValue {
+        match scalar {
+            GmlScalar::Int(i) => InternedValue::Int(i),
+            GmlScalar::UInt(u) => InternedValue::UInt(u),
+            GmlScalar::Float(f) => InternedValue::Float(f),
+            GmlScalar::Str(s) => InternedValue::Str(self.intern(&s)),
+        }
+    }
+
+    fn list(&mut self, list: InternedAttrs) -> InternedValue {
+        InternedValue::List(list)
+    }
+
+    fn insert(&mut self, list: &mut InternedAttrs, key: &'a str, value: InternedValue) {
+        let key = self.intern(key);
+        let count = match list.get(&key) {
+            None => {
+                list.insert(key, value);
+                return;
+            }
+            Some(present) => self.repeated_len(present),
+        };
+        let present = list.get_mut(&key).unwrap();
+        let count = match count {
+            Some(count) => count,
+            None => {
+                let first = std::mem::replace(present, InternedValue::List(InternedAttrs::new()));
+                if let InternedValue::List(ref mut values) = *present {
+                    values.insert(self.intern("0"), first);
+                }
+                1
+            }
+        };
+        let index = self.intern(&count.to_string());
+        if let InternedValue::List(ref mut values) = *present {
+            values.insert(index, value);
+        }
+    }
+
+    fn take_id(
+        &mut self,
+        list: &mut InternedAttrs,
+        key: &str,
+        error: &'static str,
+    ) -> Result<u64, &'static str> {
+        let key = self.get(key).ok_or(error)?;
+        match remove_attr(list, &key) {
+            Some(ref value) if self.repeated_len(value).is_some() => Err("duplicate key"),
+            Some(InternedValue::Int(id)) if id >= 0 => Ok(id as u64),
+            Some(InternedValue::UInt(id)) => Ok(id),
+            _ => Err(error),
+        }
+    }
+}
+
+/// Parses a GML string like `parse_gml_document`, interning all keys and
+/// strings.
+pub fn parse_gml_document_interned(s: &str) -> Result<InternedDocument, &'static str> {
+    let mut strings = Interner::new();
+    let parts = document_parts(s, &mut strings)?;
+    Ok(InternedDocument {
+        directed: parts.directed.unwrap_or(false),
+        attrs: parts.attrs,
+        top_attrs: parts.top_attrs,
+        nodes: parts
+            .nodes
+            .into_iter()
+            .map(|(id, attrs)| InternedNode { id, attrs })
+            .collect(),
+        edges: parts
+            .edges
+            .into_iter()
+            .map(|(source, target, attrs)| InternedEdge {
+                source,
+                target,
+                attrs,
+            })
+            .collect(),
+        strings,
+    })
+}
+
+#[test]
+fn test_parse_gml_document_interned() {
+    let gml = "graph [ directed 1 \
+               node [ id 1 label \"red\" graphics [ fill \"red\" ] ] \
+               node [ id 2 label \"red\" ] edge [ source 1 target 2 label \"e\" ] ]";
+    let doc = parse_gml_document_interned(gml).unwrap();
+    assert_eq!(2, doc.nodes.len());
+    assert_eq!(Some("red"), doc.get_str(&doc.nodes[1].attrs, "label"));
+    assert_eq!(Some("e"), doc.get_str(&doc.edges[0].attrs, "label"));
+    // label, red, fill, graphics, id, source, target, e
+    assert_eq!(8, doc.strings.len());
+    let label = doc.strings.get("label").unwrap();
+    assert_eq!(doc.nodes[0].attrs[&label], doc.nodes[1].attrs[&label]);
+    assert_eq!(crate::parse_gml_document(gml).unwrap(), doc.into_document());
+}
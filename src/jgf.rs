@@ -0,0 +1,216 @@
+//! JSON Graph Format (JGF) import and export.
+
+use crate::document::{numeric_ids, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::json::{self, Json};
+use std::io::{self, Write};
+
+/// Renders `doc` as a JGF (version 2) document.
+///
+/// `label` becomes the JGF label of the graph, nodes and edges, all other
+/// attributes go into `metadata`. Nested lists become nested objects.
+pub fn to_jgf(doc: &GmlDocument) -> String {
+    let mut graph = vec![("directed".to_string(), Json::Bool(doc.directed))];
+    push_label_and_metadata(&mut graph, &doc.attrs);
+
+    let nodes = doc
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut members = Vec::new();
+            push_label_and_metadata(&mut members, &node.attrs);
+            (node.id.to_string(), Json::Object(members))
+        })
+        .collect();
+    graph.push(("nodes".to_string(), Json::Object(nodes)));
+
+    let edges = doc
+        .edges
+        .iter()
+        .map(|edge| {
+            let mut members = vec![
+                ("source".to_string(), Json::Str(edge.source.to_string())),
+                ("target".to_string(), Json::Str(edge.target.to_string())),
+            ];
+            push_label_and_metadata(&mut members, &edge.attrs);
+            Json::Object(members)
+        })
+        .collect();
+    graph.push(("edges".to_string(), Json::Array(edges)));
+
+    Json::Object(vec![("graph".to_string(), Json::Object(graph))]).to_string_pretty()
+}
+
+/// Writes `doc` as a JGF document. See `to_jgf`.
+pub fn write_jgf<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    w.write_all(to_jgf(doc).as_bytes())
+}
+
+fn push_label_and_metadata(members: &mut Vec<(String, Json)>, attrs: &GmlAttrs) {
+    if let Some(label) = attrs.get("label").and_then(GmlValue::get_str) {
+        members.push(("label".to_string(), Json::Str(label.to_string())));
+    }
+    let metadata: Vec<_> = attrs
+        .iter()
+        .filter(|&(k, v)| !(k == "label" && v.get_str().is_some()))
+        .map(|(k, v)| (k.clone(), json::from_value(v)))
+        .collect();
+    if !metadata.is_empty() {
+        members.push(("metadata".to_string(), Json::Object(metadata)));
+    }
+}
+
+fn json_to_value(json: &Json) -> Result<Option<GmlValue>, &'static str> {
+    Ok(Some(match *json {
+        Json::Null => return Ok(None),
+        Json::Bool(b) => GmlValue::Int(b as i64),
+        Json::Int(i) => GmlValue::Int(i),
+        Json::UInt(u) => GmlValue::UInt(u),
+        Json::Float(f) => GmlValue::Float(f),
+        Json::Str(ref s) => GmlValue::from(s.clone()),
+        Json::Array(_) => return Err("arrays are not supported in metadata"),
+        Json::Object(ref members) => GmlValue::List(metadata_to_attrs(members)?),
+    }))
+}
+
+fn metadata_to_attrs(members: &[(String, Json)]) -> Result<GmlAttrs, &'static str> {
+    let mut attrs = GmlAttrs::new();
+    for (k, v) in members {
+        if let Some(v) = json_to_value(v)? {
+            attrs.insert(k.clone(), v);
+        }
+    }
+    Ok(attrs)
+}
+
+// Label and metadata of a JGF graph, node or edge object.
+fn element_attrs(element: &Json) -> Result<GmlAttrs, &'static str> {
+    let mut attrs = match element.get("metadata") {
+        Some(Json::Object(members)) => metadata_to_attrs(members)?,
+        Some(Json::Null) | None => GmlAttrs::new(),
+        Some(_) => return Err("metadata has to be an object"),
+    };
+    if let Some(label) = element.get("label").and_then(Json::as_str) {
+        attrs.insert("label".to_string(), GmlValue::from(label));
+    }
+    Ok(attrs)
+}
+
+fn json_id(json: Option<&Json>) -> Option<String> {
+    match json {
+        Some(Json::Str(s)) => Some(s.clone()),
+        Some(Json::Int(i)) => Some(i.to_string()),
+        Some(Json::UInt(u)) => Some(u.to_string()),
+        _ => None,
+    }
+}
+
+/// Parses a JGF document into a `GmlDocument`.
+///
+/// Both the version 2 layout (`nodes` as an object keyed by id) and the
+/// version 1 layout (`nodes` as an array) are accepted. For a `graphs`
+/// array, the first graph is used. Metadata is merged into the attributes,
+/// booleans become `Int` 0/1 and `null`s are dropped. Node ids which are
+/// unsigned integers are used directly; otherwise nodes are numbered from 0
+/// and the JGF id is kept as `name`.
+pub fn parse_jgf(s: &str) -> Result<GmlDocument, &'static str> {
+    let root = json::parse(s)?;
+    let graph = match (root.get("graph"), root.get("graphs")) {
+        (Some(graph), _) => graph,
+        (None, Some(Json::Array(graphs))) => graphs.first().ok_or("no graph given or invalid")?,
+        _ => return Err("no graph given or invalid"),
+    };
+
+    let mut doc = GmlDocument {
+        directed: graph
+            .get("directed")
+            .and_then(Json::as_bool)
+            .unwrap_or(true),
+        attrs: element_attrs(graph)?,
+        ..GmlDocument::default()
+    };
+
+    let mut nodes = Vec::new();
+    match graph.get("nodes") {
+        Some(Json::Object(members)) => {
+            for (id, node) in members {
+                nodes.push((id.clone(), element_attrs(node)?));
+            }
+        }
+        Some(Json::Array(items)) => {
+            for node in items {
+                let id = json_id(node.get("id")).ok_or("Invalid id")?;
+                nodes.push((id, element_attrs(node)?));
+            }
+        }
+        None => {}
+        Some(_) => return Err("nodes has to be an object or array"),
+    }
+
+    let numeric = numeric_ids(nodes.iter().map(|n| &n.0[..]), |id| id.parse().ok());
+    let mut id_map = std::collections::BTreeMap::new();
+    for (idx, (name, mut attrs)) in nodes.into_iter().enumerate() {
+        let id = match numeric {
+            Some(ref ids) => ids[idx],
+            None => {
+                attrs.insert("name".to_string(), GmlValue::from(name.clone()));
+                idx as u64
+            }
+        };
+        if id_map.insert(name, id).is_some() {
+            return Err("duplicate node-id");
+        }
+        doc.nodes.push(GmlNode { id, attrs });
+    }
+
+    match graph.get("edges") {
+        Some(Json::Array(items)) => {
+            for edge in items {
+                let source = json_id(edge.get("source")).ok_or("Invalid source id")?;
+                let target = json_id(edge.get("target")).ok_or("Invalid target id")?;
+                let mut attrs = element_attrs(edge)?;
+                if let Some(relation) = edge.get("relation").and_then(Json::as_str) {
+                    attrs.insert("relation".to_string(), GmlValue::from(relation));
+                }
+                doc.edges.push(GmlEdge {
+                    source: *id_map.get(&source).ok_or("edge references unknown node")?,
+                    target: *id_map.get(&target).ok_or("edge references unknown node")?,
+                    attrs,
+                });
+            }
+        }
+        None => {}
+        Some(_) => return Err("edges has to be an array"),
+    }
+
+    Ok(doc)
+}
+
+#[test]
+fn test_jgf_round_trip() {
+    let gml = "
+    graph [
+        directed 1
+        label \"net\"
+        node [ id 1 label \"a\" graphics [ x 1.5 ] ]
+        node [ id 2 weight 3 ]
+        edge [ source 1 target 2 label \"link\" ]
+    ]
+    ";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let jgf = to_jgf(&doc);
+    assert!(jgf.contains("\"label\": \"a\""));
+    assert!(jgf.contains("\"source\": \"1\""));
+    assert_eq!(doc, parse_jgf(&jgf).unwrap());
+
+    let v1 = r#"{"graph": {"directed": false, "nodes": [
+        {"id": "x", "label": "X", "metadata": {"active": true, "note": null}},
+        {"id": "y"}
+    ], "edges": [{"source": "x", "target": "y", "relation": "knows"}]}}"#;
+    let doc = parse_jgf(v1).unwrap();
+    assert!(!doc.directed);
+    assert_eq!(Some("x"), doc.nodes[0].attrs["name"].get_str());
+    assert_eq!(Some(1), doc.nodes[0].attrs["active"].get_int());
+    assert!(!doc.nodes[0].attrs.contains_key("note"));
+    assert_eq!((0, 1), (doc.edges[0].source, doc.edges[0].target));
+    assert_eq!(Some("knows"), doc.edges[0].attrs["relation"].get_str());
+}
@@ -0,0 +1,276 @@
+//! Lazy GML documents.
+//!
+//! `parse_gml_document_lazy` reads only the ids of the nodes and the
+//! endpoints of the edges. The other attributes of each block are kept as a
+//! slice of the input and parsed into `GmlAttrs` when they are first
+//! accessed, so a caller that only needs the topology does not decode the
+//! `graphics` and other lists of the blocks. The input is always read with
+//! the GML lexer.
+
+use crate::document::{
+    insert_attr, remove_attr, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue,
+};
+use crate::events::{lexer_events, GmlEvent, GmlEvents, GmlScalar, OwnedLists};
+use std::cell::OnceCell;
+
+/// The attributes of a node or edge block, parsed on first access.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyAttrs<'a> {
+    raw: &'a str,
+    // the keys which are not attributes, like `id`
+    ids: &'static [&'static str],
+    attrs: OnceCell<Result<GmlAttrs, &'static str>>,
+}
+
+impl<'a> LazyAttrs<'a> {
+    fn new(raw: &'a str, ids: &'static [&'static str]) -> LazyAttrs<'a> {
+        LazyAttrs {
+            raw,
+            ids,
+            attrs: OnceCell::new(),
+        }
+    }
+
+    /// The text between the brackets of the block.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Whether the attributes have been parsed.
+    pub fn is_parsed(&self) -> bool {
+        self.attrs.get().is_some()
+    }
+
+    /// Parses the attributes, if not done before. Errors inside the block
+    /// which do not affect the ids are only reported here.
+    pub fn get(&self) -> Result<&GmlAttrs, &'static str> {
+        self.attrs
+            .get_or_init(|| parse_attrs(self.raw, self.ids))
+            .as_ref()
+            .map_err(|&e| e)
+    }
+
+    pub fn into_attrs(self) -> Result<GmlAttrs, &'static str> {
+        let ids = self.ids;
+        let raw = self.raw;
+        self.attrs
+            .into_inner()
+            .unwrap_or_else(|| parse_attrs(raw, ids))
+    }
+}
+
+fn parse_attrs(raw: &str, ids: &[&str]) -> Result<GmlAttrs, &'static str> {
+    let mut events = lexer_events(raw);
+    let mut attrs = GmlAttrs::new();
+    while let Some(event) = events.next() {
+        let (key, value) = match event? {
+            GmlEvent::Value(key, scalar) => (key, scalar.into_value()),
+            GmlEvent::Begin(key) => (key, GmlValue::List(events.read_list()?)),
+            GmlEvent::End => return Err("unbalanced ]"),
+        };
+        insert_attr(&mut attrs, key, value);
+    }
+    for id in ids {
+        remove_attr(&mut attrs, *id);
+    }
+    Ok(attrs)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyNode<'a> {
+    pub id: u64,
+    /// All attributes except `id`.
+    pub attrs: LazyAttrs<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyEdge<'a> {
+    pub source: u64,
+    pub target: u64,
+    /// All attributes except `source` and `target`.
+    pub attrs: LazyAttrs<'a>,
+}
+
+/// A `GmlDocument` whose node and edge attributes are parsed on first
+/// access.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LazyDocument<'a> {
+    pub directed: bool,
+    /// Graph-level attributes except `directed`, `node` and `edge`.
+    pub attrs: GmlAttrs,
+    /// Top-level attributes outside the `graph` list.
+    pub top_attrs: GmlAttrs,
+    pub nodes: Vec<LazyNode<'a>>,
+    pub edges: Vec<LazyEdge<'a>>,
+}
+
+impl<'a> LazyDocument<'a> {
+    /// Parses the attributes of all nodes and edges.
+    pub fn into_document(self) -> Result<GmlDocument, &'static str> {
+        Ok(GmlDocument {
+            directed: self.directed,
+            attrs: self.attrs,
+            top_attrs: self.top_attrs,
+            nodes: self
+                .nodes
+                .into_iter()
+                .map(|n| {
+                    Ok(GmlNode {
+                        id: n.id,
+                        attrs: n.attrs.into_attrs()?,
+                    })
+                })
+                .collect::<Result<_, &'static str>>()?,
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| {
+                    Ok(GmlEdge {
+                        source: e.source,
+                        target: e.target,
+                        attrs: e.attrs.into_attrs()?,
+                    })
+                })
+                .collect::<Result<_, &'static str>>()?,
+        })
+    }
+}
+
+fn offset(events: &GmlEvents) -> usize {
+    events.offset().expect("events read with the GML lexer")
+}
+
+// Reads a node or edge block up to its `End`, storing the values of `keys`
+// in `ids`, and returns the text between its brackets. A key of `keys`
+// given twice is a "duplicate key", as in `parse_gml_document`.
+fn read_block<'a>(
+    s: &'a str,
+    events: &mut GmlEvents<'a>,
+    ids: &mut [Option<u64>],
+    keys: &[&str],
+) -> Result<&'a str, &'static str> {
+    let start = offset(events);
+    let mut seen = [false; 2];
+    loop {
+        let key = match events.next().ok_or("unbalanced [")?? {
+            GmlEvent::End => {
+                return Ok(&s[start..offset(events) - 1]);
+            }
+            GmlEvent::Value(key, scalar) => {
+                if let Some(k) = keys.iter().position(|&k| k == key) {
+                    ids[k] = scalar.get_uint();
+                }
+                key
+            }
+            GmlEvent::Begin(key) => {
+                events.skip_list()?;
+                key
+            }
+        };
+        if let Some(k) = keys.iter().position(|&k| k == key) {
+            if seen[k] {
+                return Err("duplicate key");
+            }
+            seen[k] = true;
+        }
+    }
+}
+
+/// Parses a GML string like `parse_gml_document`, keeping the attributes of
+/// nodes and edges unparsed until they are accessed.
+pub fn parse_gml_document_lazy(s: &str) -> Result<LazyDocument<'_>, &'static str> {
+    let mut events = lexer_events(s);
+    let mut doc = LazyDocument::default();
+    events.enter_graph_with(&mut OwnedLists, &mut doc.top_attrs)?;
+    while let Some(event) = events.next() {
+        let (key, value) = match event? {
+            GmlEvent::End => break,
+            GmlEvent::Value("directed", GmlScalar::Int(0)) => {
+                doc.directed = false;
+                continue;
+            }
+            GmlEvent::Value("directed", GmlScalar::Int(1)) => {
+                doc.directed = true;
+                continue;
+            }
+            GmlEvent::Value("directed", _) | GmlEvent::Begin("directed") => {
+                return Err("invalid directed value");
+            }
+            GmlEvent::Begin("node") => {
+                const KEYS: &[&str] = &["id"];
+                let mut ids = [None];
+                let raw = read_block(s, &mut events, &mut ids, KEYS)?;
+                doc.nodes.push(LazyNode {
+                    id: ids[0].ok_or("Invalid id")?,
+                    attrs: LazyAttrs::new(raw, KEYS),
+                });
+                continue;
+            }
+            GmlEvent::Begin("edge") => {
+                const KEYS: &[&str] = &["source", "target"];
+                let mut ids = [None, None];
+                let raw = read_block(s, &mut events, &mut ids, KEYS)?;
+                doc.edges.push(LazyEdge {
+                    source: ids[0].ok_or("Invalid source id")?,
+                    target: ids[1].ok_or("Invalid target id")?,
+                    attrs: LazyAttrs::new(raw, KEYS),
+                });
+                continue;
+            }
+            GmlEvent::Value(key, scalar) => (key, scalar.into_value()),
+            GmlEvent::Begin(key) => (key, GmlValue::List(events.read_list()?)),
+        };
+        insert_attr(&mut doc.attrs, key, value);
+    }
+    events.finish_with(&mut OwnedLists, &mut doc.top_attrs)?;
+    Ok(doc)
+}
+
+#[test]
+fn test_parse_gml_document_lazy() {
+    let gml = "graph [ directed 1 label \"test\" \
+               node [ id 1 label \"a\" graphics [ fill \"#FF0000\" ] ] \
+               node [ label \"b\" id 2 ] edge [ source 1 target 2 weight 1.5 ] ]";
+    let doc = parse_gml_document_lazy(gml).unwrap();
+    assert!(doc.directed);
+    assert_eq!(
+        vec![1, 2],
+        doc.nodes.iter().map(|n| n.id).collect::<Vec<_>>()
+    );
+    assert_eq!((1, 2), (doc.edges[0].source, doc.edges[0].target));
+    assert_eq!(" label \"b\" id 2 ", doc.nodes[1].attrs.raw());
+    assert!(!doc.nodes[0].attrs.is_parsed());
+    let attrs = doc.nodes[0].attrs.get().unwrap();
+    assert_eq!(None, attrs.get("id"));
+    assert_eq!(
+        Some("#FF0000"),
+        attrs["graphics"].get("fill").and_then(GmlValue::get_str)
+    );
+    assert!(doc.nodes[0].attrs.is_parsed());
+    assert_eq!(
+        crate::parse_gml_document(gml).unwrap(),
+        doc.into_document().unwrap()
+    );
+
+    let doc = parse_gml_document_lazy("graph [ node [ id 1 x 1 x 2 ] ]").unwrap();
+    let x = &doc.nodes[0].attrs.get().unwrap()["x"];
+    assert_eq!(vec![&GmlValue::Int(1), &GmlValue::Int(2)], x.values());
+    assert_eq!(
+        Some("Invalid id"),
+        parse_gml_document_lazy("graph [ node [ label \"a\" ] ]").err()
+    );
+
+    for gml in [
+        "graph [ node [ id 1 id 2 ] ]",
+        "graph [ node [ id 1 ] edge [ source 1 target 1 source 1 ] ]",
+        "graph [ node [ id 1 id [ x 1 ] ] ]",
+        "graph [ node [ id 1 x 1 x 2 ] edge [ source 1 target 1 ] ]",
+    ] {
+        let lazy = parse_gml_document_lazy(gml).and_then(LazyDocument::into_document);
+        assert_eq!(crate::parse_gml_document(gml), lazy, "{}", gml);
+    }
+    assert_eq!(
+        Err("duplicate key"),
+        crate::parse_gml_document("graph [ node [ id 1 id 2 ] ]")
+    );
+}
@@ -0,0 +1,217 @@
+//! LEDA native graph format (`LEDA.GRAPH`) import and export.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue, ValueType};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+// The `|{...}|` information of a node or edge line.
+fn info(s: &str) -> Result<&str, &'static str> {
+    let start = s.find("|{").ok_or("missing |{")?;
+    let end = s.rfind("}|").ok_or("missing }|")?;
+    if end < start + 2 {
+        return Err("missing }|");
+    }
+    Ok(&s[start + 2..end])
+}
+
+fn typed(info: &str, ty: &str) -> Result<Option<GmlValue>, &'static str> {
+    if ty == "void" || info.is_empty() {
+        return Ok(None);
+    }
+    let value = match ty {
+        "int" | "long" | "short" => GmlValue::Int(info.trim().parse().map_err(|_| "invalid int")?),
+        "float" | "double" => GmlValue::Float(info.trim().parse().map_err(|_| "invalid real")?),
+        _ => GmlValue::from(info),
+    };
+    Ok(Some(value))
+}
+
+/// Parses a LEDA graph file into a `GmlDocument`.
+///
+/// Nodes get the ids 1 to n of their position in the file. Node and edge
+/// information becomes the `label` attribute, typed by the declared node
+/// and edge types, and is left out for `void`. The direction line (`-1`
+/// directed, `-2` undirected) is optional, as in older files, which are
+/// directed.
+pub fn parse_leda(s: &str) -> Result<GmlDocument, &'static str> {
+    let mut lines = s
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let mut next = || lines.next().ok_or("unexpected end of file");
+
+    if next()? != "LEDA.GRAPH" {
+        return Err("missing LEDA.GRAPH header");
+    }
+    let node_type = next()?.to_string();
+    let edge_type = next()?.to_string();
+
+    let mut doc = GmlDocument {
+        directed: true,
+        ..GmlDocument::default()
+    };
+    let mut count_line = next()?;
+    match count_line {
+        "-1" => count_line = next()?,
+        "-2" => {
+            doc.directed = false;
+            count_line = next()?;
+        }
+        _ => {}
+    }
+
+    let n: u64 = count_line.parse().map_err(|_| "invalid node count")?;
+    for id in 1..=n {
+        let mut node = GmlNode {
+            id,
+            attrs: Default::default(),
+        };
+        if let Some(label) = typed(info(next()?)?, &node_type)? {
+            node.attrs.insert("label".to_string(), label);
+        }
+        doc.nodes.push(node);
+    }
+
+    let m: usize = next()?.parse().map_err(|_| "invalid edge count")?;
+    for _ in 0..m {
+        let line = next()?;
+        let mut fields = line.split_whitespace();
+        let mut endpoint = || -> Result<u64, &'static str> {
+            let id: u64 = fields
+                .next()
+                .and_then(|f| f.parse().ok())
+                .ok_or("invalid edge line")?;
+            if id == 0 || id > n {
+                return Err("edge references unknown node");
+            }
+            Ok(id)
+        };
+        let mut edge = GmlEdge {
+            source: endpoint()?,
+            target: endpoint()?,
+            attrs: Default::default(),
+        };
+        if let Some(label) = typed(info(line)?, &edge_type)? {
+            edge.attrs.insert("label".to_string(), label);
+        }
+        doc.edges.push(edge);
+    }
+    Ok(doc)
+}
+
+/// Renders `doc` as a LEDA graph file. An edge that references an unknown
+/// node is an `io::ErrorKind::InvalidData` error, as in `write_leda`.
+pub fn to_leda(doc: &GmlDocument) -> io::Result<String> {
+    let mut buf = Vec::new();
+    write_leda(doc, &mut buf)?;
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+// The LEDA type covering the labels, `void` if there are none.
+fn leda_type<'a, I: Iterator<Item = Option<&'a GmlValue>>>(labels: I) -> &'static str {
+    let ty = labels
+        .flatten()
+        .map(ValueType::of)
+        .fold(None, |acc: Option<ValueType>, ty| {
+            Some(acc.map_or(ty, |acc| acc.unify(ty)))
+        });
+    match ty {
+        None => "void",
+        Some(ValueType::Int) | Some(ValueType::Long) | Some(ValueType::UnsignedLong) => "int",
+        Some(ValueType::Double) => "double",
+        Some(ValueType::String) => "string",
+    }
+}
+
+fn label(attrs: &GmlAttrs) -> Option<&GmlValue> {
+    attrs.get("label").filter(|l| l.get_list().is_none())
+}
+
+fn info_text(label: Option<&GmlValue>) -> String {
+    let text = match label {
+        Some(GmlValue::Int(i)) => i.to_string(),
+        Some(GmlValue::UInt(u)) => u.to_string(),
+        Some(GmlValue::Float(f)) => format!("{:?}", f),
+        Some(GmlValue::Str(s)) => s.to_string(),
+        Some(GmlValue::List(_)) | None => String::new(),
+    };
+    // LEDA has no escapes within `|{...}|`
+    format!(
+        "|{{{}}}|",
+        text.replace("}|", "} |").replace(['\r', '\n'], " ")
+    )
+}
+
+/// Writes `doc` as a LEDA graph file, the inverse of `parse_leda`. Node and
+/// edge labels become the node and edge information, typed as `int`,
+/// `double` or `string` according to the values.
+///
+/// LEDA numbers nodes from 1 without gaps, so nodes are renumbered in
+/// document order.
+pub fn write_leda<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    writeln!(w, "LEDA.GRAPH")?;
+    writeln!(
+        w,
+        "{}",
+        leda_type(doc.nodes.iter().map(|n| label(&n.attrs)))
+    )?;
+    writeln!(
+        w,
+        "{}",
+        leda_type(doc.edges.iter().map(|e| label(&e.attrs)))
+    )?;
+    writeln!(w, "{}", if doc.directed { -1 } else { -2 })?;
+
+    let mut numbers = BTreeMap::new();
+    writeln!(w, "{}", doc.nodes.len())?;
+    for (i, node) in doc.nodes.iter().enumerate() {
+        numbers.insert(node.id, i + 1);
+        writeln!(w, "{}", info_text(label(&node.attrs)))?;
+    }
+
+    writeln!(w, "{}", doc.edges.len())?;
+    for edge in &doc.edges {
+        let (source, target) = match (numbers.get(&edge.source), numbers.get(&edge.target)) {
+            (Some(s), Some(t)) => (s, t),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "edge references unknown node",
+                ))
+            }
+        };
+        writeln!(
+            w,
+            "{} {} 0 {}",
+            source,
+            target,
+            info_text(label(&edge.attrs))
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_leda_round_trip() {
+    let leda = "LEDA.GRAPH\nstring\nint\n-2\n# nodes\n3\n|{v1}|\n|{}|\n|{v 3}|\n\
+                2\n1 2 0 |{5}|\n3 1 0 |{}|\n";
+    let doc = parse_leda(leda).unwrap();
+    assert!(!doc.directed);
+    assert_eq!(3, doc.nodes.len());
+    assert_eq!(Some("v 3"), doc.nodes[2].label());
+    assert!(doc.nodes[1].attrs.is_empty());
+    assert_eq!(Some(5), doc.edges[0].attrs["label"].get_int());
+    assert_eq!((3, 1), (doc.edges[1].source, doc.edges[1].target));
+    assert_eq!(leda.replace("# nodes\n", ""), to_leda(&doc).unwrap());
+
+    let dangling = crate::parse_gml_document("graph [ node [ id 1 ] edge [ source 1 target 2 ] ]");
+    let err = to_leda(&dangling.unwrap()).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+    let doc = parse_leda("LEDA.GRAPH\nvoid\nvoid\n2\n|{}|\n|{}|\n1\n2 1 0 |{}|\n").unwrap();
+    assert!(doc.directed);
+    assert_eq!(
+        Err("edge references unknown node"),
+        parse_leda("LEDA.GRAPH\nvoid\nvoid\n1\n|{}|\n1\n2 1 0 |{}|\n")
+    );
+}
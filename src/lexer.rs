@@ -0,0 +1,384 @@
+//! GML lexer.
+//!
+//! Follows the lexical rules of the GML specification: keys are
+//! identifiers, or identifiers joined by dots as some exporters write
+//! nested keys, like `graphics.x`, numbers may be signed, reals have a
+//! decimal point or an exponent, strings are enclosed in double quotes
+//! without escapes and may contain character entities (`&quot;`, `&amp;`,
+//! `&#228;`, `&auml;`, ...), and `#` starts a comment up to the end of the
+//! line.
+
+use crate::scan;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GmlToken<'a> {
+    Key(&'a str),
+    Int(i64),
+    /// An integer beyond `i64::MAX`, like a 64 bit hash used as node id.
+    UInt(u64),
+    Float(f64),
+    Str(Cow<'a, str>),
+    /// `[`
+    Open,
+    /// `]`
+    Close,
+}
+
+/// An iterator over the tokens of a GML string.
+///
+/// In strict mode, the lexer also rejects lines longer than 254 characters
+/// and non-ASCII characters, which GML files must not contain.
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    strict: bool,
+    line_start: usize,
+    // the offset of the last token or error
+    start: usize,
+}
+
+// The ISO 8859-1 entities for the characters 160 to 255.
+const LATIN1_ENTITIES: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf",
+    "laquo", "not", "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro",
+    "para", "middot", "cedil", "sup1", "ordm", "raquo", "frac14", "frac12", "frac34", "iquest",
+    "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig", "Ccedil", "Egrave", "Eacute",
+    "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve", "Oacute",
+    "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute",
+    "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde",
+    "ograve", "oacute", "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc",
+    "uuml", "yacute", "thorn", "yuml",
+];
+
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "quot" => return Some('"'),
+        "amp" => return Some('&'),
+        "lt" => return Some('<'),
+        "gt" => return Some('>'),
+        "apos" => return Some('\''),
+        _ => {}
+    }
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return std::char::from_u32(code);
+    }
+    LATIN1_ENTITIES
+        .iter()
+        .position(|&e| e == name)
+        .and_then(|i| std::char::from_u32(160 + i as u32))
+}
+
+/// Replaces the character entities in `s`. Unknown entities and lone `&`
+/// are kept as they are.
+pub fn decode_entities(s: &str) -> Cow<'_, str> {
+    if !s.contains('&') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 8)
+            .and_then(|end| entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn is_key_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
+        Lexer {
+            input,
+            pos: 0,
+            strict: false,
+            line_start: 0,
+            start: 0,
+        }
+    }
+
+    pub fn strict(mut self, strict: bool) -> Lexer<'a> {
+        self.strict = strict;
+        self
+    }
+
+    /// The byte offset of the next token.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// The byte offset of the last token, or of the input where the last
+    /// error was found.
+    pub fn token_start(&self) -> usize {
+        self.start
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn check(&mut self, from: usize, to: usize) -> Result<(), &'static str> {
+        if !self.strict {
+            return Ok(());
+        }
+        let text = &self.input[from..to];
+        if !text.is_ascii() {
+            return Err("non-ASCII character");
+        }
+        for (i, c) in text.char_indices() {
+            if c == '\n' {
+                if from + i - self.line_start > 254 {
+                    return Err("line exceeds 254 characters");
+                }
+                self.line_start = from + i + 1;
+            }
+        }
+        if to - self.line_start > 254 {
+            return Err("line exceeds 254 characters");
+        }
+        Ok(())
+    }
+
+    // Skips whitespace and comments.
+    fn skip(&mut self) -> Result<(), &'static str> {
+        let start = self.pos;
+        loop {
+            self.pos += scan::whitespace_len(self.rest().as_bytes());
+            let rest = self.rest();
+            if rest.starts_with('#') {
+                self.pos += scan::find_byte(rest.as_bytes(), b'\n').unwrap_or(rest.len());
+            } else if rest.starts_with(char::is_whitespace) {
+                // non-ASCII whitespace
+                let trimmed = rest.trim_start();
+                self.pos += rest.len() - trimmed.len();
+            } else {
+                break;
+            }
+        }
+        self.check(start, self.pos)
+    }
+
+    fn number(&mut self) -> Result<GmlToken<'a>, &'static str> {
+        let rest = self.rest();
+        let bytes = rest.as_bytes();
+        let mut len = 0;
+        if len < bytes.len() && (bytes[len] == b'+' || bytes[len] == b'-') {
+            len += 1;
+        }
+        for special in &["INF", "NAN"] {
+            if rest[len..].starts_with(special) {
+                let end = len + 3;
+                if !rest[end..].starts_with(is_key_char) {
+                    self.pos += end;
+                    let f = if *special == "NAN" {
+                        f64::NAN
+                    } else if rest.starts_with('-') {
+                        f64::NEG_INFINITY
+                    } else {
+                        f64::INFINITY
+                    };
+                    return Ok(GmlToken::Float(f));
+                }
+            }
+        }
+        let digits = |len: &mut usize| {
+            let start = *len;
+            while *len < bytes.len() && bytes[*len].is_ascii_digit() {
+                *len += 1;
+            }
+            *len - start
+        };
+        let mut real = false;
+        let mut count = digits(&mut len);
+        if len < bytes.len() && bytes[len] == b'.' {
+            len += 1;
+            real = true;
+            count += digits(&mut len);
+        }
+        if count == 0 {
+            return Err("invalid number");
+        }
+        if len < bytes.len() && (bytes[len] == b'e' || bytes[len] == b'E') {
+            let mut exp = len + 1;
+            if exp < bytes.len() && (bytes[exp] == b'+' || bytes[exp] == b'-') {
+                exp += 1;
+            }
+            if digits(&mut exp) > 0 {
+                len = exp;
+                real = true;
+            }
+        }
+        if rest[len..].starts_with(|c: char| is_key_char(c) || c == '.') {
+            return Err("invalid number");
+        }
+        let text = &rest[..len];
+        self.pos += len;
+        if real {
+            text.parse()
+                .map(GmlToken::Float)
+                .map_err(|_| "invalid number")
+        } else {
+            text.parse()
+                .map(GmlToken::Int)
+                .or_else(|_| text.parse().map(GmlToken::UInt))
+                .map_err(|_| "integer out of range")
+        }
+    }
+
+    fn string(&mut self) -> Result<GmlToken<'a>, &'static str> {
+        let rest = &self.rest()[1..];
+        let end = scan::find_byte(rest.as_bytes(), b'"').ok_or("unterminated string")?;
+        self.pos += end + 2;
+        Ok(GmlToken::Str(decode_entities(&rest[..end])))
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<GmlToken<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.start = self.pos;
+        if let Err(e) = self.skip() {
+            self.pos = self.input.len();
+            return Some(Err(e));
+        }
+        let c = self.rest().chars().next()?;
+        let start = self.pos;
+        self.start = start;
+        let token = match c {
+            '[' => {
+                self.pos += 1;
+                Ok(GmlToken::Open)
+            }
+            ']' => {
+                self.pos += 1;
+                Ok(GmlToken::Close)
+            }
+            '"' => self.string(),
+            c if c.is_ascii_digit() || c == '+' || c == '-' || c == '.' => self.number(),
+            c if is_key_start(c) => {
+                let rest = self.rest();
+                let mut len = 0;
+                // identifiers, joined by dots as in `graphics.x`
+                loop {
+                    len += rest[len..]
+                        .find(|c| !is_key_char(c))
+                        .unwrap_or(rest.len() - len);
+                    match rest[len..].strip_prefix('.') {
+                        Some(next) if next.starts_with(is_key_start) => len += 1,
+                        _ => break,
+                    }
+                }
+                self.pos += len;
+                Ok(GmlToken::Key(&rest[..len]))
+            }
+            _ => Err("unexpected character"),
+        };
+        if token.is_err() {
+            // stop after an error
+            self.pos = self.input.len();
+            return Some(token);
+        }
+        Some(self.check(start, self.pos).and(token))
+    }
+}
+
+#[test]
+fn test_lexer() {
+    let gml = "graph [ # comment\n id -3 x +1.5 y .5E1 z 2e3 w -INF \
+               label \"a &quot;b&quot; &amp; &auml;&#252;&#x41; AT&T &bogus;\" ]";
+    let tokens: Vec<_> = Lexer::new(gml).collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        vec![
+            GmlToken::Key("graph"),
+            GmlToken::Open,
+            GmlToken::Key("id"),
+            GmlToken::Int(-3),
+            GmlToken::Key("x"),
+            GmlToken::Float(1.5),
+            GmlToken::Key("y"),
+            GmlToken::Float(5.0),
+            GmlToken::Key("z"),
+            GmlToken::Float(2000.0),
+            GmlToken::Key("w"),
+            GmlToken::Float(f64::NEG_INFINITY),
+            GmlToken::Key("label"),
+            GmlToken::Str(Cow::Owned("a \"b\" & äüA AT&T &bogus;".to_string())),
+            GmlToken::Close,
+        ],
+        tokens
+    );
+
+    let ints: Vec<_> = Lexer::new(
+        "9223372036854775807 9223372036854775808 +18446744073709551615 -9223372036854775808",
+    )
+    .collect::<Result<_, _>>()
+    .unwrap();
+    assert_eq!(
+        vec![
+            GmlToken::Int(i64::MAX),
+            GmlToken::UInt(i64::MAX as u64 + 1),
+            GmlToken::UInt(u64::MAX),
+            GmlToken::Int(i64::MIN),
+        ],
+        ints
+    );
+    for out_of_range in &["18446744073709551616", "-9223372036854775809"] {
+        assert_eq!(
+            Some(Err("integer out of range")),
+            Lexer::new(out_of_range).next()
+        );
+    }
+
+    let keys: Vec<_> = Lexer::new("graphics.x 1 a.b_2.c 2")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(GmlToken::Key("graphics.x"), keys[0]);
+    assert_eq!(GmlToken::Key("a.b_2.c"), keys[2]);
+    assert_eq!(Some(Err("invalid number")), Lexer::new("a. 1").nth(1));
+
+    let mut lexer = Lexer::new("id  12ab");
+    assert_eq!(Some(Err("invalid number")), lexer.nth(1));
+    assert_eq!(4, lexer.token_start());
+    for gml in &["label \"abc", "id 1x", "a ?", "# \u{e4}\n"] {
+        let mut lexer = Lexer::new(gml).strict(true);
+        assert!(lexer.by_ref().any(|t| t.is_err()), "{}", gml);
+        assert_eq!(None, lexer.next(), "{}", gml);
+    }
+    assert_eq!(
+        Some(Err("unterminated string")),
+        Lexer::new("label \"abc").nth(1)
+    );
+    let long = format!("label \"{}\"", "x".repeat(250));
+    assert!(Lexer::new(&long).all(|t| t.is_ok()));
+    assert_eq!(
+        Some(Err("line exceeds 254 characters")),
+        Lexer::new(&long).strict(true).nth(1)
+    );
+}
@@ -1,9 +1,131 @@
 use asexp::atom::Atom;
-use asexp::token::{Token, Tokenizer};
 use asexp::Sexp;
+use events::{GmlEvent, GmlEvents, GmlScalar};
+use node_map::NodeMap;
 use petgraph::graph::NodeIndex;
 use petgraph::{Directed, Graph};
-use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use trace::{event, span};
+
+pub mod aggregate;
+pub mod anonymize;
+#[cfg(feature = "quickcheck")]
+mod arbitrary;
+pub mod base64;
+pub mod batch;
+pub mod bipartite;
+pub mod bools;
+pub mod borrowed;
+pub mod canonical;
+pub mod channel;
+pub mod cluster;
+pub mod codes;
+pub mod color;
+pub mod compact;
+pub mod csv;
+pub mod cypher;
+pub mod cytoscape;
+#[cfg(feature = "datetime")]
+pub mod datetime;
+pub mod defaults;
+pub mod detect;
+pub mod diff;
+pub mod document;
+pub mod dot;
+pub mod dotted;
+pub mod enums;
+pub mod events;
+pub mod fidelity;
+pub mod flow;
+pub mod gen;
+pub mod geo;
+pub mod gexf;
+pub mod graphics;
+pub mod graphml;
+pub mod graphology;
+pub mod graphson;
+#[cfg(feature = "gzip")]
+mod gzip;
+pub mod hierarchy;
+mod input;
+pub mod intern;
+pub mod jgf;
+mod json;
+pub mod lazy;
+pub mod leda;
+pub mod lexer;
+pub mod matrix;
+pub mod matrix_market;
+pub mod matrix_market_external;
+pub mod merge;
+pub mod mermaid;
+mod node_map;
+pub mod pajek;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod plantuml;
+pub mod preview;
+pub mod query;
+pub mod registry;
+pub mod sample;
+mod scan;
+pub mod schema;
+pub mod shell;
+pub mod sql;
+pub mod stats;
+pub mod table;
+pub mod temporal;
+pub mod tgf;
+mod trace;
+pub mod tulip;
+pub mod turtle;
+pub mod typed;
+pub mod ucinet;
+pub mod units;
+#[cfg(feature = "uuid")]
+pub mod uuid;
+pub mod validate;
+#[cfg(feature = "watch")]
+pub mod watch;
+mod writer;
+mod xml;
+#[cfg(feature = "zstd")]
+mod zstd;
+
+pub use document::{parse_gml_document, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+pub use input::{
+    read_gml, read_gml_file, read_gml_file_with_options, read_gml_with_buffer,
+    read_gml_with_options, ReadOptions, Refill,
+};
+pub use writer::{to_gml, write_gml};
+
+/// Options for `parse_gml_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct GmlOptions {
+    /// The number of nodes to allocate space for.
+    pub node_capacity: usize,
+    /// The number of edges to allocate space for.
+    pub edge_capacity: usize,
+    /// Counts the node and edge blocks in a quick first pass and allocates
+    /// space for them, if no capacity is given.
+    pub prescan: bool,
+    /// Reads the input twice, adding the nodes in the first pass and the
+    /// edges in the second, so that no edges need to be buffered.
+    pub two_pass: bool,
+    /// Reverses every edge, adding it from its target to its source.
+    pub reverse: bool,
+}
+
+impl GmlOptions {
+    pub fn with_capacity(nodes: usize, edges: usize) -> GmlOptions {
+        GmlOptions {
+            node_capacity: nodes,
+            edge_capacity: edges,
+            ..GmlOptions::default()
+        }
+    }
+}
 
 pub fn parse_gml<NodeWeightFn, EdgeWeightFn, N, E>(
     s: &str,
@@ -14,89 +136,710 @@ where
     NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
     EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
 {
-    match parse_gml_to_sexp(s) {
-        Ok(sexp) => sexp_to_graph(sexp, node_weight_fn, edge_weight_fn),
-        Err(_) => Err("Invalid GML"),
+    parse_gml_with_options(s, &GmlOptions::default(), node_weight_fn, edge_weight_fn)
+}
+
+/// Parses GML like `parse_gml`, allocating the graph as given by `options`.
+pub fn parse_gml_with_options<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<Graph<N, E, Directed>, &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let mut buffers = ParseBuffers::new();
+    parse_gml_with_buffers(s, options, &mut buffers, node_weight_fn, edge_weight_fn)
+}
+
+/// Buffers for the structures that only live while a graph is built: the
+/// node id table, the edges deferred until their nodes are known, which
+/// hold edge weights of type `E`, and the keys seen in a block. They are
+/// cleared, not freed, between parses, so passing the same `ParseBuffers`
+/// to successive `parse_gml_with_buffers` calls reuses their allocated
+/// capacity. This is plain buffer reuse, not an arena allocator.
+#[derive(Debug)]
+pub struct ParseBuffers<E> {
+    node_map: NodeMap,
+    deferred: Vec<(u64, u64, E)>,
+    // empty between parses; only its allocation is kept
+    keys: Vec<&'static str>,
+}
+
+impl<E> ParseBuffers<E> {
+    pub fn new() -> ParseBuffers<E> {
+        ParseBuffers {
+            node_map: NodeMap::default(),
+            deferred: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl<E> Default for ParseBuffers<E> {
+    fn default() -> ParseBuffers<E> {
+        ParseBuffers::new()
     }
 }
 
-fn parse_gml_to_sexp(s: &str) -> Result<Sexp, ()> {
-    let iter = Tokenizer::new(s, true).with_curly_around();
-    let iter = iter.map(|t| match t {
-        Token::OpenBracket => Token::OpenCurly,
-        Token::CloseBracket => Token::CloseCurly,
-        a => a,
-    });
+// Empties `keys`, keeping its allocation. Collecting an iterator over a
+// vector into one with elements of the same layout reuses its buffer, so
+// this only changes the lifetime of the keys it may hold.
+fn recycle_keys<'a>(mut keys: Vec<&str>) -> Vec<&'a str> {
+    keys.clear();
+    keys.into_iter().map(|_| "").collect()
+}
+
+/// Parses GML like `parse_gml_with_options`, reusing `buffers` for the
+/// structures that only live while parsing.
+pub fn parse_gml_with_buffers<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
+    buffers: &mut ParseBuffers<E>,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<Graph<N, E, Directed>, &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let mut stats = ParseStats::default();
+    parse(
+        s,
+        options,
+        &GmlFilter::default(),
+        buffers,
+        &mut stats,
+        node_weight_fn,
+        edge_weight_fn,
+    )
+}
+
+/// Measurements of a `parse_gml_with_stats` call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseStats {
+    /// The bytes of input read, counting every pass over it.
+    pub bytes: usize,
+    pub nodes: usize,
+    pub edges: usize,
+    /// The largest size, in bytes, of the structures that only live while
+    /// parsing: the node id table and the edges deferred until their nodes
+    /// are known. It is computed from their capacities, so allocator
+    /// overhead is not included.
+    pub peak_intermediate_bytes: usize,
+    /// The time spent counting blocks with `GmlOptions::prescan`.
+    pub prescan_time: Duration,
+    /// The time spent reading nodes and edges, in all passes.
+    pub parse_time: Duration,
+    /// The time spent adding the deferred edges.
+    pub finish_time: Duration,
+}
+
+impl ParseStats {
+    pub fn total_time(&self) -> Duration {
+        self.prescan_time + self.parse_time + self.finish_time
+    }
+
+    fn per_sec(&self, n: usize) -> f64 {
+        let secs = self.total_time().as_secs_f64();
+        if secs > 0.0 {
+            n as f64 / secs
+        } else {
+            0.0
+        }
+    }
 
-    Sexp::parse_iter(iter)
+    /// The nodes parsed per second, over the total time, or 0 if no time
+    /// was measured.
+    pub fn nodes_per_sec(&self) -> f64 {
+        self.per_sec(self.nodes)
+    }
+
+    /// The edges parsed per second, over the total time, or 0 if no time
+    /// was measured.
+    pub fn edges_per_sec(&self) -> f64 {
+        self.per_sec(self.edges)
+    }
 }
 
-fn sexp_to_graph<NodeWeightFn, EdgeWeightFn, N, E>(
-    sexp: Sexp,
+/// Parses GML like `parse_gml_with_options` and adds the reverse of every
+/// edge, for undirected algorithms on directed input.
+///
+/// An edge whose reverse is already in the input is not added again; the
+/// first edges of both directions get the weight `combine` returns for
+/// their weights, the one from the node of the lower index first.
+/// Self-loops are kept as they are.
+pub fn parse_gml_symmetric<NodeWeightFn, EdgeWeightFn, CombineFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
     node_weight_fn: &NodeWeightFn,
     edge_weight_fn: &EdgeWeightFn,
+    combine: &CombineFn,
 ) -> Result<Graph<N, E, Directed>, &'static str>
 where
     NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
     EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+    CombineFn: Fn(&E, &E) -> E,
+    E: Clone,
 {
-    let mut map = sexp.into_map()?;
-
-    if let Some(Sexp::Map(v)) = map.remove("graph") {
-        let mut node_map: BTreeMap<u64, NodeIndex> = BTreeMap::new();
-        let mut graph = Graph::new();
-        let mut edges = Vec::new();
-
-        for (k, v) in v {
-            match k.get_str() {
-                Some("directed") => match v.get_uint() {
-                    Some(1) => {}
-                    _ => {
-                        return Err("only directed graph supported");
+    let mut graph = parse_gml_with_options(s, options, node_weight_fn, edge_weight_fn)?;
+    // the first edge of each direction
+    let mut first = HashMap::with_capacity(graph.edge_count());
+    for edge in graph.edge_indices() {
+        let (source, target) = graph.edge_endpoints(edge).unwrap();
+        first.entry((source, target)).or_insert(edge);
+    }
+    for edge in graph.edge_indices() {
+        let (source, target) = graph.edge_endpoints(edge).unwrap();
+        if source == target {
+            continue;
+        }
+        match first.get(&(target, source)) {
+            None => {
+                let weight = graph[edge].clone();
+                graph.add_edge(target, source, weight);
+            }
+            Some(&reverse) if source < target && first[&(source, target)] == edge => {
+                let weight = combine(&graph[edge], &graph[reverse]);
+                graph[reverse] = weight.clone();
+                graph[edge] = weight;
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(graph)
+}
+
+/// Parses GML like `parse_gml_with_options`, measuring the parse.
+pub fn parse_gml_with_stats<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<(Graph<N, E, Directed>, ParseStats), &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let mut buffers = ParseBuffers::new();
+    let mut stats = ParseStats::default();
+    let graph = parse(
+        s,
+        options,
+        &GmlFilter::default(),
+        &mut buffers,
+        &mut stats,
+        node_weight_fn,
+        edge_weight_fn,
+    )?;
+    Ok((graph, stats))
+}
+
+/// Which nodes and edges `parse_gml_subgraph` keeps, by their attributes.
+/// Without a predicate, all are kept.
+#[derive(Clone, Copy, Default)]
+pub struct GmlFilter<'f> {
+    pub node: Option<&'f dyn Fn(&GmlNode) -> bool>,
+    pub edge: Option<&'f dyn Fn(&GmlEdge) -> bool>,
+}
+
+/// Parses GML like `parse_gml_with_options`, keeping only the nodes and
+/// edges `filter` selects, before their weights are computed. With a node
+/// predicate, this is the subgraph induced by the kept nodes: edges with
+/// an end that is not kept are dropped, also if it is not in the input at
+/// all.
+///
+/// Only the blocks of the elements a predicate is given for are read
+/// with all their attributes, one at a time.
+pub fn parse_gml_subgraph<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
+    filter: &GmlFilter,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<Graph<N, E, Directed>, &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let mut buffers = ParseBuffers::new();
+    let mut stats = ParseStats::default();
+    parse(
+        s,
+        options,
+        filter,
+        &mut buffers,
+        &mut stats,
+        node_weight_fn,
+        edge_weight_fn,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn parse<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
+    filter: &GmlFilter,
+    buffers: &mut ParseBuffers<E>,
+    stats: &mut ParseStats,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<Graph<N, E, Directed>, &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    span!("gml.parse", bytes = s.len());
+    let result = parse_passes(
+        s,
+        options,
+        filter,
+        buffers,
+        stats,
+        node_weight_fn,
+        edge_weight_fn,
+    );
+    match result {
+        Ok(_) => event!(
+            info,
+            nodes = stats.nodes,
+            edges = stats.edges,
+            bytes_read = stats.bytes,
+            prescan_us = stats.prescan_time.as_micros() as u64,
+            parse_us = stats.parse_time.as_micros() as u64,
+            finish_us = stats.finish_time.as_micros() as u64,
+            "parsed graph"
+        ),
+        Err(e) => trace::failed("parsing the graph", &e),
+    }
+    result
+}
+
+// `parse` without its span and events.
+fn parse_passes<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    options: &GmlOptions,
+    filter: &GmlFilter,
+    buffers: &mut ParseBuffers<E>,
+    stats: &mut ParseStats,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<Graph<N, E, Directed>, &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let start = Instant::now();
+    let capacity = if options.prescan && options.node_capacity == 0 && options.edge_capacity == 0 {
+        stats.bytes += s.len();
+        let counts = count_gml(s).unwrap_or_default();
+        (counts.nodes, counts.edges)
+    } else {
+        (options.node_capacity, options.edge_capacity)
+    };
+    buffers.node_map.reset(capacity.0);
+    buffers.deferred.clear();
+    let mut keys = recycle_keys(std::mem::take(&mut buffers.keys));
+    let mut builder = GraphBuilder {
+        graph: Graph::with_capacity(capacity.0, capacity.1),
+        node_map: &mut buffers.node_map,
+        deferred: &mut buffers.deferred,
+        weights: true,
+        reverse: options.reverse,
+        filter: *filter,
+    };
+    let passes: &[Pass] = if options.two_pass {
+        &[Pass::Nodes, Pass::Edges]
+    } else {
+        &[Pass::All]
+    };
+    let parse_start = Instant::now();
+    stats.prescan_time = parse_start - start;
+    for &pass in passes {
+        stats.bytes += s.len();
+        let mut events = events::gml_events(s);
+        events.enter_graph()?;
+        builder.build(&mut events, &mut keys, pass, node_weight_fn, edge_weight_fn)?;
+        events.finish()?;
+    }
+    buffers.keys = recycle_keys(keys);
+    // the node map and the deferred edges only grow until here
+    stats.peak_intermediate_bytes = builder.node_map.heap_size()
+        + builder.deferred.capacity() * std::mem::size_of::<(u64, u64, E)>();
+    let finish_start = Instant::now();
+    stats.parse_time = finish_start - parse_start;
+    let graph = builder.finish()?;
+    stats.finish_time = finish_start.elapsed();
+    stats.nodes = graph.node_count();
+    stats.edges = graph.edge_count();
+    Ok(graph)
+}
+
+/// The number of top-level `graph` lists and of the node and edge blocks
+/// directly inside them, as counted by `count_gml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GmlCounts {
+    pub graphs: usize,
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+/// Counts the graphs, nodes and edges of `s` from its tokens, without
+/// building anything. Only the tokens and the balance of the brackets are
+/// checked, not the rest of the structure.
+pub fn count_gml(s: &str) -> Result<GmlCounts, &'static str> {
+    let mut counts = GmlCounts::default();
+    let mut depth = 0;
+    let mut in_graph = false;
+    let mut key = None;
+    for token in lexer::Lexer::new(s) {
+        match token? {
+            lexer::GmlToken::Open => {
+                match (depth, key) {
+                    (0, Some("graph")) => {
+                        counts.graphs += 1;
+                        in_graph = true;
                     }
-                },
-                Some("node") => {
-                    let node_info = v.into_map()?;
-                    if let Some(&Sexp::Atom(Atom::UInt(node_id))) = node_info.get("id") {
-                        match node_weight_fn(node_info.get("weight")) {
-                            Some(weight) => {
-                                let idx = graph.add_node(weight);
-                                if node_map.insert(node_id, idx).is_some() {
-                                    return Err("duplicate node-id");
-                                }
+                    (1, Some("node")) if in_graph => counts.nodes += 1,
+                    (1, Some("edge")) if in_graph => counts.edges += 1,
+                    _ => {}
+                }
+                depth += 1;
+            }
+            lexer::GmlToken::Close => {
+                depth -= 1;
+                if depth == 0 {
+                    in_graph = false;
+                } else if depth < 0 {
+                    return Err("unbalanced ]");
+                }
+            }
+            lexer::GmlToken::Key(k) => {
+                key = Some(k);
+                continue;
+            }
+            _ => {}
+        }
+        key = None;
+    }
+    if depth > 0 {
+        return Err("unbalanced [");
+    }
+    Ok(counts)
+}
+
+/// The structure of a GML graph, as parsed by `parse_gml_topology`.
+#[derive(Debug)]
+pub struct GmlTopology {
+    pub graph: Graph<(), (), Directed>,
+    node_map: NodeMap,
+}
+
+impl GmlTopology {
+    /// The index of the node with the GML id `id`.
+    pub fn node_index(&self, id: u64) -> Option<NodeIndex> {
+        self.node_map.get(id)
+    }
+}
+
+/// Parses the nodes and edges of a GML string like `parse_gml`, skipping all
+/// attributes other than the ids, for callers that only need the structure.
+pub fn parse_gml_topology(s: &str) -> Result<GmlTopology, &'static str> {
+    let mut node_map = NodeMap::default();
+    let mut builder = GraphBuilder {
+        graph: Graph::new(),
+        node_map: &mut node_map,
+        deferred: &mut Vec::new(),
+        weights: false,
+        reverse: false,
+        filter: GmlFilter::default(),
+    };
+    let mut events = events::gml_events(s);
+    events.enter_graph()?;
+    builder.build(
+        &mut events,
+        &mut Vec::new(),
+        Pass::All,
+        &|_| Some(()),
+        &|_| Some(()),
+    )?;
+    events.finish()?;
+    let graph = builder.finish()?;
+    Ok(GmlTopology { graph, node_map })
+}
+
+/// The `source` and `target` ids of the edges of a GML string, in document
+/// order, without building a graph. The nodes are not read, so edges may
+/// refer to ids without a node.
+pub fn parse_edges(s: &str) -> Result<Vec<(u64, u64)>, &'static str> {
+    let mut edges = Vec::new();
+    let mut events = events::gml_events(s);
+    events.enter_graph()?;
+    read_blocks(&mut events, false, false, |block| {
+        let source = block.source.ok_or("Invalid source id")?;
+        let target = block.target.ok_or("Invalid target id")?;
+        edges.push((source, target));
+        Ok(())
+    })?;
+    events.finish()?;
+    Ok(edges)
+}
+
+// The `Sexp` passed to the weight functions. Non-negative integers become
+// `Atom::UInt`, lists become `Sexp::Map`.
+fn scalar_to_sexp(scalar: GmlScalar) -> Sexp {
+    Sexp::Atom(match scalar {
+        GmlScalar::Int(i) if i >= 0 => Atom::UInt(i as u64),
+        GmlScalar::Int(i) => Atom::SInt(i),
+        GmlScalar::UInt(u) => Atom::UInt(u),
+        GmlScalar::Float(f) => Atom::Float(f),
+        GmlScalar::Str(s) => Atom::Str(s.into_owned()),
+    })
+}
+
+fn read_sexp(events: &mut GmlEvents) -> Result<Sexp, &'static str> {
+    let mut pairs = Vec::new();
+    loop {
+        let (key, value) = match events.next().ok_or("unbalanced [")?? {
+            GmlEvent::End => return Ok(Sexp::Map(pairs)),
+            GmlEvent::Value(key, scalar) => (key, scalar_to_sexp(scalar)),
+            GmlEvent::Begin(key) => (key, read_sexp(events)?),
+        };
+        pairs.push((Sexp::Atom(Atom::Str(key.to_string())), value));
+    }
+}
+
+// The `id`, `source`, `target` and `weight` of a node or edge block. Other
+// keys are skipped without being materialized.
+#[derive(Default)]
+struct Block {
+    id: Option<u64>,
+    source: Option<u64>,
+    target: Option<u64>,
+    weight: Option<Sexp>,
+}
+
+fn id(scalar: GmlScalar, error: &'static str) -> Result<u64, &'static str> {
+    scalar.get_uint().ok_or(error)
+}
+
+// `keys` is cleared and reused for every block, to reject repeated ids and
+// weights; other keys may be repeated. Without `weights`, the weight is
+// skipped like the other keys.
+fn read_block<'a>(
+    events: &mut GmlEvents<'a>,
+    keys: &mut Vec<&'a str>,
+    weights: bool,
+) -> Result<Block, &'static str> {
+    let mut block = Block::default();
+    keys.clear();
+    loop {
+        let key = match events.next().ok_or("unbalanced [")?? {
+            GmlEvent::End => return Ok(block),
+            GmlEvent::Value(key, scalar) => {
+                match key {
+                    "id" => block.id = id(scalar, "Invalid id").ok(),
+                    "source" => block.source = id(scalar, "Invalid source id").ok(),
+                    "target" => block.target = id(scalar, "Invalid target id").ok(),
+                    "weight" if weights => block.weight = Some(scalar_to_sexp(scalar)),
+                    _ => {}
+                }
+                key
+            }
+            GmlEvent::Begin("weight") if weights => {
+                block.weight = Some(read_sexp(events)?);
+                "weight"
+            }
+            GmlEvent::Begin(key) => {
+                events.skip_list()?;
+                key
+            }
+        };
+        if let "id" | "source" | "target" | "weight" = key {
+            if keys.contains(&key) {
+                return Err("duplicate key");
+            }
+            keys.push(key);
+        }
+    }
+}
+
+fn value_to_sexp(value: &GmlValue) -> Sexp {
+    match *value {
+        GmlValue::Int(i) if i >= 0 => Sexp::Atom(Atom::UInt(i as u64)),
+        GmlValue::Int(i) => Sexp::Atom(Atom::SInt(i)),
+        GmlValue::UInt(u) => Sexp::Atom(Atom::UInt(u)),
+        GmlValue::Float(f) => Sexp::Atom(Atom::Float(f)),
+        GmlValue::Str(ref s) => Sexp::Atom(Atom::Str(s.to_string())),
+        GmlValue::List(ref list) => Sexp::Map(
+            list.iter()
+                .map(|(k, v)| (Sexp::Atom(Atom::Str(k.clone())), value_to_sexp(v)))
+                .collect(),
+        ),
+    }
+}
+
+// Reads a block like `read_block` with its weight, and also returns all its
+// attributes except the `ids`.
+fn read_full_block(
+    events: &mut GmlEvents,
+    ids: &[&str],
+) -> Result<(Block, GmlAttrs), &'static str> {
+    let mut attrs = events.read_list()?;
+    let mut block = Block {
+        weight: attrs.get("weight").map(value_to_sexp),
+        ..Block::default()
+    };
+    for &key in ids {
+        let id = document::remove_attr(&mut attrs, key).and_then(|v| v.get_uint());
+        match key {
+            "id" => block.id = id,
+            "source" => block.source = id,
+            _ => block.target = id,
+        }
+    }
+    Ok((block, attrs))
+}
+
+// Reads the items of the `graph` list, passing the node blocks, if `nodes`,
+// or else the edge blocks to `block` and skipping the others. The weights
+// are read as by `read_block`.
+fn read_blocks<'a>(
+    events: &mut GmlEvents<'a>,
+    nodes: bool,
+    weights: bool,
+    mut block: impl FnMut(Block) -> Result<(), &'static str>,
+) -> Result<(), &'static str> {
+    let mut keys = Vec::new();
+    loop {
+        match events.next().ok_or("unbalanced [")?? {
+            GmlEvent::End => return Ok(()),
+            GmlEvent::Value("directed", GmlScalar::Int(1)) => {}
+            GmlEvent::Value("directed", _) | GmlEvent::Begin("directed") => {
+                return Err("only directed graph supported");
+            }
+            GmlEvent::Begin(key @ "node") | GmlEvent::Begin(key @ "edge") => {
+                if (key == "node") == nodes {
+                    block(read_block(events, &mut keys, weights)?)?;
+                } else {
+                    events.skip_list()?;
+                }
+            }
+            _ => return Err("invalid item"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pass {
+    All,
+    Nodes,
+    Edges,
+}
+
+struct GraphBuilder<'m, N, E> {
+    graph: Graph<N, E, Directed>,
+    node_map: &'m mut NodeMap,
+    // the edges after the first one that refers to a node not yet known
+    deferred: &'m mut Vec<(u64, u64, E)>,
+    // whether weights are read for the weight functions
+    weights: bool,
+    // whether edges are added from their targets to their sources
+    reverse: bool,
+    filter: GmlFilter<'m>,
+}
+
+impl<'m, N, E> GraphBuilder<'m, N, E> {
+    // Adds the nodes and edges of the `graph` list, each as soon as its
+    // block is closed. Edges are added in document order; in a single pass,
+    // once an edge refers to a node that is not yet known, it and all
+    // following edges are deferred until after the last node.
+    fn build<'a, NodeWeightFn, EdgeWeightFn>(
+        &mut self,
+        events: &mut GmlEvents<'a>,
+        keys: &mut Vec<&'a str>,
+        pass: Pass,
+        node_weight_fn: &NodeWeightFn,
+        edge_weight_fn: &EdgeWeightFn,
+    ) -> Result<(), &'static str>
+    where
+        NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+        EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+    {
+        loop {
+            match events.next().ok_or("unbalanced [")?? {
+                GmlEvent::End => return Ok(()),
+                GmlEvent::Value("directed", GmlScalar::Int(1)) => {}
+                GmlEvent::Value("directed", _) | GmlEvent::Begin("directed") => {
+                    return Err("only directed graph supported");
+                }
+                GmlEvent::Begin("node") if pass == Pass::Edges => events.skip_list()?,
+                GmlEvent::Begin("edge") if pass == Pass::Nodes => events.skip_list()?,
+                GmlEvent::Begin("node") => {
+                    let block = match self.filter.node {
+                        None => read_block(events, keys, self.weights)?,
+                        Some(keep) => {
+                            let (block, attrs) = read_full_block(events, &["id"])?;
+                            let id = block.id.ok_or("Invalid id")?;
+                            if !keep(&GmlNode { id, attrs }) {
+                                continue;
                             }
-                            None => {
-                                return Err("invalid node weight");
+                            block
+                        }
+                    };
+                    let node_id = block.id.ok_or("Invalid id")?;
+                    match node_weight_fn(block.weight.as_ref()) {
+                        Some(weight) => {
+                            let idx = self.graph.add_node(weight);
+                            if !self.node_map.insert(node_id, idx) {
+                                return Err("duplicate node-id");
                             }
                         }
-                    } else {
-                        return Err("Invalid id");
+                        None => {
+                            return Err("invalid node weight");
+                        }
                     }
                 }
-                Some("edge") => {
-                    let edge_info = v.into_map()?;
-
-                    let source =
-                        if let Some(&Sexp::Atom(Atom::UInt(source))) = edge_info.get("source") {
-                            source
-                        } else {
-                            return Err("Invalid source id");
-                        };
-
-                    let target =
-                        if let Some(&Sexp::Atom(Atom::UInt(target))) = edge_info.get("target") {
-                            target
-                        } else {
-                            return Err("Invalid target id");
-                        };
-
-                    match edge_weight_fn(edge_info.get("weight")) {
-                        Some(weight) => {
-                            edges.push((source, target, weight));
+                GmlEvent::Begin("edge") => {
+                    let block = match self.filter.edge {
+                        None => read_block(events, keys, self.weights)?,
+                        Some(keep) => {
+                            let (block, attrs) = read_full_block(events, &["source", "target"])?;
+                            let source = block.source.ok_or("Invalid source id")?;
+                            let target = block.target.ok_or("Invalid target id")?;
+                            if !keep(&GmlEdge {
+                                source,
+                                target,
+                                attrs,
+                            }) {
+                                continue;
+                            }
+                            block
                         }
+                    };
+                    let source = block.source.ok_or("Invalid source id")?;
+                    let target = block.target.ok_or("Invalid target id")?;
+                    let (source, target) = if self.reverse {
+                        (target, source)
+                    } else {
+                        (source, target)
+                    };
+                    let weight = match edge_weight_fn(block.weight.as_ref()) {
+                        Some(weight) => weight,
                         None => {
                             return Err("invalid edge weight");
                         }
+                    };
+                    if pass == Pass::All
+                        && (!self.deferred.is_empty()
+                            || self.node_map.get(source).is_none()
+                            || self.node_map.get(target).is_none())
+                    {
+                        self.deferred.push((source, target, weight));
+                    } else {
+                        self.add_edge(source, target, weight)?;
                     }
                 }
                 _ => {
@@ -104,20 +847,33 @@ where
                 }
             }
         }
+    }
 
-        for (source, target, weight) in edges {
-            let source_idx = node_map[&source];
-            let target_idx = node_map[&target];
-            graph.add_edge(source_idx, target_idx, weight);
+    fn add_edge(&mut self, source: u64, target: u64, weight: E) -> Result<(), &'static str> {
+        match (self.node_map.get(source), self.node_map.get(target)) {
+            (Some(source_idx), Some(target_idx)) => {
+                self.graph.add_edge(source_idx, target_idx, weight);
+                Ok(())
+            }
+            _ if self.filter.node.is_some() => Ok(()),
+            _ => Err("edge references unknown node"),
         }
+    }
 
-        Ok(graph)
-    } else {
-        Err("no graph given or invalid")
+    fn finish(mut self) -> Result<Graph<N, E, Directed>, &'static str> {
+        // drained in place, so that the buffer keeps its capacity
+        let mut deferred = std::mem::take(&mut *self.deferred);
+        let result = deferred
+            .drain(..)
+            .try_for_each(|(source, target, weight)| self.add_edge(source, target, weight));
+        *self.deferred = deferred;
+        result?;
+        Ok(self.graph)
     }
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_parse_gml() {
     let gml = "
     # comment
@@ -169,3 +925,248 @@ fn test_parse_gml() {
     assert_eq!(Some(&1.0), g.node_weight(NodeIndex::new(0)));
     assert_eq!(Some(&0.0), g.node_weight(NodeIndex::new(1)));
 }
+
+#[test]
+fn test_parse_gml_subgraph() {
+    let gml = "graph [ \
+               node [ id 1 type \"router\" weight 5 ] node [ id 2 type \"host\" ] \
+               edge [ source 1 target 2 ] edge [ source 3 target 1 weight 2 ] \
+               node [ id 3 type \"router\" ] edge [ source 1 target 3 weight 7 ] ]";
+    let routers =
+        |node: &GmlNode| node.attrs.get("type").and_then(GmlValue::get_str) == Some("router");
+    let heavy = |edge: &GmlEdge| edge.attrs.get("weight").and_then(GmlValue::get_int) > Some(5);
+    let mut filter = GmlFilter {
+        node: Some(&routers),
+        ..GmlFilter::default()
+    };
+    let uint = |s: Option<&Sexp>| s.and_then(Sexp::get_uint).or(Some(0));
+    for &two_pass in &[false, true] {
+        let options = GmlOptions {
+            two_pass,
+            ..GmlOptions::default()
+        };
+        let g = parse_gml_subgraph(gml, &options, &filter, &uint, &uint).unwrap();
+        assert_eq!(
+            vec![5, 0],
+            g.raw_nodes().iter().map(|n| n.weight).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![2, 7],
+            g.raw_edges().iter().map(|e| e.weight).collect::<Vec<_>>()
+        );
+    }
+    filter.edge = Some(&heavy);
+    let g = parse_gml_subgraph(gml, &GmlOptions::default(), &filter, &uint, &uint).unwrap();
+    assert_eq!(
+        vec![7],
+        g.raw_edges().iter().map(|e| e.weight).collect::<Vec<_>>()
+    );
+    filter.node = None;
+    let g = parse_gml_subgraph(gml, &GmlOptions::default(), &filter, &uint, &uint).unwrap();
+    assert_eq!((3, 1), (g.node_count(), g.edge_count()));
+}
+
+#[test]
+fn test_parse_gml_with_buffers() {
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    let mut buffers = ParseBuffers::new();
+    for gml in &[
+        "graph [ node [ id 1 ] ]",
+        "graph [ node [ id 2 ] node [ id 1 ] ]",
+    ] {
+        let options = GmlOptions::default();
+        let g = parse_gml_with_buffers(gml, &options, &mut buffers, &weight, &weight).unwrap();
+        assert_eq!(gml.matches("node").count(), g.node_count());
+    }
+
+    let gml = "graph [ node [ id 500 ] ]";
+    let options = GmlOptions::default();
+    parse_gml_with_buffers(gml, &options, &mut buffers, &weight, &weight).unwrap();
+    let size = buffers.node_map.heap_size();
+    assert!(size > 0);
+    parse_gml_with_buffers("graph [ ]", &options, &mut buffers, &weight, &weight).unwrap();
+    assert_eq!(size, buffers.node_map.heap_size());
+
+    // an edge before its nodes is deferred
+    let gml = "graph [ edge [ source 1 target 2 ] node [ id 1 ] node [ id 2 ] ]";
+    let g = parse_gml_with_buffers(gml, &options, &mut buffers, &weight, &weight).unwrap();
+    assert_eq!(1, g.edge_count());
+    let (deferred, keys) = (buffers.deferred.capacity(), buffers.keys.capacity());
+    assert!(deferred > 0 && keys > 0);
+    assert!(buffers.deferred.is_empty() && buffers.keys.is_empty());
+    parse_gml_with_buffers("graph [ ]", &options, &mut buffers, &weight, &weight).unwrap();
+    assert_eq!(deferred, buffers.deferred.capacity());
+    assert_eq!(keys, buffers.keys.capacity());
+}
+
+#[test]
+fn test_parse_edges() {
+    let gml = "graph [ directed 1 node [ id 1 ] node [ id 2 ] node [ id 3 ] \
+               edge [ source 2 target 1 weight 1 ] edge [ source 1 target 2 weight 2 ] \
+               edge [ source 2 target 3 weight 4 ] edge [ source 3 target 3 weight 8 ] ]";
+    assert_eq!(Ok(vec![(2, 1), (1, 2), (2, 3), (3, 3)]), parse_edges(gml));
+    assert_eq!(
+        Err("Invalid target id"),
+        parse_edges("graph [ edge [ source 1 ] ]")
+    );
+}
+
+#[test]
+fn test_parse_gml_symmetric() {
+    let gml = "graph [ directed 1 node [ id 1 ] node [ id 2 ] node [ id 3 ] \
+               edge [ source 2 target 1 weight 1 ] edge [ source 1 target 2 weight 2 ] \
+               edge [ source 2 target 3 weight 4 ] edge [ source 3 target 3 weight 8 ] ]";
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    let float = |s: Option<&Sexp>| s.and_then(Sexp::get_uint).map(|w| w as f64);
+    let g =
+        parse_gml_symmetric(gml, &GmlOptions::default(), &weight, &float, &|a, b| a + b).unwrap();
+    let weight_of = |a, b| {
+        let edge = g.find_edge(NodeIndex::new(a), NodeIndex::new(b)).unwrap();
+        g[edge]
+    };
+    assert_eq!(5, g.edge_count());
+    assert_eq!((3.0, 3.0), (weight_of(0, 1), weight_of(1, 0)));
+    assert_eq!((4.0, 4.0), (weight_of(1, 2), weight_of(2, 1)));
+    assert_eq!(8.0, weight_of(2, 2));
+}
+
+#[test]
+fn test_parse_gml_with_stats() {
+    let gml = "graph [ directed 1 edge [ source 7 target 5 weight [ x 1 ] ] \
+               node [ id 5 weight \"a\" ] node [ id 7 graphics [ w 2 ] ] ]";
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    let options = GmlOptions {
+        two_pass: true,
+        ..GmlOptions::default()
+    };
+    let (g, stats) = parse_gml_with_stats(gml, &options, &weight, &weight).unwrap();
+    assert_eq!(
+        (2 * gml.len(), 2, 1),
+        (stats.bytes, stats.nodes, stats.edges)
+    );
+    assert_eq!(1, g.edge_count());
+    assert!(stats.peak_intermediate_bytes > 0);
+    assert!(stats.total_time() >= stats.parse_time);
+
+    let stats = ParseStats {
+        nodes: 2,
+        edges: 1,
+        ..ParseStats::default()
+    };
+    assert_eq!(0.0, stats.nodes_per_sec());
+    assert_eq!(0.0, stats.edges_per_sec());
+}
+
+#[test]
+fn test_parse_gml_topology() {
+    let gml = "graph [ directed 1 edge [ source 7 target 5 weight [ x 1 ] ] \
+               node [ id 5 weight \"a\" ] node [ id 7 graphics [ w 2 ] ] ]";
+    let topology = parse_gml_topology(gml).unwrap();
+    assert_eq!(
+        (2, 1),
+        (topology.graph.node_count(), topology.graph.edge_count())
+    );
+    assert_eq!(Some(NodeIndex::new(1)), topology.node_index(7));
+    assert_eq!(None, topology.node_index(6));
+    assert!(topology
+        .graph
+        .find_edge(NodeIndex::new(1), NodeIndex::new(0))
+        .is_some());
+}
+
+#[test]
+fn test_parse_gml_reverse() {
+    let gml = "graph [ directed 1 edge [ source 2 target 1 ] node [ id 1 ] node [ id 2 ] ]";
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    for &two_pass in &[false, true] {
+        let options = GmlOptions {
+            two_pass,
+            reverse: true,
+            ..GmlOptions::default()
+        };
+        let g = parse_gml_with_options(gml, &options, &weight, &weight).unwrap();
+        assert!(g.find_edge(NodeIndex::new(0), NodeIndex::new(1)).is_some());
+        assert_eq!(1, g.edge_count());
+    }
+}
+
+#[test]
+fn test_parse_gml_two_pass() {
+    let gml = "graph [ directed 1 edge [ source 2 target 1 ] node [ id 1 ] node [ id 2 ] ]";
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    let options = GmlOptions {
+        two_pass: true,
+        ..GmlOptions::default()
+    };
+    let g = parse_gml_with_options(gml, &options, &weight, &weight).unwrap();
+    assert!(g.find_edge(NodeIndex::new(1), NodeIndex::new(0)).is_some());
+}
+
+#[test]
+fn test_parse_gml_prescan() {
+    let gml = "graph [ directed 1 node [ id 0 ] node [ id 1 ] edge [ source 0 target 1 ] ]";
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    let options = GmlOptions {
+        prescan: true,
+        ..GmlOptions::default()
+    };
+    let g = parse_gml_with_options(gml, &options, &weight, &weight).unwrap();
+    assert_eq!(1, g.edge_count());
+}
+
+#[test]
+fn test_count_gml() {
+    let gml = "graph [ directed 1 node [ id 0 ] node [ id 1 ] edge [ source 0 target 1 ] ]";
+    assert_eq!(
+        Ok(GmlCounts {
+            graphs: 1,
+            nodes: 2,
+            edges: 1
+        }),
+        count_gml(gml)
+    );
+    assert_eq!(
+        Ok(GmlCounts {
+            graphs: 2,
+            nodes: 1,
+            edges: 0
+        }),
+        count_gml("x [ node [ ] ] graph [ node [ a [ node [ ] ] ] ] graph [ ]")
+    );
+    assert_eq!(Err("unbalanced ]"), count_gml("graph [ ] ]"));
+    assert_eq!(Err("unbalanced ["), count_gml("graph [ node ["));
+}
+
+#[test]
+fn test_parse_gml_edge_before_node() {
+    let weight = |_: Option<&Sexp>| -> Option<()> { Some(()) };
+    let gml = "graph [ directed 1 edge [ source 2 target 1 ] node [ id 1 ] node [ id 2 ] ]";
+    let g = parse_gml(gml, &weight, &weight).unwrap();
+    assert!(g.find_edge(NodeIndex::new(1), NodeIndex::new(0)).is_some());
+    let gml = "graph [ directed 1 node [ id 1 ] edge [ source 1 target 3 ] ]";
+    assert_eq!(
+        Some("edge references unknown node"),
+        parse_gml(gml, &weight, &weight).err()
+    );
+}
+
+#[test]
+fn test_parse_gml_large_ids() {
+    let gml = "graph [ directed 1 node [ id 18446744073709551615 weight 18446744073709551615 ] \
+               node [ id 9223372036854775808 ] \
+               edge [ source 9223372036854775808 target 18446744073709551615 ] ]";
+    let uint = |s: Option<&Sexp>| s.and_then(Sexp::get_uint).or(Some(0));
+    for &two_pass in &[false, true] {
+        let options = GmlOptions {
+            two_pass,
+            ..GmlOptions::default()
+        };
+        let g = parse_gml_with_options(gml, &options, &uint, &uint).unwrap();
+        assert_eq!(Some(&u64::MAX), g.node_weight(NodeIndex::new(0)));
+        assert!(g.find_edge(NodeIndex::new(1), NodeIndex::new(0)).is_some());
+    }
+    let topology = parse_gml_topology(gml).unwrap();
+    assert_eq!(Some(NodeIndex::new(0)), topology.node_index(u64::MAX));
+    let gml = "graph [ node [ id 18446744073709551616 ] ]";
+    assert!(parse_gml(gml, &uint, &uint).is_err());
+}
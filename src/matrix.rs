@@ -0,0 +1,264 @@
+//! Adjacency matrices of petgraph graphs, for numerical code.
+//!
+//! There is deliberately no `ndarray` feature returning an `Array2`: an
+//! `ndarray` type in the public API would tie each release of this crate
+//! to one `ndarray` version. `DenseMatrix` is a row-major buffer in the
+//! layout of `Array2` instead, which
+//! `Array2::from_shape_vec(m.shape(), m.into_raw_vec())` takes over
+//! without copying.
+//!
+//! For the same reason there is no `sprs` feature returning a `CsMat`.
+//! `CsrMatrix` holds the three CSR arrays of a `CsMat`, which
+//! `CsMat::new((m.rows, m.cols), m.indptr, m.indices, m.data)` takes over
+//! without copying.
+
+use petgraph::graph::NodeIndex;
+use petgraph::{EdgeType, Graph};
+
+/// Which node each row and column of a matrix corresponds to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeOrder {
+    /// Order of the petgraph node indices.
+    Index,
+    /// An explicit order, e.g. from `order_by_key`. Nodes not listed are
+    /// left out of the matrix.
+    Custom(Vec<NodeIndex>),
+}
+
+/// Orders the nodes of `graph` by `key`, e.g. by GML id with
+/// `order_by_key(&g, |n: &GmlNode| n.id)`.
+pub fn order_by_key<N, E, Ty, K, F>(graph: &Graph<N, E, Ty>, key: F) -> NodeOrder
+where
+    Ty: EdgeType,
+    K: Ord,
+    F: Fn(&N) -> K,
+{
+    let mut order: Vec<NodeIndex> = graph.node_indices().collect();
+    order.sort_by_key(|&idx| key(&graph[idx]));
+    NodeOrder::Custom(order)
+}
+
+fn positions<N, E, Ty: EdgeType>(
+    graph: &Graph<N, E, Ty>,
+    order: NodeOrder,
+) -> (Vec<NodeIndex>, Vec<Option<usize>>) {
+    let order = match order {
+        NodeOrder::Index => graph.node_indices().collect(),
+        NodeOrder::Custom(order) => order,
+    };
+    let mut position = vec![None; graph.node_count()];
+    for (i, idx) in order.iter().enumerate() {
+        position[idx.index()] = Some(i);
+    }
+    (order, position)
+}
+
+/// A dense row-major matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f64>,
+    /// The node of each row (and column).
+    pub nodes: Vec<NodeIndex>,
+}
+
+impl DenseMatrix {
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+
+    /// The number of rows and columns.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// The entries in row-major order, as `Array2::from_shape_vec` takes
+    /// them.
+    pub fn into_raw_vec(self) -> Vec<f64> {
+        self.data
+    }
+}
+
+/// Builds the dense adjacency matrix of `graph`. The entry for `(u, v)` is
+/// the sum of `weight_fn` over all edges from `u` to `v`. For undirected
+/// graphs the matrix is symmetric.
+pub fn to_adjacency_matrix<N, E, Ty, F>(
+    graph: &Graph<N, E, Ty>,
+    weight_fn: F,
+    order: NodeOrder,
+) -> DenseMatrix
+where
+    Ty: EdgeType,
+    F: Fn(&E) -> f64,
+{
+    let (nodes, position) = positions(graph, order);
+    let n = nodes.len();
+    let mut data = vec![0.0; n * n];
+    for edge in graph.raw_edges() {
+        let row = position[edge.source().index()];
+        let col = position[edge.target().index()];
+        if let (Some(row), Some(col)) = (row, col) {
+            let weight = weight_fn(&edge.weight);
+            data[row * n + col] += weight;
+            if !graph.is_directed() && row != col {
+                data[col * n + row] += weight;
+            }
+        }
+    }
+    DenseMatrix {
+        rows: n,
+        cols: n,
+        data,
+        nodes,
+    }
+}
+
+/// A sparse matrix in compressed sparse row (CSR) layout: the column
+/// indices and values of row `i` are `indices[indptr[i]..indptr[i + 1]]`
+/// and `data[indptr[i]..indptr[i + 1]]`, sorted by column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub data: Vec<f64>,
+    /// The node of each row (and column).
+    pub nodes: Vec<NodeIndex>,
+}
+
+impl CsrMatrix {
+    /// The number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        let range = self.indptr[row]..self.indptr[row + 1];
+        self.indices[range.clone()]
+            .binary_search(&col)
+            .ok()
+            .map(|i| self.data[range.start + i])
+    }
+
+    /// The stored entries as `(row, col, value)` triplets, row by row, as
+    /// `TriMat::from_triplets` of `sprs` takes them after unzipping.
+    pub fn triplets(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        (0..self.rows).flat_map(move |row| {
+            let range = self.indptr[row]..self.indptr[row + 1];
+            range.map(move |i| (row, self.indices[i], self.data[i]))
+        })
+    }
+
+    /// The `indptr`, `indices` and `data` buffers, in the order of
+    /// `CsMat::new`.
+    pub fn into_raw_parts(self) -> (Vec<usize>, Vec<usize>, Vec<f64>) {
+        (self.indptr, self.indices, self.data)
+    }
+}
+
+/// Builds the sparse adjacency matrix of `graph`, like
+/// `to_adjacency_matrix` but storing only the entries for existing edges.
+/// Parallel edges are summed into one entry.
+pub fn to_sparse_matrix<N, E, Ty, F>(
+    graph: &Graph<N, E, Ty>,
+    weight_fn: F,
+    order: NodeOrder,
+) -> CsrMatrix
+where
+    Ty: EdgeType,
+    F: Fn(&E) -> f64,
+{
+    let (nodes, position) = positions(graph, order);
+    let n = nodes.len();
+
+    // collect in coordinate (COO) form, then sort into rows
+    let mut triplets = Vec::with_capacity(graph.edge_count() * 2);
+    for edge in graph.raw_edges() {
+        let row = position[edge.source().index()];
+        let col = position[edge.target().index()];
+        if let (Some(row), Some(col)) = (row, col) {
+            let weight = weight_fn(&edge.weight);
+            triplets.push((row, col, weight));
+            if !graph.is_directed() && row != col {
+                triplets.push((col, row, weight));
+            }
+        }
+    }
+    triplets.sort_by_key(|&(row, col, _)| (row, col));
+
+    let mut indptr = vec![0; n + 1];
+    let mut indices: Vec<usize> = Vec::with_capacity(triplets.len());
+    let mut data: Vec<f64> = Vec::with_capacity(triplets.len());
+    let mut last = None;
+    for (row, col, weight) in triplets {
+        if last == Some((row, col)) {
+            *data.last_mut().unwrap() += weight;
+            continue;
+        }
+        last = Some((row, col));
+        indptr[row + 1] += 1;
+        indices.push(col);
+        data.push(weight);
+    }
+    for i in 0..n {
+        indptr[i + 1] += indptr[i];
+    }
+
+    CsrMatrix {
+        rows: n,
+        cols: n,
+        indptr,
+        indices,
+        data,
+        nodes,
+    }
+}
+
+#[test]
+fn test_to_adjacency_matrix() {
+    let gml = "graph [ directed 1 node [ id 9 ] node [ id 3 ] \
+               edge [ source 9 target 3 weight 2.0 ] edge [ source 9 target 3 weight 0.5 ] \
+               edge [ source 3 target 3 weight 1.0 ] ]";
+    let g = crate::parse_gml_document(gml)
+        .unwrap()
+        .into_graph()
+        .unwrap();
+    let weight = |e: &crate::GmlEdge| e.attrs["weight"].get_float().unwrap();
+
+    let m = to_adjacency_matrix(&g, weight, NodeOrder::Index);
+    assert_eq!(vec![0.0, 2.5, 0.0, 1.0], m.data);
+
+    let m = to_adjacency_matrix(&g, weight, order_by_key(&g, |n| n.id));
+    assert_eq!(vec![NodeIndex::new(1), NodeIndex::new(0)], m.nodes);
+    assert_eq!(vec![1.0, 0.0, 2.5, 0.0], m.data);
+    assert_eq!(2.5, m.get(1, 0));
+    assert_eq!((2, 2), m.shape());
+    assert_eq!(vec![1.0, 0.0, 2.5, 0.0], m.into_raw_vec());
+}
+
+#[test]
+fn test_to_sparse_matrix() {
+    let mut g = Graph::new_undirected();
+    let a = g.add_node(());
+    let b = g.add_node(());
+    let c = g.add_node(());
+    g.add_edge(a, c, 2.0);
+    g.add_edge(c, a, 1.0);
+    g.add_edge(b, b, 4.0);
+
+    let m = to_sparse_matrix(&g, |&w| w, NodeOrder::Index);
+    assert_eq!(vec![0, 1, 2, 3], m.indptr);
+    assert_eq!(vec![2, 1, 0], m.indices);
+    assert_eq!(vec![3.0, 4.0, 3.0], m.data);
+    assert_eq!(Some(3.0), m.get(2, 0));
+    assert_eq!(None, m.get(0, 1));
+    assert_eq!(
+        vec![(0, 2, 3.0), (1, 1, 4.0), (2, 0, 3.0)],
+        m.triplets().collect::<Vec<_>>()
+    );
+    let (indptr, indices, data) = m.into_raw_parts();
+    assert_eq!((vec![0, 1, 2, 3], vec![2, 1, 0]), (indptr, indices));
+    assert_eq!(vec![3.0, 4.0, 3.0], data);
+}
@@ -0,0 +1,91 @@
+//! Matrix Market export.
+
+use crate::document::{GmlDocument, GmlValue};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Renders the adjacency matrix of `doc` in Matrix Market coordinate
+/// format. See `write_matrix_market`, which also fails for an edge that
+/// references an unknown node.
+pub fn to_matrix_market(doc: &GmlDocument, weight_key: Option<&str>) -> io::Result<String> {
+    let mut buf = Vec::new();
+    write_matrix_market(doc, weight_key, &mut buf)?;
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+/// Writes the adjacency matrix of `doc` in Matrix Market coordinate format.
+///
+/// Row and column `i` correspond to the `i`-th node of the document
+/// (counting from 1). With a `weight_key`, the matrix is `real` and each
+/// entry is the edge's value for that key, or 1 if it has none. Without, a
+/// `pattern` matrix is written. Undirected documents are written as
+/// `symmetric`, with every edge in the lower triangle.
+pub fn write_matrix_market<W: Write>(
+    doc: &GmlDocument,
+    weight_key: Option<&str>,
+    w: &mut W,
+) -> io::Result<()> {
+    let index: BTreeMap<u64, usize> = doc
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id, i + 1))
+        .collect();
+
+    let field = if weight_key.is_some() {
+        "real"
+    } else {
+        "pattern"
+    };
+    let symmetry = if doc.directed { "general" } else { "symmetric" };
+    writeln!(w, "%%MatrixMarket matrix coordinate {} {}", field, symmetry)?;
+    writeln!(
+        w,
+        "{} {} {}",
+        doc.nodes.len(),
+        doc.nodes.len(),
+        doc.edges.len()
+    )?;
+
+    for edge in &doc.edges {
+        let (mut row, mut col) = match (index.get(&edge.source), index.get(&edge.target)) {
+            (Some(&s), Some(&t)) => (s, t),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "edge references unknown node",
+                ))
+            }
+        };
+        if !doc.directed && row < col {
+            std::mem::swap(&mut row, &mut col);
+        }
+        match weight_key {
+            Some(key) => {
+                let weight = edge.attrs.get(key).and_then(GmlValue::get_float);
+                writeln!(w, "{} {} {:?}", row, col, weight.unwrap_or(1.0))?;
+            }
+            None => writeln!(w, "{} {}", row, col)?,
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_to_matrix_market() {
+    let gml = "graph [ directed 0 node [ id 5 ] node [ id 7 ] node [ id 9 ] \
+               edge [ source 5 target 7 weight 2.5 ] edge [ source 9 target 7 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    assert_eq!(
+        "%%MatrixMarket matrix coordinate real symmetric\n3 3 2\n2 1 2.5\n3 2 1.0\n",
+        to_matrix_market(&doc, Some("weight")).unwrap()
+    );
+    assert_eq!(
+        "%%MatrixMarket matrix coordinate pattern symmetric\n3 3 2\n2 1\n3 2\n",
+        to_matrix_market(&doc, None).unwrap()
+    );
+
+    let dangling = crate::parse_gml_document("graph [ node [ id 1 ] edge [ source 1 target 2 ] ]");
+    let err = to_matrix_market(&dangling.unwrap(), None).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+}
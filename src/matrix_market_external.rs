@@ -0,0 +1,573 @@
+//! External-memory conversion to Matrix Market.
+//!
+//! `write_matrix_market_external` converts GML to Matrix Market like
+//! `write_matrix_market`, without building a document. The input is read a
+//! piece at a time. The node id table and the edges are kept in buffers of
+//! bounded size; full buffers are sorted and spilled to temporary files,
+//! which are merged to resolve the node ids of the edges and to restore
+//! their document order. This converts graphs much larger than the
+//! available memory.
+//!
+//! This is the only external-memory mode, and only for Matrix Market
+//! output, whose rows need nothing but the node positions and the edge
+//! weights. There is no spill-backed `parse_gml`: a graph larger than the
+//! memory would not fit into the petgraph `Graph` it builds either, so
+//! spilling its intermediate structures would not help. Other output
+//! formats would each need their own spilled representation and are not
+//! supported.
+
+use crate::channel::OwnedGmlEvent;
+use crate::events::{GmlEvent, GmlScalar};
+use crate::lexer::{GmlToken, Lexer};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Options for `write_matrix_market_external`.
+#[derive(Debug, Clone)]
+pub struct ExternalOptions {
+    /// The memory, in bytes, for buffered nodes and edges. Defaults to
+    /// 256 MiB.
+    pub memory_budget: usize,
+    /// The directory for the temporary files. Defaults to the system's
+    /// temporary directory.
+    pub temp_dir: Option<PathBuf>,
+    /// The bytes read from the input at a time. Defaults to 64 KiB.
+    pub read_size: usize,
+}
+
+impl Default for ExternalOptions {
+    fn default() -> ExternalOptions {
+        ExternalOptions {
+            memory_budget: 256 << 20,
+            temp_dir: None,
+            read_size: 64 << 10,
+        }
+    }
+}
+
+// A node `[id, index, 0, 0]` or an edge, sorted by its first column.
+type Record = [u64; 4];
+
+const RECORD_SIZE: usize = 32;
+
+fn invalid(error: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+// A file which is removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(dir: &Path) -> io::Result<(TempFile, File)> {
+        let n = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("gml-{}-{}.run", std::process::id(), n));
+        let file = File::create(&path)?;
+        Ok((TempFile(path), file))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// Sorts records, spilling sorted runs to disk whenever the buffer is full.
+struct Sorter {
+    buf: Vec<Record>,
+    capacity: usize,
+    runs: Vec<TempFile>,
+    dir: PathBuf,
+}
+
+impl Sorter {
+    fn new(budget: usize, dir: &Path) -> Sorter {
+        Sorter {
+            buf: Vec::new(),
+            capacity: (budget / RECORD_SIZE).max(1),
+            runs: Vec::new(),
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn push(&mut self, record: Record) -> io::Result<()> {
+        self.buf.push(record);
+        if self.buf.len() >= self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buf.sort_unstable();
+        let (run, file) = TempFile::create(&self.dir)?;
+        let mut w = BufWriter::new(file);
+        for record in self.buf.drain(..) {
+            for col in &record {
+                w.write_all(&col.to_le_bytes())?;
+            }
+        }
+        w.flush()?;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Sorted {
+        self.buf.sort_unstable();
+        Sorted {
+            runs: self.runs,
+            rest: self.buf,
+        }
+    }
+}
+
+// The sorted runs of a `Sorter` and its last, unspilled records.
+struct Sorted {
+    runs: Vec<TempFile>,
+    rest: Vec<Record>,
+}
+
+impl Sorted {
+    // Merges the runs, which can be done more than once.
+    fn iter(&self) -> io::Result<Merge<'_>> {
+        let mut merge = Merge {
+            readers: Vec::new(),
+            rest: &self.rest,
+            heap: BinaryHeap::new(),
+        };
+        for run in &self.runs {
+            merge.readers.push(BufReader::new(File::open(&run.0)?));
+        }
+        for i in 0..=merge.readers.len() {
+            if let Some(record) = merge.read(i)? {
+                merge.heap.push(Reverse((record, i)));
+            }
+        }
+        Ok(merge)
+    }
+}
+
+struct Merge<'a> {
+    readers: Vec<BufReader<File>>,
+    // the records after those in `readers`
+    rest: &'a [Record],
+    heap: BinaryHeap<Reverse<(Record, usize)>>,
+}
+
+impl<'a> Merge<'a> {
+    fn read(&mut self, i: usize) -> io::Result<Option<Record>> {
+        let reader = match self.readers.get_mut(i) {
+            Some(reader) => reader,
+            None => {
+                let (&first, rest) = match self.rest.split_first() {
+                    Some(split) => split,
+                    None => return Ok(None),
+                };
+                self.rest = rest;
+                return Ok(Some(first));
+            }
+        };
+        let mut bytes = [0; RECORD_SIZE];
+        match reader.read_exact(&mut bytes) {
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let mut record = [0; 4];
+        for (col, chunk) in record.iter_mut().zip(bytes.chunks(8)) {
+            *col = u64::from_le_bytes([
+                chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7],
+            ]);
+        }
+        Ok(Some(record))
+    }
+
+    fn next(&mut self) -> io::Result<Option<Record>> {
+        let Reverse((record, i)) = match self.heap.pop() {
+            Some(top) => top,
+            None => return Ok(None),
+        };
+        if let Some(next) = self.read(i)? {
+            self.heap.push(Reverse((next, i)));
+        }
+        Ok(Some(record))
+    }
+}
+
+fn check_unique(nodes: &Sorted) -> io::Result<()> {
+    let mut merge = nodes.iter()?;
+    let mut last = None;
+    while let Some(node) = merge.next()? {
+        if last == Some(node[0]) {
+            return Err(invalid("duplicate node-id"));
+        }
+        last = Some(node[0]);
+    }
+    Ok(())
+}
+
+// Replaces the node id in the first column of every edge by the index of the
+// node, pushing `f(edge, index)` to `out`.
+fn join<F>(nodes: &Sorted, edges: &Sorted, out: &mut Sorter, f: F) -> io::Result<()>
+where
+    F: Fn(Record, u64) -> Record,
+{
+    let mut nodes = nodes.iter()?;
+    let mut node = nodes.next()?;
+    let mut edges = edges.iter()?;
+    while let Some(edge) = edges.next()? {
+        while node.is_some_and(|n| n[0] < edge[0]) {
+            node = nodes.next()?;
+        }
+        match node {
+            Some(n) if n[0] == edge[0] => out.push(f(edge, n[1]))?,
+            _ => return Err(invalid("edge references unknown node")),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Scan {
+    Text,
+    Str,
+    Comment,
+}
+
+// The events of the GML read from `reader`, lexed a piece at a time. Pieces
+// end at line breaks outside strings and comments, so that no token is
+// split; a key and its value may be in different pieces.
+struct StreamEvents<R> {
+    reader: R,
+    read_size: usize,
+    // the input after the last piece
+    rest: Vec<u8>,
+    // the bytes of `rest` scanned, where `scan` holds,
+    scanned: usize,
+    scan: Scan,
+    // and the end of the last line break outside strings and comments
+    cut: usize,
+    eof: bool,
+    events: VecDeque<OwnedGmlEvent>,
+    // the key whose value is in the next token
+    key: Option<String>,
+    // the nesting depth after the last event lexed, and after the last one
+    // returned
+    lexed_depth: usize,
+    depth: usize,
+}
+
+impl<R: Read> StreamEvents<R> {
+    fn new(reader: R, read_size: usize) -> StreamEvents<R> {
+        StreamEvents {
+            reader,
+            read_size: read_size.max(1),
+            rest: Vec::new(),
+            scanned: 0,
+            scan: Scan::Text,
+            cut: 0,
+            eof: false,
+            events: VecDeque::new(),
+            key: None,
+            lexed_depth: 0,
+            depth: 0,
+        }
+    }
+
+    fn next(&mut self) -> io::Result<Option<OwnedGmlEvent>> {
+        while self.events.is_empty() {
+            if self.eof {
+                return match (&self.key, self.lexed_depth) {
+                    (Some(_), _) => Err(invalid("missing value")),
+                    (None, 0) => Ok(None),
+                    (None, _) => Err(invalid("unbalanced [")),
+                };
+            }
+            self.fill()?;
+        }
+        let event = self.events.pop_front();
+        match event {
+            Some(OwnedGmlEvent::Begin(_)) => self.depth += 1,
+            Some(OwnedGmlEvent::End) => self.depth -= 1,
+            _ => {}
+        }
+        Ok(event)
+    }
+
+    // Reads from the input and lexes the complete lines read.
+    fn fill(&mut self) -> io::Result<()> {
+        let start = self.rest.len();
+        self.rest.resize(start + self.read_size, 0);
+        let n = loop {
+            match self.reader.read(&mut self.rest[start..]) {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+        self.rest.truncate(start + n);
+        self.eof = n == 0;
+        for (i, &b) in self.rest.iter().enumerate().skip(self.scanned) {
+            self.scan = match (self.scan, b) {
+                (Scan::Text, b'"') => Scan::Str,
+                (Scan::Text, b'#') => Scan::Comment,
+                (Scan::Str, b'"') => Scan::Text,
+                (Scan::Text, b'\n') | (Scan::Comment, b'\n') => {
+                    self.cut = i + 1;
+                    Scan::Text
+                }
+                (scan, _) => scan,
+            };
+        }
+        self.scanned = self.rest.len();
+        let end = if self.eof { self.rest.len() } else { self.cut };
+        if end == 0 {
+            return Ok(());
+        }
+        let piece: Vec<u8> = self.rest.drain(..end).collect();
+        self.scanned -= end;
+        self.cut = 0;
+        let piece = std::str::from_utf8(&piece).map_err(|_| invalid("invalid UTF-8"))?;
+        for token in Lexer::new(piece) {
+            self.token(token.map_err(invalid)?)?;
+        }
+        Ok(())
+    }
+
+    // Turns the tokens into events like `GmlEvents`.
+    fn token(&mut self, token: GmlToken) -> io::Result<()> {
+        let (key, token) = match (self.key.take(), token) {
+            (None, GmlToken::Key(key)) => {
+                self.key = Some(key.to_string());
+                return Ok(());
+            }
+            (None, GmlToken::Close) if self.lexed_depth > 0 => {
+                self.lexed_depth -= 1;
+                self.events.push_back(OwnedGmlEvent::End);
+                return Ok(());
+            }
+            (None, GmlToken::Close) => return Err(invalid("unbalanced ]")),
+            (None, _) => return Err(invalid("expected key")),
+            (Some(key), token) => (key, token),
+        };
+        let scalar = match token {
+            GmlToken::Open => {
+                self.lexed_depth += 1;
+                self.events.push_back(OwnedGmlEvent::Begin(key));
+                return Ok(());
+            }
+            GmlToken::Int(i) => GmlScalar::Int(i),
+            GmlToken::UInt(u) => GmlScalar::UInt(u),
+            GmlToken::Float(f) => GmlScalar::Float(f),
+            GmlToken::Str(s) => GmlScalar::Str(Cow::Owned(s.into_owned())),
+            GmlToken::Key("INF") => GmlScalar::Float(f64::INFINITY),
+            GmlToken::Key("NAN") => GmlScalar::Float(f64::NAN),
+            _ => return Err(invalid("expected value")),
+        };
+        self.events.push_back(OwnedGmlEvent::Value(key, scalar));
+        Ok(())
+    }
+
+    fn event(&mut self) -> io::Result<OwnedGmlEvent> {
+        self.next()?.ok_or_else(|| invalid("unbalanced ["))
+    }
+
+    // Advances into the top-level `graph` list, like `GmlEvents::enter_graph`.
+    fn enter_graph(&mut self) -> io::Result<()> {
+        loop {
+            match self.next()? {
+                None => return Err(invalid("no graph given or invalid")),
+                Some(OwnedGmlEvent::Begin(key)) if key == "graph" => return Ok(()),
+                Some(OwnedGmlEvent::Begin(_)) => self.skip_list()?,
+                Some(_) => {}
+            }
+        }
+    }
+
+    // Checks the top-level keys after the `graph` list.
+    fn finish(mut self) -> io::Result<()> {
+        while let Some(event) = self.next()? {
+            match event {
+                OwnedGmlEvent::Begin(key) if key == "graph" => {
+                    return Err(invalid("duplicate key"))
+                }
+                OwnedGmlEvent::Begin(_) => self.skip_list()?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_list(&mut self) -> io::Result<()> {
+        let depth = self.depth;
+        while self.depth >= depth {
+            self.event()?;
+        }
+        Ok(())
+    }
+}
+
+fn id(scalar: &GmlScalar, error: &'static str) -> io::Result<u64> {
+    scalar.get_uint().ok_or_else(|| invalid(error))
+}
+
+// Reads a node or edge block up to its `End`, returning the ids under `keys`
+// and the value under `weight_key` as a float.
+fn read_block<R: Read>(
+    events: &mut StreamEvents<R>,
+    keys: &[(&str, &'static str)],
+    weight_key: Option<&str>,
+) -> io::Result<([u64; 2], Option<f64>)> {
+    let mut ids = [None; 2];
+    let mut weight = None;
+    loop {
+        match events.event()? {
+            OwnedGmlEvent::End => break,
+            OwnedGmlEvent::Value(key, scalar) => {
+                let key = &key[..];
+                if let Some(k) = keys.iter().position(|&(k, _)| k == key) {
+                    if ids[k].is_some() {
+                        return Err(invalid("duplicate key"));
+                    }
+                    ids[k] = Some(id(&scalar, keys[k].1)?);
+                }
+                if Some(key) == weight_key {
+                    weight = scalar.into_value().get_float();
+                }
+            }
+            OwnedGmlEvent::Begin(_) => events.skip_list()?,
+        }
+    }
+    let mut out = [0; 2];
+    for (k, &(_, error)) in keys.iter().enumerate() {
+        out[k] = ids[k].ok_or_else(|| invalid(error))?;
+    }
+    Ok((out, weight))
+}
+
+/// Writes the GML read from `reader` in Matrix Market coordinate format,
+/// like `write_matrix_market` does for its document, keeping at most
+/// `options.memory_budget` bytes of nodes and edges in memory.
+///
+/// Errors in the input are returned as `io::ErrorKind::InvalidData`, as are
+/// duplicate node ids.
+pub fn write_matrix_market_external<R: Read, W: Write>(
+    reader: R,
+    weight_key: Option<&str>,
+    options: &ExternalOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    let dir = options.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+    let budget = options.memory_budget / 2;
+    let mut nodes = Sorter::new(budget, &dir);
+    let mut edges = Sorter::new(budget, &dir);
+    let (mut node_count, mut edge_count) = (0, 0);
+    let mut directed = false;
+
+    let mut events = StreamEvents::new(reader, options.read_size);
+    events.enter_graph()?;
+    while let Some(event) = events.next()? {
+        match event.as_event() {
+            GmlEvent::End => break,
+            GmlEvent::Value("directed", GmlScalar::Int(0)) => directed = false,
+            GmlEvent::Value("directed", GmlScalar::Int(1)) => directed = true,
+            GmlEvent::Value("directed", _) | GmlEvent::Begin("directed") => {
+                return Err(invalid("invalid directed value"));
+            }
+            GmlEvent::Begin("node") => {
+                let ([id, _], _) = read_block(&mut events, &[("id", "Invalid id")], None)?;
+                nodes.push([id, node_count, 0, 0])?;
+                node_count += 1;
+            }
+            GmlEvent::Begin("edge") => {
+                const KEYS: &[(&str, &str)] = &[
+                    ("source", "Invalid source id"),
+                    ("target", "Invalid target id"),
+                ];
+                let ([source, target], weight) = read_block(&mut events, KEYS, weight_key)?;
+                let weight = weight.unwrap_or(1.0).to_bits();
+                edges.push([source, target, edge_count, weight])?;
+                edge_count += 1;
+            }
+            GmlEvent::Begin(_) => events.skip_list()?,
+            GmlEvent::Value(..) => {}
+        }
+    }
+    events.finish()?;
+
+    let nodes = nodes.finish();
+    check_unique(&nodes)?;
+    let mut by_target = Sorter::new(budget, &dir);
+    join(&nodes, &edges.finish(), &mut by_target, |e, source| {
+        [e[1], source, e[2], e[3]]
+    })?;
+    let mut by_seq = Sorter::new(budget, &dir);
+    join(&nodes, &by_target.finish(), &mut by_seq, |e, target| {
+        [e[2], e[1], target, e[3]]
+    })?;
+
+    let field = if weight_key.is_some() {
+        "real"
+    } else {
+        "pattern"
+    };
+    let symmetry = if directed { "general" } else { "symmetric" };
+    writeln!(w, "%%MatrixMarket matrix coordinate {} {}", field, symmetry)?;
+    writeln!(w, "{} {} {}", node_count, node_count, edge_count)?;
+    let by_seq = by_seq.finish();
+    let mut entries = by_seq.iter()?;
+    while let Some([_, source, target, weight]) = entries.next()? {
+        let (mut row, mut col) = (source + 1, target + 1);
+        if !directed && row < col {
+            std::mem::swap(&mut row, &mut col);
+        }
+        match weight_key {
+            Some(_) => writeln!(w, "{} {} {:?}", row, col, f64::from_bits(weight))?,
+            None => writeln!(w, "{} {}", row, col)?,
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_write_matrix_market_external() {
+    let gml = "graph [ directed 0 edge [ source\n9 target 5 ] # a \"comment\n\
+               node [ id 5 label \"two\nlines ] #\" ] node [ id 7 ]\n\
+               node [ id 9 graphics [ x 1 ] ] edge [ source 5 target 7 weight\n2.5 ]\n\
+               edge [ source 7 target 7 ] ]\n";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let dir = std::env::temp_dir().join(format!("gml-external-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for &(budget, read_size) in &[(RECORD_SIZE, 1), (RECORD_SIZE, 7), (1 << 20, 1 << 16)] {
+        let options = ExternalOptions {
+            memory_budget: budget,
+            temp_dir: Some(dir.clone()),
+            read_size,
+        };
+        for &weight_key in &[None, Some("weight")] {
+            let mut buf = Vec::new();
+            write_matrix_market_external(gml.as_bytes(), weight_key, &options, &mut buf).unwrap();
+            assert_eq!(
+                crate::matrix_market::to_matrix_market(&doc, weight_key).unwrap(),
+                String::from_utf8(buf).unwrap()
+            );
+        }
+    }
+    assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+    fs::remove_dir(&dir).unwrap();
+
+    let options = ExternalOptions::default();
+    let gml = "graph [ node [ id 1 ] node [ id 1 ] ]";
+    let err =
+        write_matrix_market_external(gml.as_bytes(), None, &options, &mut io::sink()).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    let gml = "graph [ node [ id 1 ] edge [ source 1 target 2 ] ]";
+    let err =
+        write_matrix_market_external(gml.as_bytes(), None, &options, &mut io::sink()).unwrap_err();
+    assert_eq!("edge references unknown node", err.to_string());
+}
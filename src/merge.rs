@@ -0,0 +1,168 @@
+//! Merging several GML documents into one.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use std::collections::HashMap;
+
+/// Which attribute wins when merged elements both have it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precedence {
+    /// The value of the earlier document.
+    First,
+    /// The value of the later document.
+    Last,
+}
+
+/// What `merge` does with a node whose id an earlier document already has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Fails with "duplicate node-id".
+    Error,
+    /// Gives the node an id no document has, and its edges with it.
+    Remap,
+    /// Merges the node into the earlier one. Nested lists are merged key by
+    /// key; other attributes both have are taken as the `Precedence` says.
+    MergeAttributes(Precedence),
+}
+
+/// Merges `other` into `attrs`.
+fn merge_attrs(attrs: &mut GmlAttrs, other: &GmlAttrs, precedence: Precedence) {
+    for (key, value) in other {
+        match (attrs.get_mut(key), value) {
+            (Some(GmlValue::List(inner)), GmlValue::List(other)) => {
+                merge_attrs(inner, other, precedence)
+            }
+            (Some(old), value) => {
+                if precedence == Precedence::Last {
+                    *old = value.clone();
+                }
+            }
+            (None, value) => {
+                attrs.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Merges `docs` into one document with the nodes and edges of all, in
+/// order. Node ids of different documents collide as `policy` says; edges
+/// are all kept, so edges in several documents become parallel edges.
+/// Graph attributes are merged like node attributes, with the precedence
+/// of `MergeAttributes` or else `First`. The documents must all be
+/// directed or all undirected.
+pub fn merge(docs: &[GmlDocument], policy: MergePolicy) -> Result<GmlDocument, &'static str> {
+    let mut merged = GmlDocument {
+        directed: docs.first().is_some_and(|doc| doc.directed),
+        ..GmlDocument::default()
+    };
+    if docs.iter().any(|doc| doc.directed != merged.directed) {
+        return Err("documents are not all directed or all undirected");
+    }
+    let precedence = match policy {
+        MergePolicy::MergeAttributes(precedence) => precedence,
+        _ => Precedence::First,
+    };
+    let mut next_id = docs
+        .iter()
+        .flat_map(|doc| doc.nodes.iter().map(|node| node.id))
+        .max()
+        .map_or(0, |id| id + 1);
+    // The index in `merged.nodes` of the first node of each id of the
+    // documents before the current one.
+    let mut index: HashMap<u64, usize> = HashMap::new();
+
+    for doc in docs {
+        merge_attrs(&mut merged.attrs, &doc.attrs, precedence);
+        let mut added = HashMap::new();
+        let mut ids = HashMap::new();
+        for node in &doc.nodes {
+            let i = match index.get(&node.id) {
+                Some(&i) => i,
+                None => {
+                    added.entry(node.id).or_insert(merged.nodes.len());
+                    merged.nodes.push(node.clone());
+                    continue;
+                }
+            };
+            match policy {
+                MergePolicy::Error => return Err("duplicate node-id"),
+                MergePolicy::Remap => {
+                    let id = *ids.entry(node.id).or_insert_with(|| {
+                        next_id += 1;
+                        next_id - 1
+                    });
+                    added.entry(id).or_insert(merged.nodes.len());
+                    let mut node = node.clone();
+                    node.id = id;
+                    merged.nodes.push(node);
+                }
+                MergePolicy::MergeAttributes(precedence) => {
+                    merge_attrs(&mut merged.nodes[i].attrs, &node.attrs, precedence)
+                }
+            }
+        }
+        for edge in &doc.edges {
+            let mut edge = edge.clone();
+            edge.source = ids.get(&edge.source).copied().unwrap_or(edge.source);
+            edge.target = ids.get(&edge.target).copied().unwrap_or(edge.target);
+            merged.edges.push(edge);
+        }
+        index.extend(added);
+    }
+    Ok(merged)
+}
+
+#[test]
+fn test_merge() {
+    use crate::document::parse_gml_document;
+
+    let a = parse_gml_document(
+        "graph [ label \"north\" node [ id 1 label \"a\" graphics [ x 1 ] ] node [ id 2 ]
+           edge [ source 1 target 2 ] ]",
+    )
+    .unwrap();
+    let b = parse_gml_document(
+        "graph [ label \"south\" version 2 node [ id 2 label \"b\" ]
+           node [ id 1 label \"c\" graphics [ y 2 ] ] node [ id 5 ]
+           edge [ source 1 target 5 ] ]",
+    )
+    .unwrap();
+    let docs = [a, b];
+
+    assert_eq!(Err("duplicate node-id"), merge(&docs, MergePolicy::Error));
+
+    let merged = merge(&docs, MergePolicy::Remap).unwrap();
+    let ids: Vec<_> = merged.nodes.iter().map(|n| n.id).collect();
+    assert_eq!(vec![1, 2, 6, 7, 5], ids);
+    let edges: Vec<_> = merged.edges.iter().map(|e| (e.source, e.target)).collect();
+    assert_eq!(vec![(1, 2), (7, 5)], edges);
+    assert_eq!(
+        Some("north"),
+        merged.attrs.get("label").and_then(GmlValue::get_str)
+    );
+    assert_eq!(
+        Some(2),
+        merged.attrs.get("version").and_then(GmlValue::get_int)
+    );
+
+    for (precedence, label) in [(Precedence::First, "a"), (Precedence::Last, "c")] {
+        let merged = merge(&docs, MergePolicy::MergeAttributes(precedence)).unwrap();
+        assert_eq!(
+            vec![1, 2, 5],
+            merged.nodes.iter().map(|n| n.id).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(label), merged.nodes[0].label());
+        let graphics = merged.nodes[0].attrs.get("graphics").unwrap();
+        assert_eq!(
+            (Some(1), Some(2)),
+            (
+                graphics.get("x").and_then(GmlValue::get_int),
+                graphics.get("y").and_then(GmlValue::get_int)
+            )
+        );
+        assert_eq!(2, merged.edges.len());
+    }
+
+    let directed = parse_gml_document("graph [ directed 1 ]").unwrap();
+    assert!(merge(&[docs[0].clone(), directed], MergePolicy::Remap).is_err());
+    assert_eq!(Ok(GmlDocument::default()), merge(&[], MergePolicy::Error));
+}
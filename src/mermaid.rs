@@ -0,0 +1,127 @@
+//! Mermaid flowchart export.
+
+use crate::document::GmlDocument;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MermaidDirection {
+    TopDown,
+    LeftRight,
+    BottomUp,
+    RightLeft,
+}
+
+impl MermaidDirection {
+    fn keyword(self) -> &'static str {
+        match self {
+            MermaidDirection::TopDown => "TD",
+            MermaidDirection::LeftRight => "LR",
+            MermaidDirection::BottomUp => "BT",
+            MermaidDirection::RightLeft => "RL",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MermaidOptions {
+    pub direction: MermaidDirection,
+    /// Write edge labels.
+    pub edge_labels: bool,
+    /// Write at most this many nodes (and the edges between them).
+    pub max_nodes: Option<usize>,
+    /// With `max_nodes`, pick nodes evenly spread over the document instead
+    /// of the first ones.
+    pub sample: bool,
+}
+
+impl Default for MermaidOptions {
+    fn default() -> MermaidOptions {
+        MermaidOptions {
+            direction: MermaidDirection::TopDown,
+            edge_labels: true,
+            max_nodes: None,
+            sample: false,
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+/// Renders `doc` as a Mermaid `graph` diagram. Nodes are named `n<id>` and
+/// shown with their label, or their id if they have none. If nodes are left
+/// out because of `max_nodes`, a comment says how many.
+pub fn to_mermaid(doc: &GmlDocument, options: &MermaidOptions) -> String {
+    let total = doc.nodes.len();
+    let indices: Vec<usize> = match options.max_nodes {
+        Some(max) if max < total && options.sample => (0..max).map(|i| i * total / max).collect(),
+        Some(max) => (0..total.min(max)).collect(),
+        None => (0..total).collect(),
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "graph {}", options.direction.keyword());
+    if indices.len() < total {
+        let _ = writeln!(out, "  %% showing {} of {} nodes", indices.len(), total);
+    }
+
+    let mut shown = BTreeSet::new();
+    for &i in &indices {
+        let node = &doc.nodes[i];
+        shown.insert(node.id);
+        let label = node
+            .label()
+            .map(escape)
+            .unwrap_or_else(|| node.id.to_string());
+        let _ = writeln!(out, "  n{}[\"{}\"]", node.id, label);
+    }
+
+    let arrow = if doc.directed { "-->" } else { "---" };
+    for edge in &doc.edges {
+        if !(shown.contains(&edge.source) && shown.contains(&edge.target)) {
+            continue;
+        }
+        match edge.label().filter(|_| options.edge_labels) {
+            Some(label) => {
+                let _ = writeln!(
+                    out,
+                    "  n{} {}|\"{}\"| n{}",
+                    edge.source,
+                    arrow,
+                    escape(label),
+                    edge.target
+                );
+            }
+            None => {
+                let _ = writeln!(out, "  n{} {} n{}", edge.source, arrow, edge.target);
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_to_mermaid() {
+    let gml = "graph [ directed 1 node [ id 1 label \"say &quot;hi&quot;\" ] node [ id 2 ] \
+               node [ id 3 ] edge [ source 1 target 2 label \"x\" ] edge [ source 2 target 3 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    assert_eq!(
+        "graph TD\n  n1[\"say #quot;hi#quot;\"]\n  n2[\"2\"]\n  n3[\"3\"]\n  \
+         n1 -->|\"x\"| n2\n  n2 --> n3\n",
+        to_mermaid(&doc, &MermaidOptions::default())
+    );
+
+    let options = MermaidOptions {
+        direction: MermaidDirection::LeftRight,
+        edge_labels: false,
+        max_nodes: Some(2),
+        sample: false,
+    };
+    assert_eq!(
+        "graph LR\n  %% showing 2 of 3 nodes\n  n1[\"say #quot;hi#quot;\"]\n  n2[\"2\"]\n  \
+         n1 --> n2\n",
+        to_mermaid(&doc, &options)
+    );
+}
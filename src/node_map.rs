@@ -0,0 +1,82 @@
+//! Node id resolution.
+
+use petgraph::graph::NodeIndex;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+// Ids beyond the dense part by more than this go into the map.
+const MAX_GAP: u64 = 1024;
+
+/// Maps GML node ids to node indices. Ids that are dense, like 0 to n or 1
+/// to n, are kept in a `Vec`; only ids far beyond the largest dense id go
+/// into a `BTreeMap`.
+#[derive(Debug, Default)]
+pub(crate) struct NodeMap {
+    dense: Vec<Option<NodeIndex>>,
+    sparse: BTreeMap<u64, NodeIndex>,
+}
+
+impl NodeMap {
+    /// Reserves space for the dense ids up to `n`.
+    pub(crate) fn with_capacity(n: usize) -> NodeMap {
+        NodeMap {
+            dense: Vec::with_capacity(n.saturating_add(1)),
+            sparse: BTreeMap::new(),
+        }
+    }
+
+    /// Removes all ids, keeping the allocated space, and reserves space for
+    /// the dense ids up to `n`.
+    pub(crate) fn reset(&mut self, n: usize) {
+        self.dense.clear();
+        self.dense.reserve(n.saturating_add(1));
+        self.sparse.clear();
+    }
+
+    /// The approximate size of the map on the heap, in bytes.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.dense.capacity() * std::mem::size_of::<Option<NodeIndex>>()
+            + self.sparse.len() * std::mem::size_of::<(u64, NodeIndex)>()
+    }
+
+    pub(crate) fn get(&self, id: u64) -> Option<NodeIndex> {
+        match usize::try_from(id).ok().and_then(|i| self.dense.get(i)) {
+            Some(&Some(idx)) => Some(idx),
+            _ => self.sparse.get(&id).copied(),
+        }
+    }
+
+    /// Adds `id`, or returns false if it is already present.
+    pub(crate) fn insert(&mut self, id: u64, idx: NodeIndex) -> bool {
+        if self.get(id).is_some() {
+            return false;
+        }
+        let len = self.dense.len() as u64;
+        if id < len + MAX_GAP.max(len) {
+            if id >= len {
+                self.dense.resize(id as usize + 1, None);
+            }
+            self.dense[id as usize] = Some(idx);
+        } else {
+            self.sparse.insert(id, idx);
+        }
+        true
+    }
+}
+
+#[test]
+fn test_node_map() {
+    let mut map = NodeMap::default();
+    for id in 1..=3 {
+        assert!(map.insert(id, NodeIndex::new(id as usize - 1)));
+    }
+    assert!(map.insert(u64::MAX, NodeIndex::new(3)));
+    assert!(!map.insert(2, NodeIndex::new(4)));
+    assert!(!map.insert(u64::MAX, NodeIndex::new(4)));
+    assert_eq!(Some(NodeIndex::new(1)), map.get(2));
+    assert_eq!(Some(NodeIndex::new(3)), map.get(u64::MAX));
+    assert_eq!(None, map.get(0));
+    assert_eq!(None, map.get(4));
+    assert_eq!(4, map.dense.len());
+    assert_eq!(1, map.sparse.len());
+}
@@ -0,0 +1,427 @@
+//! Pajek `.net` import and export.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+const SHAPES: &[&str] = &["ellipse", "box", "diamond", "triangle", "cross", "empty"];
+
+// Splits a line into whitespace separated tokens. Double quoted tokens may
+// contain whitespace.
+fn split_line(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut token = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => token.push(c),
+                    None => return Err("unterminated quote"),
+                }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_number(s: &str) -> Option<GmlValue> {
+    if let Ok(i) = s.parse() {
+        Some(GmlValue::Int(i))
+    } else {
+        s.parse().ok().map(GmlValue::Float)
+    }
+}
+
+fn typed(s: String) -> GmlValue {
+    parse_number(&s).unwrap_or(GmlValue::from(s))
+}
+
+fn graphics_mut(attrs: &mut GmlAttrs) -> &mut GmlAttrs {
+    let entry = attrs
+        .entry("graphics".to_string())
+        .or_insert_with(|| GmlValue::List(GmlAttrs::new()));
+    match *entry {
+        GmlValue::List(ref mut l) => l,
+        _ => unreachable!(),
+    }
+}
+
+#[derive(PartialEq)]
+enum Section {
+    None,
+    Vertices,
+    Arcs,
+    Edges,
+    ArcsList,
+    EdgesList,
+    Matrix,
+}
+
+/// Parses a Pajek `.net` file into a `GmlDocument`.
+///
+/// Vertex numbers become node ids. Labels, coordinates (as `graphics`
+/// `x`, `y`, `z`), shapes (`graphics type`) and the `ic`/`bc` colors
+/// (`graphics fill`/`outline`) are read from vertex lines; other parameters
+/// are kept as attributes. Arc and edge weights become `weight`, the `l`
+/// parameter `label` and `c` the `graphics fill` of the edge.
+///
+/// A file with only `*Edges` is undirected. If it mixes `*Arcs` and
+/// `*Edges`, the document is directed and every undirected edge is added in
+/// both directions. A `*Vertices` count larger than the length of `s` is
+/// an invalid vertex count.
+pub fn parse_pajek(s: &str) -> Result<GmlDocument, &'static str> {
+    let mut nodes: BTreeMap<u64, GmlAttrs> = BTreeMap::new();
+    let mut arcs = Vec::new();
+    let mut undirected = Vec::new();
+    let mut section = Section::None;
+    let mut graph_attrs = GmlAttrs::new();
+    let mut matrix_row = 0;
+
+    for line in s.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+
+        if line.starts_with('*') {
+            let tokens = split_line(line)?;
+            let keyword = tokens[0].to_ascii_lowercase();
+            section = match &keyword[..] {
+                "*network" => {
+                    if tokens.len() > 1 {
+                        let name = tokens[1..].join(" ");
+                        graph_attrs.insert("label".to_string(), GmlValue::from(name));
+                    }
+                    Section::None
+                }
+                "*vertices" => {
+                    // the count is untrusted, so more vertices than bytes
+                    // of input are not created
+                    let n: u64 = tokens
+                        .get(1)
+                        .and_then(|n| n.parse().ok())
+                        .filter(|&n| n <= s.len() as u64)
+                        .ok_or("invalid vertex count")?;
+                    for id in 1..=n {
+                        nodes.entry(id).or_default();
+                    }
+                    Section::Vertices
+                }
+                "*arcs" => Section::Arcs,
+                "*edges" => Section::Edges,
+                "*arcslist" => Section::ArcsList,
+                "*edgeslist" => Section::EdgesList,
+                "*matrix" => {
+                    matrix_row = 0;
+                    Section::Matrix
+                }
+                _ => return Err("unknown section"),
+            };
+            continue;
+        }
+
+        let tokens = split_line(line)?;
+        match section {
+            Section::None => return Err("data outside of a section"),
+            Section::Vertices => {
+                let mut tokens = tokens.into_iter();
+                let id: u64 = tokens
+                    .next()
+                    .and_then(|t| t.parse().ok())
+                    .ok_or("Invalid id")?;
+                let attrs = nodes.entry(id).or_default();
+                if let Some(label) = tokens.next() {
+                    attrs.insert("label".to_string(), GmlValue::from(label));
+                }
+                let mut rest: Vec<String> = tokens.collect();
+                let coords = rest
+                    .iter()
+                    .take(3)
+                    .take_while(|t| t.parse::<f64>().is_ok())
+                    .count();
+                for (key, value) in ["x", "y", "z"].iter().zip(rest.drain(..coords)) {
+                    graphics_mut(attrs).insert(key.to_string(), typed(value));
+                }
+                let mut rest = rest.into_iter();
+                while let Some(key) = rest.next() {
+                    if SHAPES.contains(&&key[..]) {
+                        graphics_mut(attrs).insert("type".to_string(), GmlValue::from(key));
+                        continue;
+                    }
+                    let value = rest.next().ok_or("missing vertex parameter value")?;
+                    match &key[..] {
+                        "ic" => {
+                            graphics_mut(attrs).insert("fill".to_string(), GmlValue::from(value));
+                        }
+                        "bc" => {
+                            graphics_mut(attrs)
+                                .insert("outline".to_string(), GmlValue::from(value));
+                        }
+                        _ => {
+                            attrs.insert(key, typed(value));
+                        }
+                    }
+                }
+            }
+            Section::Arcs | Section::Edges => {
+                let mut tokens = tokens.into_iter();
+                let source = tokens.next().and_then(|t| t.parse().ok());
+                let target = tokens.next().and_then(|t| t.parse().ok());
+                let (source, target): (u64, u64) = match (source, target) {
+                    (Some(s), Some(t)) => (s, t),
+                    _ => return Err("invalid arc or edge line"),
+                };
+                let mut attrs = GmlAttrs::new();
+                let mut rest = tokens.peekable();
+                if let Some(weight) = rest.peek().and_then(|w| parse_number(w)) {
+                    attrs.insert("weight".to_string(), weight);
+                    rest.next();
+                }
+                while let Some(key) = rest.next() {
+                    let value = rest.next().ok_or("missing edge parameter value")?;
+                    match &key[..] {
+                        "l" => {
+                            attrs.insert("label".to_string(), GmlValue::from(value));
+                        }
+                        "c" => {
+                            graphics_mut(&mut attrs)
+                                .insert("fill".to_string(), GmlValue::from(value));
+                        }
+                        _ => {
+                            attrs.insert(key, typed(value));
+                        }
+                    }
+                }
+                if section == Section::Arcs {
+                    arcs.push((source, target, attrs));
+                } else {
+                    undirected.push((source, target, attrs));
+                }
+            }
+            Section::ArcsList | Section::EdgesList => {
+                let ids: Vec<u64> = tokens
+                    .iter()
+                    .map(|t| t.parse().ok())
+                    .collect::<Option<_>>()
+                    .ok_or("invalid list line")?;
+                if let Some((&source, targets)) = ids.split_first() {
+                    for &target in targets {
+                        let edge = (source, target, GmlAttrs::new());
+                        if section == Section::ArcsList {
+                            arcs.push(edge);
+                        } else {
+                            undirected.push(edge);
+                        }
+                    }
+                }
+            }
+            Section::Matrix => {
+                matrix_row += 1;
+                for (col, value) in tokens.iter().enumerate() {
+                    let weight = parse_number(value).ok_or("invalid matrix entry")?;
+                    if weight.get_float() != Some(0.0) {
+                        let mut attrs = GmlAttrs::new();
+                        if weight.get_float() != Some(1.0) {
+                            attrs.insert("weight".to_string(), weight);
+                        }
+                        arcs.push((matrix_row, col as u64 + 1, attrs));
+                    }
+                }
+            }
+        }
+    }
+
+    let directed = !arcs.is_empty() || undirected.is_empty();
+    let mut doc = GmlDocument {
+        directed,
+        attrs: graph_attrs,
+        top_attrs: GmlAttrs::new(),
+        nodes: nodes
+            .into_iter()
+            .map(|(id, attrs)| GmlNode { id, attrs })
+            .collect(),
+        edges: Vec::new(),
+    };
+
+    for (source, target, attrs) in arcs {
+        doc.edges.push(GmlEdge {
+            source,
+            target,
+            attrs,
+        });
+    }
+    for (source, target, attrs) in undirected {
+        if directed && source != target {
+            doc.edges.push(GmlEdge {
+                source: target,
+                target: source,
+                attrs: attrs.clone(),
+            });
+        }
+        doc.edges.push(GmlEdge {
+            source,
+            target,
+            attrs,
+        });
+    }
+
+    Ok(doc)
+}
+
+/// Renders `doc` as a Pajek `.net` file. An edge that references an unknown
+/// node is an `io::ErrorKind::InvalidData` error, as in `write_pajek`.
+pub fn to_pajek(doc: &GmlDocument) -> io::Result<String> {
+    let mut buf = Vec::new();
+    write_pajek(doc, &mut buf)?;
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+fn quote(s: &str) -> String {
+    // Pajek has no escape for quotes within labels
+    format!("\"{}\"", s.replace('"', "'"))
+}
+
+fn scalar(value: &GmlValue) -> Option<String> {
+    match *value {
+        GmlValue::Int(i) => Some(i.to_string()),
+        GmlValue::UInt(u) => Some(u.to_string()),
+        GmlValue::Float(f) => Some(f.to_string()),
+        GmlValue::Str(ref s) => Some(quote(s)),
+        GmlValue::List(_) => None,
+    }
+}
+
+/// Writes `doc` as a Pajek `.net` file, the inverse of `parse_pajek`.
+///
+/// Pajek numbers vertices from 1 without gaps, so nodes are renumbered in
+/// document order.
+pub fn write_pajek<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    if let Some(label) = doc.attrs.get("label").and_then(GmlValue::get_str) {
+        writeln!(w, "*Network {}", label)?;
+    }
+
+    let mut numbers = BTreeMap::new();
+    writeln!(w, "*Vertices {}", doc.nodes.len())?;
+    for (i, node) in doc.nodes.iter().enumerate() {
+        let n = i + 1;
+        numbers.insert(node.id, n);
+        let label = node
+            .label()
+            .map(str::to_string)
+            .unwrap_or_else(|| node.id.to_string());
+        let mut line = format!("{} {}", n, quote(&label));
+
+        let graphics = node.attrs.get("graphics");
+        let coord = |k: &str| {
+            graphics
+                .and_then(|g| g.get(k))
+                .and_then(GmlValue::get_float)
+        };
+        if let (Some(x), Some(y)) = (coord("x"), coord("y")) {
+            let z = coord("z").unwrap_or(0.0);
+            line.push_str(&format!(" {:?} {:?} {:?}", x, y, z));
+        }
+        let graphics_str = |k: &str| graphics.and_then(|g| g.get(k)).and_then(GmlValue::get_str);
+        if let Some(shape) = graphics_str("type").filter(|s| SHAPES.contains(s)) {
+            line.push_str(&format!(" {}", shape));
+        }
+        if let Some(fill) = graphics_str("fill") {
+            line.push_str(&format!(" ic {}", fill));
+        }
+        if let Some(outline) = graphics_str("outline") {
+            line.push_str(&format!(" bc {}", outline));
+        }
+        writeln!(w, "{}", line)?;
+    }
+
+    writeln!(w, "{}", if doc.directed { "*Arcs" } else { "*Edges" })?;
+    for edge in &doc.edges {
+        let (source, target) = match (numbers.get(&edge.source), numbers.get(&edge.target)) {
+            (Some(s), Some(t)) => (s, t),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "edge references unknown node",
+                ))
+            }
+        };
+        let mut line = format!("{} {}", source, target);
+        if let Some(weight) = edge.attrs.get("weight").filter(|w| w.get_float().is_some()) {
+            line.push_str(&format!(" {}", scalar(weight).unwrap()));
+        }
+        if let Some(label) = edge.attrs.get("label").and_then(scalar) {
+            line.push_str(&format!(" l {}", label));
+        }
+        let fill = edge.attrs.get("graphics").and_then(|g| g.get("fill"));
+        if let Some(fill) = fill.and_then(GmlValue::get_str) {
+            line.push_str(&format!(" c {}", fill));
+        }
+        writeln!(w, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_pajek() {
+    let net = "
+    % a comment
+    *Network test
+    *Vertices 4
+    1 \"node a\" 0.1 0.2 0.0 box ic Red
+    2 \"b\"
+    *Arcs
+    1 2 1.5 l \"ab\" c Blue
+    *Edges
+    2 3
+    *Arcslist
+    4 1 2
+    ";
+    let doc = parse_pajek(net).unwrap();
+    assert!(doc.directed);
+    assert_eq!(Some("test"), doc.attrs["label"].get_str());
+    assert_eq!(4, doc.nodes.len());
+    assert_eq!(Some("node a"), doc.nodes[0].label());
+    let graphics = &doc.nodes[0].attrs["graphics"];
+    assert_eq!(Some(0.2), graphics.get("y").and_then(GmlValue::get_float));
+    assert_eq!(
+        Some("box"),
+        graphics.get("type").and_then(GmlValue::get_str)
+    );
+    assert_eq!(
+        Some("Red"),
+        graphics.get("fill").and_then(GmlValue::get_str)
+    );
+    assert_eq!(Some(1.5), doc.edges[0].attrs["weight"].get_float());
+    assert_eq!(Some("ab"), doc.edges[0].label());
+    let pairs: Vec<_> = doc.edges.iter().map(|e| (e.source, e.target)).collect();
+    assert_eq!(vec![(1, 2), (4, 1), (4, 2), (3, 2), (2, 3)], pairs);
+
+    let back = parse_pajek(&to_pajek(&doc).unwrap()).unwrap();
+    assert_eq!(doc.nodes[0], back.nodes[0]);
+    assert_eq!(doc.edges[0], back.edges[0]);
+    assert_eq!(doc.edges.len(), back.edges.len());
+
+    let dangling = crate::parse_gml_document("graph [ node [ id 1 ] edge [ source 1 target 2 ] ]");
+    let err = to_pajek(&dangling.unwrap()).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+    let huge = "*Vertices 18446744073709551615\n";
+    assert_eq!(Err("invalid vertex count"), parse_pajek(huge));
+    assert_eq!(5, parse_pajek("*Vertices 5\n").unwrap().nodes.len());
+}
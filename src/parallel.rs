@@ -0,0 +1,199 @@
+//! Parallel GML parsing.
+//!
+//! The items of the `graph` list are split into chunks at node and edge
+//! block boundaries, the chunks are parsed on separate threads and the
+//! results are merged in document order, so the document is the same as
+//! the one `parse_gml_document` returns.
+
+use crate::document::{insert_attr, parse_gml_document, GmlAttrs, GmlDocument, GmlEdge, GmlNode};
+use crate::events::{gml_events, graph_parts, DocumentParts, GmlEvent, OwnedLists};
+use std::thread;
+
+fn is_key_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+// The first top-level `graph` list of a string.
+struct Split {
+    // the offset of the `graph` key
+    key: usize,
+    // the byte ranges of the items, each but the last ending after a node or
+    // edge block
+    chunks: Vec<(usize, usize)>,
+    // the offset after the closing bracket
+    end: usize,
+}
+
+// Splits `s` into chunks of about `chunk_size` bytes, or returns None if the
+// brackets do not balance. Only quotes, comments and brackets are looked at.
+fn split(s: &str, chunk_size: usize) -> Option<Split> {
+    let bytes = s.as_bytes();
+    let mut depth = 0usize;
+    let (mut in_string, mut in_comment) = (false, false);
+    let mut open = None;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        if in_comment {
+            in_comment = b != b'\n';
+            continue;
+        }
+        if in_string {
+            in_string = b != b'"';
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'#' => in_comment = true,
+            b'[' => {
+                if depth == 0 && open.is_none() {
+                    let key = s[..i].trim_end();
+                    let len = key.bytes().rev().take_while(|&b| is_key_char(b)).count();
+                    if &key[key.len() - len..] == "graph" {
+                        open = Some(key.len() - len);
+                        start = i + 1;
+                    }
+                }
+                depth += 1;
+            }
+            b']' => {
+                depth = depth.checked_sub(1)?;
+                match (open, depth) {
+                    (Some(_), 1) if i + 1 - start >= chunk_size => {
+                        chunks.push((start, i + 1));
+                        start = i + 1;
+                    }
+                    (Some(key), 0) => {
+                        chunks.push((start, i));
+                        return Some(Split {
+                            key,
+                            chunks,
+                            end: i + 1,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Checks top-level items outside of the `graph` list.
+fn check_top_level(s: &str) -> Result<(), &'static str> {
+    let mut events = gml_events(s);
+    while let Some(event) = events.next() {
+        match event? {
+            GmlEvent::Begin("graph") => return Err("duplicate key"),
+            GmlEvent::Begin(_) => events.skip_list()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn parse_chunk(s: &str) -> Result<DocumentParts<GmlAttrs>, &'static str> {
+    graph_parts(&mut gml_events(s), &mut OwnedLists)
+}
+
+fn merge(chunks: Vec<DocumentParts<GmlAttrs>>) -> Result<GmlDocument, &'static str> {
+    let mut doc = GmlDocument::default();
+    for chunk in chunks {
+        if let Some(directed) = chunk.directed {
+            doc.directed = directed;
+        }
+        for (key, value) in chunk.attrs {
+            for value in value.values() {
+                insert_attr(&mut doc.attrs, &key, value.clone());
+            }
+        }
+        doc.nodes.extend(
+            chunk
+                .nodes
+                .into_iter()
+                .map(|(id, attrs)| GmlNode { id, attrs }),
+        );
+        doc.edges.extend(
+            chunk
+                .edges
+                .into_iter()
+                .map(|(source, target, attrs)| GmlEdge {
+                    source,
+                    target,
+                    attrs,
+                }),
+        );
+    }
+    Ok(doc)
+}
+
+/// Parses a GML string like `parse_gml_document`, using up to `threads`
+/// threads, or as many as are available if `threads` is 0.
+///
+/// If the input is invalid, it is parsed again sequentially, so that the
+/// error is the same as that of `parse_gml_document`.
+pub fn parse_gml_document_parallel(s: &str, threads: usize) -> Result<GmlDocument, &'static str> {
+    if cfg!(feature = "legacy-lexer") {
+        // the split follows the quoting rules of the GML lexer only
+        return parse_gml_document(s);
+    }
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    // a few chunks per thread even out chunks of different cost
+    let chunk_size = (s.len() / (threads * 4)).max(64 * 1024);
+    let Split { key, chunks, end } = match split(s, chunk_size) {
+        Some(split) if threads > 1 && split.chunks.len() > 1 => split,
+        _ => return parse_gml_document(s),
+    };
+
+    let mut results = Vec::with_capacity(chunks.len());
+    let per_thread = chunks.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .chunks(per_thread)
+            .map(|ranges| {
+                scope.spawn(move || {
+                    ranges
+                        .iter()
+                        .map(|&(from, to)| parse_chunk(&s[from..to]))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            results.extend(handle.join().expect("parser thread panicked"));
+        }
+    });
+
+    let parsed = check_top_level(&s[..key])
+        .and_then(|_| check_top_level(&s[end..]))
+        .and_then(|_| results.into_iter().collect::<Result<Vec<_>, _>>())
+        .and_then(merge);
+    match parsed {
+        Ok(doc) => Ok(doc),
+        Err(_) => parse_gml_document(s),
+    }
+}
+
+#[test]
+fn test_parse_gml_document_parallel() {
+    let mut gml = String::from("Creator \"test\" graph [ directed 1\n");
+    for i in 0..5000 {
+        gml.push_str(&format!("node [ id {} label \"n [{}] # x\" ] # ]\n", i, i));
+        if i > 0 {
+            gml.push_str(&format!("edge [ source {} target {} ]\n", i - 1, i));
+        }
+    }
+    gml.push_str("label \"big\" ] version 1");
+    assert!(split(&gml, 64 * 1024).unwrap().chunks.len() > 1);
+    let doc = parse_gml_document_parallel(&gml, 4).unwrap();
+    assert_eq!(parse_gml_document(&gml).unwrap(), doc);
+    assert_eq!(5000, doc.nodes.len());
+    assert_eq!(Some("n [4999] # x"), doc.nodes[4999].label());
+
+    gml.push_str(" graph [ ]");
+    assert_eq!(Err("duplicate key"), parse_gml_document_parallel(&gml, 4));
+}
@@ -0,0 +1,116 @@
+//! PlantUML object and component diagram export.
+
+use crate::document::{GmlDocument, GmlNode, GmlValue};
+use std::fmt::Write as _;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlantUmlDiagram {
+    /// Nodes as objects, listing their scalar attributes as fields.
+    Object,
+    /// Nodes as components.
+    Component,
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "'"))
+}
+
+/// Renders `doc` as a PlantUML diagram.
+///
+/// Nodes are named `n<id>` and displayed with their label (or id).
+/// `stereotype_fn` maps a node to an optional stereotype, e.g. its `type`
+/// attribute, shown as `<<stereotype>>`. Edge labels become link labels.
+pub fn to_plantuml<F>(doc: &GmlDocument, diagram: PlantUmlDiagram, stereotype_fn: &F) -> String
+where
+    F: Fn(&GmlNode) -> Option<String>,
+{
+    let keyword = match diagram {
+        PlantUmlDiagram::Object => "object",
+        PlantUmlDiagram::Component => "component",
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "@startuml");
+    for node in &doc.nodes {
+        let label = node
+            .label()
+            .map(str::to_string)
+            .unwrap_or_else(|| node.id.to_string());
+        let _ = write!(out, "{} {} as n{}", keyword, quote(&label), node.id);
+        if let Some(stereotype) = stereotype_fn(node) {
+            let _ = write!(out, " <<{}>>", stereotype.replace(['<', '>'], ""));
+        }
+
+        let fields: Vec<_> = node
+            .attrs
+            .iter()
+            .filter(|&(k, _)| k != "label")
+            .filter_map(|(k, v)| match *v {
+                GmlValue::Int(i) => Some(format!("{} = {}", k, i)),
+                GmlValue::UInt(u) => Some(format!("{} = {}", k, u)),
+                GmlValue::Float(f) => Some(format!("{} = {}", k, f)),
+                GmlValue::Str(ref s) => Some(format!("{} = {}", k, quote(s))),
+                GmlValue::List(_) => None,
+            })
+            .collect();
+        if diagram == PlantUmlDiagram::Object && !fields.is_empty() {
+            let _ = writeln!(out, " {{");
+            for field in fields {
+                let _ = writeln!(out, "  {}", field);
+            }
+            let _ = writeln!(out, "}}");
+        } else {
+            let _ = writeln!(out);
+        }
+    }
+
+    let arrow = if doc.directed { "-->" } else { "--" };
+    for edge in &doc.edges {
+        let _ = write!(out, "n{} {} n{}", edge.source, arrow, edge.target);
+        match edge.label() {
+            Some(label) => {
+                let _ = writeln!(out, " : {}", label.replace('\n', " "));
+            }
+            None => {
+                let _ = writeln!(out);
+            }
+        }
+    }
+    let _ = writeln!(out, "@enduml");
+    out
+}
+
+#[test]
+// expects the attributes in key order
+fn test_to_plantuml() {
+    let gml = "graph [ directed 1 node [ id 1 label \"web\" type \"service\" port 80 ] \
+               node [ id 2 label \"db\" graphics [ x 1 ] ] edge [ source 1 target 2 label \"sql\" ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let stereotype = |n: &GmlNode| {
+        n.attrs
+            .get("type")
+            .and_then(GmlValue::get_str)
+            .map(str::to_string)
+    };
+
+    // the fields follow the key order of the attributes
+    let sorted = |s: &str| {
+        let mut lines: Vec<_> = s.lines().map(str::to_string).collect();
+        lines.sort();
+        lines
+    };
+    let object = to_plantuml(&doc, PlantUmlDiagram::Object, &stereotype);
+    assert!(object.starts_with("@startuml\nobject \"web\" as n1 <<service>> {\n"));
+    assert_eq!(
+        sorted(
+            "@startuml\nobject \"web\" as n1 <<service>> {\n  port = 80\n  type = \"service\"\n}\n\
+             object \"db\" as n2\nn1 --> n2 : sql\n@enduml\n"
+        ),
+        sorted(&object)
+    );
+    assert_eq!(
+        "@startuml\ncomponent \"web\" as n1 <<service>>\ncomponent \"db\" as n2\n\
+         n1 --> n2 : sql\n@enduml\n",
+        to_plantuml(&doc, PlantUmlDiagram::Component, &stereotype)
+    );
+}
@@ -0,0 +1,105 @@
+//! Previews of large GML documents.
+//!
+//! `preview` reads a document only up to its first nodes and edges, so that
+//! the start of a file of gigabytes can be shown without reading all of it.
+
+use crate::document::{take_id, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::events::{lexer_events, GmlEvent};
+
+/// The start of a document, as `preview` returns it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GmlPreview {
+    /// The graph attributes and `directed` before the point where reading
+    /// stopped, with the first nodes and edges.
+    pub document: GmlDocument,
+    /// The bytes of the input read.
+    pub bytes_read: usize,
+    /// Whether the whole `graph` list was read, so that the document has
+    /// all graph attributes and no nodes or edges are left out.
+    pub complete: bool,
+}
+
+/// Reads the graph of `s` until it has seen `n` nodes and `n` edges, or up
+/// to its end. Node and edge blocks after the first `n` of their kind are
+/// skipped over until the other kind has `n` too; nothing after that is
+/// read, so errors there are not found.
+pub fn preview(s: &str, n: usize) -> Result<GmlPreview, &'static str> {
+    let mut preview = GmlPreview::default();
+    let doc = &mut preview.document;
+    let mut events = lexer_events(s);
+    events.enter_graph()?;
+    while doc.nodes.len() < n || doc.edges.len() < n {
+        let event = match events.next() {
+            Some(event) => event?,
+            None => return Err("unbalanced ["),
+        };
+        match event {
+            GmlEvent::End => {
+                preview.complete = true;
+                break;
+            }
+            GmlEvent::Value("directed", value) => {
+                doc.directed = value.into_value() == GmlValue::Int(1)
+            }
+            GmlEvent::Begin("node") if doc.nodes.len() < n => {
+                let mut attrs = events.read_list()?;
+                let id = take_id(&mut attrs, "id", "Invalid id")?;
+                doc.nodes.push(GmlNode { id, attrs });
+            }
+            GmlEvent::Begin("edge") if doc.edges.len() < n => {
+                let mut attrs = events.read_list()?;
+                let source = take_id(&mut attrs, "source", "Invalid source id")?;
+                let target = take_id(&mut attrs, "target", "Invalid target id")?;
+                doc.edges.push(GmlEdge {
+                    source,
+                    target,
+                    attrs,
+                });
+            }
+            GmlEvent::Begin("node") | GmlEvent::Begin("edge") => events.skip_list()?,
+            GmlEvent::Begin(key) => {
+                let list = events.read_list()?;
+                doc.attrs.insert(key.to_string(), GmlValue::List(list));
+            }
+            GmlEvent::Value(key, value) => {
+                doc.attrs.insert(key.to_string(), value.into_value());
+            }
+        }
+    }
+    preview.bytes_read = events.offset().unwrap_or(s.len());
+    Ok(preview)
+}
+
+#[test]
+fn test_preview() {
+    let gml = "graph [ directed 1 label \"big\"
+               node [ id 1 ] edge [ source 1 target 2 ] node [ id 2 label \"b\" ]
+               node [ id 3 ] edge [ source 2 target 3 ] creator \"late\" edge [ source 3 ";
+    let preview = preview(gml, 2).unwrap();
+    assert!(preview.document.directed);
+    assert!(!preview.complete);
+    assert_eq!(
+        Some("big"),
+        preview
+            .document
+            .attrs
+            .get("label")
+            .and_then(GmlValue::get_str)
+    );
+    assert_eq!(None, preview.document.attrs.get("creator"));
+    let ids: Vec<_> = preview.document.nodes.iter().map(|node| node.id).collect();
+    assert_eq!(vec![1, 2], ids);
+    assert_eq!(Some("b"), preview.document.nodes[1].label());
+    assert_eq!(2, preview.document.edges.len());
+    assert_eq!(gml.find("creator").unwrap() - 1, preview.bytes_read);
+
+    let gml = "graph [ node [ id 1 ] node [ id 2 ] edge [ source 1 target 2 ] ]";
+    let all = self::preview(gml, 2).unwrap();
+    assert!(all.complete);
+    assert_eq!((2, 1), (all.document.nodes.len(), all.document.edges.len()));
+    assert_eq!(Ok(&all.document), crate::parse_gml_document(gml).as_ref());
+    assert_eq!(
+        Err("Invalid id"),
+        self::preview("graph [ node [ ] ]", 1).map(|_| ())
+    );
+}
@@ -0,0 +1,494 @@
+//! A small expression language for selecting the nodes or edges of a
+//! document, as used by `gml grep`.
+//!
+//! ```text
+//! node.degree > 5 && node.attr("type") == "switch"
+//! edge.source == 1 || edge.attr("graphics.width") >= 2.5
+//! !node.label
+//! ```
+//!
+//! A query is about nodes or about edges, as its terms say. Nodes have
+//! `id`, `degree`, `in_degree`, `out_degree` and `label`, edges `source`,
+//! `target` and `label`, and both `attr("key")` for any attribute, with
+//! dotted keys for nested ones. Comparisons are `==`, `!=`, `<`, `<=`, `>`
+//! and `>=`, between numbers or between strings, combined with `&&`, `||`,
+//! `!` and parentheses. An attribute an element does not have equals
+//! nothing and is not ordered; on its own, a term is true if the element
+//! has it.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::writer::format_scalar;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Ident(String),
+    Dot,
+    Open,
+    Close,
+    Op(&'static str),
+}
+
+const OPS: &[&str] = &["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!"];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if let Some(op) = OPS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            op.len()
+        } else if c == '.' && !rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            tokens.push(Token::Dot);
+            1
+        } else if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            1
+        } else if c == '"' {
+            let end = rest[1..].find('"').ok_or("unterminated string")?;
+            tokens.push(Token::Str(rest[1..end + 1].to_string()));
+            end + 2
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let end = rest[1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-'))
+                .map_or(rest.len(), |end| end + 1);
+            let number = &rest[..end];
+            tokens.push(match number.parse() {
+                Ok(i) => Token::Int(i),
+                Err(_) => Token::Float(number.parse().map_err(|_| "invalid number")?),
+            });
+            end
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            end
+        } else {
+            return Err("invalid character");
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Whether a query selects nodes or edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Nodes,
+    Edges,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Id,
+    Degree,
+    InDegree,
+    OutDegree,
+    Source,
+    Target,
+    Attr(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Term(Term),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(&'static str, Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    target: Option<Target>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1).cloned()
+    }
+
+    fn expect(&mut self, token: Token, error: &'static str) -> Result<(), &'static str> {
+        if self.next() == Some(token) {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(&Token::Op(o)) if o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, &'static str> {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, &'static str> {
+        let mut left = self.not()?;
+        while self.eat("&&") {
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, &'static str> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        let left = self.primary()?;
+        match self.peek() {
+            Some(&Token::Op(op)) if !["&&", "||", "!"].contains(&op) => {
+                self.pos += 1;
+                let right = self.primary()?;
+                Ok(Expr::Compare(op, Box::new(left), Box::new(right)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, &'static str> {
+        match self.next().ok_or("unexpected end of query")? {
+            Token::Int(i) => Ok(Expr::Int(i)),
+            Token::Float(f) => Ok(Expr::Float(f)),
+            Token::Str(s) => Ok(Expr::Str(s)),
+            Token::Open => {
+                let expr = self.or()?;
+                self.expect(Token::Close, "expected )")?;
+                Ok(expr)
+            }
+            Token::Ident(element) => {
+                let target = match element.as_str() {
+                    "node" => Target::Nodes,
+                    "edge" => Target::Edges,
+                    _ => return Err("expected node or edge"),
+                };
+                if self.target.is_some_and(|t| t != target) {
+                    return Err("query mixes nodes and edges");
+                }
+                self.target = Some(target);
+                self.expect(Token::Dot, "expected .")?;
+                let name = match self.next() {
+                    Some(Token::Ident(name)) => name,
+                    _ => return Err("expected a property"),
+                };
+                let term = match (target, name.as_str()) {
+                    (_, "label") => Term::Attr("label".to_string()),
+                    (_, "attr") => {
+                        self.expect(Token::Open, "expected (")?;
+                        let key = match self.next() {
+                            Some(Token::Str(key)) => key,
+                            _ => return Err("expected an attribute key"),
+                        };
+                        self.expect(Token::Close, "expected )")?;
+                        Term::Attr(key)
+                    }
+                    (Target::Nodes, "id") => Term::Id,
+                    (Target::Nodes, "degree") => Term::Degree,
+                    (Target::Nodes, "in_degree") => Term::InDegree,
+                    (Target::Nodes, "out_degree") => Term::OutDegree,
+                    (Target::Edges, "source") => Term::Source,
+                    (Target::Edges, "target") => Term::Target,
+                    _ => return Err("unknown property"),
+                };
+                Ok(Expr::Term(term))
+            }
+            _ => Err("expected a value"),
+        }
+    }
+}
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    target: Target,
+    expr: Expr,
+}
+
+/// A node or edge a query selected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Match<'a> {
+    Node(&'a GmlNode),
+    Edge(&'a GmlEdge),
+}
+
+fn write_inline(f: &mut fmt::Formatter, attrs: &GmlAttrs) -> fmt::Result {
+    for (key, value) in attrs {
+        match *value {
+            GmlValue::List(ref inner) => {
+                write!(f, " {} [", key)?;
+                write_inline(f, inner)?;
+                write!(f, " ]")?;
+            }
+            ref scalar => write!(f, " {} {}", key, format_scalar(scalar))?,
+        }
+    }
+    Ok(())
+}
+
+/// The element as GML on one line, like `node [ id 1 label "a" ]`.
+impl fmt::Display for Match<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Match::Node(node) => {
+                write!(f, "node [ id {}", node.id)?;
+                write_inline(f, &node.attrs)?;
+            }
+            Match::Edge(edge) => {
+                write!(f, "edge [ source {} target {}", edge.source, edge.target)?;
+                write_inline(f, &edge.attrs)?;
+            }
+        }
+        write!(f, " ]")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value<'a> {
+    Int(i64),
+    Float(f64),
+    Str(&'a str),
+    List,
+    Bool(bool),
+    Missing,
+}
+
+impl<'a> Value<'a> {
+    fn of(value: Option<&'a GmlValue>) -> Value<'a> {
+        match value {
+            Some(&GmlValue::Int(i)) => Value::Int(i),
+            // beyond every integer literal, so compared as a real
+            Some(&GmlValue::UInt(u)) => Value::Float(u as f64),
+            Some(&GmlValue::Float(f)) => Value::Float(f),
+            Some(GmlValue::Str(s)) => Value::Str(s),
+            Some(GmlValue::List(_)) => Value::List,
+            None => Value::Missing,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        !matches!(*self, Value::Bool(false) | Value::Missing)
+    }
+
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+            (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+fn lookup<'a>(attrs: &'a GmlAttrs, key: &str) -> Option<&'a GmlValue> {
+    match key.split_once('.') {
+        Some((outer, rest)) => attrs
+            .get(outer)
+            .and_then(|v| v.get_list())
+            .and_then(|l| lookup(l, rest)),
+        None => attrs.get(key),
+    }
+}
+
+// The element an expression is evaluated for, with the degrees of a node.
+struct Element<'a> {
+    id: u64,
+    source: u64,
+    target: u64,
+    degrees: (u64, u64),
+    attrs: &'a GmlAttrs,
+}
+
+fn eval<'a>(expr: &'a Expr, element: &Element<'a>) -> Value<'a> {
+    match *expr {
+        Expr::Int(i) => Value::Int(i),
+        Expr::Float(f) => Value::Float(f),
+        Expr::Str(ref s) => Value::Str(s),
+        Expr::Term(ref term) => match *term {
+            Term::Id => Value::Int(element.id as i64),
+            Term::Source => Value::Int(element.source as i64),
+            Term::Target => Value::Int(element.target as i64),
+            Term::Degree => Value::Int((element.degrees.0 + element.degrees.1) as i64),
+            Term::InDegree => Value::Int(element.degrees.0 as i64),
+            Term::OutDegree => Value::Int(element.degrees.1 as i64),
+            Term::Attr(ref key) => Value::of(lookup(element.attrs, key)),
+        },
+        Expr::Not(ref e) => Value::Bool(!eval(e, element).truthy()),
+        Expr::And(ref a, ref b) => {
+            Value::Bool(eval(a, element).truthy() && eval(b, element).truthy())
+        }
+        Expr::Or(ref a, ref b) => {
+            Value::Bool(eval(a, element).truthy() || eval(b, element).truthy())
+        }
+        Expr::Compare(op, ref a, ref b) => {
+            let order = eval(a, element).compare(&eval(b, element));
+            Value::Bool(match op {
+                "==" => order == Some(Ordering::Equal),
+                "!=" => order != Some(Ordering::Equal),
+                "<" => order == Some(Ordering::Less),
+                "<=" => matches!(order, Some(Ordering::Less | Ordering::Equal)),
+                ">" => order == Some(Ordering::Greater),
+                _ => matches!(order, Some(Ordering::Greater | Ordering::Equal)),
+            })
+        }
+    }
+}
+
+impl Query {
+    /// Parses a query.
+    pub fn parse(s: &str) -> Result<Query, &'static str> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            target: None,
+        };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err("unexpected token");
+        }
+        Ok(Query {
+            target: parser.target.ok_or("query is not about nodes or edges")?,
+            expr,
+        })
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// The nodes or edges of `doc` the query selects, in document order.
+    /// The in-degree of a node counts the edges with it as target, the
+    /// out-degree those with it as source, also in an undirected document.
+    pub fn matches<'a>(&self, doc: &'a GmlDocument) -> Vec<Match<'a>> {
+        match self.target {
+            Target::Nodes => {
+                let mut degrees: HashMap<u64, (u64, u64)> = HashMap::new();
+                for edge in &doc.edges {
+                    degrees.entry(edge.target).or_default().0 += 1;
+                    degrees.entry(edge.source).or_default().1 += 1;
+                }
+                doc.nodes
+                    .iter()
+                    .filter(|node| {
+                        let element = Element {
+                            id: node.id,
+                            source: 0,
+                            target: 0,
+                            degrees: degrees.get(&node.id).copied().unwrap_or_default(),
+                            attrs: &node.attrs,
+                        };
+                        eval(&self.expr, &element).truthy()
+                    })
+                    .map(Match::Node)
+                    .collect()
+            }
+            Target::Edges => doc
+                .edges
+                .iter()
+                .filter(|edge| {
+                    let element = Element {
+                        id: 0,
+                        source: edge.source,
+                        target: edge.target,
+                        degrees: (0, 0),
+                        attrs: &edge.attrs,
+                    };
+                    eval(&self.expr, &element).truthy()
+                })
+                .map(Match::Edge)
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn test_query() {
+    use crate::document::parse_gml_document;
+
+    let doc = parse_gml_document(
+        "graph [ directed 1
+           node [ id 1 type \"switch\" ] node [ id 2 type \"host\" label \"h\" ]
+           node [ id 3 type \"switch\" graphics [ w 2.5 ] ]
+           edge [ source 1 target 2 ] edge [ source 1 target 3 weight 4 ]
+           edge [ source 3 target 1 weight 0.5 ] ]",
+    )
+    .unwrap();
+    let ids = |query: &str| -> Vec<u64> {
+        Query::parse(query)
+            .unwrap()
+            .matches(&doc)
+            .into_iter()
+            .map(|m| match m {
+                Match::Node(node) => node.id,
+                Match::Edge(edge) => edge.source * 10 + edge.target,
+            })
+            .collect()
+    };
+    assert_eq!(
+        vec![1],
+        ids("node.degree > 2 && node.attr(\"type\") == \"switch\"")
+    );
+    assert_eq!(vec![1, 3], ids("node.attr(\"type\") == \"switch\""));
+    assert_eq!(vec![2], ids("node.label"));
+    assert_eq!(vec![1, 3], ids("!node.label"));
+    assert_eq!(vec![3], ids("node.attr(\"graphics.w\") >= 2.5"));
+    assert_eq!(
+        vec![2, 3],
+        ids("node.in_degree == 1 && (node.id != 1 || node.out_degree > 5)")
+    );
+    assert_eq!(vec![13, 31], ids("edge.attr(\"weight\") > -1"));
+    assert_eq!(vec![12, 31], ids("edge.source == 3 || edge.target == 2"));
+    assert_eq!(vec![12, 13], ids("edge.attr(\"weight\") != .5"));
+    assert_eq!(Target::Edges, Query::parse("edge.label").unwrap().target());
+    let query = Query::parse("node.attr(\"graphics.w\")").unwrap();
+    assert!(query.matches(&doc)[0]
+        .to_string()
+        .contains(" graphics [ w 2.5 ] "));
+    let query = Query::parse("edge.attr(\"weight\") == 4").unwrap();
+    assert_eq!(
+        "edge [ source 1 target 3 weight 4 ]",
+        query.matches(&doc)[0].to_string()
+    );
+
+    assert_eq!(
+        Err("query mixes nodes and edges"),
+        Query::parse("node.id == edge.source")
+    );
+    assert_eq!(Err("unknown property"), Query::parse("edge.degree > 1"));
+    assert_eq!(
+        Err("query is not about nodes or edges"),
+        Query::parse("1 == 1")
+    );
+    assert_eq!(Err("unexpected token"), Query::parse("node.id 1"));
+    assert_eq!(
+        Err("unterminated string"),
+        Query::parse("node.label == \"x")
+    );
+}
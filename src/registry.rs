@@ -0,0 +1,158 @@
+//! Node and edge weights of different kinds, chosen by an attribute.
+//!
+//! A property graph may mix routers, hosts and VLANs with different
+//! attributes. A `WeightRegistry` has a constructor for each value of the
+//! `type` attribute, and builds the weight of each element with the one its
+//! `type` names, usually a variant of an enum of the kinds.
+
+use crate::document::{parse_gml_document, GmlAttrs, GmlDocument};
+use crate::node_map::NodeMap;
+use petgraph::{Directed, Graph};
+use std::collections::HashMap;
+
+/// A constructor of weights from the attributes of an element.
+pub type Constructor<W> = Box<dyn Fn(&GmlAttrs) -> Result<W, &'static str>>;
+
+/// The constructors of weights, by the value of a string attribute.
+pub struct WeightRegistry<W> {
+    key: String,
+    constructors: HashMap<String, Constructor<W>>,
+    fallback: Option<Constructor<W>>,
+}
+
+impl<W> WeightRegistry<W> {
+    /// A registry without constructors, choosing them by `key`.
+    pub fn new(key: &str) -> WeightRegistry<W> {
+        WeightRegistry {
+            key: key.to_string(),
+            constructors: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Adds the constructor of the elements with `key` set to `value`.
+    pub fn register<F>(mut self, value: &str, constructor: F) -> WeightRegistry<W>
+    where
+        F: Fn(&GmlAttrs) -> Result<W, &'static str> + 'static,
+    {
+        self.constructors
+            .insert(value.to_string(), Box::new(constructor));
+        self
+    }
+
+    /// Sets the constructor of the elements without the key, or with a
+    /// value which has no constructor.
+    pub fn fallback<F>(mut self, constructor: F) -> WeightRegistry<W>
+    where
+        F: Fn(&GmlAttrs) -> Result<W, &'static str> + 'static,
+    {
+        self.fallback = Some(Box::new(constructor));
+        self
+    }
+
+    /// Builds the weight of an element with `attrs`. Without a fallback,
+    /// fails for an element without the key or with an unknown value.
+    pub fn build(&self, attrs: &GmlAttrs) -> Result<W, &'static str> {
+        let kind = attrs.get(&self.key).and_then(|v| v.get_str());
+        match kind.and_then(|kind| self.constructors.get(kind)) {
+            Some(constructor) => constructor(attrs),
+            None => match (&self.fallback, kind) {
+                (Some(fallback), _) => fallback(attrs),
+                (None, Some(_)) => Err("unknown weight type"),
+                (None, None) => Err("element without weight type"),
+            },
+        }
+    }
+}
+
+impl GmlDocument {
+    /// Converts the document into a petgraph `Graph` with weights built by
+    /// the registries, like `into_graph`.
+    pub fn to_graph_with<N, E>(
+        &self,
+        nodes: &WeightRegistry<N>,
+        edges: &WeightRegistry<E>,
+    ) -> Result<Graph<N, E, Directed>, &'static str> {
+        let mut node_map = NodeMap::with_capacity(self.nodes.len());
+        let mut graph = Graph::with_capacity(self.nodes.len(), self.edges.len());
+        for node in &self.nodes {
+            if !node_map.insert(node.id, graph.add_node(nodes.build(&node.attrs)?)) {
+                return Err("duplicate node-id");
+            }
+        }
+        for edge in &self.edges {
+            match (node_map.get(edge.source), node_map.get(edge.target)) {
+                (Some(source), Some(target)) => {
+                    graph.add_edge(source, target, edges.build(&edge.attrs)?);
+                }
+                _ => return Err("edge references unknown node"),
+            }
+        }
+        Ok(graph)
+    }
+}
+
+/// Parses a GML string into a graph with weights built by the registries.
+pub fn parse_gml_registry<N, E>(
+    s: &str,
+    nodes: &WeightRegistry<N>,
+    edges: &WeightRegistry<E>,
+) -> Result<Graph<N, E, Directed>, &'static str> {
+    parse_gml_document(s)?.to_graph_with(nodes, edges)
+}
+
+#[test]
+fn test_parse_gml_registry() {
+    use crate::document::GmlValue;
+    use petgraph::graph::NodeIndex;
+
+    #[derive(Debug, PartialEq)]
+    enum Device {
+        Router { ports: i64 },
+        Host { ip: String },
+        Vlan(i64),
+    }
+
+    let number = |attrs: &GmlAttrs, key| attrs.get(key).and_then(GmlValue::get_int);
+    let nodes = WeightRegistry::new("type")
+        .register("router", move |attrs| {
+            let ports = number(attrs, "ports").ok_or("router without ports")?;
+            Ok(Device::Router { ports })
+        })
+        .register("host", |attrs| {
+            let ip = attrs.get("ip").and_then(GmlValue::get_str).unwrap_or("");
+            Ok(Device::Host { ip: ip.to_string() })
+        })
+        .register("vlan", move |attrs| {
+            Ok(Device::Vlan(number(attrs, "tag").unwrap_or(1)))
+        });
+    let edges = WeightRegistry::new("type").fallback(|_| Ok(()));
+
+    let gml = "graph [ node [ id 1 type \"router\" ports 24 ]
+                       node [ id 2 type \"host\" ip \"10.0.0.2\" ] node [ id 3 type \"vlan\" tag 7 ]
+                       edge [ source 1 target 2 ] edge [ source 1 target 3 type \"trunk\" ] ]";
+    let graph = parse_gml_registry(gml, &nodes, &edges).unwrap();
+    assert_eq!(Device::Router { ports: 24 }, graph[NodeIndex::new(0)]);
+    assert_eq!(
+        Device::Host {
+            ip: "10.0.0.2".to_string()
+        },
+        graph[NodeIndex::new(1)]
+    );
+    assert_eq!(Device::Vlan(7), graph[NodeIndex::new(2)]);
+    assert_eq!(2, graph.edge_count());
+
+    let check = |gml: &str| parse_gml_registry(gml, &nodes, &edges).map(|_| ());
+    assert_eq!(
+        Err("unknown weight type"),
+        check("graph [ node [ id 1 type \"switch\" ] ]")
+    );
+    assert_eq!(
+        Err("element without weight type"),
+        check("graph [ node [ id 1 ] ]")
+    );
+    assert_eq!(
+        Err("router without ports"),
+        check("graph [ node [ id 1 type \"router\" ] ]")
+    );
+}
@@ -0,0 +1,206 @@
+//! Uniform random samples of GML graphs, taken while streaming.
+//!
+//! `parse_gml_sample` keeps a reservoir of the sampled node or edge blocks,
+//! so that it takes space for the sample only, however large the input.
+
+use crate::events::gml_events;
+use crate::node_map::NodeMap;
+use crate::{read_blocks, Block};
+use asexp::Sexp;
+use petgraph::{Directed, Graph};
+use std::collections::HashSet;
+
+/// What `parse_gml_sample` samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sample {
+    /// This many nodes and the edges between them.
+    Nodes(usize),
+    /// This many edges and the nodes at their ends.
+    Edges(usize),
+}
+
+// SplitMix64, which is enough for sampling and needs no dependency.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A number below `n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+// Algorithm R: after every offer, each of the items offered so far is in
+// the reservoir with the same probability.
+struct Reservoir<T> {
+    size: usize,
+    seen: usize,
+    // the items with their positions among the offered ones
+    items: Vec<(usize, T)>,
+}
+
+impl<T> Reservoir<T> {
+    fn new(size: usize) -> Reservoir<T> {
+        Reservoir {
+            size,
+            seen: 0,
+            items: Vec::new(),
+        }
+    }
+
+    fn offer(&mut self, item: T, rng: &mut Rng) {
+        self.seen += 1;
+        if self.items.len() < self.size {
+            self.items.push((self.seen - 1, item));
+        } else {
+            let i = rng.below(self.seen);
+            if i < self.size {
+                self.items[i] = (self.seen - 1, item);
+            }
+        }
+    }
+
+    // The items in the order they were offered in.
+    fn into_items(mut self) -> impl Iterator<Item = T> {
+        self.items.sort_by_key(|item| item.0);
+        self.items.into_iter().map(|item| item.1)
+    }
+}
+
+fn blocks(
+    s: &str,
+    nodes: bool,
+    block: impl FnMut(Block) -> Result<(), &'static str>,
+) -> Result<(), &'static str> {
+    let mut events = gml_events(s);
+    events.enter_graph()?;
+    read_blocks(&mut events, nodes, true, block)?;
+    events.finish()
+}
+
+/// Parses a uniform random sample of the graph of a GML string, with the
+/// weights of `parse_gml`. The same `seed` gives the same sample of the
+/// same input. Nodes and edges are added in document order.
+///
+/// The input is read twice: for `Nodes`, the nodes are sampled and then
+/// the edges between them added; for `Edges`, the edges are sampled and
+/// then the nodes at their ends added. If the input has fewer nodes or
+/// edges than asked for, the sample is the whole graph.
+pub fn parse_gml_sample<NodeWeightFn, EdgeWeightFn, N, E>(
+    s: &str,
+    sample: Sample,
+    seed: u64,
+    node_weight_fn: &NodeWeightFn,
+    edge_weight_fn: &EdgeWeightFn,
+) -> Result<Graph<N, E, Directed>, &'static str>
+where
+    NodeWeightFn: Fn(Option<&Sexp>) -> Option<N>,
+    EdgeWeightFn: Fn(Option<&Sexp>) -> Option<E>,
+{
+    let mut rng = Rng(seed);
+    let mut graph = Graph::new();
+    let mut node_map = NodeMap::default();
+    let mut add_node = |graph: &mut Graph<N, E>, id: u64, weight: Option<&Sexp>| {
+        let weight = node_weight_fn(weight).ok_or("invalid node weight")?;
+        if !node_map.insert(id, graph.add_node(weight)) {
+            return Err("duplicate node-id");
+        }
+        Ok(())
+    };
+
+    match sample {
+        Sample::Nodes(size) => {
+            let mut reservoir = Reservoir::new(size);
+            blocks(s, true, |block| {
+                reservoir.offer((block.id.ok_or("Invalid id")?, block.weight), &mut rng);
+                Ok(())
+            })?;
+            for (id, weight) in reservoir.into_items() {
+                add_node(&mut graph, id, weight.as_ref())?;
+            }
+            blocks(s, false, |block| {
+                let source = block.source.ok_or("Invalid source id")?;
+                let target = block.target.ok_or("Invalid target id")?;
+                if let (Some(source), Some(target)) = (node_map.get(source), node_map.get(target)) {
+                    let weight =
+                        edge_weight_fn(block.weight.as_ref()).ok_or("invalid edge weight")?;
+                    graph.add_edge(source, target, weight);
+                }
+                Ok(())
+            })?;
+        }
+        Sample::Edges(size) => {
+            let mut reservoir = Reservoir::new(size);
+            blocks(s, false, |block| {
+                let source = block.source.ok_or("Invalid source id")?;
+                let target = block.target.ok_or("Invalid target id")?;
+                reservoir.offer((source, target, block.weight), &mut rng);
+                Ok(())
+            })?;
+            let edges: Vec<_> = reservoir.into_items().collect();
+            let ends: HashSet<u64> = edges.iter().flat_map(|e| [e.0, e.1]).collect();
+            blocks(s, true, |block| {
+                let id = block.id.ok_or("Invalid id")?;
+                if ends.contains(&id) {
+                    add_node(&mut graph, id, block.weight.as_ref())?;
+                }
+                Ok(())
+            })?;
+            for (source, target, weight) in edges {
+                match (node_map.get(source), node_map.get(target)) {
+                    (Some(source), Some(target)) => {
+                        let weight =
+                            edge_weight_fn(weight.as_ref()).ok_or("invalid edge weight")?;
+                        graph.add_edge(source, target, weight);
+                    }
+                    _ => return Err("edge references unknown node"),
+                }
+            }
+        }
+    }
+    Ok(graph)
+}
+
+#[test]
+fn test_parse_gml_sample() {
+    let mut gml = String::from("graph [ directed 1 ");
+    for i in 0..100 {
+        gml.push_str(&format!("node [ id {} weight {} ] ", i, i));
+        gml.push_str(&format!("edge [ source {} target {} ] ", i, (i + 1) % 100));
+    }
+    gml.push(']');
+    let weight = |s: Option<&Sexp>| s.and_then(Sexp::get_uint).or(Some(0));
+    let ids = |g: &Graph<u64, u64>| g.raw_nodes().iter().map(|n| n.weight).collect::<Vec<_>>();
+
+    let g = parse_gml_sample(&gml, Sample::Nodes(10), 1, &weight, &weight).unwrap();
+    let sampled = ids(&g);
+    assert_eq!(10, sampled.len());
+    assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+    for edge in g.raw_edges() {
+        let (source, target) = (g[edge.source()], g[edge.target()]);
+        assert_eq!((source + 1) % 100, target);
+    }
+    let again = parse_gml_sample(&gml, Sample::Nodes(10), 1, &weight, &weight).unwrap();
+    assert_eq!(sampled, ids(&again));
+    let other = parse_gml_sample(&gml, Sample::Nodes(10), 2, &weight, &weight).unwrap();
+    assert_ne!(sampled, ids(&other));
+
+    let g = parse_gml_sample(&gml, Sample::Edges(5), 1, &weight, &weight).unwrap();
+    assert_eq!(5, g.edge_count());
+    assert!(g.node_count() <= 10);
+    let g = parse_gml_sample(&gml, Sample::Nodes(1000), 1, &weight, &weight).unwrap();
+    assert_eq!((100, 100), (g.node_count(), g.edge_count()));
+
+    let gml = "graph [ node [ id 1 ] edge [ source 1 target 2 ] ]";
+    assert_eq!(
+        Some("edge references unknown node"),
+        parse_gml_sample(gml, Sample::Edges(1), 0, &weight, &weight).err()
+    );
+}
@@ -0,0 +1,119 @@
+//! Byte scanning for the lexer.
+//!
+//! With the `simd` feature on x86_64, runs of whitespace and the ends of
+//! strings and comments are found 16 bytes at a time with SSE2. Elsewhere
+//! the scalar versions are used.
+
+fn is_whitespace(b: u8) -> bool {
+    // the ASCII characters for which `char::is_whitespace` holds
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c)
+}
+
+fn find_byte_scalar(bytes: &[u8], needle: u8) -> Option<usize> {
+    bytes.iter().position(|&b| b == needle)
+}
+
+fn whitespace_len_scalar(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|&&b| is_whitespace(b)).count()
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use std::arch::x86_64::*;
+
+    // SSE2 is part of the x86_64 baseline, so no runtime detection is
+    // needed.
+
+    pub(super) fn find_byte(bytes: &[u8], needle: u8) -> Option<usize> {
+        let mut i = 0;
+        // SAFETY: the intrinsics only need SSE2, which every x86_64 CPU
+        // has. Each load reads the 16 bytes at `i`, which are within
+        // `bytes` as `i + 16 <= bytes.len()`, and `_mm_loadu_si128` needs
+        // no alignment.
+        unsafe {
+            let n = _mm_set1_epi8(needle as i8);
+            while i + 16 <= bytes.len() {
+                let chunk = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
+                let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(chunk, n));
+                if mask != 0 {
+                    return Some(i + mask.trailing_zeros() as usize);
+                }
+                i += 16;
+            }
+        }
+        super::find_byte_scalar(&bytes[i..], needle).map(|j| i + j)
+    }
+
+    pub(super) fn whitespace_len(bytes: &[u8]) -> usize {
+        let mut i = 0;
+        // SAFETY: as in `find_byte`, SSE2 is always there and each load
+        // reads 16 bytes within `bytes`.
+        unsafe {
+            let space = _mm_set1_epi8(b' ' as i8);
+            // \t, \n, 0x0b, 0x0c and \r are 9 to 13
+            let low = _mm_set1_epi8(8);
+            let high = _mm_set1_epi8(14);
+            while i + 16 <= bytes.len() {
+                let chunk = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
+                let controls =
+                    _mm_and_si128(_mm_cmpgt_epi8(chunk, low), _mm_cmplt_epi8(chunk, high));
+                let ws = _mm_or_si128(_mm_cmpeq_epi8(chunk, space), controls);
+                let mask = !_mm_movemask_epi8(ws) & 0xffff;
+                if mask != 0 {
+                    return i + mask.trailing_zeros() as usize;
+                }
+                i += 16;
+            }
+        }
+        i + super::whitespace_len_scalar(&bytes[i..])
+    }
+}
+
+/// The offset of the first `needle` in `bytes`.
+pub(crate) fn find_byte(bytes: &[u8], needle: u8) -> Option<usize> {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return sse2::find_byte(bytes, needle);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    find_byte_scalar(bytes, needle)
+}
+
+/// The length of the run of ASCII whitespace at the start of `bytes`.
+pub(crate) fn whitespace_len(bytes: &[u8]) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return sse2::whitespace_len(bytes);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    whitespace_len_scalar(bytes)
+}
+
+#[test]
+fn test_scan() {
+    let mut text = " \t\r\n".repeat(9).into_bytes();
+    text.extend_from_slice("\x0b\x0cnode [ label \"a b\" ]\n# end".as_bytes());
+    for from in 0..text.len() {
+        let bytes = &text[from..];
+        assert_eq!(whitespace_len_scalar(bytes), whitespace_len(bytes));
+        for &needle in b"\"\n#x" {
+            assert_eq!(find_byte_scalar(bytes, needle), find_byte(bytes, needle));
+        }
+    }
+    assert_eq!(38, whitespace_len(&text));
+    // the first match at every offset of up to three chunks, after a run
+    // of non-matching bytes, so that the SIMD loop and the scalar tail
+    // meet at each chunk boundary
+    for len in 0..48 {
+        for at in 0..=len {
+            let mut bytes = vec![b' '; len];
+            if at < len {
+                bytes[at] = b'"';
+            }
+            assert_eq!(whitespace_len_scalar(&bytes), whitespace_len(&bytes));
+            assert_eq!(find_byte_scalar(&bytes, b'"'), find_byte(&bytes, b'"'));
+            bytes
+                .iter_mut()
+                .for_each(|b| *b = if *b == b' ' { b'x' } else { b'\n' });
+            assert_eq!(find_byte_scalar(&bytes, b'\n'), find_byte(&bytes, b'\n'));
+        }
+    }
+    // non-ASCII bytes are not whitespace: their sign bit is set
+    assert_eq!(1, whitespace_len(" \u{a0}                 ".as_bytes()));
+}
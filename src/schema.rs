@@ -0,0 +1,516 @@
+//! Schemas for the attributes of GML documents.
+//!
+//! A `Schema` lists the attributes the graph, its nodes and its edges must
+//! or may have, with their types, ranges and allowed values, and can add
+//! rules for the nodes and edges of each value of a `type` attribute.
+//! `validate_against` reports where a document does not follow it, and
+//! `infer_schema` describes the attributes a document has.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlValue};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The type of the values of an attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrType {
+    Int,
+    Float,
+    /// An integer or a real.
+    Number,
+    Str,
+    List,
+    Any,
+}
+
+impl AttrType {
+    fn matches(self, value: &GmlValue) -> bool {
+        matches!(
+            (self, value),
+            (AttrType::Any, _)
+                | (AttrType::Int, GmlValue::Int(_))
+                | (AttrType::Float, GmlValue::Float(_))
+                | (AttrType::Number, GmlValue::Int(_))
+                | (AttrType::Number, GmlValue::Float(_))
+                | (AttrType::Str, GmlValue::Str(_))
+                | (AttrType::List, GmlValue::List(_))
+        )
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AttrType::Int => "an integer",
+            AttrType::Float => "a real",
+            AttrType::Number => "a number",
+            AttrType::Str => "a string",
+            AttrType::List => "a list",
+            AttrType::Any => "any value",
+        }
+    }
+
+    // The narrowest type of the values of both types.
+    fn unify(self, other: AttrType) -> AttrType {
+        use self::AttrType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Int, Float) | (Float, Int) | (Number, Int) | (Number, Float) => Number,
+            (Int, Number) | (Float, Number) => Number,
+            _ => Any,
+        }
+    }
+
+    fn of(value: &GmlValue) -> AttrType {
+        match *value {
+            GmlValue::Int(_) | GmlValue::UInt(_) => AttrType::Int,
+            GmlValue::Float(_) => AttrType::Float,
+            GmlValue::Str(_) => AttrType::Str,
+            GmlValue::List(_) => AttrType::List,
+        }
+    }
+}
+
+/// The rule for one attribute. `key` may name an attribute of a nested
+/// list with a dotted path, like `graphics.fill`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttrRule {
+    pub key: String,
+    pub value_type: AttrType,
+    pub required: bool,
+    /// The smallest number the attribute may be.
+    pub min: Option<f64>,
+    /// The largest number the attribute may be.
+    pub max: Option<f64>,
+    /// The values the attribute may take, or any if empty.
+    pub allowed: Vec<GmlValue>,
+    /// Values of the attribute, as `infer_schema` found them. They are not
+    /// checked.
+    pub examples: Vec<GmlValue>,
+}
+
+impl AttrRule {
+    /// An optional attribute of type `value_type`.
+    pub fn new(key: &str, value_type: AttrType) -> AttrRule {
+        AttrRule {
+            key: key.to_string(),
+            value_type,
+            required: false,
+            min: None,
+            max: None,
+            allowed: Vec::new(),
+            examples: Vec::new(),
+        }
+    }
+
+    pub fn required(mut self) -> AttrRule {
+        self.required = true;
+        self
+    }
+
+    pub fn range(mut self, min: f64, max: f64) -> AttrRule {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn one_of<I, V>(mut self, values: I) -> AttrRule
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<GmlValue>,
+    {
+        self.allowed = values.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// The rules for the attributes of the graph, of the nodes or of the edges.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElementSchema {
+    pub attrs: Vec<AttrRule>,
+    /// Rejects the attributes without a rule.
+    pub closed: bool,
+}
+
+/// The attributes a document must have.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub graph: ElementSchema,
+    pub nodes: ElementSchema,
+    pub edges: ElementSchema,
+    /// The attribute whose string value selects the rules of `node_types`
+    /// and `edge_types`, `type` by default.
+    pub type_key: String,
+    /// Rules for the nodes of each type, in addition to those of `nodes`.
+    /// If there are any, nodes of other types are rejected.
+    pub node_types: BTreeMap<String, ElementSchema>,
+    /// Rules for the edges of each type, like `node_types`.
+    pub edge_types: BTreeMap<String, ElementSchema>,
+}
+
+impl Default for Schema {
+    fn default() -> Schema {
+        Schema {
+            graph: ElementSchema::default(),
+            nodes: ElementSchema::default(),
+            edges: ElementSchema::default(),
+            type_key: "type".to_string(),
+            node_types: BTreeMap::new(),
+            edge_types: BTreeMap::new(),
+        }
+    }
+}
+
+/// The part of a document a `Violation` is found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element {
+    Graph,
+    /// The node with this id.
+    Node(u64),
+    /// The edge with this index in `GmlDocument::edges`.
+    Edge(usize),
+}
+
+/// An attribute which does not follow a `Schema`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub element: Element,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.element {
+            Element::Graph => write!(f, "graph")?,
+            Element::Node(id) => write!(f, "node {}", id)?,
+            Element::Edge(i) => write!(f, "edge {}", i)?,
+        }
+        write!(f, ": `{}` {}", self.key, self.message)
+    }
+}
+
+fn lookup<'a>(attrs: &'a GmlAttrs, key: &str) -> Option<&'a GmlValue> {
+    let mut parts = key.split('.');
+    let mut value = attrs.get(parts.next()?)?;
+    for part in parts {
+        value = value.get(part)?;
+    }
+    Some(value)
+}
+
+fn show(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) => f.to_string(),
+        GmlValue::Str(ref s) => format!("\"{}\"", s),
+        GmlValue::List(_) => "a list".to_string(),
+    }
+}
+
+// The problem of `value` with `rule`, if any.
+fn check(rule: &AttrRule, value: &GmlValue) -> Option<String> {
+    if !rule.value_type.matches(value) {
+        return Some(format!(
+            "is {}, not {}",
+            AttrType::of(value).name(),
+            rule.value_type.name()
+        ));
+    }
+    if let Some(n) = value.get_float() {
+        if rule.min.is_some_and(|min| n < min) || rule.max.is_some_and(|max| n > max) {
+            let bound = |b: Option<f64>| b.map_or("any".to_string(), |b| b.to_string());
+            return Some(format!(
+                "is {}, outside of {} to {}",
+                show(value),
+                bound(rule.min),
+                bound(rule.max)
+            ));
+        }
+    }
+    if !rule.allowed.is_empty() && !rule.allowed.contains(value) {
+        let allowed: Vec<_> = rule.allowed.iter().map(show).collect();
+        return Some(format!(
+            "is {}, not one of {}",
+            show(value),
+            allowed.join(", ")
+        ));
+    }
+    None
+}
+
+// Checks `attrs` against `base` and, if the element has a type, against the
+// rules of the type in `types`.
+fn check_element(
+    element: Element,
+    attrs: &GmlAttrs,
+    base: &ElementSchema,
+    types: &BTreeMap<String, ElementSchema>,
+    type_key: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let mut violation = |key: &str, message: String| {
+        violations.push(Violation {
+            element,
+            key: key.to_string(),
+            message,
+        })
+    };
+    let typed = match attrs.get(type_key) {
+        _ if types.is_empty() => None,
+        Some(GmlValue::Str(ty)) => match types.get(&ty[..]) {
+            Some(schema) => Some(schema),
+            None => {
+                violation(type_key, format!("is the unknown type \"{}\"", ty));
+                return;
+            }
+        },
+        Some(value) => {
+            violation(
+                type_key,
+                format!("is {}, not a string", AttrType::of(value).name()),
+            );
+            return;
+        }
+        None => None,
+    };
+    let schemas = [Some(base), typed];
+    let rules = || schemas.iter().flatten().flat_map(|schema| &schema.attrs);
+    for rule in rules() {
+        match lookup(attrs, &rule.key) {
+            Some(value) => {
+                if let Some(message) = check(rule, value) {
+                    violation(&rule.key, message);
+                }
+            }
+            None if rule.required => violation(&rule.key, "is missing".to_string()),
+            None => {}
+        }
+    }
+    if schemas.iter().flatten().any(|schema| schema.closed) {
+        for key in attrs.keys() {
+            let known = key == type_key && typed.is_some()
+                || rules().any(|rule| rule.key.split('.').next() == Some(key.as_str()));
+            if !known {
+                violation(key, "is not in the schema".to_string());
+            }
+        }
+    }
+}
+
+/// Checks the attributes of `doc` against `schema`, returning where they do
+/// not follow it, or nothing if they do.
+pub fn validate_against(doc: &GmlDocument, schema: &Schema) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let no_types = BTreeMap::new();
+    check_element(
+        Element::Graph,
+        &doc.attrs,
+        &schema.graph,
+        &no_types,
+        &schema.type_key,
+        &mut violations,
+    );
+    for node in &doc.nodes {
+        check_element(
+            Element::Node(node.id),
+            &node.attrs,
+            &schema.nodes,
+            &schema.node_types,
+            &schema.type_key,
+            &mut violations,
+        );
+    }
+    for (i, edge) in doc.edges.iter().enumerate() {
+        check_element(
+            Element::Edge(i),
+            &edge.attrs,
+            &schema.edges,
+            &schema.edge_types,
+            &schema.type_key,
+            &mut violations,
+        );
+    }
+    violations
+}
+
+// The number of example values `infer_schema` keeps for an attribute.
+const EXAMPLES: usize = 3;
+
+fn infer<'a>(elements: impl Iterator<Item = &'a GmlAttrs>) -> ElementSchema {
+    let mut rules: Vec<AttrRule> = Vec::new();
+    let mut index = BTreeMap::new();
+    // the number of elements with each attribute
+    let mut counts = Vec::new();
+    let mut n = 0;
+    for attrs in elements {
+        n += 1;
+        for (key, value) in flatten(attrs) {
+            let value_type = AttrType::of(value);
+            let i = *index.entry(key).or_insert_with_key(|key| {
+                rules.push(AttrRule::new(key, value_type));
+                counts.push(0);
+                rules.len() - 1
+            });
+            counts[i] += 1;
+            let rule = &mut rules[i];
+            rule.value_type = rule.value_type.unify(value_type);
+            if let Some(f) = value.get_float() {
+                rule.min = Some(rule.min.map_or(f, |min| min.min(f)));
+                rule.max = Some(rule.max.map_or(f, |max| max.max(f)));
+            }
+            if rule.examples.len() < EXAMPLES && !rule.examples.contains(value) {
+                rule.examples.push(value.clone());
+            }
+        }
+    }
+    for (rule, count) in rules.iter_mut().zip(counts) {
+        rule.required = count == n;
+        if let AttrType::Any = rule.value_type {
+            rule.min = None;
+            rule.max = None;
+        }
+    }
+    ElementSchema {
+        attrs: rules,
+        closed: false,
+    }
+}
+
+/// Describes the attributes of `doc`: every key of the graph, of its nodes
+/// and of its edges, with the attributes of nested lists as dotted paths,
+/// in the order they are first found. For each, the schema has the type of
+/// all its values, whether every element has it, the range of its numbers
+/// and a few example values.
+///
+/// `doc` follows the schema, which is a starting point for a stricter one.
+pub fn infer_schema(doc: &GmlDocument) -> Schema {
+    Schema {
+        graph: infer(std::iter::once(&doc.attrs)),
+        nodes: infer(doc.nodes.iter().map(|node| &node.attrs)),
+        edges: infer(doc.edges.iter().map(|edge| &edge.attrs)),
+        ..Schema::default()
+    }
+}
+
+#[test]
+fn test_validate_against() {
+    use crate::document::parse_gml_document;
+
+    let gml = "graph [ label \"g\" \
+               node [ id 1 type \"person\" name \"a\" age 30 graphics [ fill \"red\" ] ] \
+               node [ id 2 type \"person\" age 300 ] \
+               node [ id 3 type \"city\" name \"b\" extra 1 ] \
+               node [ id 4 type \"planet\" name \"c\" ] \
+               edge [ source 1 target 3 kind \"lives\" weight 1 ] \
+               edge [ source 2 target 3 kind \"works\" ] ]";
+    let doc = parse_gml_document(gml).unwrap();
+    let mut schema = Schema::default();
+    schema
+        .graph
+        .attrs
+        .push(AttrRule::new("label", AttrType::Str).required());
+    schema
+        .nodes
+        .attrs
+        .push(AttrRule::new("name", AttrType::Str).required());
+    schema
+        .nodes
+        .attrs
+        .push(AttrRule::new("graphics.fill", AttrType::Str).one_of(vec!["red"]));
+    let person = ElementSchema {
+        attrs: vec![AttrRule::new("age", AttrType::Int).range(0.0, 150.0)],
+        closed: false,
+    };
+    schema.node_types.insert("person".to_string(), person);
+    let city = ElementSchema {
+        attrs: Vec::new(),
+        closed: true,
+    };
+    schema.node_types.insert("city".to_string(), city);
+    schema
+        .edges
+        .attrs
+        .push(AttrRule::new("kind", AttrType::Str).one_of(vec!["lives"]));
+    schema
+        .edges
+        .attrs
+        .push(AttrRule::new("weight", AttrType::Number));
+
+    let violations: Vec<_> = validate_against(&doc, &schema)
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(
+        vec![
+            "node 2: `name` is missing",
+            "node 2: `age` is 300, outside of 0 to 150",
+            "node 3: `extra` is not in the schema",
+            "node 4: `type` is the unknown type \"planet\"",
+            "edge 1: `kind` is \"works\", not one of \"lives\"",
+        ],
+        violations
+    );
+
+    schema.graph.attrs[0].value_type = AttrType::Int;
+    assert_eq!(
+        Violation {
+            element: Element::Graph,
+            key: "label".to_string(),
+            message: "is a string, not an integer".to_string()
+        },
+        validate_against(&doc, &schema)[0]
+    );
+}
+
+#[test]
+fn test_infer_schema() {
+    use crate::document::parse_gml_document;
+
+    let gml = "graph [ node [ id 1 label \"a\" x 1 graphics [ w 2 ] ] \
+               node [ id 2 label \"b\" x 2.5 ] node [ id 3 label \"a\" x \"?\" ] \
+               node [ id 4 label \"c\" x 1 ] edge [ source 1 target 2 w 3 ] ]";
+    let doc = parse_gml_document(gml).unwrap();
+    let schema = infer_schema(&doc);
+    assert!(schema.graph.attrs.is_empty());
+    let mut keys: Vec<_> = schema
+        .nodes
+        .attrs
+        .iter()
+        .map(|rule| &rule.key[..])
+        .collect();
+    keys.sort();
+    assert_eq!(vec!["graphics.w", "label", "x"], keys);
+    let rule = |key| {
+        schema
+            .nodes
+            .attrs
+            .iter()
+            .find(|rule| rule.key == key)
+            .unwrap()
+    };
+    assert_eq!(
+        (AttrType::Str, true),
+        (rule("label").value_type, rule("label").required)
+    );
+    assert_eq!(
+        vec![
+            GmlValue::from("a"),
+            GmlValue::from("b"),
+            GmlValue::from("c")
+        ],
+        rule("label").examples
+    );
+    assert_eq!((AttrType::Any, None), (rule("x").value_type, rule("x").min));
+    let w = rule("graphics.w");
+    assert_eq!(
+        (AttrType::Int, false, Some(2.0)),
+        (w.value_type, w.required, w.max)
+    );
+    assert_eq!(
+        (AttrType::Int, true),
+        (
+            schema.edges.attrs[0].value_type,
+            schema.edges.attrs[0].required
+        )
+    );
+    assert!(validate_against(&doc, &schema).is_empty());
+}
@@ -0,0 +1,263 @@
+//! The commands of `gml shell`, for looking around in a graph.
+//!
+//! ```text
+//! node 42
+//! neighbors 42
+//! find label=~"foo"
+//! find type="switch"
+//! stats
+//! ```
+//!
+//! `GmlShell` works on a `LazyDocument`, so only the attributes of the
+//! nodes a command looks at are parsed.
+
+use crate::document::{GmlAttrs, GmlNode, GmlValue};
+use crate::lazy::LazyDocument;
+use crate::query::Match;
+use crate::stats::summarize;
+use crate::writer::format_scalar;
+use petgraph::{Directed, Graph, Undirected};
+use std::collections::HashMap;
+
+/// The help text of the shell commands.
+pub const HELP: &str = "commands:
+    node ID            the node with id ID
+    neighbors ID       the nodes ID has an edge to (->), from (<-) or with (--)
+    find KEY=VALUE     the nodes whose attribute KEY is VALUE
+    find KEY=~TEXT     the nodes whose attribute KEY contains TEXT
+    find KEY           the nodes with an attribute KEY
+    stats              a summary of the graph
+    help               this text
+    quit               leave the shell
+KEY may be dotted, like graphics.fill, and VALUE and TEXT quoted.";
+
+/// A graph loaded for `gml shell`, with its nodes and edges indexed by id.
+pub struct GmlShell<'a> {
+    doc: LazyDocument<'a>,
+    // the position of each node in `doc.nodes`
+    nodes: HashMap<u64, usize>,
+    // the positions of the edges in `doc.edges`, by source and by target
+    out_edges: HashMap<u64, Vec<usize>>,
+    in_edges: HashMap<u64, Vec<usize>>,
+}
+
+// The value at the dotted `key` of `attrs`.
+fn lookup<'v>(attrs: &'v GmlAttrs, key: &str) -> Option<&'v GmlValue> {
+    let mut parts = key.split('.');
+    let mut value = attrs.get(parts.next()?)?;
+    for part in parts {
+        value = value.get(part)?;
+    }
+    Some(value)
+}
+
+// A scalar as it is compared by `find`: strings without quotes.
+fn text(value: &GmlValue) -> Option<String> {
+    match *value {
+        GmlValue::Str(ref s) => Some(s.to_string()),
+        GmlValue::List(_) => None,
+        ref scalar => Some(format_scalar(scalar)),
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+impl<'a> GmlShell<'a> {
+    pub fn new(doc: LazyDocument<'a>) -> GmlShell<'a> {
+        let nodes = doc
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id, i))
+            .collect();
+        let (mut out_edges, mut in_edges) = (HashMap::new(), HashMap::new());
+        for (i, edge) in doc.edges.iter().enumerate() {
+            out_edges
+                .entry(edge.source)
+                .or_insert_with(Vec::new)
+                .push(i);
+            in_edges.entry(edge.target).or_insert_with(Vec::new).push(i);
+        }
+        GmlShell {
+            doc,
+            nodes,
+            out_edges,
+            in_edges,
+        }
+    }
+
+    fn node(&self, id: &str) -> Result<(u64, &GmlAttrs), &'static str> {
+        let id = id.trim().parse().map_err(|_| "invalid node id")?;
+        let &i = self.nodes.get(&id).ok_or("unknown node")?;
+        Ok((id, self.doc.nodes[i].attrs.get()?))
+    }
+
+    // The id of a node, with its label if it has one.
+    fn describe(&self, id: u64) -> Result<String, &'static str> {
+        let label = match self.nodes.get(&id) {
+            Some(&i) => self.doc.nodes[i].attrs.get()?.get("label"),
+            None => None,
+        };
+        Ok(match label {
+            Some(label) => format!("{} label {}", id, format_scalar(label)),
+            None => id.to_string(),
+        })
+    }
+
+    fn neighbors(&self, id: u64) -> Result<Vec<String>, &'static str> {
+        let mut lines = Vec::new();
+        let (out, into) = if self.doc.directed {
+            ("->", "<-")
+        } else {
+            ("--", "--")
+        };
+        for &i in self.out_edges.get(&id).into_iter().flatten() {
+            lines.push(format!(
+                "{} {}",
+                out,
+                self.describe(self.doc.edges[i].target)?
+            ));
+        }
+        for &i in self.in_edges.get(&id).into_iter().flatten() {
+            let edge = &self.doc.edges[i];
+            // an undirected self-loop is listed once
+            if self.doc.directed || edge.source != edge.target {
+                lines.push(format!("{} {}", into, self.describe(edge.source)?));
+            }
+        }
+        Ok(lines)
+    }
+
+    fn find(&self, condition: &str) -> Result<Vec<String>, &'static str> {
+        let (key, value, contains) = if let Some((key, text)) = condition.split_once("=~") {
+            (key, Some(unquote(text)), true)
+        } else if let Some((key, value)) = condition.split_once('=') {
+            (key, Some(unquote(value)), false)
+        } else {
+            (condition, None, false)
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err("missing key");
+        }
+        let mut lines = Vec::new();
+        for node in &self.doc.nodes {
+            let found = match (lookup(node.attrs.get()?, key), value) {
+                (Some(_), None) => true,
+                (Some(found), Some(value)) => text(found).is_some_and(|found| {
+                    if contains {
+                        found.contains(value)
+                    } else {
+                        found == value
+                    }
+                }),
+                (None, _) => false,
+            };
+            if found {
+                lines.push(self.describe(node.id)?);
+            }
+        }
+        Ok(lines)
+    }
+
+    fn stats(&self) -> String {
+        let mut graph =
+            Graph::<(), (), Directed>::with_capacity(self.doc.nodes.len(), self.doc.edges.len());
+        let indices: HashMap<u64, _> = self
+            .doc
+            .nodes
+            .iter()
+            .map(|node| (node.id, graph.add_node(())))
+            .collect();
+        for edge in &self.doc.edges {
+            if let (Some(&a), Some(&b)) = (indices.get(&edge.source), indices.get(&edge.target)) {
+                graph.add_edge(a, b, ());
+            }
+        }
+        if self.doc.directed {
+            summarize(&graph).to_string()
+        } else {
+            summarize(&graph.into_edge_type::<Undirected>()).to_string()
+        }
+    }
+
+    /// Runs the command `line` and returns its output, without a trailing
+    /// line break. `quit` is left to the caller.
+    pub fn run(&self, line: &str) -> Result<String, &'static str> {
+        let line = line.trim();
+        let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match command {
+            "node" => {
+                let (id, attrs) = self.node(args)?;
+                let node = GmlNode {
+                    id,
+                    attrs: attrs.clone(),
+                };
+                Ok(Match::Node(&node).to_string())
+            }
+            "neighbors" => {
+                let (id, _) = self.node(args)?;
+                Ok(self.neighbors(id)?.join("\n"))
+            }
+            "find" => Ok(self.find(args)?.join("\n")),
+            "stats" if args.is_empty() => Ok(self.stats()),
+            "help" => Ok(HELP.to_string()),
+            "" => Ok(String::new()),
+            _ => Err("unknown command, try help"),
+        }
+    }
+}
+
+#[test]
+fn test_gml_shell() {
+    use crate::lazy::parse_gml_document_lazy;
+
+    let gml = "graph [ directed 1
+               node [ id 1 label \"foo\" type \"switch\" ]
+               node [ id 2 label \"food\" graphics [ fill \"#f00\" ] ]
+               node [ id 3 weight 2 ]
+               edge [ source 1 target 2 ] edge [ source 3 target 1 ] ]";
+    let shell = GmlShell::new(parse_gml_document_lazy(gml).unwrap());
+    assert_eq!(
+        Ok("node [ id 1 label \"foo\" type \"switch\" ]".to_string()),
+        shell.run("node 1")
+    );
+    assert_eq!(Err("unknown node"), shell.run("node 42"));
+    assert_eq!(Err("invalid node id"), shell.run("neighbors x"));
+    assert_eq!(
+        Ok("-> 2 label \"food\"\n<- 3".to_string()),
+        shell.run("neighbors 1")
+    );
+    assert_eq!(
+        Ok("1 label \"foo\"\n2 label \"food\"".to_string()),
+        shell.run("find label=~\"foo\"")
+    );
+    assert_eq!(
+        Ok("1 label \"foo\"".to_string()),
+        shell.run("find label=foo")
+    );
+    assert_eq!(Ok("3".to_string()), shell.run("find weight = 2"));
+    assert_eq!(
+        Ok("2 label \"food\"".to_string()),
+        shell.run("find graphics")
+    );
+    assert_eq!(
+        Ok("2 label \"food\"".to_string()),
+        shell.run("find graphics.fill=\"#f00\"")
+    );
+    assert!(shell
+        .run("stats")
+        .unwrap()
+        .contains("nodes:                3"));
+    assert_eq!(Err("unknown command, try help"), shell.run("nodes"));
+    assert_eq!(Ok(String::new()), shell.run("  "));
+
+    let shell = GmlShell::new(parse_gml_document_lazy(gml).unwrap());
+    shell.run("node 1").unwrap();
+    assert!(!shell.doc.nodes[2].attrs.is_parsed());
+}
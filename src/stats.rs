@@ -0,0 +1,146 @@
+//! Summaries of graphs.
+//!
+//! `summarize` reports the size, degrees, components and density of a
+//! graph, for checking what a file contains.
+
+use petgraph::algo::{connected_components, kosaraju_scc};
+use petgraph::graph::{Graph, IndexType};
+use petgraph::EdgeType;
+use std::fmt;
+
+/// The summary `summarize` returns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphSummary {
+    pub nodes: usize,
+    pub edges: usize,
+    pub directed: bool,
+    pub self_loops: usize,
+    /// The number of nodes of each degree, indexed by the degree. In a
+    /// directed graph, the degree is the sum of the in- and out-degree; a
+    /// self-loop adds 2.
+    pub degree_distribution: Vec<usize>,
+    pub min_degree: usize,
+    pub max_degree: usize,
+    pub mean_degree: f64,
+    /// The number of connected components, or of weakly connected ones in
+    /// a directed graph.
+    pub connected_components: usize,
+    /// The number of strongly connected components of a directed graph.
+    pub strongly_connected_components: Option<usize>,
+    /// The edges divided by the edges of a complete graph without
+    /// self-loops, or 0 for fewer than 2 nodes.
+    pub density: f64,
+}
+
+/// Summarizes `graph`.
+pub fn summarize<N, E, Ty: EdgeType, Ix: IndexType>(graph: &Graph<N, E, Ty, Ix>) -> GraphSummary {
+    let (n, m) = (graph.node_count(), graph.edge_count());
+    let mut degrees = vec![0; n];
+    let mut self_loops = 0;
+    for edge in graph.raw_edges() {
+        let (source, target) = (edge.source().index(), edge.target().index());
+        degrees[source] += 1;
+        degrees[target] += 1;
+        if source == target {
+            self_loops += 1;
+        }
+    }
+    let max_degree = degrees.iter().copied().max().unwrap_or(0);
+    let mut degree_distribution = vec![0; if n == 0 { 0 } else { max_degree + 1 }];
+    for &degree in &degrees {
+        degree_distribution[degree] += 1;
+    }
+    let pairs = n as f64 * n.saturating_sub(1) as f64;
+    let density = match (n, graph.is_directed()) {
+        (0..=1, _) => 0.0,
+        (_, true) => m as f64 / pairs,
+        (_, false) => 2.0 * m as f64 / pairs,
+    };
+    GraphSummary {
+        nodes: n,
+        edges: m,
+        directed: graph.is_directed(),
+        self_loops,
+        degree_distribution,
+        min_degree: degrees.iter().copied().min().unwrap_or(0),
+        max_degree,
+        mean_degree: if n == 0 {
+            0.0
+        } else {
+            2.0 * m as f64 / n as f64
+        },
+        connected_components: connected_components(graph),
+        strongly_connected_components: if graph.is_directed() {
+            Some(kosaraju_scc(graph).len())
+        } else {
+            None
+        },
+        density,
+    }
+}
+
+impl fmt::Display for GraphSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "directed:             {}", self.directed)?;
+        writeln!(f, "nodes:                {}", self.nodes)?;
+        writeln!(f, "edges:                {}", self.edges)?;
+        writeln!(f, "self-loops:           {}", self.self_loops)?;
+        writeln!(
+            f,
+            "degree:               min {}, max {}, mean {:.3}",
+            self.min_degree, self.max_degree, self.mean_degree
+        )?;
+        let distribution: Vec<_> = self
+            .degree_distribution
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(degree, count)| format!("{}: {}", degree, count))
+            .collect();
+        writeln!(f, "degree distribution:  {}", distribution.join(", "))?;
+        if self.directed {
+            writeln!(f, "weak components:      {}", self.connected_components)?;
+        } else {
+            writeln!(f, "components:           {}", self.connected_components)?;
+        }
+        if let Some(count) = self.strongly_connected_components {
+            writeln!(f, "strong components:    {}", count)?;
+        }
+        write!(f, "density:              {:.6}", self.density)
+    }
+}
+
+#[test]
+fn test_summarize() {
+    use petgraph::{Directed, Undirected};
+
+    let mut graph = Graph::<(), (), Directed>::new();
+    let nodes: Vec<_> = (0..5).map(|_| graph.add_node(())).collect();
+    graph.extend_with_edges([
+        (nodes[0], nodes[1]),
+        (nodes[1], nodes[0]),
+        (nodes[1], nodes[2]),
+    ]);
+    graph.add_edge(nodes[3], nodes[3], ());
+    let summary = summarize(&graph);
+    assert_eq!(
+        (5, 4, 1),
+        (summary.nodes, summary.edges, summary.self_loops)
+    );
+    assert_eq!(vec![1, 1, 2, 1], summary.degree_distribution);
+    assert_eq!(
+        (0, 3, 1.6),
+        (summary.min_degree, summary.max_degree, summary.mean_degree)
+    );
+    assert_eq!(3, summary.connected_components);
+    assert_eq!(Some(4), summary.strongly_connected_components);
+    assert_eq!(0.2, summary.density);
+    assert!(summary.to_string().contains("strong components:    4"));
+
+    let summary = summarize(&graph.into_edge_type::<Undirected>());
+    assert_eq!(None, summary.strongly_connected_components);
+    assert_eq!(0.4, summary.density);
+    let summary = summarize(&Graph::<(), ()>::new());
+    assert!(summary.degree_distribution.is_empty());
+    assert_eq!((0, 0.0), (summary.max_degree, summary.density));
+}
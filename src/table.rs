@@ -0,0 +1,318 @@
+//! Columnar node and edge tables, a neutral export for columnar tools.
+//!
+//! Each column holds unsigned or signed integers, floats or strings, with
+//! `None` for nulls, i.e. the `UInt64`, `Int32`, `Int64`, `Float64` and
+//! `Utf8` types of Arrow.
+//!
+//! There is deliberately no `arrow` feature writing record batches or
+//! Parquet files: `arrow` and `parquet` pull in a large dependency tree
+//! and release a breaking version every few weeks, which would pin this
+//! crate to one of them. Each `Column` converts into the matching Arrow
+//! array, e.g. with `UInt64Array::from(v)` or `StringArray::from(v)`, and
+//! a record batch of those is written to Parquet with `ArrowWriter`.
+//!
+//! For the same reason there is no `polars` feature with DataFrames.
+//! `to_tables` and `from_tables` take the place of the two conversions,
+//! and each column's vector becomes a series with `Series::new(name, v)`.
+
+use crate::document::{
+    flatten, insert_dotted, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue, ValueType,
+};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    UInt64(Vec<Option<u64>>),
+    Int32(Vec<Option<i32>>),
+    Int64(Vec<Option<i64>>),
+    Float64(Vec<Option<f64>>),
+    Utf8(Vec<Option<String>>),
+}
+
+fn text(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) => f.to_string(),
+        GmlValue::Str(ref s) => s.to_string(),
+        GmlValue::List(_) => unreachable!(),
+    }
+}
+
+impl Column {
+    pub fn len(&self) -> usize {
+        match *self {
+            Column::UInt64(ref v) => v.len(),
+            Column::Int32(ref v) => v.len(),
+            Column::Int64(ref v) => v.len(),
+            Column::Float64(ref v) => v.len(),
+            Column::Utf8(ref v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn null_count(&self) -> usize {
+        match *self {
+            Column::UInt64(ref v) => v.iter().filter(|x| x.is_none()).count(),
+            Column::Int32(ref v) => v.iter().filter(|x| x.is_none()).count(),
+            Column::Int64(ref v) => v.iter().filter(|x| x.is_none()).count(),
+            Column::Float64(ref v) => v.iter().filter(|x| x.is_none()).count(),
+            Column::Utf8(ref v) => v.iter().filter(|x| x.is_none()).count(),
+        }
+    }
+
+    // The value of row `i`, `None` for nulls. Unsigned values above
+    // `i64::MAX` become `GmlValue::UInt`.
+    fn value(&self, i: usize) -> Option<GmlValue> {
+        match *self {
+            Column::UInt64(ref v) => v[i].map(|x| match i64::try_from(x) {
+                Ok(x) => GmlValue::Int(x),
+                Err(_) => GmlValue::UInt(x),
+            }),
+            Column::Int32(ref v) => v[i].map(|x| GmlValue::Int(x as i64)),
+            Column::Int64(ref v) => v[i].map(GmlValue::Int),
+            Column::Float64(ref v) => v[i].map(GmlValue::Float),
+            Column::Utf8(ref v) => v[i].clone().map(GmlValue::from),
+        }
+    }
+
+    fn id(&self, i: usize) -> Result<u64, &'static str> {
+        if let Column::UInt64(ref v) = *self {
+            return v[i].ok_or("null node-id");
+        }
+        match self.value(i) {
+            Some(value) => value.get_uint().ok_or("invalid node-id"),
+            None => Err("null node-id"),
+        }
+    }
+
+    fn with_type(ty: ValueType, values: Vec<Option<&GmlValue>>) -> Column {
+        match ty {
+            ValueType::Int => Column::Int32(
+                values
+                    .into_iter()
+                    .map(|v| v.and_then(GmlValue::get_int).map(|i| i as i32))
+                    .collect(),
+            ),
+            ValueType::Long => Column::Int64(
+                values
+                    .into_iter()
+                    .map(|v| v.and_then(GmlValue::get_int))
+                    .collect(),
+            ),
+            ValueType::UnsignedLong => Column::UInt64(
+                values
+                    .into_iter()
+                    .map(|v| v.and_then(GmlValue::get_uint))
+                    .collect(),
+            ),
+            ValueType::Double => Column::Float64(
+                values
+                    .into_iter()
+                    .map(|v| v.and_then(GmlValue::get_float))
+                    .collect(),
+            ),
+            ValueType::String => Column::Utf8(values.into_iter().map(|v| v.map(text)).collect()),
+        }
+    }
+}
+
+/// A table of named columns of equal length.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Table {
+    pub columns: Vec<(String, Column)>,
+}
+
+impl Table {
+    pub fn num_rows(&self) -> usize {
+        self.columns.first().map_or(0, |(_, c)| c.len())
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|(n, _)| n == name).map(|(_, c)| c)
+    }
+
+    fn key_column(&self, name: &str) -> Result<&Column, &'static str> {
+        self.column(name).ok_or("missing column")
+    }
+
+    fn check_lengths(&self) -> Result<(), &'static str> {
+        if self.columns.iter().any(|(_, c)| c.len() != self.num_rows()) {
+            return Err("columns differ in length");
+        }
+        Ok(())
+    }
+
+    // The attributes of row `i`, without the columns in `skip`. Nulls are
+    // omitted.
+    fn attrs(&self, i: usize, skip: &[&str]) -> GmlAttrs {
+        let mut attrs = GmlAttrs::new();
+        for (name, column) in &self.columns {
+            if skip.contains(&&name[..]) {
+                continue;
+            }
+            if let Some(value) = column.value(i) {
+                insert_dotted(&mut attrs, name, value);
+            }
+        }
+        attrs
+    }
+}
+
+fn attr_columns<'a, I>(rows: I) -> Vec<(String, Column)>
+where
+    I: Iterator<Item = &'a GmlAttrs> + Clone,
+{
+    let mut types: BTreeMap<String, ValueType> = BTreeMap::new();
+    for attrs in rows.clone() {
+        for (name, value) in flatten(attrs) {
+            let ty = ValueType::of(value);
+            types
+                .entry(name)
+                .and_modify(|t| *t = t.unify(ty))
+                .or_insert(ty);
+        }
+    }
+    let flat: Vec<BTreeMap<String, &GmlValue>> = rows
+        .map(|attrs| flatten(attrs).into_iter().collect())
+        .collect();
+    types
+        .into_iter()
+        .map(|(name, ty)| {
+            let values = flat.iter().map(|row| row.get(&name).cloned()).collect();
+            let column = Column::with_type(ty, values);
+            (name, column)
+        })
+        .collect()
+}
+
+/// Converts `doc` into a nodes table and an edges table. The nodes table
+/// has an `id` column, the edges table `source` and `target` columns, each
+/// followed by one column per attribute sorted by name. Nested lists are
+/// flattened into dotted names; each column gets the narrowest type of its
+/// values, with numbers in string columns converted to text.
+pub fn to_tables(doc: &GmlDocument) -> (Table, Table) {
+    let mut nodes = vec![(
+        "id".to_string(),
+        Column::UInt64(doc.nodes.iter().map(|n| Some(n.id)).collect()),
+    )];
+    nodes.extend(attr_columns(doc.nodes.iter().map(|n| &n.attrs)));
+
+    let mut edges = vec![
+        (
+            "source".to_string(),
+            Column::UInt64(doc.edges.iter().map(|e| Some(e.source)).collect()),
+        ),
+        (
+            "target".to_string(),
+            Column::UInt64(doc.edges.iter().map(|e| Some(e.target)).collect()),
+        ),
+    ];
+    edges.extend(attr_columns(doc.edges.iter().map(|e| &e.attrs)));
+
+    (Table { columns: nodes }, Table { columns: edges })
+}
+
+/// Builds a document from a nodes table and an edges table, the inverse of
+/// `to_tables`. Ids are read from the `id`, `source` and `target` columns,
+/// which must hold non-negative integers; all other columns become
+/// attributes, with dotted names expanded into nested lists and nulls
+/// omitted. Unsigned values above `i64::MAX` become `GmlValue::UInt`.
+pub fn from_tables(
+    nodes: &Table,
+    edges: &Table,
+    directed: bool,
+) -> Result<GmlDocument, &'static str> {
+    nodes.check_lengths()?;
+    edges.check_lengths()?;
+    let mut doc = GmlDocument {
+        directed,
+        ..GmlDocument::default()
+    };
+
+    let ids = nodes.key_column("id")?;
+    for i in 0..nodes.num_rows() {
+        doc.nodes.push(GmlNode {
+            id: ids.id(i)?,
+            attrs: nodes.attrs(i, &["id"]),
+        });
+    }
+
+    let sources = edges.key_column("source")?;
+    let targets = edges.key_column("target")?;
+    for i in 0..edges.num_rows() {
+        doc.edges.push(GmlEdge {
+            source: sources.id(i)?,
+            target: targets.id(i)?,
+            attrs: edges.attrs(i, &["source", "target"]),
+        });
+    }
+    Ok(doc)
+}
+
+#[test]
+fn test_to_tables() {
+    let gml = "graph [ node [ id 1 label \"a\" size 2 graphics [ x 1 ] ] \
+               node [ id 2 label 7 graphics [ x 2.5 ] ] edge [ source 1 target 2 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let (nodes, edges) = to_tables(&doc);
+    assert_eq!(2, nodes.num_rows());
+    assert_eq!(
+        vec!["id", "graphics.x", "label", "size"],
+        nodes
+            .columns
+            .iter()
+            .map(|(n, _)| &n[..])
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(&Column::Float64(vec![Some(1.0), Some(2.5)])),
+        nodes.column("graphics.x")
+    );
+    assert_eq!(
+        Some(&Column::Utf8(vec![
+            Some("a".to_string()),
+            Some("7".to_string())
+        ])),
+        nodes.column("label")
+    );
+    assert_eq!(1, nodes.column("size").unwrap().null_count());
+    assert_eq!(Some(&Column::UInt64(vec![Some(2)])), edges.column("target"));
+    assert_eq!(2, edges.columns.len());
+}
+
+#[test]
+fn test_from_tables() {
+    let gml = "graph [ directed 1 node [ id 1 label \"a\" graphics [ x 1.5 ] ] node [ id 2 ] \
+               edge [ source 1 target 2 weight 3 ] edge [ source 2 target 1 ] ]";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    let (nodes, edges) = to_tables(&doc);
+    assert_eq!(doc, from_tables(&nodes, &edges, true).unwrap());
+
+    let nodes = Table {
+        columns: vec![("id".to_string(), Column::Int64(vec![Some(-1)]))],
+    };
+    assert_eq!(
+        Err("invalid node-id"),
+        from_tables(&nodes, &Table::default(), true)
+    );
+
+    let nodes = Table {
+        columns: vec![
+            ("id".to_string(), Column::UInt64(vec![Some(u64::MAX)])),
+            ("count".to_string(), Column::UInt64(vec![Some(u64::MAX)])),
+        ],
+    };
+    let (_, edges) = to_tables(&GmlDocument::default());
+    let doc = from_tables(&nodes, &edges, true).unwrap();
+    assert_eq!(u64::MAX, doc.nodes[0].id);
+    assert_eq!(
+        Some(&GmlValue::UInt(u64::MAX)),
+        doc.nodes[0].attrs.get("count")
+    );
+    assert_eq!(nodes, to_tables(&doc).0);
+}
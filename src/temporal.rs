@@ -0,0 +1,222 @@
+//! Time-evolving graphs, with the `start` and `end` attributes of nodes and
+//! edges as their lifetimes.
+//!
+//! An element is present at the times `t` with `start <= t < end`; without
+//! a `start` it is present from the beginning and without an `end` until
+//! the end. Times are numbers, as written by the dynamic GEXF export with
+//! the `integer` or `double` time format.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlEdge};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The lifetime of a node or edge.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Lifetime {
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+}
+
+impl Lifetime {
+    fn of(attrs: &GmlAttrs) -> Result<Lifetime, &'static str> {
+        let time = |key| match attrs.get(key) {
+            None => Ok(None),
+            Some(value) => value.get_float().map(Some).ok_or("invalid start or end"),
+        };
+        Ok(Lifetime {
+            start: time("start")?,
+            end: time("end")?,
+        })
+    }
+
+    /// Whether the element is present at `t`.
+    pub fn contains(&self, t: f64) -> bool {
+        self.start.is_none_or(|s| s <= t) && self.end.is_none_or(|e| t < e)
+    }
+
+    /// Whether the element is present at some time in `range`.
+    pub fn overlaps(&self, range: &Range<f64>) -> bool {
+        self.start.is_none_or(|s| s < range.end) && self.end.is_none_or(|e| range.start < e)
+    }
+}
+
+/// What happens at the `start` or `end` of an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// A node by id.
+    NodeAdded(u64),
+    NodeRemoved(u64),
+    /// An edge by its index in the document.
+    EdgeAdded(usize),
+    EdgeRemoved(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Change {
+    pub time: f64,
+    pub kind: ChangeKind,
+}
+
+/// A view of a document as a graph changing in time.
+#[derive(Debug, Clone)]
+pub struct TemporalGraph<'a> {
+    doc: &'a GmlDocument,
+    nodes: HashMap<u64, Lifetime>,
+    edges: Vec<Lifetime>,
+}
+
+impl<'a> TemporalGraph<'a> {
+    /// Reads the lifetimes of the nodes and edges of `doc`. Fails if a
+    /// `start` or `end` is not a number.
+    pub fn new(doc: &'a GmlDocument) -> Result<TemporalGraph<'a>, &'static str> {
+        let nodes = doc
+            .nodes
+            .iter()
+            .map(|n| Ok((n.id, Lifetime::of(&n.attrs)?)))
+            .collect::<Result<_, &'static str>>()?;
+        let edges = doc
+            .edges
+            .iter()
+            .map(|e| Lifetime::of(&e.attrs))
+            .collect::<Result<_, _>>()?;
+        Ok(TemporalGraph { doc, nodes, edges })
+    }
+
+    pub fn node_lifetime(&self, id: u64) -> Option<Lifetime> {
+        self.nodes.get(&id).copied()
+    }
+
+    pub fn edge_lifetime(&self, index: usize) -> Option<Lifetime> {
+        self.edges.get(index).copied()
+    }
+
+    // Whether the edge and both of its nodes are present at `t`.
+    fn edge_at(&self, index: usize, t: f64) -> bool {
+        let edge = &self.doc.edges[index];
+        let node = |id| self.nodes.get(&id).is_some_and(|l| l.contains(t));
+        self.edges[index].contains(t) && node(edge.source) && node(edge.target)
+    }
+
+    /// The graph at `t`: the nodes present then, and the edges present
+    /// with both of their nodes.
+    pub fn snapshot_at(&self, t: f64) -> GmlDocument {
+        GmlDocument {
+            directed: self.doc.directed,
+            attrs: self.doc.attrs.clone(),
+            top_attrs: self.doc.top_attrs.clone(),
+            nodes: self
+                .doc
+                .nodes
+                .iter()
+                .filter(|n| self.nodes[&n.id].contains(t))
+                .cloned()
+                .collect(),
+            edges: (0..self.edges.len())
+                .filter(|&i| self.edge_at(i, t))
+                .map(|i| self.doc.edges[i].clone())
+                .collect(),
+        }
+    }
+
+    /// The edges present at some time in `range`, in document order.
+    pub fn active_edges(&self, range: Range<f64>) -> impl Iterator<Item = &'a GmlEdge> + '_ {
+        self.doc
+            .edges
+            .iter()
+            .zip(&self.edges)
+            .filter(move |(_, lifetime)| lifetime.overlaps(&range))
+            .map(|(edge, _)| edge)
+    }
+
+    /// The starts and ends of all nodes and edges, by time. At the same
+    /// time, edges are removed before nodes, and nodes added before edges.
+    pub fn changes(&self) -> impl Iterator<Item = Change> {
+        let mut changes = Vec::new();
+        let mut push = |time: Option<f64>, kind| {
+            if let Some(time) = time {
+                changes.push(Change { time, kind });
+            }
+        };
+        for node in &self.doc.nodes {
+            let lifetime = self.nodes[&node.id];
+            push(lifetime.start, ChangeKind::NodeAdded(node.id));
+            push(lifetime.end, ChangeKind::NodeRemoved(node.id));
+        }
+        for (i, lifetime) in self.edges.iter().enumerate() {
+            push(lifetime.start, ChangeKind::EdgeAdded(i));
+            push(lifetime.end, ChangeKind::EdgeRemoved(i));
+        }
+        let rank = |kind: &ChangeKind| match *kind {
+            ChangeKind::EdgeRemoved(_) => 0,
+            ChangeKind::NodeRemoved(_) => 1,
+            ChangeKind::NodeAdded(_) => 2,
+            ChangeKind::EdgeAdded(_) => 3,
+        };
+        // stable, so that elements keep their document order
+        changes.sort_by(|a, b| {
+            a.time
+                .total_cmp(&b.time)
+                .then(rank(&a.kind).cmp(&rank(&b.kind)))
+        });
+        changes.into_iter()
+    }
+}
+
+#[test]
+fn test_temporal_graph() {
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 ] node [ id 2 start 2 end 6 ] node [ id 3 start 4.5 ]
+                 edge [ source 1 target 2 start 1 ] edge [ source 1 target 3 end 8 ]
+                 edge [ source 2 target 3 start 5 end 9 ] ]",
+    )
+    .unwrap();
+    let graph = TemporalGraph::new(&doc).unwrap();
+    assert_eq!(
+        Some(Lifetime {
+            start: Some(2.0),
+            end: Some(6.0)
+        }),
+        graph.node_lifetime(2)
+    );
+
+    let ids = |doc: &GmlDocument| doc.nodes.iter().map(|n| n.id).collect::<Vec<_>>();
+    let pairs = |doc: &GmlDocument| {
+        doc.edges
+            .iter()
+            .map(|e| (e.source, e.target))
+            .collect::<Vec<_>>()
+    };
+    let at = graph.snapshot_at(0.0);
+    assert_eq!((vec![1], vec![]), (ids(&at), pairs(&at)));
+    let at = graph.snapshot_at(5.0);
+    assert_eq!(vec![1, 2, 3], ids(&at));
+    assert_eq!(vec![(1, 2), (1, 3), (2, 3)], pairs(&at));
+    // the end is not included, and edges end with their nodes
+    let at = graph.snapshot_at(6.0);
+    assert_eq!((vec![1, 3], vec![(1, 3)]), (ids(&at), pairs(&at)));
+
+    let active: Vec<_> = graph.active_edges(8.0..10.0).map(|e| e.target).collect();
+    assert_eq!(vec![2, 3], active);
+    assert_eq!(3, graph.active_edges(5.0..5.5).count());
+
+    let changes: Vec<_> = graph.changes().map(|c| (c.time, c.kind)).collect();
+    use ChangeKind::*;
+    assert_eq!(
+        vec![
+            (1.0, EdgeAdded(0)),
+            (2.0, NodeAdded(2)),
+            (4.5, NodeAdded(3)),
+            (5.0, EdgeAdded(2)),
+            (6.0, NodeRemoved(2)),
+            (8.0, EdgeRemoved(1)),
+            (9.0, EdgeRemoved(2)),
+        ],
+        changes
+    );
+
+    let doc = crate::parse_gml_document("graph [ node [ id 1 start \"monday\" ] ]").unwrap();
+    assert_eq!(
+        Err("invalid start or end"),
+        TemporalGraph::new(&doc).map(|_| ())
+    );
+}
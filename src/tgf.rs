@@ -0,0 +1,125 @@
+//! Trivial Graph Format (TGF) import and export.
+
+use crate::document::{numeric_ids, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+// Splits off the first whitespace separated word.
+fn split_word(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, ""),
+    }
+}
+
+/// Parses a TGF file into a `GmlDocument`.
+///
+/// The text after the node id becomes the `label` of a node, the text after
+/// source and target the `label` of an edge. TGF does not record
+/// directionality; the document is directed, as yEd treats TGF files. Node
+/// ids which are unsigned integers are used directly; otherwise nodes are
+/// numbered from 0 and the TGF id is kept as `name`.
+pub fn parse_tgf(s: &str) -> Result<GmlDocument, &'static str> {
+    let mut lines = s.lines();
+    let mut nodes = Vec::new();
+    for line in &mut lines {
+        if line.trim() == "#" {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        nodes.push(split_word(line));
+    }
+
+    let numeric = numeric_ids(nodes.iter().map(|n| n.0), |id| id.parse().ok());
+    let mut doc = GmlDocument {
+        directed: true,
+        ..GmlDocument::default()
+    };
+    let mut id_map = BTreeMap::new();
+    for (idx, (name, label)) in nodes.into_iter().enumerate() {
+        let mut node = GmlNode {
+            id: idx as u64,
+            attrs: Default::default(),
+        };
+        match numeric {
+            Some(ref ids) => node.id = ids[idx],
+            None => {
+                node.attrs.insert("name".to_string(), GmlValue::from(name));
+            }
+        }
+        if !label.is_empty() {
+            node.attrs
+                .insert("label".to_string(), GmlValue::from(label));
+        }
+        if id_map.insert(name, node.id).is_some() {
+            return Err("duplicate node-id");
+        }
+        doc.nodes.push(node);
+    }
+
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (source, rest) = split_word(line);
+        let (target, label) = split_word(rest);
+        let mut edge = GmlEdge {
+            source: *id_map.get(source).ok_or("edge references unknown node")?,
+            target: *id_map.get(target).ok_or("edge references unknown node")?,
+            attrs: Default::default(),
+        };
+        if !label.is_empty() {
+            edge.attrs
+                .insert("label".to_string(), GmlValue::from(label));
+        }
+        doc.edges.push(edge);
+    }
+
+    Ok(doc)
+}
+
+/// Renders `doc` as TGF.
+pub fn to_tgf(doc: &GmlDocument) -> String {
+    let mut buf = Vec::new();
+    write_tgf(doc, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as TGF. Only ids and labels are kept; line breaks in labels
+/// are replaced by spaces.
+pub fn write_tgf<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    let clean = |label: &str| label.replace(['\r', '\n'], " ");
+    for node in &doc.nodes {
+        match node.label() {
+            Some(label) => writeln!(w, "{} {}", node.id, clean(label))?,
+            None => writeln!(w, "{}", node.id)?,
+        }
+    }
+    writeln!(w, "#")?;
+    for edge in &doc.edges {
+        match edge.label() {
+            Some(label) => writeln!(w, "{} {} {}", edge.source, edge.target, clean(label))?,
+            None => writeln!(w, "{} {}", edge.source, edge.target)?,
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_tgf_round_trip() {
+    let tgf = "1 First node\n2\n3 Third\n#\n1 2 an edge\n3 1\n";
+    let doc = parse_tgf(tgf).unwrap();
+    assert_eq!(3, doc.nodes.len());
+    assert_eq!(Some("First node"), doc.nodes[0].label());
+    assert_eq!(None, doc.nodes[1].label());
+    assert_eq!(Some("an edge"), doc.edges[0].label());
+    assert_eq!((3, 1), (doc.edges[1].source, doc.edges[1].target));
+    assert_eq!(tgf, to_tgf(&doc));
+
+    let doc = parse_tgf("a A\nb B\n#\nb a\n").unwrap();
+    assert_eq!(Some("b"), doc.nodes[1].attrs["name"].get_str());
+    assert_eq!((1, 0), (doc.edges[0].source, doc.edges[0].target));
+}
@@ -0,0 +1,108 @@
+//! Telemetry about parsing and writing, with the `tracing` feature.
+//!
+//! `span!` enters an `info` span until the end of the enclosing block and
+//! `event!` emits an event at the given level, both with the syntax of the
+//! `tracing` macros. Without the feature they expand to nothing, so their
+//! arguments must not be the only use of a value; errors are reported with
+//! `failed` instead.
+
+macro_rules! span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+
+macro_rules! event {
+    ($level:ident, $($args:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($args)*);
+    }};
+}
+
+pub(crate) use event;
+pub(crate) use span;
+
+/// Emits a warning that `what` failed with `error`.
+#[cfg(feature = "tracing")]
+pub(crate) fn failed(what: &str, error: &dyn std::fmt::Display) {
+    tracing::warn!(error = %error, "{} failed", what);
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn failed(_: &str, _: &dyn std::fmt::Display) {}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_trace() {
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Records the names of the spans and the fields of the events.
+    #[derive(Default, Clone)]
+    struct Recorder {
+        spans: Arc<Mutex<Vec<&'static str>>>,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes) -> Id {
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata().name());
+            Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, _: &Id, _: &Record) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event) {
+            let mut fields = Fields(event.metadata().level().to_string());
+            event.record(&mut fields);
+            self.events.lock().unwrap().push(fields.0);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let gml = "graph [ node [ id 1 ] node [ id 2 ] edge [ source 1 target 2 ] ]";
+        let doc = crate::parse_gml_document(gml).unwrap();
+        crate::to_gml(&doc);
+        crate::read_gml(gml.as_bytes()).unwrap();
+        let graph: Result<petgraph::Graph<(), ()>, _> =
+            crate::parse_gml(gml, &|_| Some(()), &|_| Some(()));
+        assert!(graph.is_ok());
+        assert!(crate::parse_gml_document("graph [ node [ ] ]").is_err());
+    });
+
+    assert_eq!(
+        vec![
+            "gml.parse_document",
+            "gml.write",
+            "gml.read",
+            "gml.parse",
+            "gml.parse_document"
+        ],
+        *recorder.spans.lock().unwrap()
+    );
+    let events = recorder.events.lock().unwrap();
+    assert!(events[0].starts_with("INFO"));
+    assert!(events[0].contains("nodes=2 edges=1"));
+    assert!(events.iter().any(|e| e.contains("parse_us=")));
+    let last = events.last().unwrap();
+    assert!(last.starts_with("WARN"));
+    assert!(last.contains("message=parsing the document failed"));
+    assert!(last.contains("error=Invalid id"));
+}
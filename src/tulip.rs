@@ -0,0 +1,548 @@
+//! Tulip TLP import and export.
+
+use crate::document::{
+    flatten, insert_dotted, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue, ValueType,
+};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tlp {
+    Atom(String),
+    Str(String),
+    List(Vec<Tlp>),
+}
+
+impl Tlp {
+    fn text(&self) -> Option<&str> {
+        match *self {
+            Tlp::Atom(ref s) | Tlp::Str(ref s) => Some(s),
+            Tlp::List(_) => None,
+        }
+    }
+}
+
+fn parse_tlp_tree(s: &str) -> Result<Tlp, &'static str> {
+    let mut stack: Vec<Vec<Tlp>> = vec![Vec::new()];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                // comment up to the end of the line
+                for c in &mut chars {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => stack.push(Vec::new()),
+            ')' => {
+                let list = stack.pop().unwrap();
+                stack
+                    .last_mut()
+                    .ok_or("unbalanced parentheses")?
+                    .push(Tlp::List(list));
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => s.push(chars.next().ok_or("unterminated string")?),
+                        Some(c) => s.push(c),
+                        None => return Err("unterminated string"),
+                    }
+                }
+                stack.last_mut().unwrap().push(Tlp::Str(s));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut atom = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    atom.push(c);
+                    chars.next();
+                }
+                stack.last_mut().unwrap().push(Tlp::Atom(atom));
+            }
+        }
+    }
+    if stack.len() != 1 {
+        return Err("unbalanced parentheses");
+    }
+    let mut top = stack.pop().unwrap();
+    match (top.pop(), top.is_empty()) {
+        (Some(tlp @ Tlp::List(_)), true) => Ok(tlp),
+        _ => Err("expected a single (tlp ...) expression"),
+    }
+}
+
+fn parse_id(s: &str) -> Result<u64, &'static str> {
+    s.parse().map_err(|_| "invalid id")
+}
+
+// The numbers of a tuple such as `(1.5,2,0)`.
+fn tuple(s: &str) -> Vec<f64> {
+    s.trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .filter_map(|x| x.trim().parse().ok())
+        .collect()
+}
+
+fn float(f: f64) -> GmlValue {
+    if f.fract() == 0.0 && f.abs() < 1e15 {
+        GmlValue::Int(f as i64)
+    } else {
+        GmlValue::Float(f)
+    }
+}
+
+// Applies the value of property `name` of type `ty` to `attrs`, using the
+// GML conventions for Tulip's view properties.
+fn apply_property(attrs: &mut GmlAttrs, name: &str, ty: &str, value: &str) {
+    let graphics = |attrs: &mut GmlAttrs, key: &str, v: GmlValue| {
+        insert_dotted(attrs, &format!("graphics.{}", key), v)
+    };
+    match (name, ty) {
+        ("viewLabel", _) => {
+            if !value.is_empty() {
+                attrs.insert("label".to_string(), GmlValue::from(value));
+            }
+        }
+        ("viewLayout", "layout") => {
+            let coords = tuple(value);
+            for (key, &c) in ["x", "y", "z"].iter().zip(&coords) {
+                if *key != "z" || c != 0.0 {
+                    graphics(attrs, key, float(c));
+                }
+            }
+        }
+        ("viewSize", "size") => {
+            let dims = tuple(value);
+            for (key, &d) in ["w", "h"].iter().zip(&dims) {
+                graphics(attrs, key, float(d));
+            }
+        }
+        ("viewColor", "color") => {
+            let rgba = tuple(value);
+            if rgba.len() >= 3 {
+                let hex = format!(
+                    "#{:02X}{:02X}{:02X}",
+                    rgba[0] as u8, rgba[1] as u8, rgba[2] as u8
+                );
+                graphics(attrs, "fill", GmlValue::from(hex));
+            }
+        }
+        (_, "int") => {
+            if let Ok(i) = value.parse() {
+                insert_dotted(attrs, name, GmlValue::Int(i));
+            }
+        }
+        (_, "double") => {
+            if let Ok(f) = value.parse() {
+                insert_dotted(attrs, name, GmlValue::Float(f));
+            }
+        }
+        (_, "bool") => insert_dotted(attrs, name, GmlValue::Int((value == "true") as i64)),
+        _ => insert_dotted(attrs, name, GmlValue::from(value)),
+    }
+}
+
+// The value of a property for node or edge `id`. The default only applies
+// to properties set on at least one node (or edge).
+fn property_value<'a>(
+    values: &BTreeMap<u64, &'a str>,
+    id: &u64,
+    default: Option<&'a str>,
+    use_default: bool,
+) -> Option<&'a str> {
+    values
+        .get(id)
+        .cloned()
+        .or_else(|| default.filter(|_| use_default && !values.is_empty()))
+}
+
+/// Parses a Tulip TLP file into a `GmlDocument`.
+///
+/// Tulip's `viewLabel`, `viewLayout`, `viewSize` and `viewColor` become
+/// `label` and the `graphics` `x`, `y`, `z`, `w`, `h` and `fill` attributes.
+/// Other properties keep their name; `int`, `double` and `bool` properties
+/// become numbers, all others strings. The default value of a property is
+/// applied to the nodes (or edges) without an explicit value, provided at
+/// least one node (or edge) has one. Clusters are ignored. Tulip graphs
+/// are directed. `nodes` ranges which together are longer than `s` are an
+/// error.
+pub fn parse_tulip(s: &str) -> Result<GmlDocument, &'static str> {
+    let tree = parse_tlp_tree(s)?;
+    let items = match tree {
+        Tlp::List(ref items) => items,
+        _ => unreachable!(),
+    };
+    if items.first().and_then(Tlp::text) != Some("tlp") {
+        return Err("missing tlp header");
+    }
+
+    let mut doc = GmlDocument {
+        directed: true,
+        ..GmlDocument::default()
+    };
+    let mut nodes: BTreeMap<u64, GmlAttrs> = BTreeMap::new();
+    let mut edges: BTreeMap<u64, GmlEdge> = BTreeMap::new();
+    // the ranges are untrusted, so in all they create no more nodes than
+    // there are bytes of input
+    let mut range_budget = s.len() as u64;
+
+    for item in &items[1..] {
+        let list = match *item {
+            Tlp::List(ref list) if !list.is_empty() => list,
+            _ => continue,
+        };
+        match list[0].text().unwrap_or("") {
+            "nodes" => {
+                for t in &list[1..] {
+                    let text = t.text().ok_or("invalid id")?;
+                    let range: Vec<&str> = text.splitn(2, "..").collect();
+                    let (first, last) = match range[..] {
+                        [a, b] => (a.parse::<u64>(), b.parse::<u64>()),
+                        _ => (text.parse(), text.parse()),
+                    };
+                    match (first, last) {
+                        (Ok(first), Ok(last)) => {
+                            let count = if last < first {
+                                0
+                            } else {
+                                (last - first).saturating_add(1)
+                            };
+                            range_budget = range_budget
+                                .checked_sub(count)
+                                .ok_or("node range too large")?;
+                            for id in first..=last {
+                                nodes.entry(id).or_default();
+                            }
+                        }
+                        _ => return Err("invalid id"),
+                    }
+                }
+            }
+            "edge" => {
+                if list.len() != 4 {
+                    return Err("invalid edge");
+                }
+                let id = |i: usize| list[i].text().ok_or("invalid id").and_then(parse_id);
+                let edge = GmlEdge {
+                    source: id(2)?,
+                    target: id(3)?,
+                    attrs: Default::default(),
+                };
+                edges.insert(id(1)?, edge);
+            }
+            "property" => {
+                // (property <cluster> <type> <name> (default ..) (node ..) (edge ..))
+                let ty = list.get(2).and_then(Tlp::text).ok_or("invalid property")?;
+                let name = list.get(3).and_then(Tlp::text).ok_or("invalid property")?;
+                let mut defaults = (None, None);
+                let mut node_values = BTreeMap::new();
+                let mut edge_values = BTreeMap::new();
+                for entry in &list[4..] {
+                    let entry = match *entry {
+                        Tlp::List(ref e) => e,
+                        _ => return Err("invalid property"),
+                    };
+                    let kind = entry.first().and_then(Tlp::text);
+                    let arg = |i: usize| entry.get(i).and_then(Tlp::text).ok_or("invalid property");
+                    match kind {
+                        Some("default") => defaults = (Some(arg(1)?), Some(arg(2)?)),
+                        Some("node") => {
+                            node_values.insert(parse_id(arg(1)?)?, arg(2)?);
+                        }
+                        Some("edge") => {
+                            edge_values.insert(parse_id(arg(1)?)?, arg(2)?);
+                        }
+                        _ => return Err("invalid property"),
+                    }
+                }
+                // view properties have a default for every node and edge
+                let use_defaults = !name.starts_with("view");
+                for (id, attrs) in nodes.iter_mut() {
+                    if let Some(value) = property_value(&node_values, id, defaults.0, use_defaults)
+                    {
+                        apply_property(attrs, name, ty, value);
+                    }
+                }
+                for (id, edge) in edges.iter_mut() {
+                    if let Some(value) = property_value(&edge_values, id, defaults.1, use_defaults)
+                    {
+                        apply_property(&mut edge.attrs, name, ty, value);
+                    }
+                }
+            }
+            "graph_attributes" => {
+                for attr in list.iter().skip(2) {
+                    if let Tlp::List(ref a) = *attr {
+                        let ty = a.first().and_then(Tlp::text);
+                        let name = a.get(1).and_then(Tlp::text);
+                        let value = a.get(2).and_then(Tlp::text);
+                        if let (Some(ty), Some(name), Some(value)) = (ty, name, value) {
+                            let name = if name == "name" { "viewLabel" } else { name };
+                            apply_property(&mut doc.attrs, name, ty, value);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for edge in edges.values() {
+        if !nodes.contains_key(&edge.source) || !nodes.contains_key(&edge.target) {
+            return Err("edge references unknown node");
+        }
+    }
+    doc.nodes = nodes
+        .into_iter()
+        .map(|(id, attrs)| GmlNode { id, attrs })
+        .collect();
+    doc.edges = edges.into_values().collect();
+    Ok(doc)
+}
+
+/// Renders `doc` as a Tulip TLP file. An edge that references an unknown
+/// node is an `io::ErrorKind::InvalidData` error, as in `write_tulip`.
+pub fn to_tulip(doc: &GmlDocument) -> io::Result<String> {
+    let mut buf = Vec::new();
+    write_tulip(doc, &mut buf)?;
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn text(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) => f.to_string(),
+        GmlValue::Str(ref s) => s.to_string(),
+        GmlValue::List(_) => unreachable!(),
+    }
+}
+
+#[derive(Default)]
+struct Property {
+    ty: &'static str,
+    default: &'static str,
+    nodes: Vec<(usize, String)>,
+    edges: Vec<(usize, String)>,
+}
+
+impl Property {
+    fn push(&mut self, edge: bool, i: usize, value: String) {
+        if edge {
+            self.edges.push((i, value));
+        } else {
+            self.nodes.push((i, value));
+        }
+    }
+}
+
+// The view property values of `attrs`, the inverse of `apply_property`.
+fn view_values(attrs: &GmlAttrs) -> Vec<(&'static str, String)> {
+    let mut values = Vec::new();
+    if let Some(label) = attrs.get("label").filter(|l| l.get_list().is_none()) {
+        values.push(("viewLabel", text(label)));
+    }
+    let g = |k: &str| {
+        attrs
+            .get("graphics")
+            .and_then(|g| g.get(k))
+            .and_then(GmlValue::get_float)
+    };
+    if let (Some(x), Some(y)) = (g("x"), g("y")) {
+        values.push((
+            "viewLayout",
+            format!("({},{},{})", x, y, g("z").unwrap_or(0.0)),
+        ));
+    }
+    if let (Some(w), Some(h)) = (g("w"), g("h")) {
+        values.push(("viewSize", format!("({},{},0)", w, h)));
+    }
+    let fill = attrs
+        .get("graphics")
+        .and_then(|g| g.get("fill"))
+        .and_then(GmlValue::get_str)
+        .and_then(|f| f.strip_prefix('#'))
+        .filter(|f| f.len() == 6)
+        .and_then(|f| u32::from_str_radix(f, 16).ok());
+    if let Some(rgb) = fill {
+        values.push((
+            "viewColor",
+            format!("({},{},{},255)", rgb >> 16, (rgb >> 8) & 0xff, rgb & 0xff),
+        ));
+    }
+    values
+}
+
+const VIEW_TYPES: &[(&str, &str, &str)] = &[
+    ("viewLabel", "string", ""),
+    ("viewLayout", "layout", "(0,0,0)"),
+    ("viewSize", "size", "(1,1,1)"),
+    ("viewColor", "color", "(0,0,0,255)"),
+];
+
+// Attributes other than those covered by the view properties.
+fn data_values(attrs: &GmlAttrs) -> Vec<(String, &GmlValue)> {
+    flatten(attrs)
+        .into_iter()
+        .filter(|(k, _)| {
+            !matches!(
+                &k[..],
+                "label"
+                    | "graphics.x"
+                    | "graphics.y"
+                    | "graphics.z"
+                    | "graphics.w"
+                    | "graphics.h"
+                    | "graphics.fill"
+            )
+        })
+        .collect()
+}
+
+/// Writes `doc` as a Tulip TLP file, the inverse of `parse_tulip`. Other
+/// attributes become `int`, `double` or `string` properties, nested lists
+/// flattened into dotted names.
+///
+/// Tulip numbers nodes and edges from 0 without gaps, so nodes are
+/// renumbered in document order.
+pub fn write_tulip<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    let mut numbers = BTreeMap::new();
+    for (i, node) in doc.nodes.iter().enumerate() {
+        numbers.insert(node.id, i);
+    }
+
+    writeln!(w, "(tlp \"2.3\"")?;
+    writeln!(w, "(nb_nodes {})", doc.nodes.len())?;
+    if !doc.nodes.is_empty() {
+        writeln!(w, "(nodes 0..{})", doc.nodes.len() - 1)?;
+    }
+    writeln!(w, "(nb_edges {})", doc.edges.len())?;
+    for (i, edge) in doc.edges.iter().enumerate() {
+        match (numbers.get(&edge.source), numbers.get(&edge.target)) {
+            (Some(s), Some(t)) => writeln!(w, "(edge {} {} {})", i, s, t)?,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "edge references unknown node",
+                ))
+            }
+        }
+    }
+
+    let mut properties: BTreeMap<String, Property> = BTreeMap::new();
+    let mut types: BTreeMap<String, ValueType> = BTreeMap::new();
+    let elements = doc.nodes.iter().map(|n| (false, &n.attrs));
+    let elements = elements.chain(doc.edges.iter().map(|e| (true, &e.attrs)));
+    let mut counts = [0, 0];
+    for (edge, attrs) in elements {
+        let i = counts[edge as usize];
+        counts[edge as usize] += 1;
+        for (name, value) in view_values(attrs) {
+            let &(_, ty, default) = VIEW_TYPES.iter().find(|(n, _, _)| *n == name).unwrap();
+            let prop = properties.entry(name.to_string()).or_default();
+            prop.ty = ty;
+            prop.default = default;
+            prop.push(edge, i, value);
+        }
+        for (name, value) in data_values(attrs) {
+            let ty = ValueType::of(value);
+            types
+                .entry(name.clone())
+                .and_modify(|t| *t = t.unify(ty))
+                .or_insert(ty);
+            properties
+                .entry(name)
+                .or_default()
+                .push(edge, i, text(value));
+        }
+    }
+    for (name, ty) in types {
+        let prop = properties.get_mut(&name).unwrap();
+        let (ty, default) = match ty {
+            ValueType::Int | ValueType::Long | ValueType::UnsignedLong => ("int", "0"),
+            ValueType::Double => ("double", "0"),
+            ValueType::String => ("string", ""),
+        };
+        prop.ty = ty;
+        prop.default = default;
+    }
+
+    for (name, prop) in &properties {
+        writeln!(w, "(property 0 {} {}", prop.ty, quote(name))?;
+        writeln!(
+            w,
+            "  (default {} {})",
+            quote(prop.default),
+            quote(prop.default)
+        )?;
+        for (i, value) in &prop.nodes {
+            writeln!(w, "  (node {} {})", i, quote(value))?;
+        }
+        for (i, value) in &prop.edges {
+            writeln!(w, "  (edge {} {})", i, quote(value))?;
+        }
+        writeln!(w, ")")?;
+    }
+
+    if let Some(label) = doc.attrs.get("label").and_then(GmlValue::get_str) {
+        writeln!(w, "(graph_attributes 0 (string \"name\" {}))", quote(label))?;
+    }
+    writeln!(w, ")")
+}
+
+#[test]
+fn test_tulip_round_trip() {
+    let tlp = "(tlp \"2.3\"\n\
+               (author \"me\") ; a comment\n\
+               (nb_nodes 3)\n(nodes 0..1 2)\n(nb_edges 2)\n\
+               (edge 0 0 1)\n(edge 1 1 2)\n\
+               (property 0 string \"viewLabel\"\n  (default \"\" \"\")\n  (node 0 \"say \\\"a\\\"\")\n)\n\
+               (property 0 layout \"viewLayout\"\n  (default \"(0,0,0)\" \"()\")\n  \
+               (node 1 \"(1.5,2,0)\")\n)\n\
+               (property 0 color \"viewColor\"\n  (default \"(0,0,0,255)\" \"(0,0,0,255)\")\n  \
+               (node 2 \"(255,128,0,255)\")\n)\n\
+               (property 0 double \"weight\"\n  (default \"1\" \"1\")\n  (edge 1 \"2.5\")\n)\n\
+               (graph_attributes 0 (string \"name\" \"g\"))\n)\n";
+    let doc = parse_tulip(tlp).unwrap();
+    assert_eq!(3, doc.nodes.len());
+    assert_eq!(Some("say \"a\""), doc.nodes[0].label());
+    let graphics = &doc.nodes[1].attrs["graphics"];
+    assert_eq!(Some(1.5), graphics.get("x").and_then(GmlValue::get_float));
+    assert_eq!(Some(2), graphics.get("y").and_then(GmlValue::get_int));
+    assert_eq!(
+        Some("#FF8000"),
+        doc.nodes[2].attrs["graphics"]
+            .get("fill")
+            .and_then(GmlValue::get_str)
+    );
+    assert_eq!(Some(1.0), doc.edges[0].attrs["weight"].get_float());
+    assert_eq!(Some(2.5), doc.edges[1].attrs["weight"].get_float());
+    assert!(!doc.nodes[0].attrs.contains_key("weight"));
+    assert_eq!(Some("g"), doc.attrs["label"].get_str());
+
+    let written = to_tulip(&doc).unwrap();
+    assert_eq!(doc, parse_tulip(&written).unwrap());
+
+    let dangling = crate::parse_gml_document("graph [ node [ id 1 ] edge [ source 1 target 2 ] ]");
+    let err = to_tulip(&dangling.unwrap()).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+    let huge = "(tlp \"2.3\" (nodes 0..18446744073709551615))";
+    assert_eq!(Err("node range too large"), parse_tulip(huge));
+    let many = "(tlp \"2.3\" (nodes 1..20 1..20 1..20))";
+    assert_eq!(Err("node range too large"), parse_tulip(many));
+    let doc = parse_tulip("(tlp \"2.3\" (nodes 0..4 7))").unwrap();
+    assert_eq!(6, doc.nodes.len());
+}
@@ -0,0 +1,205 @@
+//! RDF/Turtle export.
+
+use crate::document::{flatten, GmlAttrs, GmlDocument, GmlValue};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone)]
+pub struct TurtleOptions {
+    /// Namespace of node IRIs; node `1` becomes `<{node_namespace}1>`.
+    pub node_namespace: String,
+    /// Namespace of the predicates made from attribute names and of the
+    /// `Node` class.
+    pub vocab_namespace: String,
+    /// An attribute whose string value becomes the predicate of an edge.
+    /// Edges without it use `default_predicate`.
+    pub predicate_key: Option<String>,
+    pub default_predicate: String,
+    /// Whether to write the remaining edge attributes as RDF-star
+    /// annotations (`{| ... |}`). They are dropped otherwise.
+    pub annotate_edges: bool,
+}
+
+impl Default for TurtleOptions {
+    fn default() -> TurtleOptions {
+        TurtleOptions {
+            node_namespace: "http://example.org/node/".to_string(),
+            vocab_namespace: "http://example.org/vocab/".to_string(),
+            predicate_key: None,
+            default_predicate: "edge".to_string(),
+            annotate_edges: false,
+        }
+    }
+}
+
+fn is_local_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        && !s.ends_with('.')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+// A prefixed name if `name` is a valid local name, a percent-encoded IRI
+// otherwise.
+fn vocab_term(name: &str, options: &TurtleOptions) -> String {
+    if is_local_name(name) {
+        return format!("vocab:{}", name);
+    }
+    let mut iri = format!("<{}", options.vocab_namespace);
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            iri.push(b as char);
+        } else {
+            iri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    iri.push('>');
+    iri
+}
+
+fn literal(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) if f.is_finite() => format!("{:e}", f),
+        GmlValue::Float(f) => {
+            let lexical = if f.is_nan() {
+                "NaN"
+            } else if f > 0.0 {
+                "INF"
+            } else {
+                "-INF"
+            };
+            format!("\"{}\"^^<http://www.w3.org/2001/XMLSchema#double>", lexical)
+        }
+        GmlValue::Str(ref s) => {
+            let mut out = String::with_capacity(s.len() + 2);
+            out.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+        GmlValue::List(_) => unreachable!(),
+    }
+}
+
+fn predicate_objects(
+    attrs: &GmlAttrs,
+    skip: Option<&String>,
+    options: &TurtleOptions,
+) -> Vec<String> {
+    flatten(attrs)
+        .into_iter()
+        .filter(|(k, _)| Some(k) != skip)
+        .map(|(k, v)| format!("{} {}", vocab_term(&k, options), literal(v)))
+        .collect()
+}
+
+/// Renders `doc` as Turtle. See `write_turtle`.
+pub fn to_turtle(doc: &GmlDocument, options: &TurtleOptions) -> String {
+    let mut buf = Vec::new();
+    write_turtle(doc, options, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as Turtle. Every node becomes a `vocab:Node` with its
+/// attributes as literal-valued properties, nested lists flattened into
+/// dotted names. Every edge becomes one triple from source to target, also
+/// for undirected documents.
+pub fn write_turtle<W: Write>(
+    doc: &GmlDocument,
+    options: &TurtleOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    writeln!(w, "@prefix node: <{}> .", options.node_namespace)?;
+    writeln!(w, "@prefix vocab: <{}> .", options.vocab_namespace)?;
+
+    for n in &doc.nodes {
+        writeln!(w)?;
+        write!(w, "node:{} a vocab:Node", n.id)?;
+        for po in predicate_objects(&n.attrs, None, options) {
+            write!(w, " ;\n    {}", po)?;
+        }
+        writeln!(w, " .")?;
+    }
+
+    if !doc.edges.is_empty() {
+        writeln!(w)?;
+    }
+    for e in &doc.edges {
+        let predicate = options
+            .predicate_key
+            .as_ref()
+            .and_then(|k| e.attrs.get(k))
+            .and_then(GmlValue::get_str)
+            .unwrap_or(&options.default_predicate);
+        write!(
+            w,
+            "node:{} {} node:{}",
+            e.source,
+            vocab_term(predicate, options),
+            e.target
+        )?;
+        if options.annotate_edges {
+            let annotations = predicate_objects(&e.attrs, options.predicate_key.as_ref(), options);
+            if !annotations.is_empty() {
+                write!(w, " {{| {} |}}", annotations.join(" ; "))?;
+            }
+        }
+        writeln!(w, " .")?;
+    }
+    Ok(())
+}
+
+#[test]
+// expects the attributes in key order
+fn test_to_turtle() {
+    let gml = "graph [ directed 1 node [ id 1 label \"say &quot;hi&quot;\" graphics [ x 1.5 ] ] \
+               node [ id 2 ] edge [ source 1 target 2 rel \"knows\" since 2009 ] \
+               edge [ source 2 target 1 ] ]";
+    let mut doc = crate::parse_gml_document(gml).unwrap();
+    // not a valid GML key
+    doc.edges[1]
+        .attrs
+        .insert("first name".to_string(), GmlValue::from("x"));
+    let options = TurtleOptions {
+        predicate_key: Some("rel".to_string()),
+        annotate_edges: true,
+        ..TurtleOptions::default()
+    };
+    // the predicates follow the key order of the attributes, and the last
+    // one of a subject ends its statement
+    let sorted = |s: &str| {
+        let mut lines: Vec<_> = s
+            .lines()
+            .map(|l| l.trim_end_matches([';', '.']).trim_end())
+            .map(str::to_string)
+            .collect();
+        lines.sort();
+        lines
+    };
+    let turtle = to_turtle(&doc, &options);
+    assert_eq!(6, turtle.matches(" .\n").count());
+    assert_eq!(
+        sorted(
+            "@prefix node: <http://example.org/node/> .\n\
+             @prefix vocab: <http://example.org/vocab/> .\n\
+             \n\
+             node:1 a vocab:Node ;\n    vocab:graphics.x 1.5e0 ;\n    vocab:label \"say \\\"hi\\\"\" .\n\
+             \n\
+             node:2 a vocab:Node .\n\
+             \n\
+             node:1 vocab:knows node:2 {| vocab:since 2009 |} .\n\
+             node:2 vocab:edge node:1 {| <http://example.org/vocab/first%20name> \"x\" |} .\n"
+        ),
+        sorted(&turtle)
+    );
+}
@@ -0,0 +1,210 @@
+//! Ready-made node and edge weights with the common keys typed.
+//!
+//! Between the weight functions of `parse_gml`, which see only `weight`,
+//! and `GmlDocument`, which keeps every attribute untyped, `NodeAttrs` and
+//! `EdgeAttrs` have fields for `id`, `label`, `weight` and `value` and keep
+//! the other attributes in `extra`.
+
+use crate::document::{parse_gml_document, GmlAttrs, GmlDocument, GmlEdge, GmlNode, GmlValue};
+use crate::node_map::NodeMap;
+use petgraph::{Directed, Graph};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAttrs {
+    pub id: u64,
+    pub label: Option<String>,
+    pub weight: Option<f64>,
+    /// The `value`, which is a number in some datasets and a string in
+    /// others.
+    pub value: Option<GmlValue>,
+    /// The other attributes, and those above with values of another type.
+    pub extra: GmlAttrs,
+}
+
+/// The attributes of an edge, without its endpoints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeAttrs {
+    /// The `id`, which only some tools give edges.
+    pub id: Option<u64>,
+    pub label: Option<String>,
+    pub weight: Option<f64>,
+    pub value: Option<GmlValue>,
+    pub extra: GmlAttrs,
+}
+
+struct Common {
+    id: Option<u64>,
+    label: Option<String>,
+    weight: Option<f64>,
+    value: Option<GmlValue>,
+}
+
+// Takes the typed fields out of `attrs`, leaving the rest.
+fn take_common(attrs: &mut GmlAttrs) -> Common {
+    let mut common = Common {
+        id: None,
+        label: None,
+        weight: None,
+        value: None,
+    };
+    let mut extra = GmlAttrs::new();
+    for (key, value) in std::mem::take(attrs) {
+        match (key.as_str(), &value) {
+            ("id", v) if v.get_uint().is_some() => common.id = v.get_uint(),
+            ("label", GmlValue::Str(s)) => common.label = Some(s.to_string()),
+            ("weight", v) if v.get_float().is_some() => common.weight = v.get_float(),
+            ("value", v) if v.get_list().is_none() => common.value = Some(value),
+            _ => {
+                extra.insert(key, value);
+            }
+        }
+    }
+    *attrs = extra;
+    common
+}
+
+// Puts the typed fields back, before the others.
+fn put_common(common: Common, extra: GmlAttrs) -> GmlAttrs {
+    let mut attrs = GmlAttrs::new();
+    if let Some(id) = common.id {
+        attrs.insert("id".to_string(), GmlValue::Int(id as i64));
+    }
+    if let Some(label) = common.label {
+        attrs.insert("label".to_string(), GmlValue::from(label));
+    }
+    if let Some(weight) = common.weight {
+        attrs.insert("weight".to_string(), GmlValue::Float(weight));
+    }
+    if let Some(value) = common.value {
+        attrs.insert("value".to_string(), value);
+    }
+    attrs.extend(extra);
+    attrs
+}
+
+impl From<GmlNode> for NodeAttrs {
+    fn from(node: GmlNode) -> NodeAttrs {
+        let mut extra = node.attrs;
+        let common = take_common(&mut extra);
+        NodeAttrs {
+            id: node.id,
+            label: common.label,
+            weight: common.weight,
+            value: common.value,
+            extra,
+        }
+    }
+}
+
+impl From<NodeAttrs> for GmlNode {
+    fn from(node: NodeAttrs) -> GmlNode {
+        let common = Common {
+            id: None,
+            label: node.label,
+            weight: node.weight,
+            value: node.value,
+        };
+        GmlNode {
+            id: node.id,
+            attrs: put_common(common, node.extra),
+        }
+    }
+}
+
+impl From<GmlEdge> for EdgeAttrs {
+    fn from(edge: GmlEdge) -> EdgeAttrs {
+        let mut extra = edge.attrs;
+        let common = take_common(&mut extra);
+        EdgeAttrs {
+            id: common.id,
+            label: common.label,
+            weight: common.weight,
+            value: common.value,
+            extra,
+        }
+    }
+}
+
+impl EdgeAttrs {
+    /// The edge from `source` to `target` with these attributes.
+    pub fn into_edge(self, source: u64, target: u64) -> GmlEdge {
+        let common = Common {
+            id: self.id,
+            label: self.label,
+            weight: self.weight,
+            value: self.value,
+        };
+        GmlEdge {
+            source,
+            target,
+            attrs: put_common(common, self.extra),
+        }
+    }
+}
+
+impl GmlDocument {
+    /// Converts the document into a petgraph `Graph` with `NodeAttrs` and
+    /// `EdgeAttrs` weights, like `into_graph`.
+    pub fn into_typed_graph(self) -> Result<Graph<NodeAttrs, EdgeAttrs, Directed>, &'static str> {
+        let mut node_map = NodeMap::with_capacity(self.nodes.len());
+        let mut graph = Graph::with_capacity(self.nodes.len(), self.edges.len());
+        for node in self.nodes {
+            let id = node.id;
+            if !node_map.insert(id, graph.add_node(NodeAttrs::from(node))) {
+                return Err("duplicate node-id");
+            }
+        }
+        for edge in self.edges {
+            match (node_map.get(edge.source), node_map.get(edge.target)) {
+                (Some(source), Some(target)) => {
+                    graph.add_edge(source, target, EdgeAttrs::from(edge));
+                }
+                _ => return Err("edge references unknown node"),
+            }
+        }
+        Ok(graph)
+    }
+}
+
+/// Parses a GML string into a graph with `NodeAttrs` and `EdgeAttrs`
+/// weights.
+pub fn parse_gml_typed(s: &str) -> Result<Graph<NodeAttrs, EdgeAttrs, Directed>, &'static str> {
+    parse_gml_document(s)?.into_typed_graph()
+}
+
+#[test]
+fn test_parse_gml_typed() {
+    use petgraph::graph::{EdgeIndex, NodeIndex};
+
+    let graph = parse_gml_typed(
+        "graph [ directed 1
+                 node [ id 1 label \"a\" value \"n\" weight 2 color \"red\" ]
+                 node [ id 2 label 7 value [ x 1 ] ]
+                 edge [ id 9 source 1 target 2 weight 1.5 value 3 label \"e\" ] ]",
+    )
+    .unwrap();
+    let a = &graph[NodeIndex::new(0)];
+    assert_eq!((1, Some("a")), (a.id, a.label.as_deref()));
+    assert_eq!(
+        (Some(2.0), Some(GmlValue::from("n"))),
+        (a.weight, a.value.clone())
+    );
+    assert_eq!(Some(&GmlValue::from("red")), a.extra.get("color"));
+    assert_eq!(1, a.extra.len());
+    let b = &graph[NodeIndex::new(1)];
+    assert_eq!((None, None), (b.label.as_deref(), b.value.as_ref()));
+    assert_eq!(Some(&GmlValue::Int(7)), b.extra.get("label"));
+    assert!(b.extra.contains_key("value"));
+    let e = &graph[EdgeIndex::new(0)];
+    assert_eq!((Some(9), Some(1.5)), (e.id, e.weight));
+    assert_eq!(Some(GmlValue::Int(3)), e.value);
+    assert!(e.extra.is_empty());
+
+    let node = GmlNode::from(a.clone());
+    assert_eq!(a, &NodeAttrs::from(node.clone()));
+    assert_eq!(Some(&GmlValue::Float(2.0)), node.attrs.get("weight"));
+    let edge = e.clone().into_edge(1, 2);
+    assert_eq!((1, 2), (edge.source, edge.target));
+    assert_eq!(Some(&GmlValue::Int(9)), edge.attrs.get("id"));
+    assert_eq!(e, &EdgeAttrs::from(edge));
+}
@@ -0,0 +1,347 @@
+//! UCINET DL import.
+
+use crate::document::{GmlDocument, GmlEdge, GmlNode, GmlValue};
+use std::collections::BTreeMap;
+
+// Splits at whitespace and commas, keeping `=` and `:` as tokens of their
+// own. Double quotes group a token.
+fn tokenize(s: &str) -> Result<Vec<String>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() || c == ',' => {
+                chars.next();
+            }
+            '=' | ':' => {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => token.push(c),
+                        None => return Err("unterminated quoted label"),
+                    }
+                }
+                tokens.push(token);
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ',' || c == '=' || c == ':' || c == '"' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    FullMatrix,
+    EdgeList,
+    NodeList,
+}
+
+struct Header {
+    n: usize,
+    format: Format,
+    labels: Vec<String>,
+    embedded: bool,
+    diagonal: bool,
+}
+
+const KEYWORDS: &[&str] = &["n", "nm", "format", "diagonal", "labels"];
+
+fn parse_header(tokens: &[String]) -> Result<Header, &'static str> {
+    let mut header = Header {
+        n: 0,
+        format: Format::FullMatrix,
+        labels: Vec::new(),
+        embedded: false,
+        diagonal: true,
+    };
+    let mut tokens = tokens.iter().map(|t| &t[..]).peekable();
+    if !tokens.next().is_some_and(|t| t.eq_ignore_ascii_case("dl")) {
+        return Err("missing DL header");
+    }
+
+    fn value<'a, I: Iterator<Item = &'a str>>(tokens: &mut I) -> Result<String, &'static str> {
+        if tokens.next() != Some("=") {
+            return Err("expected =");
+        }
+        tokens
+            .next()
+            .map(str::to_ascii_lowercase)
+            .ok_or("missing value")
+    }
+
+    let mut n = None;
+    while let Some(keyword) = tokens.next() {
+        match &keyword.to_ascii_lowercase()[..] {
+            "n" => n = Some(value(&mut tokens)?.parse().map_err(|_| "invalid n")?),
+            "nm" => {
+                if value(&mut tokens)? != "1" {
+                    return Err("multiple matrices are not supported");
+                }
+            }
+            "format" => {
+                header.format = match &value(&mut tokens)?[..] {
+                    "fullmatrix" | "fm" => Format::FullMatrix,
+                    "edgelist1" | "el1" => Format::EdgeList,
+                    "nodelist1" | "nl1" => Format::NodeList,
+                    _ => return Err("unsupported format"),
+                }
+            }
+            "diagonal" => header.diagonal = value(&mut tokens)? == "present",
+            "labels" => {
+                if tokens
+                    .peek()
+                    .is_some_and(|t| t.eq_ignore_ascii_case("embedded"))
+                {
+                    tokens.next();
+                    header.embedded = true;
+                    if tokens.peek() == Some(&":") {
+                        tokens.next();
+                    }
+                    continue;
+                }
+                if tokens.next() != Some(":") {
+                    return Err("expected :");
+                }
+                // labels run up to the next `keyword =`
+                loop {
+                    let mut ahead = tokens.clone();
+                    match (ahead.next(), ahead.next()) {
+                        (Some(k), Some("=")) if KEYWORDS.contains(&&k.to_ascii_lowercase()[..]) => {
+                            break
+                        }
+                        (Some(label), _) => {
+                            header.labels.push(label.to_string());
+                            tokens.next();
+                        }
+                        (None, _) => break,
+                    }
+                }
+            }
+            "row" | "column" | "nr" | "nc" => return Err("two-mode data is not supported"),
+            _ => return Err("unknown keyword"),
+        }
+    }
+    header.n = n.ok_or("missing n")?;
+    if header.labels.len() > header.n {
+        return Err("more labels than nodes");
+    }
+    Ok(header)
+}
+
+fn number(s: &str) -> Result<GmlValue, &'static str> {
+    if let Ok(i) = s.parse() {
+        Ok(GmlValue::Int(i))
+    } else {
+        s.parse().map(GmlValue::Float).map_err(|_| "invalid value")
+    }
+}
+
+fn is_zero(value: &GmlValue) -> bool {
+    value.get_float() == Some(0.0)
+}
+
+// Maps the node references of the data section to node ids 1 to n.
+struct Nodes<'a> {
+    header: &'a Header,
+    labels: Vec<String>,
+    by_label: BTreeMap<String, u64>,
+}
+
+impl<'a> Nodes<'a> {
+    fn resolve(&mut self, token: &str) -> Result<u64, &'static str> {
+        if !self.header.embedded {
+            return match token.parse() {
+                Ok(id) if id >= 1 && id <= self.header.n as u64 => Ok(id),
+                Ok(_) => Err("edge references unknown node"),
+                Err(_) => Err("invalid node number"),
+            };
+        }
+        if let Some(&id) = self.by_label.get(token) {
+            return Ok(id);
+        }
+        if self.labels.len() == self.header.n {
+            return Err("more labels than nodes");
+        }
+        self.labels.push(token.to_string());
+        let id = self.labels.len() as u64;
+        self.by_label.insert(token.to_string(), id);
+        Ok(id)
+    }
+}
+
+/// Parses a UCINET DL file into a `GmlDocument`.
+///
+/// Supports one-mode data in the `fullmatrix`, `edgelist1` and `nodelist1`
+/// formats, with labels in a `labels:` section or embedded in the data.
+/// Nodes get the ids 1 to n and their labels as `label`. Every non-zero
+/// matrix entry and every edge list line becomes an edge, with the value as
+/// `weight`. The document is directed. An `n` larger than the length of
+/// `s` is invalid.
+pub fn parse_ucinet(s: &str) -> Result<GmlDocument, &'static str> {
+    // the header ends at the `data:` line
+    let mut header_text = String::new();
+    let mut data_lines = Vec::new();
+    let mut in_data = false;
+    for line in s.lines() {
+        if in_data {
+            data_lines.push(line);
+            continue;
+        }
+        let trimmed = line.trim_start();
+        if trimmed
+            .get(..5)
+            .is_some_and(|k| k.eq_ignore_ascii_case("data:"))
+        {
+            in_data = true;
+            data_lines.push(&trimmed[5..]);
+        } else {
+            header_text.push_str(line);
+            header_text.push('\n');
+        }
+    }
+    if !in_data {
+        return Err("missing data section");
+    }
+    let header = parse_header(&tokenize(&header_text)?)?;
+    // `n` is untrusted, so more nodes than bytes of input are not created
+    if header.n > s.len() {
+        return Err("invalid n");
+    }
+
+    let mut doc = GmlDocument {
+        directed: true,
+        ..GmlDocument::default()
+    };
+    let mut nodes = Nodes {
+        header: &header,
+        labels: header.labels.clone(),
+        by_label: BTreeMap::new(),
+    };
+    let weighted = |source, target, weight: Option<GmlValue>| {
+        let mut edge = GmlEdge {
+            source,
+            target,
+            attrs: Default::default(),
+        };
+        if let Some(weight) = weight {
+            edge.attrs.insert("weight".to_string(), weight);
+        }
+        edge
+    };
+
+    match header.format {
+        Format::FullMatrix => {
+            let mut tokens = tokenize(&data_lines.join("\n"))?.into_iter();
+            if header.embedded {
+                let columns: Vec<String> = tokens.by_ref().take(header.n).collect();
+                for label in &columns {
+                    nodes.resolve(label)?;
+                }
+            }
+            for row in 1..=header.n as u64 {
+                if header.embedded {
+                    let label = tokens.next().ok_or("missing row label")?;
+                    if nodes.by_label.get(&label) != Some(&row) {
+                        return Err("row labels do not match column labels");
+                    }
+                }
+                for col in 1..=header.n as u64 {
+                    if row == col && !header.diagonal {
+                        continue;
+                    }
+                    let value = number(&tokens.next().ok_or("matrix too short")?)?;
+                    if !is_zero(&value) {
+                        doc.edges.push(weighted(row, col, Some(value)));
+                    }
+                }
+            }
+            if tokens.next().is_some() {
+                return Err("matrix too long");
+            }
+        }
+        Format::EdgeList | Format::NodeList => {
+            for line in data_lines {
+                let tokens = tokenize(line)?;
+                if tokens.is_empty() {
+                    continue;
+                }
+                let source = nodes.resolve(&tokens[0])?;
+                if header.format == Format::NodeList {
+                    for t in &tokens[1..] {
+                        let target = nodes.resolve(t)?;
+                        doc.edges.push(weighted(source, target, None));
+                    }
+                    continue;
+                }
+                let target = nodes.resolve(tokens.get(1).ok_or("missing target")?)?;
+                let weight = tokens.get(2).map(|w| number(w)).transpose()?;
+                if weight.as_ref().is_some_and(is_zero) {
+                    continue;
+                }
+                doc.edges.push(weighted(source, target, weight));
+            }
+        }
+    }
+
+    for id in 1..=header.n as u64 {
+        let mut node = GmlNode {
+            id,
+            attrs: Default::default(),
+        };
+        if let Some(label) = nodes.labels.get(id as usize - 1) {
+            node.attrs
+                .insert("label".to_string(), GmlValue::from(label.clone()));
+        }
+        doc.nodes.push(node);
+    }
+    Ok(doc)
+}
+
+#[test]
+fn test_parse_ucinet() {
+    let dl = "DL N=3\nformat = fullmatrix\nlabels:\nann, \"bob b\"\ncarl\ndata:\n\
+              0 1 0\n0 0 2.5\n1 0 0\n";
+    let doc = parse_ucinet(dl).unwrap();
+    assert_eq!(3, doc.nodes.len());
+    assert_eq!(Some("bob b"), doc.nodes[1].label());
+    assert_eq!(3, doc.edges.len());
+    assert_eq!((2, 3), (doc.edges[1].source, doc.edges[1].target));
+    assert_eq!(Some(2.5), doc.edges[1].attrs["weight"].get_float());
+
+    let dl = "dl n=4 format=edgelist1\nlabels embedded\ndata:\na b 2\nb c\nc a 0\n";
+    let doc = parse_ucinet(dl).unwrap();
+    assert_eq!(4, doc.nodes.len());
+    assert_eq!(Some("c"), doc.nodes[2].label());
+    assert!(doc.nodes[3].attrs.is_empty());
+    assert_eq!(2, doc.edges.len());
+    assert_eq!(Some(2), doc.edges[0].attrs["weight"].get_int());
+    assert!(doc.edges[1].attrs.is_empty());
+
+    let dl = "DL N=2 labels embedded diagonal=absent\ndata:\nx y\nx 1\ny 0\n";
+    let doc = parse_ucinet(dl).unwrap();
+    assert_eq!(1, doc.edges.len());
+    assert_eq!(
+        Err("matrix too short"),
+        parse_ucinet("DL N=2\ndata:\n0 1 0\n")
+    );
+    assert_eq!(
+        Err("invalid n"),
+        parse_ucinet("dl n=4000000000 format=edgelist1\ndata:\n")
+    );
+}
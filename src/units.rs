@@ -0,0 +1,149 @@
+//! Changing the units of numeric attributes, like milliseconds to seconds,
+//! when reading a document and back when writing it.
+
+use crate::document::{parse_gml_document, GmlAttrs, GmlDocument, GmlValue};
+use crate::writer::write_gml;
+use std::io::{self, Write};
+
+/// A linear change of unit, from `x` to `x * factor + offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale {
+    pub factor: f64,
+    pub offset: f64,
+}
+
+impl Scale {
+    /// The scale multiplying by `factor`, like 0.001 for milliseconds to
+    /// seconds.
+    pub fn new(factor: f64) -> Scale {
+        Scale {
+            factor,
+            offset: 0.0,
+        }
+    }
+
+    pub fn apply(&self, x: f64) -> f64 {
+        x * self.factor + self.offset
+    }
+
+    /// The value `apply` maps to `y`.
+    pub fn invert(&self, y: f64) -> f64 {
+        (y - self.offset) / self.factor
+    }
+}
+
+/// Options for `scale` and `unscale`.
+#[derive(Debug, Clone, Default)]
+pub struct UnitOptions {
+    /// The keys of the node and edge attributes to change, dotted for
+    /// nested ones, like `graphics.w`, with their scales.
+    pub scales: Vec<(String, Scale)>,
+}
+
+impl UnitOptions {
+    /// Adds the scale of `key`.
+    pub fn with(mut self, key: &str, scale: Scale) -> UnitOptions {
+        self.scales.push((key.to_string(), scale));
+        self
+    }
+}
+
+// Changes the number at the dotted `key` of `attrs`, which then becomes a
+// real. Other values are left as they are.
+fn change(attrs: &mut GmlAttrs, key: &str, f: &dyn Fn(f64) -> f64) {
+    if let Some((outer, rest)) = key.split_once('.') {
+        if let Some(GmlValue::List(inner)) = attrs.get_mut(outer) {
+            change(inner, rest, f);
+        }
+        return;
+    }
+    if let Some(value) = attrs.get_mut(key) {
+        if let Some(x) = value.get_float() {
+            *value = GmlValue::Float(f(x));
+        }
+    }
+}
+
+fn change_all(doc: &mut GmlDocument, options: &UnitOptions, invert: bool) {
+    let nodes = doc.nodes.iter_mut().map(|node| &mut node.attrs);
+    let edges = doc.edges.iter_mut().map(|edge| &mut edge.attrs);
+    for attrs in nodes.chain(edges) {
+        for (key, scale) in &options.scales {
+            if invert {
+                change(attrs, key, &|y| scale.invert(y));
+            } else {
+                change(attrs, key, &|x| scale.apply(x));
+            }
+        }
+    }
+}
+
+/// Applies the scales of `options` to the node and edge attributes of
+/// `doc`. Numbers at the keys become reals; other values are kept.
+pub fn scale(doc: &mut GmlDocument, options: &UnitOptions) {
+    change_all(doc, options, false);
+}
+
+/// Undoes `scale`, up to rounding, and leaving the numbers as reals.
+pub fn unscale(doc: &mut GmlDocument, options: &UnitOptions) {
+    change_all(doc, options, true);
+}
+
+/// Parses a GML string into a document, and applies the scales of
+/// `options`.
+pub fn parse_gml_scaled(s: &str, options: &UnitOptions) -> Result<GmlDocument, &'static str> {
+    let mut doc = parse_gml_document(s)?;
+    scale(&mut doc, options);
+    Ok(doc)
+}
+
+/// Writes `doc` as GML in the original units, undoing the scales of
+/// `options`.
+pub fn write_gml_scaled<W: Write>(
+    doc: &GmlDocument,
+    options: &UnitOptions,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut doc = doc.clone();
+    unscale(&mut doc, options);
+    write_gml(&doc, w)
+}
+
+#[test]
+fn test_scale() {
+    let gml = "graph [ node [ id 1 delay 1500 graphics [ w 2 ] ] node [ id 2 delay \"slow\" ]
+                       edge [ source 1 target 2 delay 250 weight 3 ] ]";
+    let options = UnitOptions::default()
+        .with("delay", Scale::new(0.001))
+        .with("graphics.w", Scale::new(10.0));
+    let doc = parse_gml_scaled(gml, &options).unwrap();
+    let number = |attrs: &GmlAttrs, key| attrs.get(key).and_then(GmlValue::get_float);
+    assert_eq!(Some(1.5), number(&doc.nodes[0].attrs, "delay"));
+    assert_eq!(
+        Some(20.0),
+        doc.nodes[0].attrs["graphics"]
+            .get("w")
+            .and_then(GmlValue::get_float)
+    );
+    assert_eq!(
+        Some(&GmlValue::from("slow")),
+        doc.nodes[1].attrs.get("delay")
+    );
+    assert_eq!(Some(0.25), number(&doc.edges[0].attrs, "delay"));
+    assert_eq!(Some(&GmlValue::Int(3)), doc.edges[0].attrs.get("weight"));
+
+    let mut buf = Vec::new();
+    write_gml_scaled(&doc, &options, &mut buf).unwrap();
+    let written = parse_gml_document(std::str::from_utf8(&buf).unwrap()).unwrap();
+    assert_eq!(
+        Some(&GmlValue::Float(1500.0)),
+        written.nodes[0].attrs.get("delay")
+    );
+    assert_eq!(Some(250.0), number(&written.edges[0].attrs, "delay"));
+
+    let celsius = Scale {
+        factor: 1.0,
+        offset: 273.15,
+    };
+    assert_eq!(20.0, celsius.invert(celsius.apply(20.0)));
+}
@@ -0,0 +1,135 @@
+//! UUIDs in attributes, with the `uuid` feature.
+//!
+//! GML has no UUID type, so they are written as strings, like
+//! `uuid "67e55044-10b1-426f-9247-bb680e5fe0c8"`. `UuidAttrs::get_uuid`
+//! reads them, and `put_uuid` writes them in that form.
+
+use crate::document::{GmlAttrs, GmlDocument, GmlValue};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A UUID, as its 16 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Uuid(pub [u8; 16]);
+
+impl Uuid {
+    /// Parses a UUID of 32 hex digits in any case, hyphenated as
+    /// 8-4-4-4-12 or not, optionally in braces or after `urn:uuid:`.
+    pub fn parse(s: &str) -> Result<Uuid, &'static str> {
+        let invalid = "invalid uuid";
+        let s = s.strip_prefix("urn:uuid:").unwrap_or(s);
+        let s = match s.strip_prefix('{') {
+            Some(inner) => inner.strip_suffix('}').ok_or(invalid)?,
+            None => s,
+        };
+        let hyphens = [8, 13, 18, 23];
+        let digits: Vec<u8> = match s.len() {
+            32 => s.bytes().collect(),
+            36 if hyphens.iter().all(|&i| s.as_bytes()[i] == b'-') => s
+                .bytes()
+                .enumerate()
+                .filter(|(i, _)| !hyphens.contains(i))
+                .map(|(_, b)| b)
+                .collect(),
+            _ => return Err(invalid),
+        };
+        let mut bytes = [0; 16];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let hex = |d: u8| (d as char).to_digit(16).ok_or(invalid);
+            *byte = (hex(pair[0])? * 16 + hex(pair[1])?) as u8;
+        }
+        Ok(Uuid(bytes))
+    }
+
+    /// The version, from 1 to 8 for the standard kinds, like 4 for random
+    /// UUIDs.
+    pub fn version(&self) -> u8 {
+        self.0[6] >> 4
+    }
+
+    /// The UUID as a hyphenated string, as by `Display`.
+    pub fn to_value(&self) -> GmlValue {
+        GmlValue::from(self.to_string())
+    }
+}
+
+/// Writes the UUID hyphenated, in lowercase.
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if [4, 6, 8, 10].contains(&i) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// UUID accessors of attributes.
+pub trait UuidAttrs {
+    /// The UUID at `key`, if it is a string with one.
+    fn get_uuid(&self, key: &str) -> Option<Uuid>;
+    /// Sets `key` to `uuid` as a string.
+    fn put_uuid(&mut self, key: &str, uuid: Uuid);
+}
+
+impl UuidAttrs for GmlAttrs {
+    fn get_uuid(&self, key: &str) -> Option<Uuid> {
+        self.get(key)
+            .and_then(GmlValue::get_str)
+            .and_then(|s| Uuid::parse(s).ok())
+    }
+
+    fn put_uuid(&mut self, key: &str, uuid: Uuid) {
+        self.insert(key.to_string(), uuid.to_value());
+    }
+}
+
+/// The node ids of `doc` by the UUIDs at `key`, for documents which identify
+/// nodes by UUID. Every node must have a different one.
+pub fn uuid_ids(doc: &GmlDocument, key: &str) -> Result<HashMap<Uuid, u64>, &'static str> {
+    let mut ids = HashMap::with_capacity(doc.nodes.len());
+    for node in &doc.nodes {
+        let uuid = node.attrs.get_uuid(key).ok_or("node without uuid")?;
+        if ids.insert(uuid, node.id).is_some() {
+            return Err("duplicate uuid");
+        }
+    }
+    Ok(ids)
+}
+
+#[test]
+fn test_uuid() {
+    let doc = crate::parse_gml_document(
+        "graph [ node [ id 1 uuid \"67e55044-10b1-426f-9247-bb680e5fe0c8\" ]
+                 node [ id 2 uuid \"{67E5504410B1426F9247BB680E5FE0C9}\" ] ]",
+    )
+    .unwrap();
+    let a = doc.nodes[0].attrs.get_uuid("uuid").unwrap();
+    assert_eq!("67e55044-10b1-426f-9247-bb680e5fe0c8", a.to_string());
+    assert_eq!(4, a.version());
+    let b = doc.nodes[1].attrs.get_uuid("uuid").unwrap();
+    assert_eq!("67e55044-10b1-426f-9247-bb680e5fe0c9", b.to_string());
+    assert_eq!(
+        Ok(a),
+        Uuid::parse("urn:uuid:67E55044-10B1-426F-9247-BB680E5FE0C8")
+    );
+    for bad in &[
+        "67e55044-10b1-426f-9247",
+        "67e55044+10b1-426f-9247-bb680e5fe0c8",
+        "{67e5}",
+    ] {
+        assert_eq!(Err("invalid uuid"), Uuid::parse(bad));
+    }
+
+    let ids = uuid_ids(&doc, "uuid").unwrap();
+    assert_eq!((Some(&1), Some(&2)), (ids.get(&a), ids.get(&b)));
+    let mut attrs = GmlAttrs::new();
+    attrs.put_uuid("uuid", b);
+    assert_eq!(Some(b), attrs.get_uuid("uuid"));
+    assert_eq!(
+        Err("node without uuid"),
+        uuid_ids(&doc, "other").map(|_| ())
+    );
+}
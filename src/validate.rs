@@ -0,0 +1,714 @@
+//! Validation of GML text.
+//!
+//! `validate_gml` checks a GML string for the syntax and semantic errors
+//! which make `parse_gml` or `parse_gml_document` fail, without building a
+//! graph, and returns each of them with its location for editors and CI.
+//! `lint_gml` adds warnings for valid but suspicious input.
+
+use crate::codes::{error_code, ErrorCode};
+use crate::lexer::{GmlToken, Lexer};
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found by `validate_gml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The byte range of the input the diagnostic refers to.
+    pub span: Range<usize>,
+    /// A stable identifier of the kind of problem, like `duplicate-key`, the
+    /// name of its `ErrorCode`.
+    pub code: &'static str,
+    pub message: String,
+    /// The node or edge the problem is in, if any.
+    pub entity: Option<Entity>,
+}
+
+/// The node or edge a `Diagnostic` is about, as users find it in their data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entity {
+    /// A node, with its id and label, if it has them.
+    Node {
+        id: Option<u64>,
+        label: Option<String>,
+    },
+    /// The edge at `index` among the edges of the graph, counting from 0,
+    /// with its ends and label, if it has them.
+    Edge {
+        index: usize,
+        source: Option<u64>,
+        target: Option<u64>,
+        label: Option<String>,
+    },
+}
+
+/// Like `node 3 (label "a")`, or `edge 1041 (source 3, target 77)`.
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut details = Vec::new();
+        let label = match *self {
+            Entity::Node {
+                id: Some(id),
+                ref label,
+            } => {
+                write!(f, "node {}", id)?;
+                label
+            }
+            Entity::Node {
+                id: None,
+                ref label,
+            } => {
+                write!(f, "node without id")?;
+                label
+            }
+            Entity::Edge {
+                index,
+                source,
+                target,
+                ref label,
+            } => {
+                write!(f, "edge {}", index)?;
+                let end = |id: Option<u64>| id.map_or("?".to_string(), |id| id.to_string());
+                details.push(format!("source {}", end(source)));
+                details.push(format!("target {}", end(target)));
+                label
+            }
+        };
+        if let Some(ref label) = *label {
+            details.push(format!("label \"{}\"", label));
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Diagnostic {
+    /// The stable code of the kind of problem, like `GML0006` for
+    /// `duplicate-key`.
+    pub fn error_code(&self) -> &'static ErrorCode {
+        error_code(self.code).expect("diagnostic codes are in ERROR_CODES")
+    }
+
+    /// The 1-based line and column, in characters, of the start of the span
+    /// in `s`.
+    pub fn line_col(&self, s: &str) -> (usize, usize) {
+        let before = &s[..self.span.start.min(s.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}[{} {}]: {}",
+            severity,
+            self.error_code().code,
+            self.code,
+            self.message
+        )?;
+        match self.entity {
+            Some(ref entity) => write!(f, ", on {}", entity),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The checks of `lint_gml`, each of which can be turned off.
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    /// Nodes which no edge refers to, `unreferenced-node`.
+    pub unreferenced_nodes: bool,
+    /// Nodes with the same `label`, `duplicate-label`.
+    pub duplicate_labels: bool,
+    /// Node attributes which are of different types in different nodes,
+    /// like a string in one and a number in another, `inconsistent-type`.
+    pub inconsistent_types: bool,
+    /// Edges with a `weight` of 0, `zero-weight`.
+    pub zero_weights: bool,
+    /// Graphs without a `directed` flag, which are undirected,
+    /// `missing-directed`.
+    pub missing_directed: bool,
+}
+
+impl LintOptions {
+    const NONE: LintOptions = LintOptions {
+        unreferenced_nodes: false,
+        duplicate_labels: false,
+        inconsistent_types: false,
+        zero_weights: false,
+        missing_directed: false,
+    };
+}
+
+impl Default for LintOptions {
+    fn default() -> LintOptions {
+        LintOptions {
+            unreferenced_nodes: true,
+            duplicate_labels: true,
+            inconsistent_types: true,
+            zero_weights: true,
+            missing_directed: true,
+        }
+    }
+}
+
+fn type_name(value: &Option<GmlToken>) -> &'static str {
+    match *value {
+        Some(GmlToken::Int(_)) | Some(GmlToken::UInt(_)) => "an integer",
+        Some(GmlToken::Float(_)) => "a real",
+        Some(GmlToken::Str(_)) => "a string",
+        _ => "a list",
+    }
+}
+
+// The string `label` of a node or edge block.
+fn label(pairs: &[Pair]) -> Option<String> {
+    match pairs.iter().find(|pair| pair.key == "label")?.value {
+        Some(GmlToken::Str(ref label)) => Some(label.to_string()),
+        _ => None,
+    }
+}
+
+// A value as read by `Validator::item`. The items of a list value are read
+// by the caller.
+enum Value<'a> {
+    Scalar(GmlToken<'a>),
+    List,
+}
+
+enum Item<'a> {
+    Pair {
+        key: &'a str,
+        key_span: Range<usize>,
+        value: Value<'a>,
+        value_span: Range<usize>,
+    },
+    End,
+}
+
+// A key-value pair of a node or edge block.
+struct Pair<'a> {
+    key: &'a str,
+    value: Option<GmlToken<'a>>,
+    value_span: Range<usize>,
+}
+
+struct Validator<'a> {
+    s: &'a str,
+    lexer: Lexer<'a>,
+    // set after a syntax error, which ends the validation
+    failed: bool,
+    lints: LintOptions,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, span: Range<usize>, code: &'static str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            span,
+            code,
+            message,
+            entity: None,
+        });
+    }
+
+    fn warning(&mut self, span: Range<usize>, code: &'static str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            span,
+            code,
+            message,
+            entity: None,
+        });
+    }
+
+    fn syntax_error(&mut self, span: Range<usize>, code: &'static str, message: &str) {
+        self.error(span, code, message.to_string());
+        self.failed = true;
+    }
+
+    fn token(&mut self) -> Option<(Range<usize>, GmlToken<'a>)> {
+        if self.failed {
+            return None;
+        }
+        match self.lexer.next()? {
+            Ok(token) => Some((self.lexer.token_start()..self.lexer.offset(), token)),
+            Err(e) => {
+                let start = self.lexer.token_start();
+                let end = self.s[start..]
+                    .chars()
+                    .next()
+                    .map_or(start, |c| start + c.len_utf8());
+                self.syntax_error(start..end, "invalid-token", e);
+                None
+            }
+        }
+    }
+
+    // The next item of a list, or None at the end of the input or after a
+    // syntax error.
+    fn item(&mut self) -> Option<Item<'a>> {
+        let (key_span, key) = match self.token()? {
+            (span, GmlToken::Key(key)) => (span, key),
+            (_, GmlToken::Close) => return Some(Item::End),
+            (span, _) => {
+                self.syntax_error(span, "expected-key", "expected key");
+                return None;
+            }
+        };
+        let (value_span, value) = match self.token() {
+            None if !self.failed => {
+                self.syntax_error(key_span, "missing-value", "missing value");
+                return None;
+            }
+            None => return None,
+            Some((span, GmlToken::Open)) => (span, Value::List),
+            Some((span, GmlToken::Key(_))) | Some((span, GmlToken::Close)) => {
+                self.syntax_error(span, "expected-value", "expected value");
+                return None;
+            }
+            Some((span, scalar)) => (span, Value::Scalar(scalar)),
+        };
+        Some(Item::Pair {
+            key,
+            key_span,
+            value,
+            value_span,
+        })
+    }
+
+    // Sets the entity of the diagnostics from `first` on.
+    fn attribute(&mut self, first: usize, entity: &Entity) {
+        for diagnostic in &mut self.diagnostics[first..] {
+            diagnostic.entity = Some(entity.clone());
+        }
+    }
+
+    fn unbalanced(&mut self, open: Range<usize>) {
+        if !self.failed {
+            self.syntax_error(open, "unbalanced-bracket", "unclosed [");
+        }
+    }
+
+    // Reads the rest of a list opened at `open`, returning whether it is
+    // closed.
+    fn list(&mut self, open: Range<usize>) -> bool {
+        self.block(open, |_| {}).is_some()
+    }
+
+    // Reads the rest of a list opened at `open`, passing its pairs to
+    // `pair`. Returns None if the list is not closed.
+    fn block(&mut self, open: Range<usize>, mut pair: impl FnMut(Pair<'a>)) -> Option<()> {
+        loop {
+            let (key, value, value_span) = match self.item() {
+                None => {
+                    self.unbalanced(open);
+                    return None;
+                }
+                Some(Item::End) => return Some(()),
+                Some(Item::Pair {
+                    key,
+                    value,
+                    value_span,
+                    ..
+                }) => (key, value, value_span),
+            };
+            let value = match value {
+                Value::Scalar(scalar) => Some(scalar),
+                Value::List => {
+                    if !self.list(value_span.clone()) {
+                        return None;
+                    }
+                    None
+                }
+            };
+            pair(Pair {
+                key,
+                value,
+                value_span,
+            });
+        }
+    }
+
+    // Reads the id under `key` of a node or edge block opened at `open`.
+    // Other keys may be repeated, but an id may not.
+    fn id(&mut self, pairs: &[Pair], key: &str, what: &str, open: &Range<usize>) -> Option<u64> {
+        if let Some(pair) = pairs.iter().filter(|pair| pair.key == key).nth(1) {
+            let message = format!("duplicate key `{}`", key);
+            self.error(pair.value_span.clone(), "duplicate-key", message);
+        }
+        match pairs.iter().find(|pair| pair.key == key) {
+            Some(Pair {
+                value: Some(GmlToken::Int(i)),
+                ..
+            }) if *i >= 0 => Some(*i as u64),
+            Some(Pair {
+                value: Some(GmlToken::UInt(u)),
+                ..
+            }) => Some(*u),
+            Some(pair) => {
+                let message = format!("the {} `{}` is not a non-negative integer", what, key);
+                self.error(pair.value_span.clone(), "invalid-id", message);
+                None
+            }
+            None => {
+                let message = format!("the {} has no `{}`", what, key);
+                self.error(open.clone(), "missing-id", message);
+                None
+            }
+        }
+    }
+
+    fn graph(&mut self, graph_span: Range<usize>, open: Range<usize>) {
+        let mut directed = false;
+        // the span of the id and the label of every node
+        let mut nodes: HashMap<u64, (Range<usize>, Option<String>)> = HashMap::new();
+        // the ids the edges refer to, their spans and edges
+        let mut references = Vec::new();
+        let mut edges = 0;
+        let mut labels = HashSet::new();
+        // the type of every node attribute where it is first seen
+        let mut types = HashMap::new();
+        loop {
+            let (key, key_span, value, value_span) = match self.item() {
+                None => return self.unbalanced(open),
+                Some(Item::End) => break,
+                Some(Item::Pair {
+                    key,
+                    key_span,
+                    value,
+                    value_span,
+                }) => (key, key_span, value, value_span),
+            };
+            match (key, value) {
+                ("directed", Value::Scalar(GmlToken::Int(0)))
+                | ("directed", Value::Scalar(GmlToken::Int(1))) => directed = true,
+                ("directed", value) => {
+                    directed = true;
+                    let message = "`directed` is neither 0 nor 1".to_string();
+                    self.error(value_span.clone(), "invalid-directed", message);
+                    if let Value::List = value {
+                        if !self.list(value_span) {
+                            return;
+                        }
+                    }
+                }
+                ("node", Value::List) => {
+                    let first = self.diagnostics.len();
+                    let mut pairs = Vec::new();
+                    if self.block(value_span, |pair| pairs.push(pair)).is_none() {
+                        return;
+                    }
+                    let id = self.id(&pairs, "id", "node", &key_span);
+                    let label = label(&pairs);
+                    if let Some(id) = id {
+                        let span = pairs.iter().find(|pair| pair.key == "id").unwrap();
+                        let span = span.value_span.clone();
+                        match nodes.entry(id) {
+                            Entry::Occupied(_) => {
+                                let message = format!("duplicate node id {}", id);
+                                self.error(span, "duplicate-node-id", message);
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert((span, label.clone()));
+                            }
+                        }
+                    }
+                    self.lint_node(&pairs, &mut labels, &mut types);
+                    self.attribute(first, &Entity::Node { id, label });
+                }
+                ("edge", Value::List) => {
+                    let mut pairs = Vec::new();
+                    if self.block(value_span, |pair| pairs.push(pair)).is_none() {
+                        return;
+                    }
+                    let first = self.diagnostics.len();
+                    let mut ends = [None, None];
+                    for (i, &end) in ["source", "target"].iter().enumerate() {
+                        ends[i] = self.id(&pairs, end, "edge", &key_span);
+                    }
+                    let entity = Entity::Edge {
+                        index: edges,
+                        source: ends[0],
+                        target: ends[1],
+                        label: label(&pairs),
+                    };
+                    edges += 1;
+                    for (&end, id) in ["source", "target"].iter().zip(ends) {
+                        if let Some(id) = id {
+                            let pair = pairs.iter().find(|pair| pair.key == end).unwrap();
+                            references.push((id, pair.value_span.clone(), entity.clone()));
+                        }
+                    }
+                    let weight = pairs.iter().find(|pair| pair.key == "weight");
+                    if let Some(pair) = weight.filter(|_| self.lints.zero_weights) {
+                        if let Some(GmlToken::Int(0)) = pair.value {
+                            let message = "the edge has a weight of 0".to_string();
+                            self.warning(pair.value_span.clone(), "zero-weight", message);
+                        } else if let Some(GmlToken::Float(f)) = pair.value {
+                            if f == 0.0 {
+                                let message = "the edge has a weight of 0".to_string();
+                                self.warning(pair.value_span.clone(), "zero-weight", message);
+                            }
+                        }
+                    }
+                    self.attribute(first, &entity);
+                }
+                (_, value) => {
+                    if let Value::List = value {
+                        if !self.list(value_span) {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+        for &(id, ref span, ref entity) in &references {
+            if !nodes.contains_key(&id) {
+                let message = format!("the edge refers to the unknown node {}", id);
+                self.error(span.clone(), "unknown-node", message);
+                self.attribute(self.diagnostics.len() - 1, entity);
+            }
+        }
+        if self.lints.unreferenced_nodes {
+            for (id, _, _) in references {
+                nodes.remove(&id);
+            }
+            for (id, (span, label)) in nodes {
+                let message = format!("no edge refers to the node {}", id);
+                self.warning(span, "unreferenced-node", message);
+                let node = Entity::Node {
+                    id: Some(id),
+                    label,
+                };
+                self.attribute(self.diagnostics.len() - 1, &node);
+            }
+        }
+        if self.lints.missing_directed && !directed {
+            let message = "the graph has no `directed` flag and is undirected".to_string();
+            self.warning(graph_span, "missing-directed", message);
+        }
+    }
+
+    fn lint_node(
+        &mut self,
+        pairs: &[Pair<'a>],
+        labels: &mut HashSet<String>,
+        types: &mut HashMap<&'a str, &'static str>,
+    ) {
+        for pair in pairs {
+            if pair.key == "id" {
+                continue;
+            }
+            if self.lints.duplicate_labels && pair.key == "label" {
+                if let Some(GmlToken::Str(ref label)) = pair.value {
+                    if !labels.insert(label.to_string()) {
+                        let message = format!("another node has the label \"{}\"", label);
+                        self.warning(pair.value_span.clone(), "duplicate-label", message);
+                    }
+                }
+            }
+            if self.lints.inconsistent_types {
+                let name = type_name(&pair.value);
+                let first = *types.entry(pair.key).or_insert(name);
+                if first != name {
+                    let message = format!(
+                        "`{}` is {} here but {} in an earlier node",
+                        pair.key, name, first
+                    );
+                    self.warning(pair.value_span.clone(), "inconsistent-type", message);
+                }
+            }
+        }
+    }
+}
+
+/// Checks `s` for the errors `parse_gml` and `parse_gml_document` report,
+/// and for edges referring to unknown nodes, and returns them in the
+/// order of the input. Checking continues after semantic errors but stops
+/// at the first syntax error.
+pub fn validate_gml(s: &str) -> Vec<Diagnostic> {
+    check(s, LintOptions::NONE)
+}
+
+/// Checks `s` like `validate_gml` and also for the problems `options`
+/// selects, which are reported as warnings.
+pub fn lint_gml(s: &str, options: &LintOptions) -> Vec<Diagnostic> {
+    check(s, options.clone())
+}
+
+fn check(s: &str, lints: LintOptions) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        s,
+        lexer: Lexer::new(s),
+        failed: false,
+        lints,
+        diagnostics: Vec::new(),
+    };
+    let mut graph = None;
+    loop {
+        match validator.item() {
+            None => break,
+            Some(Item::End) => {
+                let start = validator.lexer.token_start();
+                validator.syntax_error(start..start + 1, "unbalanced-bracket", "unbalanced ]");
+                break;
+            }
+            Some(Item::Pair {
+                key: "graph",
+                key_span,
+                value: Value::List,
+                value_span,
+            }) => {
+                if graph.is_some() {
+                    let message = "a second `graph` list".to_string();
+                    validator.error(key_span, "duplicate-graph", message);
+                    if !validator.list(value_span) {
+                        break;
+                    }
+                } else {
+                    graph = Some(());
+                    validator.graph(key_span, value_span);
+                }
+            }
+            Some(Item::Pair {
+                value: Value::List,
+                value_span,
+                ..
+            }) => {
+                if !validator.list(value_span) {
+                    break;
+                }
+            }
+            Some(Item::Pair { .. }) => {}
+        }
+    }
+    if graph.is_none() && !validator.failed {
+        let end = s.len();
+        validator.error(end..end, "missing-graph", "no `graph` list".to_string());
+    }
+    let mut diagnostics = validator.diagnostics;
+    diagnostics.sort_by_key(|d| d.span.start);
+    diagnostics
+}
+
+#[test]
+fn test_validate_gml() {
+    let gml = "graph [ directed 1 node [ id 1 ] node [ id 1 label \"a\" id 2 ]\n\
+               node [ x [ y 1 ] ] edge [ source 1 target 3 ] directed 2 ]";
+    let codes: Vec<_> = validate_gml(gml).iter().map(|d| d.code).collect();
+    assert_eq!(
+        vec![
+            "duplicate-node-id",
+            "duplicate-key",
+            "missing-id",
+            "unknown-node",
+            "invalid-directed"
+        ],
+        codes
+    );
+    let diagnostics = validate_gml(gml);
+    assert_eq!("1", &gml[diagnostics[0].span.clone()]);
+    assert_eq!((2, 43), diagnostics[3].line_col(gml));
+    assert_eq!(
+        "error[GML0011 unknown-node]: the edge refers to the unknown node 3, \
+         on edge 0 (source 1, target 3)",
+        diagnostics[3].to_string()
+    );
+    let entities: Vec<_> = diagnostics
+        .iter()
+        .map(|d| d.entity.as_ref().map(Entity::to_string))
+        .collect();
+    assert_eq!(
+        vec![
+            Some("node 1 (label \"a\")".to_string()),
+            Some("node 1 (label \"a\")".to_string()),
+            Some("node without id".to_string()),
+            Some("edge 0 (source 1, target 3)".to_string()),
+            None
+        ],
+        entities
+    );
+
+    for (gml, code) in &[
+        ("graph [ node [ id 1 ]", "unbalanced-bracket"),
+        ("graph [ ] ]", "unbalanced-bracket"),
+        ("graph [ id ]", "expected-value"),
+        ("graph [ 1 2 ]", "expected-key"),
+        ("graph [ label \"a ]", "invalid-token"),
+        ("Creator \"x\"", "missing-graph"),
+        ("graph [ ] graph [ ]", "duplicate-graph"),
+        ("graph [ node [ id -1 ] ]", "invalid-id"),
+    ] {
+        let diagnostics = validate_gml(gml);
+        assert_eq!(
+            vec![*code],
+            diagnostics.iter().map(|d| d.code).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(
+        Vec::<Diagnostic>::new(),
+        validate_gml("graph [ node [ id 1 ] ]")
+    );
+}
+
+#[test]
+fn test_lint_gml() {
+    let gml = "graph [ node [ id 1 label \"a\" x 1 ] node [ id 2 label \"a\" x \"1\" ] \
+               node [ id 3 ] edge [ source 1 target 2 weight 0.0 ] ]";
+    let codes = |options: &LintOptions| {
+        lint_gml(gml, options)
+            .iter()
+            .map(|d| (d.severity, d.code))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec![
+            (Severity::Warning, "missing-directed"),
+            (Severity::Warning, "duplicate-label"),
+            (Severity::Warning, "inconsistent-type"),
+            (Severity::Warning, "unreferenced-node"),
+            (Severity::Warning, "zero-weight"),
+        ],
+        codes(&LintOptions::default())
+    );
+    let options = LintOptions {
+        missing_directed: false,
+        inconsistent_types: false,
+        ..LintOptions::default()
+    };
+    assert_eq!(
+        vec![
+            (Severity::Warning, "duplicate-label"),
+            (Severity::Warning, "unreferenced-node"),
+            (Severity::Warning, "zero-weight"),
+        ],
+        codes(&options)
+    );
+    assert_eq!(
+        "warning[GML0103 inconsistent-type]: `x` is a string here but an integer in an earlier \
+         node, on node 2 (label \"a\")",
+        lint_gml(gml, &LintOptions::default())[2].to_string()
+    );
+    assert!(validate_gml(gml).is_empty());
+}
@@ -0,0 +1,203 @@
+//! Reloading GML files when they change.
+//!
+//! `watch_gml` polls a file on a background thread and parses it again
+//! each time it has been modified and then left alone for a moment, so that
+//! a viewer can follow a file while another program writes it.
+
+use crate::codes::parse_error_code;
+use crate::document::{parse_gml_document, GmlDocument};
+use crate::input::read_gml_file;
+use crate::validate::{validate_gml, Diagnostic, Severity};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Options for `watch_gml_with_options`.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// How often the modification time and size of the file are looked at.
+    pub interval: Duration,
+    /// How long the file must stay unchanged after a change before it is
+    /// read, so that a file being written is read once, when it is done.
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> WatchOptions {
+        WatchOptions {
+            interval: Duration::from_millis(100),
+            debounce: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Why a watched file could not be loaded.
+#[derive(Debug)]
+pub enum WatchError {
+    /// The file could not be read, for instance because it was removed.
+    Io(io::Error),
+    /// The file is not a valid GML document. `text` is its content, which
+    /// the spans of the diagnostics refer to.
+    Invalid {
+        text: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WatchError::Io(ref e) => write!(f, "{}", e),
+            WatchError::Invalid {
+                ref text,
+                ref diagnostics,
+            } => {
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    let (line, col) = diagnostic.line_col(text);
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}:{}: {}", line, col, diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A running `watch_gml`, which stops when it is dropped.
+pub struct GmlWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl GmlWatcher {
+    /// Stops watching and waits for a running callback to return.
+    pub fn stop(self) {}
+}
+
+impl Drop for GmlWatcher {
+    fn drop(&mut self) {
+        // dropping the sender wakes the thread up
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// What tells whether a file has changed; `None` if it does not exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn load(path: &Path) -> Result<GmlDocument, WatchError> {
+    let text = read_gml_file(path).map_err(WatchError::Io)?;
+    match parse_gml_document(&text) {
+        Ok(doc) => Ok(doc),
+        Err(e) => {
+            let mut diagnostics = validate_gml(&text);
+            if diagnostics.is_empty() {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    span: 0..0,
+                    code: parse_error_code(e).name,
+                    entity: None,
+                    message: e.to_string(),
+                });
+            }
+            Err(WatchError::Invalid { text, diagnostics })
+        }
+    }
+}
+
+/// Watches the GML file at `path` with the default `WatchOptions`.
+pub fn watch_gml<P, F>(path: P, callback: F) -> GmlWatcher
+where
+    P: AsRef<Path>,
+    F: FnMut(Result<GmlDocument, WatchError>) + Send + 'static,
+{
+    watch_gml_with_options(path, &WatchOptions::default(), callback)
+}
+
+/// Loads the GML file at `path`, which may be compressed as for
+/// `read_gml_file`, and loads it again whenever it changes, on a new
+/// thread. Each load hands the document, or why there is none, to
+/// `callback`, starting with the file as it is now.
+pub fn watch_gml_with_options<P, F>(path: P, options: &WatchOptions, mut callback: F) -> GmlWatcher
+where
+    P: AsRef<Path>,
+    F: FnMut(Result<GmlDocument, WatchError>) + Send + 'static,
+{
+    let path: PathBuf = path.as_ref().to_path_buf();
+    let options = options.clone();
+    let (stop, stopped) = channel::<()>();
+    let thread = thread::spawn(move || {
+        let mut loaded = stamp(&path);
+        callback(load(&path));
+        // the last stamp seen and when it was first seen
+        let mut seen = (loaded, Instant::now());
+        loop {
+            match stopped.recv_timeout(options.interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+            let now = stamp(&path);
+            if now != seen.0 {
+                seen = (now, Instant::now());
+            } else if now != loaded && seen.1.elapsed() >= options.debounce {
+                loaded = now;
+                callback(load(&path));
+            }
+        }
+    });
+    GmlWatcher {
+        stop: Some(stop),
+        thread: Some(thread),
+    }
+}
+
+#[test]
+fn test_watch_gml() {
+    use std::sync::mpsc;
+
+    let dir = std::env::temp_dir().join(format!("gml-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("g.gml");
+    fs::write(&path, "graph [ node [ id 1 ] ]").unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let options = WatchOptions {
+        interval: Duration::from_millis(10),
+        debounce: Duration::from_millis(30),
+    };
+    let watcher = watch_gml_with_options(&path, &options, move |result| {
+        let _ = tx.send(result);
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(1, next().unwrap().nodes.len());
+
+    fs::write(&path, "graph [ node [ id 1 ] node [ id 2 ] ]").unwrap();
+    assert_eq!(2, next().unwrap().nodes.len());
+
+    fs::write(&path, "graph [ node [ id 1 ] node [ id 1 ] node [ ").unwrap();
+    match next() {
+        Err(WatchError::Invalid { diagnostics, .. }) => {
+            assert!(diagnostics.iter().any(|d| d.code == "duplicate-node-id"))
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(next(), Err(WatchError::Io(_))));
+    watcher.stop();
+    assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
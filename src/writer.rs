@@ -0,0 +1,132 @@
+use crate::document::{repeated, GmlAttrs, GmlDocument, GmlValue};
+use crate::trace::{self, event, span};
+use std::io::{self, Write};
+
+/// Renders `doc` as GML.
+pub fn to_gml(doc: &GmlDocument) -> String {
+    let mut buf = Vec::new();
+    write_gml(doc, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// Writes `doc` as GML, indenting nested lists by two spaces.
+pub fn write_gml<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    span!(
+        "gml.write",
+        nodes = doc.nodes.len(),
+        edges = doc.edges.len()
+    );
+    let result = write_document(doc, w);
+    match result {
+        Ok(()) => event!(debug, "wrote document"),
+        Err(ref e) => trace::failed("writing the document", e),
+    }
+    result
+}
+
+fn write_document<W: Write>(doc: &GmlDocument, w: &mut W) -> io::Result<()> {
+    write_attrs(w, &doc.top_attrs, 0)?;
+    writeln!(w, "graph [")?;
+    writeln!(w, "  directed {}", doc.directed as u8)?;
+    write_attrs(w, &doc.attrs, 1)?;
+    for node in &doc.nodes {
+        writeln!(w, "  node [")?;
+        writeln!(w, "    id {}", node.id)?;
+        write_attrs(w, &node.attrs, 2)?;
+        writeln!(w, "  ]")?;
+    }
+    for edge in &doc.edges {
+        writeln!(w, "  edge [")?;
+        writeln!(w, "    source {}", edge.source)?;
+        writeln!(w, "    target {}", edge.target)?;
+        write_attrs(w, &edge.attrs, 2)?;
+        writeln!(w, "  ]")?;
+    }
+    writeln!(w, "]")
+}
+
+pub(crate) fn write_attrs<W: Write>(w: &mut W, attrs: &GmlAttrs, level: usize) -> io::Result<()> {
+    for (key, value) in attrs {
+        match repeated(value) {
+            Some(values) => {
+                for value in values {
+                    write_attr(w, key, value, level)?;
+                }
+            }
+            None => write_attr(w, key, value, level)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_attr<W: Write>(w: &mut W, key: &str, value: &GmlValue, level: usize) -> io::Result<()> {
+    let indent = "  ".repeat(level);
+    match *value {
+        GmlValue::List(ref inner) => {
+            writeln!(w, "{}{} [", indent, key)?;
+            write_attrs(w, inner, level + 1)?;
+            writeln!(w, "{}]", indent)
+        }
+        ref scalar => writeln!(w, "{}{} {}", indent, key, format_scalar(scalar)),
+    }
+}
+
+/// Formats a scalar value as GML. Reals always get a decimal point or an
+/// exponent so that they are read back as reals. In strings, `"` and `&`
+/// are written as character entities.
+pub(crate) fn format_scalar(value: &GmlValue) -> String {
+    match *value {
+        GmlValue::Int(i) => i.to_string(),
+        GmlValue::UInt(u) => u.to_string(),
+        GmlValue::Float(f) if f.is_nan() => "NAN".to_string(),
+        GmlValue::Float(f) if f.is_infinite() => {
+            (if f > 0.0 { "+INF" } else { "-INF" }).to_string()
+        }
+        GmlValue::Float(f) => format!("{:?}", f),
+        GmlValue::Str(ref s) => {
+            let mut out = String::with_capacity(s.len() + 2);
+            out.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("&quot;"),
+                    '&' => out.push_str("&amp;"),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+        GmlValue::List(_) => panic!("not a scalar"),
+    }
+}
+
+#[test]
+fn test_write_gml_round_trip() {
+    let gml = "
+    graph [
+        directed 1
+        label \"say &quot;hi&quot; &amp; bye\"
+        node [ id 1 label \"a\" graphics [ x 1.0 y -2 ] ]
+        node [ id 2 ]
+        edge [ source 1 target 2 weight 0.5 ]
+    ]
+    ";
+    let doc = crate::parse_gml_document(gml).unwrap();
+    assert_eq!(Some("say \"hi\" & bye"), doc.attrs["label"].get_str());
+    let written = to_gml(&doc);
+    assert!(written.contains("    graphics [\n      x 1.0\n      y -2\n    ]\n"));
+    assert_eq!(doc, crate::parse_gml_document(&written).unwrap());
+
+    let doc = crate::parse_gml_document(
+        "Creator \"yFiles\" Version 2 graph [ node [ id 1 ] ] meta [ by \"x\" ]",
+    )
+    .unwrap();
+    assert_eq!(Some("yFiles"), doc.top_attrs["Creator"].get_str());
+    assert_eq!(
+        Some("x"),
+        doc.top_attrs["meta"].get("by").and_then(GmlValue::get_str)
+    );
+    let written = to_gml(&doc);
+    assert!(written.starts_with("Creator \"yFiles\"\n"));
+    assert_eq!(doc, crate::parse_gml_document(&written).unwrap());
+}
@@ -0,0 +1,271 @@
+//! Minimal XML helpers shared by the XML based formats.
+
+/// Escapes `s` for use in XML text and attribute values.
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// An XML element with its attributes and children. Namespace prefixes are
+/// stripped from element and attribute names.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum XmlNode {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|&(k, _)| k == name)
+            .map(|(_, v)| &v[..])
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match *c {
+            XmlNode::Element(ref e) => Some(e),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    pub fn elements_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    /// The concatenated text of all direct text children.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for c in &self.children {
+            if let XmlNode::Text(ref t) = *c {
+                text.push_str(t);
+            }
+        }
+        text
+    }
+}
+
+/// Parses an XML document and returns its root element.
+pub(crate) fn parse(s: &str) -> Result<Element, &'static str> {
+    let mut parser = XmlParser { s, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.parse_element()?;
+    parser.skip_misc()?;
+    if parser.pos != s.len() {
+        return Err("trailing content after root element");
+    }
+    Ok(root)
+}
+
+struct XmlParser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+fn local_name(name: &str) -> &str {
+    match name.find(':') {
+        Some(i) => &name[i + 1..],
+        None => name,
+    }
+}
+
+impl<'a> XmlParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str, err: &'static str) -> Result<(), &'static str> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(err),
+        }
+    }
+
+    // Skips whitespace, comments, processing instructions and DOCTYPE.
+    fn skip_misc(&mut self) -> Result<(), &'static str> {
+        loop {
+            self.skip_ws();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>", "unterminated processing instruction")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->", "unterminated comment")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.skip_doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_doctype(&mut self) -> Result<(), &'static str> {
+        let mut depth = 0;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth == 0 => {
+                    self.pos += i + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err("unterminated DOCTYPE")
+    }
+
+    fn parse_name(&mut self) -> Result<&'a str, &'static str> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>' || c == '=')
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err("expected name");
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn parse_element(&mut self) -> Result<Element, &'static str> {
+        if !self.rest().starts_with('<') {
+            return Err("expected element");
+        }
+        self.pos += 1;
+        let qname = self.parse_name()?;
+        let mut element = Element {
+            name: local_name(qname).to_string(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+        };
+
+        loop {
+            self.skip_ws();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let name = self.parse_name()?;
+            self.skip_ws();
+            if !self.rest().starts_with('=') {
+                return Err("expected '='");
+            }
+            self.pos += 1;
+            self.skip_ws();
+            let quote = match self.rest().chars().next() {
+                Some(q) if q == '"' || q == '\'' => q,
+                _ => return Err("expected quoted attribute value"),
+            };
+            self.pos += 1;
+            let len = self
+                .rest()
+                .find(quote)
+                .ok_or("unterminated attribute value")?;
+            let value = unescape(&self.rest()[..len])?;
+            self.pos += len + 1;
+            if name != "xmlns" && !name.starts_with("xmlns:") {
+                element.attrs.push((local_name(name).to_string(), value));
+            }
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let end = self.parse_name()?;
+                if end != qname {
+                    return Err("mismatched end tag");
+                }
+                self.skip_ws();
+                if !self.rest().starts_with('>') {
+                    return Err("expected '>'");
+                }
+                self.pos += 1;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->", "unterminated comment")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += 9;
+                let len = self.rest().find("]]>").ok_or("unterminated CDATA")?;
+                let text = self.rest()[..len].to_string();
+                self.pos += len + 3;
+                element.children.push(XmlNode::Text(text));
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>", "unterminated processing instruction")?;
+            } else if rest.starts_with('<') {
+                let child = self.parse_element()?;
+                element.children.push(XmlNode::Element(child));
+            } else if rest.is_empty() {
+                return Err("unexpected end of input");
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let text = unescape(&rest[..len])?;
+                self.pos += len;
+                element.children.push(XmlNode::Text(text));
+            }
+        }
+    }
+}
+
+fn unescape(s: &str) -> Result<String, &'static str> {
+    if !s.contains('&') {
+        return Ok(s.to_string());
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest.find(';').ok_or("unterminated entity")?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32)
+                .ok_or("invalid character reference")?,
+            _ if entity.starts_with('#') => entity[1..]
+                .parse::<u32>()
+                .ok()
+                .and_then(std::char::from_u32)
+                .ok_or("invalid character reference")?,
+            _ => return Err("unknown entity"),
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
@@ -0,0 +1,772 @@
+//! Zstandard decompression.
+//!
+//! A decoder for Zstandard frames (RFC 8878) without dictionaries. Skippable
+//! frames are skipped and content checksums are verified.
+
+type Result<T> = std::result::Result<T, &'static str>;
+
+const TRUNCATED: &str = "truncated zstd data";
+const CORRUPT: &str = "corrupt zstd data";
+
+fn byte(data: &[u8], pos: usize) -> Result<usize> {
+    data.get(pos).map(|&b| b as usize).ok_or(TRUNCATED)
+}
+
+fn le(data: &[u8]) -> u64 {
+    data.iter()
+        .rev()
+        .fold(0, |value, &b| (value << 8) | b as u64)
+}
+
+fn slice(data: &[u8], from: usize, len: usize) -> Result<&[u8]> {
+    data.get(from..from + len).ok_or(TRUNCATED)
+}
+
+fn highbit(n: u32) -> u32 {
+    31 - n.leading_zeros()
+}
+
+// A forward bit stream, read from the least significant end, as used for
+// FSE table descriptions.
+struct Forward<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> Forward<'a> {
+    fn read(&mut self, n: u32) -> Result<u32> {
+        let value = self.peek(n);
+        self.bit += n as usize;
+        if self.bit > self.data.len() * 8 {
+            return Err(TRUNCATED);
+        }
+        Ok(value)
+    }
+
+    fn peek(&self, n: u32) -> u32 {
+        let start = self.bit / 8;
+        let end = (start + 8).min(self.data.len());
+        let window = le(self.data.get(start..end).unwrap_or(&[])) >> (self.bit % 8);
+        (window & ((1 << n) - 1)) as u32
+    }
+
+    fn bytes_read(&self) -> usize {
+        self.bit.div_ceil(8)
+    }
+}
+
+// A backward bit stream: it starts at the highest set bit of the last byte
+// and is read towards the first byte, each value from its most significant
+// bit. Reading past the first byte yields zeros.
+struct Backward<'a> {
+    data: &'a [u8],
+    // the number of unread bits
+    bits: isize,
+}
+
+impl<'a> Backward<'a> {
+    fn new(data: &'a [u8]) -> Result<Backward<'a>> {
+        let last = *data.last().ok_or(CORRUPT)?;
+        if last == 0 {
+            return Err(CORRUPT);
+        }
+        Ok(Backward {
+            data,
+            bits: (data.len() * 8 - 8 + highbit(last as u32) as usize) as isize,
+        })
+    }
+
+    fn peek(&self, n: u32) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        let start = self.bits - n as isize;
+        let (from, shift) = if start >= 0 {
+            (start as usize, 0)
+        } else {
+            (0, (-start) as u32)
+        };
+        let first = from / 8;
+        let end = (first + 8).min(self.data.len());
+        let window = le(&self.data[first..end]) >> (from % 8);
+        let value = window & ((1u64 << (n - shift.min(n))) - 1);
+        value << shift.min(n)
+    }
+
+    fn read(&mut self, n: u32) -> u64 {
+        let value = self.peek(n);
+        self.bits -= n as isize;
+        value
+    }
+
+    fn overflowed(&self) -> bool {
+        self.bits < 0
+    }
+
+    fn finished(&self) -> bool {
+        self.bits == 0
+    }
+}
+
+// A decoding table entry of an FSE or Huffman table.
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    symbol: u8,
+    bits: u8,
+    base: u16,
+}
+
+#[derive(Clone, Default)]
+struct Fse {
+    log: u32,
+    table: Vec<Entry>,
+}
+
+impl Fse {
+    fn new(counts: &[i16], log: u32) -> Result<Fse> {
+        let size = 1usize << log;
+        let mut table = vec![Entry::default(); size];
+        let mut high = size - 1;
+        let mut next = vec![0u32; counts.len()];
+        for (s, &count) in counts.iter().enumerate() {
+            if count == -1 {
+                table[high].symbol = s as u8;
+                high = high.wrapping_sub(1);
+                next[s] = 1;
+            } else {
+                next[s] = count.max(0) as u32;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut pos = 0;
+        for (s, &count) in counts.iter().enumerate() {
+            for _ in 0..count.max(0) {
+                table[pos].symbol = s as u8;
+                pos = (pos + step) & (size - 1);
+                while pos > high {
+                    pos = (pos + step) & (size - 1);
+                }
+            }
+        }
+        if pos != 0 {
+            return Err(CORRUPT);
+        }
+        for entry in &mut table {
+            let state = next[entry.symbol as usize];
+            next[entry.symbol as usize] += 1;
+            entry.bits = (log - highbit(state)) as u8;
+            entry.base = ((state << entry.bits) - size as u32) as u16;
+        }
+        Ok(Fse { log, table })
+    }
+
+    // A table which always yields `symbol`.
+    fn rle(symbol: u8) -> Fse {
+        Fse {
+            log: 0,
+            table: vec![Entry {
+                symbol,
+                bits: 0,
+                base: 0,
+            }],
+        }
+    }
+
+    // Reads a table description, returning the table and its length.
+    fn read(data: &[u8], max_log: u32, max_symbol: usize) -> Result<(Fse, usize)> {
+        let mut bits = Forward { data, bit: 0 };
+        let log = bits.read(4)? + 5;
+        if log > max_log {
+            return Err(CORRUPT);
+        }
+        let mut counts = Vec::new();
+        let mut remaining = (1i32 << log) + 1;
+        let mut threshold = 1i32 << log;
+        let mut nbits = log + 1;
+        while remaining > 1 {
+            if counts.len() > max_symbol {
+                return Err(CORRUPT);
+            }
+            let max = 2 * threshold - 1 - remaining;
+            let low = bits.peek(nbits - 1) as i32;
+            let mut count = if low < max {
+                bits.read(nbits - 1)?;
+                low
+            } else {
+                let value = bits.read(nbits)? as i32;
+                if value >= threshold {
+                    value - max
+                } else {
+                    value
+                }
+            };
+            count -= 1;
+            remaining -= count.abs();
+            counts.push(count as i16);
+            if count == 0 {
+                loop {
+                    let repeat = bits.read(2)?;
+                    counts.extend(std::iter::repeat_n(0, repeat as usize));
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+            while remaining < threshold {
+                nbits -= 1;
+                threshold >>= 1;
+            }
+        }
+        if remaining != 1 || counts.len() > max_symbol + 1 {
+            return Err(CORRUPT);
+        }
+        Ok((Fse::new(&counts, log)?, bits.bytes_read()))
+    }
+}
+
+struct State<'t> {
+    table: &'t [Entry],
+    state: usize,
+}
+
+impl<'t> State<'t> {
+    fn new(fse: &'t Fse, bits: &mut Backward) -> State<'t> {
+        State {
+            table: &fse.table,
+            state: bits.read(fse.log) as usize,
+        }
+    }
+
+    fn symbol(&self) -> u8 {
+        self.table[self.state].symbol
+    }
+
+    fn update(&mut self, bits: &mut Backward) {
+        let entry = self.table[self.state];
+        self.state = entry.base as usize + bits.read(entry.bits as u32) as usize;
+    }
+}
+
+struct Huffman {
+    log: u32,
+    table: Vec<Entry>,
+}
+
+impl Huffman {
+    // Reads a Huffman tree description, returning the table and its length.
+    fn read(data: &[u8]) -> Result<(Huffman, usize)> {
+        let header = byte(data, 0)?;
+        let (mut weights, len) = if header < 128 {
+            let data = slice(data, 1, header)?;
+            let (fse, n) = Fse::read(data, 6, 255)?;
+            let mut bits = Backward::new(data.get(n..).ok_or(CORRUPT)?)?;
+            let mut states = [State::new(&fse, &mut bits), State::new(&fse, &mut bits)];
+            let mut weights = Vec::new();
+            for i in (0..2).cycle() {
+                weights.push(states[i].symbol());
+                states[i].update(&mut bits);
+                if bits.overflowed() {
+                    weights.push(states[1 - i].symbol());
+                    break;
+                }
+                if weights.len() > 255 {
+                    return Err(CORRUPT);
+                }
+            }
+            (weights, 1 + header)
+        } else {
+            let n = header - 127;
+            let packed = slice(data, 1, n.div_ceil(2))?;
+            let weights = (0..n)
+                .map(|i| (packed[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 15)
+                .collect();
+            (weights, 1 + n.div_ceil(2))
+        };
+
+        let mut sum = 0u32;
+        for &w in &weights {
+            if w > 11 {
+                return Err(CORRUPT);
+            }
+            sum += (1 << w) >> 1;
+        }
+        if sum == 0 {
+            return Err(CORRUPT);
+        }
+        let log = highbit(sum) + 1;
+        let rest = (1 << log) - sum;
+        if !rest.is_power_of_two() || log > 11 {
+            return Err(CORRUPT);
+        }
+        weights.push(highbit(rest) as u8 + 1);
+
+        let mut starts = [0u32; 13];
+        for w in 1..=11 {
+            let count = weights.iter().filter(|&&x| x == w as u8).count() as u32;
+            starts[w + 1] = starts[w] + (count << (w - 1));
+        }
+        let mut table = vec![Entry::default(); 1 << log];
+        for (symbol, &w) in weights.iter().enumerate() {
+            if w == 0 {
+                continue;
+            }
+            let w = w as usize;
+            let len = 1 << (w - 1);
+            let start = starts[w] as usize;
+            for entry in &mut table[start..start + len] {
+                *entry = Entry {
+                    symbol: symbol as u8,
+                    bits: (log + 1 - w as u32) as u8,
+                    base: 0,
+                };
+            }
+            starts[w] += len as u32;
+        }
+        Ok((Huffman { log, table }, len))
+    }
+
+    // Decodes `n` symbols from a stream.
+    fn decode(&self, data: &[u8], n: usize, out: &mut Vec<u8>) -> Result<()> {
+        let mut bits = Backward::new(data)?;
+        for _ in 0..n {
+            let entry = self.table[bits.peek(self.log) as usize];
+            out.push(entry.symbol);
+            bits.read(entry.bits as u32);
+        }
+        if !bits.finished() {
+            return Err(CORRUPT);
+        }
+        Ok(())
+    }
+}
+
+// The baselines and numbers of extra bits of the literal length and match
+// length codes.
+const LL_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 20, 22, 24, 28, 32, 40, 48, 64,
+    128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+const LL_BITS: [u32; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+const ML_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+    28, 29, 30, 31, 32, 33, 34, 35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027,
+    2051, 4099, 8195, 16387, 32771, 65539,
+];
+const ML_BITS: [u32; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+];
+
+const LL_DEFAULT: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+
+const ML_DEFAULT: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+
+const OF_DEFAULT: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+// The tables and offsets which carry over from one block to the next.
+struct Frame {
+    huffman: Option<Huffman>,
+    ll: Option<Fse>,
+    of: Option<Fse>,
+    ml: Option<Fse>,
+    offsets: [usize; 3],
+}
+
+// Reads the table of one sequence symbol type as given by `mode`, with the
+// predefined counts and accuracy log in `default` and the largest accuracy
+// log and symbol in `max`.
+fn read_table(
+    data: &[u8],
+    pos: &mut usize,
+    mode: u8,
+    table: &mut Option<Fse>,
+    default: (&[i16], u32),
+    max: (u32, usize),
+) -> Result<()> {
+    match mode {
+        0 => *table = Some(Fse::new(default.0, default.1)?),
+        1 => {
+            *table = Some(Fse::rle(byte(data, *pos)? as u8));
+            *pos += 1;
+        }
+        2 => {
+            let (fse, len) = Fse::read(data.get(*pos..).ok_or(TRUNCATED)?, max.0, max.1)?;
+            *table = Some(fse);
+            *pos += len;
+        }
+        _ => {
+            if table.is_none() {
+                return Err(CORRUPT);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn literals(data: &[u8], frame: &mut Frame) -> Result<(Vec<u8>, usize)> {
+    let b0 = byte(data, 0)?;
+    let kind = b0 & 3;
+    let format = (b0 >> 2) & 3;
+    if kind < 2 {
+        let (size, header) = match format {
+            0 | 2 => (b0 >> 3, 1),
+            1 => ((b0 >> 4) + (byte(data, 1)? << 4), 2),
+            _ => (
+                (b0 >> 4) + (byte(data, 1)? << 4) + (byte(data, 2)? << 12),
+                3,
+            ),
+        };
+        return if kind == 0 {
+            Ok((slice(data, header, size)?.to_vec(), header + size))
+        } else {
+            Ok((vec![byte(data, header)? as u8; size], header + 1))
+        };
+    }
+
+    let (header, bits) = match format {
+        0 | 1 => (3, 10),
+        2 => (4, 14),
+        _ => (5, 18),
+    };
+    let value = le(slice(data, 0, header)?);
+    let mask = (1 << bits) - 1;
+    let size = ((value >> 4) & mask) as usize;
+    let compressed = ((value >> (4 + bits)) & mask) as usize;
+    let streams = if format == 0 { 1 } else { 4 };
+    let mut data = slice(data, header, compressed)?;
+    if kind == 2 {
+        let (huffman, len) = Huffman::read(data)?;
+        frame.huffman = Some(huffman);
+        data = &data[len..];
+    }
+    let huffman = frame.huffman.as_ref().ok_or(CORRUPT)?;
+    let mut out = Vec::with_capacity(size);
+    if streams == 1 {
+        huffman.decode(data, size, &mut out)?;
+    } else {
+        let jump = slice(data, 0, 6)?;
+        let mut sizes = [0; 4];
+        for (i, size) in sizes.iter_mut().take(3).enumerate() {
+            *size = le(&jump[2 * i..2 * i + 2]) as usize;
+        }
+        let total: usize = sizes[..3].iter().sum();
+        sizes[3] = data.len().checked_sub(6 + total).ok_or(CORRUPT)?;
+        let per_stream = size.div_ceil(4);
+        let mut start = 6;
+        for (i, &len) in sizes.iter().enumerate() {
+            let n = if i < 3 {
+                per_stream
+            } else {
+                size.checked_sub(3 * per_stream).ok_or(CORRUPT)?
+            };
+            huffman.decode(&data[start..start + len], n, &mut out)?;
+            start += len;
+        }
+    }
+    Ok((out, header + compressed))
+}
+
+fn block(data: &[u8], frame: &mut Frame, out: &mut Vec<u8>) -> Result<()> {
+    let (literals, mut pos) = literals(data, frame)?;
+    let b0 = byte(data, pos)?;
+    let count = if b0 < 128 {
+        pos += 1;
+        b0
+    } else if b0 < 255 {
+        pos += 2;
+        ((b0 - 128) << 8) + byte(data, pos - 1)?
+    } else {
+        pos += 3;
+        byte(data, pos - 2)? + (byte(data, pos - 1)? << 8) + 0x7F00
+    };
+    if count == 0 {
+        out.extend_from_slice(&literals);
+        return Ok(());
+    }
+
+    let modes = byte(data, pos)? as u8;
+    pos += 1;
+    read_table(
+        data,
+        &mut pos,
+        modes >> 6,
+        &mut frame.ll,
+        (&LL_DEFAULT, 6),
+        (9, 35),
+    )?;
+    read_table(
+        data,
+        &mut pos,
+        (modes >> 4) & 3,
+        &mut frame.of,
+        (&OF_DEFAULT, 5),
+        (8, 31),
+    )?;
+    read_table(
+        data,
+        &mut pos,
+        (modes >> 2) & 3,
+        &mut frame.ml,
+        (&ML_DEFAULT, 6),
+        (9, 52),
+    )?;
+    let tables = (frame.ll.as_ref(), frame.of.as_ref(), frame.ml.as_ref());
+    let (ll, of, ml) = match tables {
+        (Some(ll), Some(of), Some(ml)) => (ll, of, ml),
+        _ => return Err(CORRUPT),
+    };
+
+    let mut bits = Backward::new(data.get(pos..).ok_or(TRUNCATED)?)?;
+    let mut ll = State::new(ll, &mut bits);
+    let mut of = State::new(of, &mut bits);
+    let mut ml = State::new(ml, &mut bits);
+    let mut lit = 0;
+    let offsets = &mut frame.offsets;
+    for i in 0..count {
+        let of_code = of.symbol() as u32;
+        let (ml_code, ll_code) = (ml.symbol() as usize, ll.symbol() as usize);
+        if of_code > 31 || ml_code >= ML_BASE.len() || ll_code >= LL_BASE.len() {
+            return Err(CORRUPT);
+        }
+        let offset_value = (1u64 << of_code) + bits.read(of_code);
+        let match_len = (ML_BASE[ml_code] as u64 + bits.read(ML_BITS[ml_code])) as usize;
+        let lit_len = (LL_BASE[ll_code] as u64 + bits.read(LL_BITS[ll_code])) as usize;
+
+        let offset = if offset_value > 3 {
+            let offset = offset_value as usize - 3;
+            *offsets = [offset, offsets[0], offsets[1]];
+            offset
+        } else {
+            let index = offset_value as usize - usize::from(lit_len != 0);
+            match index {
+                0 => offsets[0],
+                1 | 2 => {
+                    let offset = offsets[index];
+                    *offsets = [offset, offsets[0], offsets[if index == 1 { 2 } else { 1 }]];
+                    offset
+                }
+                _ => {
+                    let offset = offsets[0]
+                        .checked_sub(1)
+                        .filter(|&o| o > 0)
+                        .ok_or(CORRUPT)?;
+                    *offsets = [offset, offsets[0], offsets[1]];
+                    offset
+                }
+            }
+        };
+
+        out.extend_from_slice(literals.get(lit..lit + lit_len).ok_or(CORRUPT)?);
+        lit += lit_len;
+        if offset > out.len() {
+            return Err(CORRUPT);
+        }
+        let start = out.len() - offset;
+        for k in 0..match_len {
+            let b = out[start + k];
+            out.push(b);
+        }
+
+        if i + 1 < count {
+            ll.update(&mut bits);
+            ml.update(&mut bits);
+            of.update(&mut bits);
+        }
+    }
+    if !bits.finished() {
+        return Err(CORRUPT);
+    }
+    out.extend_from_slice(&literals[lit..]);
+    Ok(())
+}
+
+// Decodes a frame after its magic number, returning the number of bytes read.
+fn frame(data: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+    let descriptor = byte(data, 0)?;
+    let single_segment = descriptor & 0x20 != 0;
+    let checksum = descriptor & 4 != 0;
+    let dict_len = [0, 1, 2, 4][descriptor & 3];
+    let size_len = match descriptor >> 6 {
+        0 if single_segment => 1,
+        0 => 0,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    if descriptor & 8 != 0 {
+        return Err(CORRUPT);
+    }
+    let mut pos = 1 + usize::from(!single_segment);
+    if le(slice(data, pos, dict_len)?) != 0 {
+        return Err("zstd dictionaries are not supported");
+    }
+    pos += dict_len + size_len;
+
+    let start = out.len();
+    let mut frame = Frame {
+        huffman: None,
+        ll: None,
+        of: None,
+        ml: None,
+        offsets: [1, 4, 8],
+    };
+    loop {
+        let header = le(slice(data, pos, 3)?) as usize;
+        pos += 3;
+        let size = header >> 3;
+        match (header >> 1) & 3 {
+            0 => out.extend_from_slice(slice(data, pos, size)?),
+            1 => {
+                let b = byte(data, pos)? as u8;
+                out.extend(std::iter::repeat_n(b, size));
+            }
+            2 => block(slice(data, pos, size)?, &mut frame, out)?,
+            _ => return Err(CORRUPT),
+        }
+        pos += if (header >> 1) & 3 == 1 { 1 } else { size };
+        if header & 1 != 0 {
+            break;
+        }
+    }
+    if checksum {
+        let expected = le(slice(data, pos, 4)?);
+        if xxh64(&out[start..]) & 0xFFFF_FFFF != expected {
+            return Err("zstd checksum mismatch");
+        }
+        pos += 4;
+    }
+    Ok(pos)
+}
+
+/// Decompresses the frames of a Zstandard file.
+pub(crate) fn decompress(mut data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let magic = le(slice(data, 0, 4)?) as u32;
+        let len = if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+            8 + le(slice(data, 4, 4)?) as usize
+        } else if magic == 0xFD2F_B528 {
+            4 + frame(&data[4..], &mut out)?
+        } else {
+            return Err("invalid zstd header");
+        };
+        data = data.get(len..).ok_or(TRUNCATED)?;
+    }
+    Ok(out)
+}
+
+const PRIME1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME1)
+}
+
+fn xxh64_merge(acc: u64, v: u64) -> u64 {
+    (acc ^ xxh64_round(0, v))
+        .wrapping_mul(PRIME1)
+        .wrapping_add(PRIME4)
+}
+
+// XXH64 with seed 0.
+fn xxh64(data: &[u8]) -> u64 {
+    let mut chunks = data.chunks_exact(32);
+    let mut h = if data.len() >= 32 {
+        let mut v = [
+            PRIME1.wrapping_add(PRIME2),
+            PRIME2,
+            0,
+            0u64.wrapping_sub(PRIME1),
+        ];
+        for chunk in &mut chunks {
+            for (i, lane) in v.iter_mut().enumerate() {
+                *lane = xxh64_round(*lane, le(&chunk[8 * i..8 * i + 8]));
+            }
+        }
+        let mut h = v[0]
+            .rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18));
+        for &lane in &v {
+            h = xxh64_merge(h, lane);
+        }
+        h
+    } else {
+        PRIME5
+    };
+    h = h.wrapping_add(data.len() as u64);
+    let mut rest = chunks.remainder();
+    while rest.len() >= 8 {
+        h ^= xxh64_round(0, le(&rest[..8]));
+        h = h.rotate_left(27).wrapping_mul(PRIME1).wrapping_add(PRIME4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        h ^= le(&rest[..4]).wrapping_mul(PRIME1);
+        h = h.rotate_left(23).wrapping_mul(PRIME2).wrapping_add(PRIME3);
+        rest = &rest[4..];
+    }
+    for &b in rest {
+        h ^= (b as u64).wrapping_mul(PRIME5);
+        h = h.rotate_left(11).wrapping_mul(PRIME1);
+    }
+    h ^= h >> 33;
+    h = h.wrapping_mul(PRIME2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(PRIME3);
+    h ^ (h >> 32)
+}
+
+#[test]
+fn test_decompress() {
+    let text: String = (0..40)
+        .map(|i| format!("node [ id {} label \"n{}\" ]\n", i, i % 7))
+        .collect();
+    // `zstd -19` of `text`, a compressed block with a checksum
+    let compressed = [
+        0x28, 0xb5, 0x2f, 0xfd, 0x04, 0x68, 0xc5, 0x03, 0x00, 0xa2, 0x45, 0x10, 0x10, 0xb0, 0x1d,
+        0x03, 0xfc, 0xd5, 0xd4, 0x4a, 0xee, 0xac, 0x36, 0x92, 0xb2, 0x2a, 0xe2, 0x5c, 0x0c, 0x09,
+        0x1d, 0xf3, 0x72, 0xf2, 0xdf, 0xef, 0xc7, 0x13, 0x3a, 0xc6, 0xc5, 0xc4, 0xb7, 0x5d, 0x0f,
+        0x27, 0x74, 0x4c, 0x0b, 0xe9, 0xeb, 0xb6, 0xa3, 0x09, 0x1d, 0x63, 0x4a, 0x21, 0xe4, 0xb7,
+        0x5b, 0xb0, 0x82, 0x18, 0x52, 0x84, 0xf8, 0x1a, 0x8b, 0x61, 0x04, 0x07, 0x83, 0x0a, 0xcf,
+        0x4d, 0x19, 0x2e, 0xa8, 0x11, 0x40, 0xfa, 0xfe, 0x6f, 0xa0, 0xa3, 0x92, 0x07, 0x11, 0x6c,
+        0x22, 0x82, 0x49, 0x78, 0xe3, 0x07, 0xff, 0x6f, 0x46, 0xe5, 0xfd, 0xc1, 0x85, 0xa9, 0x78,
+        0x8e, 0x70, 0x1b, 0xcd, 0x82, 0x0d, 0x6b, 0x23, 0x4d, 0xcd, 0xcc, 0xca, 0x88, 0xe0, 0xc2,
+        0xc2, 0xe0, 0xc1, 0x5c, 0x00, 0x8c, 0xb1, 0x5f, 0x05, 0xa3, 0x70, 0x94, 0x50,
+    ];
+    assert_eq!(text.as_bytes(), &decompress(&compressed).unwrap()[..]);
+
+    let mut frames = vec![0x50, 0x2A, 0x4D, 0x18, 2, 0, 0, 0, 0xAA, 0xBB];
+    frames.extend_from_slice(&compressed);
+    // a frame of a raw and an RLE block without a checksum
+    frames.extend_from_slice(&[
+        0x28, 0xB5, 0x2F, 0xFD, 0x00, 0x00, 0x18, 0, 0, b'a', b'b', b'c',
+    ]);
+    frames.extend_from_slice(&[0x13, 0, 0, b'!']);
+    let mut expected = text.as_bytes().to_vec();
+    expected.extend_from_slice(b"abc!!");
+    assert_eq!(expected, decompress(&frames).unwrap());
+
+    let mut corrupt = compressed;
+    let last = corrupt.len() - 1;
+    corrupt[last] ^= 1;
+    assert_eq!(Err("zstd checksum mismatch"), decompress(&corrupt));
+    assert_eq!(Err(TRUNCATED), decompress(&compressed[..20]));
+}